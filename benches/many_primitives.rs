@@ -0,0 +1,142 @@
+//! Measures drawing 10000-element plots, comparing the backend, which reuses
+//! its path builder, stroke, and buffers across primitives, with building a
+//! new path builder and stroke for every primitive.
+//!
+//! Run with `cargo bench --bench many_primitives`.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    hint::black_box,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+use gtk::{gdk, graphene::Point, gsk};
+use plotters::prelude::*;
+use plotters_backend::DrawingBackend;
+use plotters_gtk4::{headless, SnapshotBackend};
+
+const N_ELEMENTS: usize = 10_000;
+const N_RUNS: u32 = 20;
+const SIZE: (u32, u32) = (1000, 1000);
+
+/// Allocator that counts the allocations made from Rust.
+///
+/// Allocations made by GTK itself are not counted, so only the time tells
+/// the cost of creating GTK objects.
+struct CountingAlloc;
+
+static N_ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        N_ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn points() -> Vec<(i32, i32)> {
+    (0..N_ELEMENTS)
+        .map(|index| {
+            let x = (index * 7919 % 1000) as i32;
+            let y = (index * 104_729 % 1000) as i32;
+            (x, y)
+        })
+        .collect()
+}
+
+/// Runs `draw` on a new snapshot `N_RUNS` times, returning the mean time and
+/// Rust allocations of a run.
+fn measure(mut draw: impl FnMut(&gtk::Snapshot)) -> (Duration, usize) {
+    let mut elapsed = Duration::ZERO;
+    let mut n_allocations = 0;
+
+    for _ in 0..N_RUNS {
+        let snapshot = gtk::Snapshot::new();
+        let allocations_before = N_ALLOCATIONS.load(Ordering::Relaxed);
+        let start = Instant::now();
+
+        draw(&snapshot);
+
+        elapsed += start.elapsed();
+        n_allocations += N_ALLOCATIONS.load(Ordering::Relaxed) - allocations_before;
+        black_box(snapshot.to_node());
+    }
+
+    (elapsed / N_RUNS, n_allocations / N_RUNS as usize)
+}
+
+fn report(name: &str, (elapsed, n_allocations): (Duration, usize)) {
+    println!(
+        "{:<32} {:>10.2?} {:>8} allocations ({:.2} per element)",
+        name,
+        elapsed,
+        n_allocations,
+        n_allocations as f64 / N_ELEMENTS as f64
+    );
+}
+
+fn main() {
+    headless::init().expect("Failed to initialize GTK");
+
+    let points = points();
+    let color = gdk::RGBA::new(0.2, 0.4, 0.8, 1.0);
+
+    report(
+        "lines, backend",
+        measure(|snapshot| {
+            let mut backend = SnapshotBackend::new(snapshot, SIZE);
+            for pair in points.windows(2) {
+                backend.draw_line(pair[0], pair[1], &BLUE).unwrap();
+            }
+        }),
+    );
+    report(
+        "lines, new builder per line",
+        measure(|snapshot| {
+            for pair in points.windows(2) {
+                let path_builder = gsk::PathBuilder::new();
+                path_builder.move_to(pair[0].0 as f32, pair[0].1 as f32);
+                path_builder.line_to(pair[1].0 as f32, pair[1].1 as f32);
+                snapshot.append_stroke(&path_builder.to_path(), &gsk::Stroke::new(1.0), &color);
+            }
+        }),
+    );
+
+    report(
+        "circles, backend",
+        measure(|snapshot| {
+            let mut backend = SnapshotBackend::new(snapshot, SIZE);
+            for &point in &points {
+                backend.draw_circle(point, 3, &BLUE, true).unwrap();
+            }
+        }),
+    );
+    report(
+        "circles, new builder per circle",
+        measure(|snapshot| {
+            for &(x, y) in &points {
+                let path_builder = gsk::PathBuilder::new();
+                path_builder.add_circle(&Point::new(x as f32, y as f32), 3.0);
+                snapshot.append_fill(&path_builder.to_path(), gsk::FillRule::Winding, &color);
+            }
+        }),
+    );
+
+    report(
+        "series, backend",
+        measure(|snapshot| {
+            let root = SnapshotBackend::new(snapshot, SIZE).into_drawing_area();
+            root.draw(&PathElement::new(points.clone(), BLUE)).unwrap();
+            root.draw(&Polygon::new(points.clone(), BLUE.mix(0.2)))
+                .unwrap();
+        }),
+    );
+}
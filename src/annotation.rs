@@ -0,0 +1,585 @@
+use std::borrow::Cow;
+
+use gtk::{
+    gdk, glib,
+    graphene::{Point, Rect},
+    gsk, pango,
+    prelude::*,
+};
+
+use crate::{colormaps::Colormap, context, Paintable};
+
+/// The length of the head of an [`Annotation::Arrow`], relative to its
+/// width.
+const ARROW_HEAD_SCALE: f32 = 4.0;
+
+/// A note drawn above the plot of a [`Paintable`].
+///
+/// Positions are in pixels of the paintable, as in the drawing backends.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Annotation {
+    /// Text with its top-left corner at the position.
+    Text {
+        position: (f32, f32),
+        text: String,
+        color: gdk::RGBA,
+    },
+    /// Text wrapped onto lines of at most `max_width` pixels, in a box of
+    /// the `background` color extending past the text by `padding`, with
+    /// the top-left corner of the box at the position.
+    Note {
+        position: (f32, f32),
+        text: String,
+        color: gdk::RGBA,
+        max_width: f32,
+        wrap: pango::WrapMode,
+        background: gdk::RGBA,
+        padding: f32,
+    },
+    /// A line from one point to another, with an arrow head at the end.
+    Arrow {
+        from: (f32, f32),
+        to: (f32, f32),
+        color: gdk::RGBA,
+        width: f32,
+    },
+    /// A line across the whole width of the paintable.
+    HorizontalLine {
+        y: f32,
+        color: gdk::RGBA,
+        width: f32,
+    },
+    /// A line across the whole height of the paintable.
+    VerticalLine {
+        x: f32,
+        color: gdk::RGBA,
+        width: f32,
+    },
+    /// A rect filled with the color, such as to shade a range of interest.
+    Region {
+        upper_left: (f32, f32),
+        bottom_right: (f32, f32),
+        color: gdk::RGBA,
+    },
+    /// A rect filled with the gradient of the colormap, from bottom to top
+    /// if it is taller than wide, or from left to right otherwise.
+    Colorbar {
+        upper_left: (f32, f32),
+        bottom_right: (f32, f32),
+        colormap: Colormap,
+    },
+}
+
+type Rgba = (f64, f64, f64, f64);
+
+fn rgba_to_tuple(rgba: &gdk::RGBA) -> Rgba {
+    (
+        rgba.red() as f64,
+        rgba.green() as f64,
+        rgba.blue() as f64,
+        rgba.alpha() as f64,
+    )
+}
+
+fn rgba_from_tuple((red, green, blue, alpha): Rgba) -> gdk::RGBA {
+    gdk::RGBA::new(red as f32, green as f32, blue as f32, alpha as f32)
+}
+
+fn wrap_to_nick(wrap: pango::WrapMode) -> &'static str {
+    match wrap {
+        pango::WrapMode::Word => "word",
+        pango::WrapMode::Char => "char",
+        _ => "word-char",
+    }
+}
+
+fn wrap_from_nick(nick: &str) -> Option<pango::WrapMode> {
+    match nick {
+        "word" => Some(pango::WrapMode::Word),
+        "char" => Some(pango::WrapMode::Char),
+        "word-char" => Some(pango::WrapMode::WordChar),
+        _ => None,
+    }
+}
+
+fn point_to_tuple((x, y): (f32, f32)) -> (f64, f64) {
+    (x as f64, y as f64)
+}
+
+fn point_from_tuple((x, y): (f64, f64)) -> (f32, f32) {
+    (x as f32, y as f32)
+}
+
+/// Annotations are serialized as `(sv)`, the kind of annotation and its
+/// fields.
+impl glib::StaticVariantType for Annotation {
+    fn static_variant_type() -> Cow<'static, glib::VariantTy> {
+        <(String, glib::Variant)>::static_variant_type()
+    }
+}
+
+impl glib::ToVariant for Annotation {
+    fn to_variant(&self) -> glib::Variant {
+        let (kind, fields) = match self {
+            Self::Text {
+                position,
+                text,
+                color,
+            } => (
+                "text",
+                (point_to_tuple(*position), text, rgba_to_tuple(color)).to_variant(),
+            ),
+            Self::Note {
+                position,
+                text,
+                color,
+                max_width,
+                wrap,
+                background,
+                padding,
+            } => (
+                "note",
+                (
+                    point_to_tuple(*position),
+                    text,
+                    rgba_to_tuple(color),
+                    *max_width as f64,
+                    wrap_to_nick(*wrap),
+                    rgba_to_tuple(background),
+                    *padding as f64,
+                )
+                    .to_variant(),
+            ),
+            Self::Arrow {
+                from,
+                to,
+                color,
+                width,
+            } => (
+                "arrow",
+                (
+                    point_to_tuple(*from),
+                    point_to_tuple(*to),
+                    rgba_to_tuple(color),
+                    *width as f64,
+                )
+                    .to_variant(),
+            ),
+            Self::HorizontalLine { y, color, width } => (
+                "horizontal-line",
+                (*y as f64, rgba_to_tuple(color), *width as f64).to_variant(),
+            ),
+            Self::VerticalLine { x, color, width } => (
+                "vertical-line",
+                (*x as f64, rgba_to_tuple(color), *width as f64).to_variant(),
+            ),
+            Self::Region {
+                upper_left,
+                bottom_right,
+                color,
+            } => (
+                "region",
+                (
+                    point_to_tuple(*upper_left),
+                    point_to_tuple(*bottom_right),
+                    rgba_to_tuple(color),
+                )
+                    .to_variant(),
+            ),
+            Self::Colorbar {
+                upper_left,
+                bottom_right,
+                colormap,
+            } => (
+                "colorbar",
+                (
+                    point_to_tuple(*upper_left),
+                    point_to_tuple(*bottom_right),
+                    colormap.nick(),
+                )
+                    .to_variant(),
+            ),
+        };
+        (kind, fields).to_variant()
+    }
+}
+
+impl glib::FromVariant for Annotation {
+    fn from_variant(variant: &glib::Variant) -> Option<Self> {
+        let (kind, fields) = variant.get::<(String, glib::Variant)>()?;
+        let annotation = match kind.as_str() {
+            "text" => {
+                let (position, text, color) = fields.get::<((f64, f64), String, Rgba)>()?;
+                Self::Text {
+                    position: point_from_tuple(position),
+                    text,
+                    color: rgba_from_tuple(color),
+                }
+            }
+            "note" => {
+                let (position, text, color, max_width, wrap, background, padding) =
+                    fields.get::<((f64, f64), String, Rgba, f64, String, Rgba, f64)>()?;
+                Self::Note {
+                    position: point_from_tuple(position),
+                    text,
+                    color: rgba_from_tuple(color),
+                    max_width: max_width as f32,
+                    wrap: wrap_from_nick(&wrap)?,
+                    background: rgba_from_tuple(background),
+                    padding: padding as f32,
+                }
+            }
+            "arrow" => {
+                let (from, to, color, width) =
+                    fields.get::<((f64, f64), (f64, f64), Rgba, f64)>()?;
+                Self::Arrow {
+                    from: point_from_tuple(from),
+                    to: point_from_tuple(to),
+                    color: rgba_from_tuple(color),
+                    width: width as f32,
+                }
+            }
+            "horizontal-line" => {
+                let (y, color, width) = fields.get::<(f64, Rgba, f64)>()?;
+                Self::HorizontalLine {
+                    y: y as f32,
+                    color: rgba_from_tuple(color),
+                    width: width as f32,
+                }
+            }
+            "vertical-line" => {
+                let (x, color, width) = fields.get::<(f64, Rgba, f64)>()?;
+                Self::VerticalLine {
+                    x: x as f32,
+                    color: rgba_from_tuple(color),
+                    width: width as f32,
+                }
+            }
+            "region" => {
+                let (upper_left, bottom_right, color) =
+                    fields.get::<((f64, f64), (f64, f64), Rgba)>()?;
+                Self::Region {
+                    upper_left: point_from_tuple(upper_left),
+                    bottom_right: point_from_tuple(bottom_right),
+                    color: rgba_from_tuple(color),
+                }
+            }
+            "colorbar" => {
+                let (upper_left, bottom_right, colormap) =
+                    fields.get::<((f64, f64), (f64, f64), String)>()?;
+                Self::Colorbar {
+                    upper_left: point_from_tuple(upper_left),
+                    bottom_right: point_from_tuple(bottom_right),
+                    colormap: Colormap::from_nick(&colormap)?,
+                }
+            }
+            _ => return None,
+        };
+        Some(annotation)
+    }
+}
+
+/// Identifies an annotation added with [`Annotations::add`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AnnotationId(pub(crate) u64);
+
+/// The annotations of a [`Paintable`].
+///
+/// Annotations are drawn above the plot, and can be added, replaced, and
+/// removed without redrawing the plot. This is returned by
+/// [`Paintable::annotations`].
+#[derive(Debug, Clone)]
+pub struct Annotations {
+    pub(crate) paintable: Paintable,
+}
+
+impl Annotations {
+    /// Adds an annotation, returning its ID.
+    pub fn add(&self, annotation: Annotation) -> AnnotationId {
+        self.paintable.update_annotations(|annotations, next_id| {
+            let id = AnnotationId(*next_id);
+            *next_id += 1;
+            annotations.push((id, annotation));
+            id
+        })
+    }
+
+    /// Replaces the annotation with the given ID, returning whether it
+    /// exists.
+    pub fn replace(&self, id: AnnotationId, annotation: Annotation) -> bool {
+        self.paintable.update_annotations(|annotations, _| {
+            match annotations.iter_mut().find(|(other_id, _)| *other_id == id) {
+                Some((_, old)) => {
+                    *old = annotation;
+                    true
+                }
+                None => false,
+            }
+        })
+    }
+
+    /// Removes the annotation with the given ID, returning whether it
+    /// existed.
+    pub fn remove(&self, id: AnnotationId) -> bool {
+        self.paintable.update_annotations(|annotations, _| {
+            let len = annotations.len();
+            annotations.retain(|(other_id, _)| *other_id != id);
+            annotations.len() != len
+        })
+    }
+
+    /// Removes all annotations.
+    pub fn clear(&self) {
+        self.paintable.update_annotations(|annotations, _| {
+            annotations.clear();
+        });
+    }
+
+    /// Returns the annotation with the given ID.
+    pub fn get(&self, id: AnnotationId) -> Option<Annotation> {
+        self.paintable
+            .annotations_list()
+            .into_iter()
+            .find(|(other_id, _)| *other_id == id)
+            .map(|(_, annotation)| annotation)
+    }
+
+    /// Returns the IDs of the annotations, in the order they are drawn.
+    pub fn ids(&self) -> Vec<AnnotationId> {
+        self.paintable
+            .annotations_list()
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect()
+    }
+}
+
+/// Returns the node of the annotations, drawn in order, within `size`.
+pub(crate) fn annotations_node(
+    annotations: &[(AnnotationId, Annotation)],
+    (width, height): (u32, u32),
+) -> Option<gsk::RenderNode> {
+    let snapshot = gtk::Snapshot::new();
+    let (width, height) = (width as f32, height as f32);
+
+    let mut layout = None;
+
+    for (_, annotation) in annotations {
+        match annotation {
+            Annotation::Text {
+                position: (x, y),
+                text,
+                color,
+            } => {
+                let layout = layout
+                    .get_or_insert_with(|| pango::Layout::new(&context::default_pango_context()));
+                layout.set_width(-1);
+                layout.set_text(text);
+
+                snapshot.save();
+                snapshot.translate(&Point::new(*x, *y));
+                snapshot.append_layout(layout, color);
+                snapshot.restore();
+            }
+            Annotation::Note {
+                position: (x, y),
+                text,
+                color,
+                max_width,
+                wrap,
+                background,
+                padding,
+            } => {
+                let layout = layout
+                    .get_or_insert_with(|| pango::Layout::new(&context::default_pango_context()));
+                layout.set_width((max_width.max(0.0) * pango::SCALE as f32) as i32);
+                layout.set_wrap(*wrap);
+                layout.set_text(text);
+
+                // The box is sized to the wrapped lines, which may be
+                // narrower than the maximum width.
+                let (_, extents) = layout.pixel_extents();
+                let bounds = Rect::new(
+                    *x,
+                    *y,
+                    extents.width() as f32 + padding * 2.0,
+                    extents.height() as f32 + padding * 2.0,
+                );
+                snapshot.append_color(background, &bounds);
+
+                snapshot.save();
+                snapshot.translate(&Point::new(
+                    x + padding - extents.x() as f32,
+                    y + padding - extents.y() as f32,
+                ));
+                snapshot.append_layout(layout, color);
+                snapshot.restore();
+            }
+            Annotation::Arrow {
+                from: (x0, y0),
+                to: (x1, y1),
+                color,
+                width,
+            } => {
+                let path_builder = gsk::PathBuilder::new();
+                path_builder.move_to(*x0, *y0);
+                path_builder.line_to(*x1, *y1);
+                let path = path_builder.to_path();
+                snapshot.append_stroke(&path, &gsk::Stroke::new(*width), color);
+
+                let (dx, dy) = (x1 - x0, y1 - y0);
+                let len = dx.hypot(dy);
+                if len > 0.0 {
+                    // The head is a triangle with its tip at the end, as wide
+                    // as it is long.
+                    let head_len = width * ARROW_HEAD_SCALE;
+                    let (ux, uy) = (dx / len, dy / len);
+                    let (bx, by) = (x1 - ux * head_len, y1 - uy * head_len);
+                    let (nx, ny) = (-uy * head_len / 2.0, ux * head_len / 2.0);
+
+                    let path_builder = gsk::PathBuilder::new();
+                    path_builder.move_to(*x1, *y1);
+                    path_builder.line_to(bx + nx, by + ny);
+                    path_builder.line_to(bx - nx, by - ny);
+                    path_builder.close();
+                    let path = path_builder.to_path();
+                    snapshot.append_fill(&path, gsk::FillRule::Winding, color);
+                }
+            }
+            Annotation::HorizontalLine {
+                y,
+                color,
+                width: line_width,
+            } => {
+                snapshot.append_color(
+                    color,
+                    &Rect::new(0.0, y - line_width / 2.0, width, *line_width),
+                );
+            }
+            Annotation::VerticalLine {
+                x,
+                color,
+                width: line_width,
+            } => {
+                snapshot.append_color(
+                    color,
+                    &Rect::new(x - line_width / 2.0, 0.0, *line_width, height),
+                );
+            }
+            Annotation::Region {
+                upper_left: (x0, y0),
+                bottom_right: (x1, y1),
+                color,
+            } => {
+                snapshot.append_color(
+                    color,
+                    &Rect::new(x0.min(*x1), y0.min(*y1), (x1 - x0).abs(), (y1 - y0).abs()),
+                );
+            }
+            Annotation::Colorbar {
+                upper_left: (x0, y0),
+                bottom_right: (x1, y1),
+                colormap,
+            } => {
+                let bounds = Rect::new(x0.min(*x1), y0.min(*y1), (x1 - x0).abs(), (y1 - y0).abs());
+                let (start, end) = if bounds.height() > bounds.width() {
+                    (bounds.bottom_left(), bounds.top_left())
+                } else {
+                    (bounds.top_left(), bounds.top_right())
+                };
+                snapshot.append_linear_gradient(&bounds, &start, &end, &colormap.color_stops());
+            }
+        }
+    }
+
+    snapshot.to_node()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn variant_round_trips() {
+        let color = gdk::RGBA::new(0.25, 0.5, 0.75, 1.0);
+        let annotations = [
+            Annotation::Text {
+                position: (1.0, 2.0),
+                text: "Peak".to_string(),
+                color,
+            },
+            Annotation::Note {
+                position: (3.0, 4.0),
+                text: "A longer note".to_string(),
+                color,
+                max_width: 120.0,
+                wrap: pango::WrapMode::Char,
+                background: gdk::RGBA::new(1.0, 1.0, 1.0, 0.5),
+                padding: 6.0,
+            },
+            Annotation::Arrow {
+                from: (5.0, 6.0),
+                to: (7.0, 8.0),
+                color,
+                width: 1.5,
+            },
+            Annotation::HorizontalLine {
+                y: 9.0,
+                color,
+                width: 1.0,
+            },
+            Annotation::VerticalLine {
+                x: 10.0,
+                color,
+                width: 2.0,
+            },
+            Annotation::Region {
+                upper_left: (11.0, 12.0),
+                bottom_right: (13.0, 14.0),
+                color,
+            },
+            Annotation::Colorbar {
+                upper_left: (15.0, 16.0),
+                bottom_right: (17.0, 18.0),
+                colormap: Colormap::Coolwarm,
+            },
+        ];
+
+        for annotation in annotations {
+            let variant = annotation.to_variant();
+            assert!(variant.is_type(&Annotation::static_variant_type()));
+            assert_eq!(variant.get::<Annotation>(), Some(annotation));
+        }
+    }
+
+    #[test]
+    fn from_variant_rejects_invalid_annotations() {
+        let unknown_kind = ("circle", (1.0, 2.0).to_variant()).to_variant();
+        assert_eq!(unknown_kind.get::<Annotation>(), None);
+
+        let wrong_fields = ("horizontal-line", (1.0, 2.0).to_variant()).to_variant();
+        assert_eq!(wrong_fields.get::<Annotation>(), None);
+
+        let unknown_colormap =
+            ("colorbar", ((0.0, 0.0), (1.0, 1.0), "rainbow").to_variant()).to_variant();
+        assert_eq!(unknown_colormap.get::<Annotation>(), None);
+
+        let unknown_wrap = (
+            "note",
+            (
+                (0.0, 0.0),
+                "Note",
+                (0.0, 0.0, 0.0, 1.0),
+                100.0,
+                "hyphen",
+                (1.0, 1.0, 1.0, 1.0),
+                4.0,
+            )
+                .to_variant(),
+        )
+            .to_variant();
+        assert_eq!(unknown_wrap.get::<Annotation>(), None);
+
+        assert_eq!("text".to_variant().get::<Annotation>(), None);
+    }
+}
@@ -0,0 +1,243 @@
+//! Chart appearance preferences stored in [`gio::Settings`].
+//!
+//! Apps commonly let users choose whether the grid is shown, the colors of
+//! the series, and how thick lines are. [`AppearanceSettings`] reads these
+//! from keys of the app's own schema, and redraws a [`PlotView`] when they
+//! change, so the draw function only has to read the current
+//! [`Appearance`].
+//!
+//! The schema should have these keys, or keys of the same types passed to
+//! [`AppearanceSettings::with_keys`]:
+//!
+//! ```xml
+//! <key name="show-grid" type="b">
+//!   <default>true</default>
+//! </key>
+//! <key name="palette" type="s">
+//!   <choices>
+//!     <choice value="adaptive"/>
+//!     <choice value="gnome"/>
+//!     <choice value="gnome-dark"/>
+//!   </choices>
+//!   <default>"adaptive"</default>
+//! </key>
+//! <key name="line-thickness" type="s">
+//!   <choices>
+//!     <choice value="thin"/>
+//!     <choice value="normal"/>
+//!     <choice value="thick"/>
+//!   </choices>
+//!   <default>"normal"</default>
+//! </key>
+//! ```
+//!
+//! Keys missing from the schema and unknown values fall back to the
+//! defaults of [`Appearance`].
+
+use gtk::{
+    gio,
+    glib::{self, clone},
+    prelude::*,
+};
+use plotters::style::{Palette, RGBColor};
+
+use crate::{
+    palette::{AdaptivePalette, Gnome, GnomeDark},
+    PlotView,
+};
+
+/// The palette series colors are picked from.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AppearancePalette {
+    /// The [`AdaptivePalette`] of the widget the plot is shown in.
+    #[default]
+    Adaptive,
+    /// The [`Gnome`] palette.
+    Gnome,
+    /// The [`GnomeDark`] palette.
+    GnomeDark,
+}
+
+impl AppearancePalette {
+    /// Returns the colors of the palette, following the style of `widget`
+    /// for [`AppearancePalette::Adaptive`].
+    pub fn colors(self, widget: &impl IsA<gtk::Widget>) -> Vec<RGBColor> {
+        let to_colors = |colors: &[(u8, u8, u8)]| -> Vec<RGBColor> {
+            colors.iter().map(|&(r, g, b)| RGBColor(r, g, b)).collect()
+        };
+
+        match self {
+            Self::Adaptive => AdaptivePalette::for_widget(widget).colors(),
+            Self::Gnome => to_colors(Gnome::COLORS),
+            Self::GnomeDark => to_colors(GnomeDark::COLORS),
+        }
+    }
+
+    fn from_nick(nick: &str) -> Option<Self> {
+        match nick {
+            "adaptive" => Some(Self::Adaptive),
+            "gnome" => Some(Self::Gnome),
+            "gnome-dark" => Some(Self::GnomeDark),
+            _ => None,
+        }
+    }
+}
+
+/// A preset of the thickness of lines.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LineThickness {
+    /// Lines half as thick as drawn.
+    Thin,
+    /// Lines as thick as drawn.
+    #[default]
+    Normal,
+    /// Lines twice as thick as drawn.
+    Thick,
+}
+
+impl LineThickness {
+    /// Returns the multiplier of the stroke width of the preset, to be set
+    /// with [`SnapshotBackend::set_stroke_width_scale`] or
+    /// [`PaintableBackend::set_stroke_width_scale`].
+    ///
+    /// [`SnapshotBackend::set_stroke_width_scale`]: crate::SnapshotBackend::set_stroke_width_scale
+    /// [`PaintableBackend::set_stroke_width_scale`]: crate::PaintableBackend::set_stroke_width_scale
+    pub fn stroke_width_scale(self) -> f32 {
+        match self {
+            Self::Thin => 0.5,
+            Self::Normal => 1.0,
+            Self::Thick => 2.0,
+        }
+    }
+
+    fn from_nick(nick: &str) -> Option<Self> {
+        match nick {
+            "thin" => Some(Self::Thin),
+            "normal" => Some(Self::Normal),
+            "thick" => Some(Self::Thick),
+            _ => None,
+        }
+    }
+}
+
+/// The appearance options read from [`AppearanceSettings`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Appearance {
+    /// Whether the grid of the chart is shown, such as with
+    /// `configure_mesh().disable_mesh()` in plotters if not.
+    pub shows_grid: bool,
+    /// The palette series colors are picked from.
+    pub palette: AppearancePalette,
+    /// The thickness of lines.
+    pub line_thickness: LineThickness,
+}
+
+impl Default for Appearance {
+    fn default() -> Self {
+        Self {
+            shows_grid: true,
+            palette: AppearancePalette::default(),
+            line_thickness: LineThickness::default(),
+        }
+    }
+}
+
+/// Appearance options bound to keys of a [`gio::Settings`].
+///
+/// See the [module documentation](self) for the keys.
+#[derive(Debug, Clone)]
+pub struct AppearanceSettings {
+    settings: gio::Settings,
+    grid_key: String,
+    palette_key: String,
+    line_thickness_key: String,
+}
+
+impl AppearanceSettings {
+    /// Creates appearance options read from the `show-grid`, `palette`, and
+    /// `line-thickness` keys of `settings`.
+    pub fn new(settings: &gio::Settings) -> Self {
+        Self::with_keys(settings, "show-grid", "palette", "line-thickness")
+    }
+
+    /// Creates appearance options read from the given keys of `settings`,
+    /// such as when the schema already has keys of other names.
+    pub fn with_keys(
+        settings: &gio::Settings,
+        grid_key: &str,
+        palette_key: &str,
+        line_thickness_key: &str,
+    ) -> Self {
+        Self {
+            settings: settings.clone(),
+            grid_key: grid_key.to_string(),
+            palette_key: palette_key.to_string(),
+            line_thickness_key: line_thickness_key.to_string(),
+        }
+    }
+
+    /// Returns the settings the options are read from.
+    pub fn settings(&self) -> &gio::Settings {
+        &self.settings
+    }
+
+    /// Returns the current appearance options.
+    pub fn appearance(&self) -> Appearance {
+        let default = Appearance::default();
+        Appearance {
+            shows_grid: if self.has_key(&self.grid_key) {
+                self.settings.boolean(&self.grid_key)
+            } else {
+                default.shows_grid
+            },
+            palette: self
+                .string(&self.palette_key)
+                .and_then(|nick| AppearancePalette::from_nick(&nick))
+                .unwrap_or(default.palette),
+            line_thickness: self
+                .string(&self.line_thickness_key)
+                .and_then(|nick| LineThickness::from_nick(&nick))
+                .unwrap_or(default.line_thickness),
+        }
+    }
+
+    /// Redraws `view` whenever one of the keys changes.
+    ///
+    /// The returned handler can be disconnected from
+    /// [`AppearanceSettings::settings`] to stop redrawing.
+    pub fn bind(&self, view: &PlotView) -> glib::SignalHandlerId {
+        let keys = [
+            self.grid_key.clone(),
+            self.palette_key.clone(),
+            self.line_thickness_key.clone(),
+        ];
+        let handler_id = self.settings.connect_changed(
+            None,
+            clone!(
+                #[weak]
+                view,
+                move |_, key| {
+                    if keys.iter().any(|k| k == key) {
+                        view.redraw();
+                    }
+                }
+            ),
+        );
+        // GSettings only emits `changed` for keys that have been read since
+        // the handler was connected.
+        self.appearance();
+        handler_id
+    }
+
+    /// Returns whether the schema of the settings has `key`, as reading a
+    /// missing key aborts.
+    fn has_key(&self, key: &str) -> bool {
+        self.settings
+            .settings_schema()
+            .is_some_and(|schema| schema.has_key(key))
+    }
+
+    fn string(&self, key: &str) -> Option<glib::GString> {
+        self.has_key(key).then(|| self.settings.string(key))
+    }
+}
@@ -0,0 +1,129 @@
+//! Helpers for drawing plots with a break in an axis, such as to skip an
+//! uninteresting range of values.
+//!
+//! The plot is drawn as if it were continuous, with the skipped range in
+//! it, and is then cut at the break: the content before the break is kept
+//! as is, the skipped range is dropped, and the content after it is shifted
+//! back to leave a gap, where zig-zag markers are drawn.
+
+use std::ops::Range;
+
+use gtk::{
+    graphene::{Point, Rect},
+    gsk,
+    prelude::*,
+};
+use plotters_backend::BackendStyle;
+
+use crate::{common::BackendColorExt, GtkBackend};
+
+/// The distance between the peaks of the zig-zag markers, in pixels.
+const MARKER_PERIOD: f32 = 8.0;
+
+/// The distance the zig-zag markers deviate from the break, in pixels.
+const MARKER_AMPLITUDE: f32 = 3.0;
+
+/// A break in an axis.
+///
+/// Coordinates are in pixels of the continuous plot, as drawn before the
+/// break is applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AxisBreak {
+    /// The axis that is broken, [`gtk::Orientation::Horizontal`] for the x
+    /// axis and [`gtk::Orientation::Vertical`] for the y axis.
+    pub orientation: gtk::Orientation,
+    /// The coordinate where the skipped range starts.
+    pub start: i32,
+    /// The coordinate where the skipped range ends.
+    pub end: i32,
+    /// The space left between both sides of the break.
+    pub gap: u32,
+    /// The range along the other axis the break markers are drawn across,
+    /// such as the plotting area.
+    pub marker_span: Range<i32>,
+}
+
+impl AxisBreak {
+    /// Returns the distance the content after the break is shifted back by.
+    pub fn shift(&self) -> i32 {
+        (self.end - self.start - self.gap as i32).max(0)
+    }
+}
+
+/// Calls `f` to draw the continuous plot on each side of `axis_break`, then
+/// draws the break markers with `marker_style`.
+///
+/// As `f` is called once for each side, it should draw the same content
+/// every time. Content drawn past the backend size, such as plotted data in
+/// the skipped range, is clipped away.
+pub fn draw_with_axis_break<B: GtkBackend, S: BackendStyle>(
+    backend: &mut B,
+    axis_break: &AxisBreak,
+    marker_style: &S,
+    mut f: impl FnMut(&mut B),
+) {
+    let (width, height) = backend.get_size();
+    let (width, height) = (width as f32, height as f32);
+    let is_horizontal = axis_break.orientation == gtk::Orientation::Horizontal;
+    let start = axis_break.start as f32;
+    let after_gap = start + axis_break.gap as f32;
+    let shift = axis_break.shift() as f32;
+
+    let (before_clip, after_clip, offset) = if is_horizontal {
+        (
+            Rect::new(0.0, 0.0, start, height),
+            Rect::new(after_gap, 0.0, (width - after_gap).max(0.0), height),
+            Point::new(-shift, 0.0),
+        )
+    } else {
+        (
+            Rect::new(0.0, 0.0, width, start),
+            Rect::new(0.0, after_gap, width, (height - after_gap).max(0.0)),
+            Point::new(0.0, -shift),
+        )
+    };
+
+    for (clip, offset) in [(before_clip, None), (after_clip, Some(offset))] {
+        let transform = offset.map(|offset| gsk::Transform::new().translate(&offset));
+        backend.with_clip(&clip, transform.as_ref(), &mut f);
+    }
+
+    let path_builder = gsk::PathBuilder::new();
+    for edge in [start, after_gap] {
+        add_zig_zag(&path_builder, is_horizontal, edge, &axis_break.marker_span);
+    }
+    let stroke = gsk::Stroke::new(backend.stroke_width(marker_style));
+    backend.gtk_snapshot().append_stroke(
+        &path_builder.to_path(),
+        &stroke,
+        &marker_style.color().to_rgba(),
+    );
+}
+
+/// Adds a zig-zag line along `edge`, across `span`.
+fn add_zig_zag(path_builder: &gsk::PathBuilder, is_horizontal: bool, edge: f32, span: &Range<i32>) {
+    let point = |along: f32, across: f32| {
+        if is_horizontal {
+            (edge + across, along)
+        } else {
+            (along, edge + across)
+        }
+    };
+
+    let (start, end) = (
+        span.start.min(span.end) as f32,
+        span.start.max(span.end) as f32,
+    );
+
+    let (x, y) = point(start, 0.0);
+    path_builder.move_to(x, y);
+
+    let mut along = start;
+    let mut sign = 1.0;
+    while along < end {
+        along = (along + MARKER_PERIOD / 2.0).min(end);
+        let (x, y) = point(along, sign * MARKER_AMPLITUDE);
+        path_builder.line_to(x, y);
+        sign = -sign;
+    }
+}
@@ -0,0 +1,423 @@
+use std::{convert::Infallible, ops::Deref};
+
+use gtk::{graphene::Rect, gsk, prelude::*};
+use plotters_backend::{
+    BackendColor, BackendCoord, BackendStyle, BackendTextStyle, DrawingBackend, DrawingErrorKind,
+};
+
+use crate::{PaintableBackend, SnapshotBackend, TextAttributes, TextExtents};
+
+/// Common interface of [`PaintableBackend`](crate::PaintableBackend) and
+/// [`SnapshotBackend`](crate::SnapshotBackend) for drawing with GTK APIs
+/// that have no [`DrawingBackend`] equivalent.
+///
+/// This is used by the helper modules of this crate, such as [`polar`](crate::polar).
+pub trait GtkBackend: DrawingBackend<ErrorType = Infallible> {
+    /// Returns the snapshot being drawn to, preparing the backend if needed.
+    fn gtk_snapshot(&mut self) -> &gtk::Snapshot;
+
+    /// Returns the width `style` is stroked with on this backend.
+    ///
+    /// This takes into account the stroke width scale and hairline width of
+    /// the backend.
+    fn stroke_width<S: BackendStyle>(&self, style: &S) -> f32;
+
+    /// Fills `path` with `fill_style` and strokes it with `stroke_style` as
+    /// one shape.
+    ///
+    /// Unlike filling and then stroking the path, a translucent stroke is
+    /// not blended over the fill, which would darken its inner half, and a
+    /// translucent shape of a single color is faded as a whole.
+    fn draw_shape<F: BackendStyle, S: BackendStyle>(
+        &mut self,
+        path: &gsk::Path,
+        fill_style: &F,
+        stroke_style: &S,
+    ) -> Result<(), DrawingErrorKind<Infallible>>;
+
+    /// Strokes `path` with `style`, like a path drawn with
+    /// [`DrawingBackend::draw_path`].
+    ///
+    /// This draws many shapes of one style built into a single path as one
+    /// node, such as the helpers of [`bulk`](crate::bulk) do.
+    fn stroke_path<S: BackendStyle>(
+        &mut self,
+        path: &gsk::Path,
+        style: &S,
+    ) -> Result<(), DrawingErrorKind<Infallible>>;
+
+    /// Fills `path` with `style` and the fill rule of the backend, like a
+    /// polygon drawn with [`DrawingBackend::fill_polygon`].
+    fn fill_path<S: BackendStyle>(
+        &mut self,
+        path: &gsk::Path,
+        style: &S,
+    ) -> Result<(), DrawingErrorKind<Infallible>>;
+
+    /// Sets the attributes of the text drawn and measured.
+    fn set_text_attributes(&mut self, text_attributes: TextAttributes);
+
+    /// Returns the attributes of the text drawn and measured.
+    fn text_attributes(&self) -> TextAttributes;
+
+    /// Sets the extents of text that are measured and aligned to the text
+    /// anchor.
+    fn set_text_extents(&mut self, text_extents: TextExtents);
+
+    /// Returns the extents of text that are measured and aligned to the
+    /// text anchor.
+    fn text_extents(&self) -> TextExtents;
+
+    /// Calls `f` with the drawing clipped to `clip`, then transformed by
+    /// `transform`, if any.
+    ///
+    /// Like [`PaintableBackend::with_transform`], this is a scope of the
+    /// backend, so the clip and the transform are kept around what `f`
+    /// draws even if the backend flushes it in parts, and primitives moved
+    /// into view by the transform are not culled.
+    fn with_clip<R>(
+        &mut self,
+        clip: &Rect,
+        transform: Option<&gsk::Transform>,
+        f: impl FnOnce(&mut Self) -> R,
+    ) -> R;
+}
+
+/// The state of a backend that is reset within a scope, such as a clip, to
+/// restore when leaving it.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ScopeState {
+    pub(crate) cull_bounds: Option<(BackendCoord, BackendCoord)>,
+    pub(crate) tile_size: Option<u32>,
+}
+
+/// The snapshot of a backend, with what is drawn on it appended to the
+/// backend when dropped.
+///
+/// This is returned by [`PaintableBackend::snapshot_mut`] and
+/// [`SnapshotBackend::snapshot_mut`] to draw with GTK APIs between plotters
+/// primitives. What is drawn on it is collected into a node of its own, so
+/// transforms, clips, and other state pushed on the snapshot do not leak into
+/// what is drawn afterwards, even if a `push_*` is not matched with a `pop`.
+///
+/// [`PaintableBackend::snapshot_mut`]: crate::PaintableBackend::snapshot_mut
+/// [`SnapshotBackend::snapshot_mut`]: crate::SnapshotBackend::snapshot_mut
+#[derive(Debug)]
+pub struct SnapshotScope<'a> {
+    parent: &'a gtk::Snapshot,
+    snapshot: gtk::Snapshot,
+}
+
+impl<'a> SnapshotScope<'a> {
+    pub(crate) fn new(parent: &'a gtk::Snapshot) -> Self {
+        Self {
+            parent,
+            snapshot: gtk::Snapshot::new(),
+        }
+    }
+}
+
+impl Deref for SnapshotScope<'_> {
+    type Target = gtk::Snapshot;
+
+    fn deref(&self) -> &Self::Target {
+        &self.snapshot
+    }
+}
+
+impl Drop for SnapshotScope<'_> {
+    fn drop(&mut self) {
+        // This also pops what was left pushed.
+        if let Some(node) = self.snapshot.clone().to_node() {
+            self.parent.append_node(node);
+        }
+    }
+}
+
+/// A backend that is either a [`PaintableBackend`] or a
+/// [`SnapshotBackend`], chosen at runtime.
+///
+/// Chart code is usually generic over [`DrawingBackend`], which needs a
+/// separate instance of it for each backend. This dispatches to either
+/// backend instead, so that the same chart code can draw to a paintable or
+/// a snapshot depending on a setting, such as drawing directly in a
+/// widget's snapshot or to a cached paintable.
+///
+/// The backend should be configured before it is wrapped, as its setters
+/// are not available through this.
+#[derive(Debug)]
+pub enum BoxedBackend<'a> {
+    /// Draws to a [`Paintable`](crate::Paintable).
+    ///
+    /// This is boxed, as the paintable backend is much larger than the
+    /// snapshot one.
+    Paintable(Box<PaintableBackend<'a>>),
+    /// Draws to a [`gtk::Snapshot`].
+    Snapshot(SnapshotBackend),
+}
+
+impl From<PaintableBackend<'_>> for BoxedBackend<'_> {
+    fn from(backend: PaintableBackend<'_>) -> Self {
+        Self::Paintable(Box::new(backend))
+    }
+}
+
+impl From<SnapshotBackend> for BoxedBackend<'_> {
+    fn from(backend: SnapshotBackend) -> Self {
+        Self::Snapshot(backend)
+    }
+}
+
+impl BoxedBackend<'_> {
+    /// Returns the snapshot of the backend, to draw with GTK APIs between
+    /// plotters primitives.
+    ///
+    /// See [`PaintableBackend::snapshot_mut`] and
+    /// [`SnapshotBackend::snapshot_mut`].
+    pub fn snapshot_mut(&mut self) -> SnapshotScope<'_> {
+        match self {
+            Self::Paintable(backend) => backend.snapshot_mut(),
+            Self::Snapshot(backend) => backend.snapshot_mut(),
+        }
+    }
+
+    fn enter_scope(&mut self) -> (gtk::Snapshot, ScopeState) {
+        match self {
+            Self::Paintable(backend) => backend.enter_scope(),
+            Self::Snapshot(backend) => backend.enter_scope(),
+        }
+    }
+
+    fn leave_scope(&mut self, scope_state: ScopeState) {
+        match self {
+            Self::Paintable(backend) => backend.leave_scope(scope_state),
+            Self::Snapshot(backend) => backend.leave_scope(scope_state),
+        }
+    }
+}
+
+impl DrawingBackend for BoxedBackend<'_> {
+    type ErrorType = Infallible;
+
+    #[inline]
+    fn get_size(&self) -> (u32, u32) {
+        match self {
+            Self::Paintable(backend) => backend.get_size(),
+            Self::Snapshot(backend) => backend.get_size(),
+        }
+    }
+
+    #[inline]
+    fn ensure_prepared(&mut self) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        match self {
+            Self::Paintable(backend) => backend.ensure_prepared(),
+            Self::Snapshot(backend) => backend.ensure_prepared(),
+        }
+    }
+
+    #[inline]
+    fn present(&mut self) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        match self {
+            Self::Paintable(backend) => backend.present(),
+            Self::Snapshot(backend) => backend.present(),
+        }
+    }
+
+    #[inline]
+    fn draw_pixel(
+        &mut self,
+        point: BackendCoord,
+        color: BackendColor,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        match self {
+            Self::Paintable(backend) => backend.draw_pixel(point, color),
+            Self::Snapshot(backend) => backend.draw_pixel(point, color),
+        }
+    }
+
+    #[inline]
+    fn draw_line<S: BackendStyle>(
+        &mut self,
+        from: BackendCoord,
+        to: BackendCoord,
+        style: &S,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        match self {
+            Self::Paintable(backend) => backend.draw_line(from, to, style),
+            Self::Snapshot(backend) => backend.draw_line(from, to, style),
+        }
+    }
+
+    #[inline]
+    fn draw_rect<S: BackendStyle>(
+        &mut self,
+        upper_left: BackendCoord,
+        bottom_right: BackendCoord,
+        style: &S,
+        fill: bool,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        match self {
+            Self::Paintable(backend) => backend.draw_rect(upper_left, bottom_right, style, fill),
+            Self::Snapshot(backend) => backend.draw_rect(upper_left, bottom_right, style, fill),
+        }
+    }
+
+    #[inline]
+    fn draw_path<S: BackendStyle, I: IntoIterator<Item = BackendCoord>>(
+        &mut self,
+        raw_path: I,
+        style: &S,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        match self {
+            Self::Paintable(backend) => backend.draw_path(raw_path, style),
+            Self::Snapshot(backend) => backend.draw_path(raw_path, style),
+        }
+    }
+
+    #[inline]
+    fn fill_polygon<S: BackendStyle, I: IntoIterator<Item = BackendCoord>>(
+        &mut self,
+        vert: I,
+        style: &S,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        match self {
+            Self::Paintable(backend) => backend.fill_polygon(vert, style),
+            Self::Snapshot(backend) => backend.fill_polygon(vert, style),
+        }
+    }
+
+    #[inline]
+    fn draw_circle<S: BackendStyle>(
+        &mut self,
+        center: BackendCoord,
+        radius: u32,
+        style: &S,
+        fill: bool,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        match self {
+            Self::Paintable(backend) => backend.draw_circle(center, radius, style, fill),
+            Self::Snapshot(backend) => backend.draw_circle(center, radius, style, fill),
+        }
+    }
+
+    #[inline]
+    fn estimate_text_size<TStyle: BackendTextStyle>(
+        &self,
+        text: &str,
+        style: &TStyle,
+    ) -> Result<(u32, u32), DrawingErrorKind<Self::ErrorType>> {
+        match self {
+            Self::Paintable(backend) => backend.estimate_text_size(text, style),
+            Self::Snapshot(backend) => backend.estimate_text_size(text, style),
+        }
+    }
+
+    #[inline]
+    fn draw_text<TStyle: BackendTextStyle>(
+        &mut self,
+        text: &str,
+        style: &TStyle,
+        pos: BackendCoord,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        match self {
+            Self::Paintable(backend) => backend.draw_text(text, style, pos),
+            Self::Snapshot(backend) => backend.draw_text(text, style, pos),
+        }
+    }
+}
+
+impl GtkBackend for BoxedBackend<'_> {
+    fn gtk_snapshot(&mut self) -> &gtk::Snapshot {
+        match self {
+            Self::Paintable(backend) => backend.gtk_snapshot(),
+            Self::Snapshot(backend) => backend.gtk_snapshot(),
+        }
+    }
+
+    fn stroke_width<S: BackendStyle>(&self, style: &S) -> f32 {
+        match self {
+            Self::Paintable(backend) => backend.stroke_width(style),
+            Self::Snapshot(backend) => backend.stroke_width(style),
+        }
+    }
+
+    fn draw_shape<F: BackendStyle, S: BackendStyle>(
+        &mut self,
+        path: &gsk::Path,
+        fill_style: &F,
+        stroke_style: &S,
+    ) -> Result<(), DrawingErrorKind<Infallible>> {
+        match self {
+            Self::Paintable(backend) => backend.draw_shape(path, fill_style, stroke_style),
+            Self::Snapshot(backend) => backend.draw_shape(path, fill_style, stroke_style),
+        }
+    }
+
+    fn stroke_path<S: BackendStyle>(
+        &mut self,
+        path: &gsk::Path,
+        style: &S,
+    ) -> Result<(), DrawingErrorKind<Infallible>> {
+        match self {
+            Self::Paintable(backend) => backend.stroke_path(path, style),
+            Self::Snapshot(backend) => backend.stroke_path(path, style),
+        }
+    }
+
+    fn fill_path<S: BackendStyle>(
+        &mut self,
+        path: &gsk::Path,
+        style: &S,
+    ) -> Result<(), DrawingErrorKind<Infallible>> {
+        match self {
+            Self::Paintable(backend) => backend.fill_path(path, style),
+            Self::Snapshot(backend) => backend.fill_path(path, style),
+        }
+    }
+
+    fn set_text_attributes(&mut self, text_attributes: TextAttributes) {
+        match self {
+            Self::Paintable(backend) => backend.set_text_attributes(text_attributes),
+            Self::Snapshot(backend) => backend.set_text_attributes(text_attributes),
+        }
+    }
+
+    fn text_attributes(&self) -> TextAttributes {
+        match self {
+            Self::Paintable(backend) => backend.text_attributes(),
+            Self::Snapshot(backend) => backend.text_attributes(),
+        }
+    }
+
+    fn set_text_extents(&mut self, text_extents: TextExtents) {
+        match self {
+            Self::Paintable(backend) => backend.set_text_extents(text_extents),
+            Self::Snapshot(backend) => backend.set_text_extents(text_extents),
+        }
+    }
+
+    fn text_extents(&self) -> TextExtents {
+        match self {
+            Self::Paintable(backend) => backend.text_extents(),
+            Self::Snapshot(backend) => backend.text_extents(),
+        }
+    }
+
+    fn with_clip<R>(
+        &mut self,
+        clip: &Rect,
+        transform: Option<&gsk::Transform>,
+        f: impl FnOnce(&mut Self) -> R,
+    ) -> R {
+        let (snapshot, scope_state) = self.enter_scope();
+        snapshot.push_clip(clip);
+        snapshot.save();
+        snapshot.transform(transform);
+        let ret = f(self);
+        self.leave_scope(scope_state);
+        snapshot.restore();
+        snapshot.pop();
+
+        ret
+    }
+}
@@ -0,0 +1,207 @@
+//! Helpers for drawing many repeated shapes, such as error bars,
+//! candlesticks, and stacked areas, with few nodes.
+//!
+//! Drawing these with plotters elements creates a node for every line and
+//! rect, so OHLC charts with thousands of candles become slow to render.
+//! These helpers batch the shapes of each style into a single path instead.
+//!
+//! Coordinates are in pixels of the backend, such as the ones returned by
+//! the coordinate transform of a plotters chart. The paths are drawn with
+//! [`GtkBackend::stroke_path`] and [`GtkBackend::fill_path`], so they are
+//! styled, tiled, and culled like the other primitives of the backend.
+
+use std::convert::Infallible;
+
+use gtk::{graphene::Rect, gsk};
+use plotters_backend::{BackendCoord, BackendStyle, DrawingErrorKind};
+
+use crate::GtkBackend;
+
+/// A vertical error bar, with caps at both ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ErrorBar {
+    /// The x coordinate of the bar.
+    pub x: i32,
+    /// The y coordinate of the upper end of the bar.
+    pub y_high: i32,
+    /// The y coordinate of the lower end of the bar.
+    pub y_low: i32,
+}
+
+/// A candlestick of an OHLC chart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Candle {
+    /// The x coordinate of the center of the candle.
+    pub x: i32,
+    /// The y coordinate of the opening value.
+    pub open: i32,
+    /// The y coordinate of the highest value.
+    pub high: i32,
+    /// The y coordinate of the lowest value.
+    pub low: i32,
+    /// The y coordinate of the closing value.
+    pub close: i32,
+}
+
+impl Candle {
+    /// Returns whether the closing value is above the opening value.
+    ///
+    /// As y coordinates grow downwards, this is when `close` is less than
+    /// `open`.
+    pub fn is_rising(&self) -> bool {
+        self.close < self.open
+    }
+}
+
+/// Draws the error bars as a single stroke, with caps of `cap_width` at both
+/// ends of each bar, or without caps if `cap_width` is zero.
+pub fn draw_error_bars<B: GtkBackend, S: BackendStyle>(
+    backend: &mut B,
+    error_bars: impl IntoIterator<Item = ErrorBar>,
+    cap_width: u32,
+    style: &S,
+) -> Result<(), DrawingErrorKind<Infallible>> {
+    let path_builder = gsk::PathBuilder::new();
+    let half_cap = cap_width as f32 / 2.0;
+
+    let mut is_empty = true;
+    for ErrorBar { x, y_high, y_low } in error_bars {
+        let (x, y_high, y_low) = (x as f32, y_high as f32, y_low as f32);
+
+        path_builder.move_to(x, y_high);
+        path_builder.line_to(x, y_low);
+
+        if cap_width > 0 {
+            for y in [y_high, y_low] {
+                path_builder.move_to(x - half_cap, y);
+                path_builder.line_to(x + half_cap, y);
+            }
+        }
+
+        is_empty = false;
+    }
+
+    if !is_empty {
+        backend.stroke_path(&path_builder.to_path(), style)?;
+    }
+
+    Ok(())
+}
+
+/// Draws the candles with bodies of `body_width`, using `rising` for candles
+/// that close above their opening value and `falling` for the others.
+///
+/// The bodies of each style are filled as one path, and the wicks stroked as
+/// another, so this draws at most four nodes regardless of the number of
+/// candles. The bodies are not stroked, so they are exactly `body_width`
+/// wide, and the wicks stop at the bodies, so they do not show through
+/// translucent ones.
+pub fn draw_candles<B: GtkBackend, R: BackendStyle, F: BackendStyle>(
+    backend: &mut B,
+    candles: impl IntoIterator<Item = Candle>,
+    body_width: u32,
+    rising: &R,
+    falling: &F,
+) -> Result<(), DrawingErrorKind<Infallible>> {
+    let rising_paths = (gsk::PathBuilder::new(), gsk::PathBuilder::new());
+    let falling_paths = (gsk::PathBuilder::new(), gsk::PathBuilder::new());
+    let half_body = body_width as f32 / 2.0;
+
+    let (mut has_rising, mut has_falling) = (false, false);
+    for candle in candles {
+        let (body_path, wick_path) = if candle.is_rising() {
+            has_rising = true;
+            &rising_paths
+        } else {
+            has_falling = true;
+            &falling_paths
+        };
+
+        // Keep flat candles visible as a line of at least one pixel.
+        let x = candle.x as f32;
+        let top = candle.open.min(candle.close) as f32;
+        let height = (candle.open.abs_diff(candle.close) as f32).max(1.0);
+        body_path.add_rect(&Rect::new(x - half_body, top, body_width as f32, height));
+
+        let (high, low) = (candle.high as f32, candle.low as f32);
+        for (from, to) in [(high.min(top), top), (top + height, low.max(top + height))] {
+            if from < to {
+                wick_path.move_to(x, from);
+                wick_path.line_to(x, to);
+            }
+        }
+    }
+
+    if has_rising {
+        backend.fill_path(&rising_paths.0.to_path(), rising)?;
+        backend.stroke_path(&rising_paths.1.to_path(), rising)?;
+    }
+
+    if has_falling {
+        backend.fill_path(&falling_paths.0.to_path(), falling)?;
+        backend.stroke_path(&falling_paths.1.to_path(), falling)?;
+    }
+
+    Ok(())
+}
+
+/// Fills the bands of a stacked area chart, each as a single path.
+///
+/// `curves` are the cumulative tops of the series, in order from the bottom,
+/// such as the points of the running sums of their values. The first band is
+/// filled between `baseline` and the first curve, and each other band
+/// between the previous curve and its own, with the style at its index in
+/// `styles`.
+///
+/// Filling adjacent bands with separate polygons leaves hairline seams
+/// where their antialiased edges meet. If all styles are opaque, each band
+/// is filled down to the baseline instead, from the top band downwards, so
+/// that every band covers the edge of the band above it. Otherwise, the
+/// bands share the exact same vertices along their edges, so that at least
+/// they do not overlap.
+pub fn fill_stacked_areas<B: GtkBackend, S: BackendStyle>(
+    backend: &mut B,
+    baseline: &[BackendCoord],
+    curves: &[&[BackendCoord]],
+    styles: &[S],
+) -> Result<(), DrawingErrorKind<Infallible>> {
+    let n_bands = curves.len().min(styles.len());
+    let is_opaque = styles[..n_bands]
+        .iter()
+        .all(|style| style.color().alpha >= 1.0);
+
+    if is_opaque {
+        for (curve, style) in curves[..n_bands].iter().zip(styles).rev() {
+            fill_band(backend, baseline, curve, style)?;
+        }
+    } else {
+        let lower_curves = std::iter::once(baseline).chain(curves.iter().copied());
+        for ((lower, upper), style) in lower_curves.zip(curves).zip(styles) {
+            fill_band(backend, lower, upper, style)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Fills the band between `lower` and `upper`.
+fn fill_band<B: GtkBackend, S: BackendStyle>(
+    backend: &mut B,
+    lower: &[BackendCoord],
+    upper: &[BackendCoord],
+    style: &S,
+) -> Result<(), DrawingErrorKind<Infallible>> {
+    let mut points = upper.iter().chain(lower.iter().rev());
+    let Some(&(x, y)) = points.next() else {
+        return Ok(());
+    };
+
+    let path_builder = gsk::PathBuilder::new();
+    path_builder.move_to(x as f32, y as f32);
+    for &(x, y) in points {
+        path_builder.line_to(x as f32, y as f32);
+    }
+    path_builder.close();
+
+    backend.fill_path(&path_builder.to_path(), style)
+}
@@ -0,0 +1,306 @@
+//! Colormaps for coloring data by value.
+//!
+//! The colormaps are sampled from the ones of
+//! [matplotlib](https://matplotlib.org/stable/users/explain/colors/colormaps.html)
+//! and interpolated linearly, which is indistinguishable at plot sizes, so
+//! heatmaps and colorbars need no extra dependency. A colorbar can be shown
+//! above a plot with [`Annotation::Colorbar`](crate::Annotation::Colorbar).
+
+use std::ops::Range;
+
+use gtk::{gdk, graphene::Rect, gsk, prelude::*};
+use plotters::style::{
+    text_anchor::{HPos, Pos, VPos},
+    Color, RGBColor, TextStyle,
+};
+use plotters_backend::{BackendCoord, DrawingBackend};
+
+use crate::{common::BackendColorExt, GtkBackend};
+
+/// The length of the ticks of a colorbar, in pixels.
+const COLORBAR_TICK_LENGTH: i32 = 4;
+
+/// The space between the ticks of a colorbar and their labels, in pixels.
+const COLORBAR_LABEL_SPACING: i32 = 2;
+
+const VIRIDIS: &[RGBColor] = &[
+    RGBColor(0x44, 0x01, 0x54),
+    RGBColor(0x47, 0x2c, 0x7a),
+    RGBColor(0x3b, 0x52, 0x8b),
+    RGBColor(0x2c, 0x72, 0x8e),
+    RGBColor(0x21, 0x91, 0x8c),
+    RGBColor(0x28, 0xae, 0x80),
+    RGBColor(0x5e, 0xc9, 0x62),
+    RGBColor(0xad, 0xdc, 0x30),
+    RGBColor(0xfd, 0xe7, 0x25),
+];
+
+const INFERNO: &[RGBColor] = &[
+    RGBColor(0x00, 0x00, 0x04),
+    RGBColor(0x1f, 0x0c, 0x48),
+    RGBColor(0x55, 0x0f, 0x6d),
+    RGBColor(0x88, 0x22, 0x6a),
+    RGBColor(0xba, 0x36, 0x55),
+    RGBColor(0xe3, 0x59, 0x33),
+    RGBColor(0xf9, 0x8e, 0x09),
+    RGBColor(0xf9, 0xcb, 0x35),
+    RGBColor(0xfc, 0xff, 0xa4),
+];
+
+const MAGMA: &[RGBColor] = &[
+    RGBColor(0x00, 0x00, 0x04),
+    RGBColor(0x1c, 0x10, 0x44),
+    RGBColor(0x4f, 0x12, 0x7b),
+    RGBColor(0x81, 0x25, 0x81),
+    RGBColor(0xb5, 0x36, 0x7a),
+    RGBColor(0xe5, 0x50, 0x64),
+    RGBColor(0xfb, 0x87, 0x61),
+    RGBColor(0xfe, 0xc2, 0x87),
+    RGBColor(0xfc, 0xfd, 0xbf),
+];
+
+const PLASMA: &[RGBColor] = &[
+    RGBColor(0x0d, 0x08, 0x87),
+    RGBColor(0x4c, 0x02, 0xa1),
+    RGBColor(0x7e, 0x03, 0xa8),
+    RGBColor(0xa9, 0x23, 0x95),
+    RGBColor(0xcc, 0x47, 0x78),
+    RGBColor(0xe5, 0x6b, 0x5d),
+    RGBColor(0xf8, 0x94, 0x41),
+    RGBColor(0xfd, 0xc3, 0x28),
+    RGBColor(0xf0, 0xf9, 0x21),
+];
+
+const CIVIDIS: &[RGBColor] = &[
+    RGBColor(0x00, 0x22, 0x4e),
+    RGBColor(0x12, 0x35, 0x70),
+    RGBColor(0x3b, 0x49, 0x6c),
+    RGBColor(0x57, 0x5d, 0x6d),
+    RGBColor(0x70, 0x71, 0x73),
+    RGBColor(0x8a, 0x87, 0x79),
+    RGBColor(0xa6, 0x9d, 0x75),
+    RGBColor(0xc4, 0xb5, 0x6c),
+    RGBColor(0xfe, 0xe8, 0x38),
+];
+
+const TURBO: &[RGBColor] = &[
+    RGBColor(0x30, 0x12, 0x3b),
+    RGBColor(0x46, 0x62, 0xd7),
+    RGBColor(0x36, 0xaa, 0xf9),
+    RGBColor(0x1a, 0xe4, 0xb6),
+    RGBColor(0x72, 0xfe, 0x5e),
+    RGBColor(0xc8, 0xef, 0x34),
+    RGBColor(0xfa, 0xba, 0x39),
+    RGBColor(0xf6, 0x6b, 0x19),
+    RGBColor(0xca, 0x2a, 0x04),
+    RGBColor(0x7a, 0x04, 0x03),
+];
+
+const COOLWARM: &[RGBColor] = &[
+    RGBColor(0x3b, 0x4c, 0xc0),
+    RGBColor(0x62, 0x82, 0xea),
+    RGBColor(0x8d, 0xb0, 0xfe),
+    RGBColor(0xb8, 0xd0, 0xf9),
+    RGBColor(0xdd, 0xdc, 0xdc),
+    RGBColor(0xf5, 0xc4, 0xac),
+    RGBColor(0xf4, 0x9a, 0x7b),
+    RGBColor(0xde, 0x60, 0x4d),
+    RGBColor(0xb4, 0x04, 0x26),
+];
+
+/// A map from values in `0.0..=1.0` to colors.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Colormap {
+    /// Perceptually uniform, from dark purple to yellow.
+    #[default]
+    Viridis,
+    /// Perceptually uniform, from black through red to pale yellow.
+    Inferno,
+    /// Perceptually uniform, from black through pink to pale yellow.
+    Magma,
+    /// Perceptually uniform, from dark blue through pink to yellow.
+    Plasma,
+    /// Perceptually uniform and readable with color vision deficiency, from
+    /// dark blue to yellow.
+    Cividis,
+    /// A rainbow from dark blue to dark red, for values where detail
+    /// matters more than perceptual uniformity, such as depth maps.
+    Turbo,
+    /// Diverging, from blue through light gray to red, for values around a
+    /// meaningful midpoint, such as deviations from zero.
+    Coolwarm,
+}
+
+impl Colormap {
+    /// Returns the color at `t`, clamped to `0.0..=1.0`.
+    ///
+    /// `NaN` maps to the color at `0.0`.
+    pub fn map(&self, t: f64) -> RGBColor {
+        let stops = self.stops();
+
+        let t = if t.is_nan() { 0.0 } else { t.clamp(0.0, 1.0) };
+        let pos = t * (stops.len() - 1) as f64;
+        let index = (pos.floor() as usize).min(stops.len() - 2);
+        let frac = pos - index as f64;
+
+        let RGBColor(r0, g0, b0) = stops[index];
+        let RGBColor(r1, g1, b1) = stops[index + 1];
+        let lerp = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * frac).round() as u8;
+        RGBColor(lerp(r0, r1), lerp(g0, g1), lerp(b0, b1))
+    }
+
+    /// Returns the color at `t` as a [`gdk::RGBA`], clamped to `0.0..=1.0`.
+    pub fn map_rgba(&self, t: f64) -> gdk::RGBA {
+        self.map(t).to_backend_color().to_rgba()
+    }
+
+    /// Returns the color of `value` within `range`.
+    pub fn map_range(&self, value: f64, range: &Range<f64>) -> RGBColor {
+        self.map(normalize(value, range))
+    }
+
+    fn stops(&self) -> &'static [RGBColor] {
+        match self {
+            Self::Viridis => VIRIDIS,
+            Self::Inferno => INFERNO,
+            Self::Magma => MAGMA,
+            Self::Plasma => PLASMA,
+            Self::Cividis => CIVIDIS,
+            Self::Turbo => TURBO,
+            Self::Coolwarm => COOLWARM,
+        }
+    }
+
+    /// Returns the name of the colormap, as used when serializing it.
+    pub(crate) fn nick(&self) -> &'static str {
+        match self {
+            Self::Viridis => "viridis",
+            Self::Inferno => "inferno",
+            Self::Magma => "magma",
+            Self::Plasma => "plasma",
+            Self::Cividis => "cividis",
+            Self::Turbo => "turbo",
+            Self::Coolwarm => "coolwarm",
+        }
+    }
+
+    /// Returns the colormap named `nick`, as returned by
+    /// [`Colormap::nick`].
+    pub(crate) fn from_nick(nick: &str) -> Option<Self> {
+        [
+            Self::Viridis,
+            Self::Inferno,
+            Self::Magma,
+            Self::Plasma,
+            Self::Cividis,
+            Self::Turbo,
+            Self::Coolwarm,
+        ]
+        .into_iter()
+        .find(|colormap| colormap.nick() == nick)
+    }
+
+    /// Returns the stops of a gradient from `0.0` to `1.0` of the colormap.
+    pub(crate) fn color_stops(&self) -> Vec<gsk::ColorStop> {
+        let stops = self.stops();
+        stops
+            .iter()
+            .enumerate()
+            .map(|(index, color)| {
+                gsk::ColorStop::new(
+                    index as f32 / (stops.len() - 1) as f32,
+                    color.to_backend_color().to_rgba(),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Draws a colorbar of `colormap` in the rect from `upper_left` to
+/// `bottom_right`, with ticks at `ticks` within `range`, labeled with their
+/// value formatted by `label_formatter`.
+///
+/// The formatters in [`labels`](crate::labels), such as
+/// [`number_formatter`](crate::labels::number_formatter), match the labels of
+/// the axes of the chart.
+///
+/// The gradient goes from bottom to top, with the labels on the right, if
+/// the rect is taller than wide, or from left to right, with the labels
+/// below, otherwise. The ticks and the outline of the bar are drawn with the
+/// color of `text_style`.
+pub fn draw_colorbar<B: GtkBackend>(
+    backend: &mut B,
+    upper_left: BackendCoord,
+    bottom_right: BackendCoord,
+    colormap: Colormap,
+    range: Range<f64>,
+    ticks: &[f64],
+    label_formatter: impl Fn(&f64) -> String,
+    text_style: &TextStyle<'_>,
+) {
+    let (x0, y0) = (
+        upper_left.0.min(bottom_right.0),
+        upper_left.1.min(bottom_right.1),
+    );
+    let (x1, y1) = (
+        upper_left.0.max(bottom_right.0),
+        upper_left.1.max(bottom_right.1),
+    );
+    let is_vertical = y1 - y0 > x1 - x0;
+
+    let bounds = Rect::new(x0 as f32, y0 as f32, (x1 - x0) as f32, (y1 - y0) as f32);
+    let (start, end) = if is_vertical {
+        (bounds.bottom_left(), bounds.top_left())
+    } else {
+        (bounds.top_left(), bounds.top_right())
+    };
+    backend
+        .gtk_snapshot()
+        .append_linear_gradient(&bounds, &start, &end, &colormap.color_stops());
+
+    let color = text_style.color;
+    let _ = backend.draw_rect((x0, y0), (x1, y1), &color, false);
+
+    let label_style = if is_vertical {
+        text_style.pos(Pos::new(HPos::Left, VPos::Center))
+    } else {
+        text_style.pos(Pos::new(HPos::Center, VPos::Top))
+    };
+
+    for &tick in ticks {
+        let t = normalize(tick, &range);
+        if !(0.0..=1.0).contains(&t) {
+            continue;
+        }
+
+        let label = label_formatter(&tick);
+        if is_vertical {
+            let y = y1 - (t * (y1 - y0) as f64).round() as i32;
+            let _ = backend.draw_line((x1, y), (x1 + COLORBAR_TICK_LENGTH, y), &color);
+            let _ = backend.draw_text(
+                &label,
+                &label_style,
+                (x1 + COLORBAR_TICK_LENGTH + COLORBAR_LABEL_SPACING, y),
+            );
+        } else {
+            let x = x0 + (t * (x1 - x0) as f64).round() as i32;
+            let _ = backend.draw_line((x, y1), (x, y1 + COLORBAR_TICK_LENGTH), &color);
+            let _ = backend.draw_text(
+                &label,
+                &label_style,
+                (x, y1 + COLORBAR_TICK_LENGTH + COLORBAR_LABEL_SPACING),
+            );
+        }
+    }
+}
+
+/// Returns the position of `value` within `range`, where `range.start` is
+/// `0.0` and `range.end` is `1.0`.
+pub(crate) fn normalize(value: f64, range: &Range<f64>) -> f64 {
+    let span = range.end - range.start;
+    if span == 0.0 {
+        0.0
+    } else {
+        (value - range.start) / span
+    }
+}
@@ -1,7 +1,7 @@
-use std::convert::Infallible;
+use std::{convert::Infallible, ops::Range, rc::Rc};
 
 use gtk::{
-    gdk,
+    cairo, gdk,
     graphene::{Point, Rect},
     gsk, pango,
     prelude::*,
@@ -12,106 +12,797 @@ use plotters_backend::{
     FontTransform,
 };
 
-const FILL_RULE: gsk::FillRule = gsk::FillRule::Winding;
+use crate::{
+    colormaps::Colormap, ClassStyle, InvalidInputError, InvalidInputPolicy, Quality, RendererCaps,
+    TextAttributes, TextCoverage, TextExtents, TextHalo,
+};
+
+/// The largest absolute coordinate, radius, or stroke width that is drawn.
+///
+/// `f32` represents every integer up to this exactly, and larger values are
+/// most likely garbage from user data, which graphene and GSK may assert on
+/// or render incorrectly.
+const MAX_COORD: i64 = 1 << 24;
+
+/// Drawing options shared by both backends.
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub stroke_width_scale: f32,
+    pub hairline_width: Option<f32>,
+    pub fill_rule: gsk::FillRule,
+    /// The point subtracted from every coordinate before converting it to
+    /// `f32`, used to keep coordinates small when tiling.
+    pub origin: BackendCoord,
+    pub text_halo: Option<TextHalo>,
+    pub text_extents: TextExtents,
+    pub vertical_text: bool,
+    pub text_attributes: TextAttributes,
+    /// Families that are tried, in order, for characters that the family
+    /// of the text style does not have.
+    pub fallback_families: Rc<[String]>,
+    pub pixel_snap: bool,
+    pub invalid_input_policy: InvalidInputPolicy,
+    /// The style of the class the primitives are drawn within.
+    pub class_style: ClassStyle,
+    /// The corners of the area outside of which primitives are skipped, or
+    /// `None` to draw all primitives.
+    pub cull_bounds: Option<(BackendCoord, BackendCoord)>,
+    pub quality: Quality,
+    pub renderer_caps: RendererCaps,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            stroke_width_scale: 1.0,
+            hairline_width: None,
+            fill_rule: gsk::FillRule::Winding,
+            origin: (0, 0),
+            text_halo: None,
+            text_extents: TextExtents::Logical,
+            vertical_text: false,
+            text_attributes: TextAttributes::default(),
+            fallback_families: Vec::new().into(),
+            pixel_snap: false,
+            invalid_input_policy: InvalidInputPolicy::Skip,
+            class_style: ClassStyle::default(),
+            cull_bounds: None,
+            quality: Quality::default(),
+            renderer_caps: RendererCaps::default(),
+        }
+    }
+}
+
+impl Options {
+    /// Returns the effective stroke width of the style, treating a stroke
+    /// width of zero as a hairline if a hairline width is set.
+    pub fn stroke_width<S: BackendStyle>(&self, style: &S) -> f32 {
+        if let Some(stroke_width) = self.class_style.stroke_width {
+            return stroke_width;
+        }
+
+        match style.stroke_width() {
+            0 => self.hairline_width.unwrap_or(0.0),
+            width if self.pixel_snap => (width as f32 * self.stroke_width_scale).round().max(1.0),
+            width => width as f32 * self.stroke_width_scale,
+        }
+    }
+
+    /// Returns the color to draw `color` with, applying the class style.
+    pub fn color(&self, color: BackendColor) -> BackendColor {
+        self.class_style.color(color)
+    }
+
+    /// Returns `Ok(true)` if the input is valid, or otherwise applies the
+    /// invalid input policy, returning `Ok(false)` if the primitive must be
+    /// skipped.
+    fn validate(
+        &self,
+        is_valid: bool,
+        reason: &'static str,
+    ) -> Result<bool, DrawingErrorKind<Infallible>> {
+        if is_valid {
+            return Ok(true);
+        }
+
+        match self.invalid_input_policy {
+            InvalidInputPolicy::Skip => Ok(false),
+            // This is the only variant that can carry an error other than
+            // the backend error type, which is `Infallible`.
+            InvalidInputPolicy::Error => {
+                Err(DrawingErrorKind::FontError(Box::new(InvalidInputError {
+                    reason,
+                })))
+            }
+        }
+    }
+
+    /// Validates the points of a primitive.
+    fn validate_points(
+        &self,
+        points: impl IntoIterator<Item = BackendCoord>,
+    ) -> Result<bool, DrawingErrorKind<Infallible>> {
+        let is_valid = points.into_iter().all(is_valid_coord);
+        self.validate(is_valid, "coordinate out of range")
+    }
+
+    /// Validates the stroke width of a style.
+    fn validate_stroke_width(
+        &self,
+        stroke_width: f32,
+    ) -> Result<bool, DrawingErrorKind<Infallible>> {
+        let is_valid =
+            stroke_width.is_finite() && stroke_width >= 0.0 && stroke_width <= MAX_COORD as f32;
+        self.validate(is_valid, "invalid stroke width")
+    }
+
+    /// Returns whether the bounds of the points, grown by `pad`, are
+    /// completely outside of the cull bounds.
+    fn is_culled(&self, points: impl IntoIterator<Item = BackendCoord>, pad: i32) -> bool {
+        let Some((min, max)) = self.cull_bounds else {
+            return false;
+        };
+
+        let mut points = points.into_iter();
+        let Some(first) = points.next() else {
+            return false;
+        };
+        let (lo, hi) = points.fold((first, first), |(lo, hi), (x, y)| {
+            ((lo.0.min(x), lo.1.min(y)), (hi.0.max(x), hi.1.max(y)))
+        });
+
+        hi.0.saturating_add(pad) < min.0
+            || hi.1.saturating_add(pad) < min.1
+            || lo.0.saturating_sub(pad) > max.0
+            || lo.1.saturating_sub(pad) > max.1
+    }
+
+    /// Returns the point relative to the origin.
+    fn point(&self, (x, y): BackendCoord) -> (f32, f32) {
+        ((x - self.origin.0) as f32, (y - self.origin.1) as f32)
+    }
+
+    /// Returns the point relative to the origin of a stroke with the given
+    /// width.
+    ///
+    /// With pixel snapping, points of odd-width strokes are moved to the
+    /// center of the pixel, so that the stroke covers whole pixels instead of
+    /// blending into the pixels on both sides.
+    fn stroke_point(&self, point: BackendCoord, stroke_width: f32) -> (f32, f32) {
+        let (x, y) = self.point(point);
+        if self.pixel_snap && stroke_width.round() as i32 % 2 == 1 {
+            (x + 0.5, y + 0.5)
+        } else {
+            (x, y)
+        }
+    }
+}
+
+/// Returns the cull bounds of a backend of `size`, grown by `margin` on
+/// every side.
+pub fn cull_bounds((width, height): (u32, u32), margin: u32) -> (BackendCoord, BackendCoord) {
+    let margin = margin.min(i32::MAX as u32) as i32;
+    (
+        (-margin, -margin),
+        (
+            (width.min(i32::MAX as u32) as i32).saturating_add(margin),
+            (height.min(i32::MAX as u32) as i32).saturating_add(margin),
+        ),
+    )
+}
+
+/// Returns how far a stroke of `stroke_width` may extend past its points,
+/// with a pixel of room for antialiasing.
+fn stroke_pad(stroke_width: f32) -> i32 {
+    stroke_width.ceil() as i32 + 1
+}
+
+fn is_valid_coord((x, y): BackendCoord) -> bool {
+    (x as i64).abs() <= MAX_COORD && (y as i64).abs() <= MAX_COORD
+}
+
+/// State reused across primitives, so that drawing many of them does not
+/// create a new path builder, stroke, and buffer for each.
+#[derive(Debug)]
+pub struct Scratch {
+    path_builder: gsk::PathBuilder,
+    stroke: gsk::Stroke,
+    /// The last converted color, as its alpha and RGB components.
+    color: Option<((f64, (u8, u8, u8)), gdk::RGBA)>,
+    /// Buffer for the points of a primitive that is drawn more than once,
+    /// such as into multiple tiles.
+    pub points: Vec<BackendCoord>,
+    /// Lines that are not yet appended, one batch for every snapshot drawn
+    /// to, all of the same color and stroke width.
+    line_batches: Vec<LineBatch>,
+    /// The number of nodes appended, which is reset by the backend.
+    pub n_nodes: usize,
+    /// The last circle marker, by its radius, stroke width, or `None` if
+    /// filled, and color.
+    marker: Option<((u32, Option<f32>, gdk::RGBA), Option<gsk::RenderNode>)>,
+}
+
+/// Consecutive lines of the same style, which are appended as one stroke
+/// node instead of one for each line.
+#[derive(Debug)]
+struct LineBatch {
+    snapshot: gtk::Snapshot,
+    color: gdk::RGBA,
+    stroke_width: f32,
+    path_builder: gsk::PathBuilder,
+}
+
+impl Default for Scratch {
+    fn default() -> Self {
+        Self {
+            path_builder: gsk::PathBuilder::new(),
+            stroke: gsk::Stroke::new(1.0),
+            color: None,
+            points: Vec::new(),
+            line_batches: Vec::new(),
+            n_nodes: 0,
+            marker: None,
+        }
+    }
+}
+
+impl Scratch {
+    /// Returns the path builder, which is reset every time a path is built
+    /// with [`gsk::PathBuilder::to_path`].
+    fn path_builder(&self) -> &gsk::PathBuilder {
+        &self.path_builder
+    }
+
+    /// Returns the stroke with the given width.
+    ///
+    /// Stroke nodes copy the stroke, so it can be reused once appended.
+    fn stroke(&mut self, width: f32) -> &gsk::Stroke {
+        if self.stroke.line_width() != width {
+            self.stroke.set_line_width(width);
+        }
+        &self.stroke
+    }
+
+    /// Returns the node of a circle of `radius` around the origin, stroked
+    /// with `stroke_width`, or filled if it is `None`, reusing the node of
+    /// the last circle if it was drawn alike.
+    fn circle_marker(
+        &mut self,
+        radius: u32,
+        stroke_width: Option<f32>,
+        color: gdk::RGBA,
+    ) -> Option<gsk::RenderNode> {
+        let key = (radius, stroke_width, color);
+        if let Some((cached_key, node)) = &self.marker {
+            if *cached_key == key {
+                return node.clone();
+            }
+        }
+
+        self.path_builder
+            .add_circle(&Point::new(0.0, 0.0), radius as f32);
+        let path = self.path_builder.to_path();
+        let snapshot = gtk::Snapshot::new();
+        match stroke_width {
+            Some(stroke_width) => snapshot.append_stroke(&path, self.stroke(stroke_width), &color),
+            None => snapshot.append_fill(&path, gsk::FillRule::Winding, &color),
+        }
+        let node = snapshot.to_node();
+
+        self.marker = Some((key, node.clone()));
+        node
+    }
+
+    /// Returns the path builder of the lines of the given style to be
+    /// appended to `snapshot`, appending the lines of other styles first.
+    fn line_batch(
+        &mut self,
+        snapshot: &gtk::Snapshot,
+        color: gdk::RGBA,
+        stroke_width: f32,
+    ) -> &gsk::PathBuilder {
+        if self
+            .line_batches
+            .first()
+            .is_some_and(|batch| batch.color != color || batch.stroke_width != stroke_width)
+        {
+            self.flush();
+        }
+
+        let index = match self
+            .line_batches
+            .iter()
+            .position(|batch| batch.snapshot == *snapshot)
+        {
+            Some(index) => index,
+            None => {
+                self.line_batches.push(LineBatch {
+                    snapshot: snapshot.clone(),
+                    color,
+                    stroke_width,
+                    path_builder: gsk::PathBuilder::new(),
+                });
+                self.line_batches.len() - 1
+            }
+        };
+        &self.line_batches[index].path_builder
+    }
+
+    /// Appends the pending lines to their snapshots.
+    ///
+    /// This must be called before anything else is appended to the
+    /// snapshots, to keep the drawing order.
+    pub fn flush(&mut self) {
+        let mut line_batches = std::mem::take(&mut self.line_batches);
+        for batch in line_batches.drain(..) {
+            let path = batch.path_builder.to_path();
+            let stroke = self.stroke(batch.stroke_width);
+            batch.snapshot.append_stroke(&path, stroke, &batch.color);
+            self.n_nodes += 1;
+        }
+        self.line_batches = line_batches;
+    }
+
+    /// Returns the color as [`gdk::RGBA`], reusing the last conversion, as
+    /// consecutive primitives usually share a color.
+    fn rgba(&mut self, color: BackendColor) -> gdk::RGBA {
+        let key = (color.alpha, color.rgb);
+        match self.color {
+            Some((cached_key, rgba)) if cached_key == key => rgba,
+            _ => {
+                let rgba = color.to_rgba();
+                self.color = Some((key, rgba));
+                rgba
+            }
+        }
+    }
+}
 
 pub fn draw_pixel(
     snapshot: &gtk::Snapshot,
+    options: &Options,
+    scratch: &mut Scratch,
     point: BackendCoord,
     color: BackendColor,
 ) -> Result<(), DrawingErrorKind<Infallible>> {
-    snapshot.append_color(
-        &color.to_rgba(),
-        &Rect::new(point.0 as f32, point.1 as f32, 1.0, 1.0),
-    );
+    if !options.validate_points([point])? || options.is_culled([point], 1) {
+        return Ok(());
+    }
+
+    let (x, y) = options.point(point);
+    let color = scratch.rgba(options.color(color));
+    // Translucent pixels are not batched, as a pixel drawn twice would only
+    // be blended once.
+    if options.quality.batches_pixels() && color.alpha() >= 1.0 {
+        // A unit line through the middle of the pixel covers the same square.
+        let path_builder = scratch.line_batch(snapshot, color, 1.0);
+        path_builder.move_to(x, y + 0.5);
+        path_builder.line_to(x + 1.0, y + 0.5);
+    } else {
+        scratch.flush();
+        snapshot.append_color(&color, &Rect::new(x, y, 1.0, 1.0));
+        scratch.n_nodes += 1;
+    }
     Ok(())
 }
 
 pub fn draw_line<S: BackendStyle>(
     snapshot: &gtk::Snapshot,
+    options: &Options,
+    scratch: &mut Scratch,
     from: BackendCoord,
     to: BackendCoord,
     style: &S,
 ) -> Result<(), DrawingErrorKind<Infallible>> {
-    let path_builder = gsk::PathBuilder::new();
-    path_builder.move_to(from.0 as f32, from.1 as f32);
-    path_builder.line_to(to.0 as f32, to.1 as f32);
-    let path = path_builder.to_path();
+    let stroke_width = options.stroke_width(style);
+    if !options.validate_points([from, to])?
+        || !options.validate_stroke_width(stroke_width)?
+        || options.is_culled([from, to], stroke_pad(stroke_width))
+    {
+        return Ok(());
+    }
+
+    let color = scratch.rgba(options.color(style.color()));
 
-    let stroke = gsk::Stroke::new(style.stroke_width() as f32);
-    snapshot.append_stroke(&path, &stroke, &style.color().to_rgba());
+    // Grids and wicks are drawn as many lines of the same style, which are
+    // cheaper to render as one path. Translucent lines are not batched, as
+    // where lines of one path cross, they are only blended once.
+    let is_batched = color.alpha() >= 1.0;
+    let path_builder = if is_batched {
+        scratch.line_batch(snapshot, color, stroke_width)
+    } else {
+        scratch.flush();
+        scratch.path_builder()
+    };
+    let (x, y) = options.stroke_point(from, stroke_width);
+    path_builder.move_to(x, y);
+    let (x, y) = options.stroke_point(to, stroke_width);
+    path_builder.line_to(x, y);
+
+    if !is_batched {
+        let path = path_builder.to_path();
+        snapshot.append_stroke(&path, scratch.stroke(stroke_width), &color);
+        scratch.n_nodes += 1;
+    }
 
     Ok(())
 }
 
 pub fn draw_rect<S: BackendStyle>(
     snapshot: &gtk::Snapshot,
+    options: &Options,
+    scratch: &mut Scratch,
+    size: (u32, u32),
     upper_left: BackendCoord,
     bottom_right: BackendCoord,
     style: &S,
     fill: bool,
 ) -> Result<(), DrawingErrorKind<Infallible>> {
-    let bounds = Rect::new(
-        upper_left.0 as f32,
-        upper_left.1 as f32,
-        (bottom_right.0 - upper_left.0) as f32,
-        (bottom_right.1 - upper_left.1) as f32,
-    );
+    scratch.flush();
+
+    if !options.validate_points([upper_left, bottom_right])?
+        || !options.validate_stroke_width(options.stroke_width(style))?
+    {
+        return Ok(());
+    }
+
+    let color = scratch.rgba(options.color(style.color()));
     if fill {
-        snapshot.append_color(&style.color().to_rgba(), &bounds);
+        if let Some(bounds) = clamped_rect(options, upper_left, bottom_right, size, 0.0) {
+            snapshot.append_color(&color, &bounds);
+            scratch.n_nodes += 1;
+        }
     } else {
-        snapshot.append_border(
-            &gsk::RoundedRect::from_rect(bounds, 0.0),
-            &[style.stroke_width() as f32; 4],
-            &[style.color().to_rgba(); 4],
-        );
+        let stroke_width = options.stroke_width(style);
+        // Keep the clamped edges outside the visible area, so that clamping
+        // does not draw borders that are not part of the rect.
+        if let Some(bounds) = clamped_rect(options, upper_left, bottom_right, size, stroke_width) {
+            snapshot.append_border(
+                &gsk::RoundedRect::from_rect(bounds, 0.0),
+                &[stroke_width; 4],
+                &[color; 4],
+            );
+            scratch.n_nodes += 1;
+        }
     }
 
     Ok(())
 }
 
+/// Returns the rect spanned by the two corners, given in any order, and
+/// relative to the origin.
+///
+/// If culling is enabled, the rect is clamped to the backend bounds grown by
+/// `margin`. It is not clamped within scopes, where culling is disabled, as
+/// their transforms may move the rest of it into view.
+///
+/// This returns `None` if the rect is empty after clamping.
+fn clamped_rect(
+    options: &Options,
+    a: BackendCoord,
+    b: BackendCoord,
+    (width, height): (u32, u32),
+    margin: f32,
+) -> Option<Rect> {
+    let (mut x0, mut y0) = (a.0.min(b.0) as f32, a.1.min(b.1) as f32);
+    let (mut x1, mut y1) = (a.0.max(b.0) as f32, a.1.max(b.1) as f32);
+
+    if options.cull_bounds.is_some() {
+        x0 = x0.max(-margin);
+        y0 = y0.max(-margin);
+        x1 = x1.min(width as f32 + margin);
+        y1 = y1.min(height as f32 + margin);
+    }
+
+    if x1 <= x0 || y1 <= y0 {
+        return None;
+    }
+
+    let (ox, oy) = (options.origin.0 as f32, options.origin.1 as f32);
+    Some(Rect::new(x0 - ox, y0 - oy, x1 - x0, y1 - y0))
+}
+
 pub fn draw_path<S: BackendStyle, I: IntoIterator<Item = BackendCoord>>(
     snapshot: &gtk::Snapshot,
+    options: &Options,
+    scratch: &mut Scratch,
     raw_path: I,
     style: &S,
 ) -> Result<(), DrawingErrorKind<Infallible>> {
-    let mut raw_path_iter = raw_path.into_iter();
-    if let Some((x, y)) = raw_path_iter.next() {
-        let path_builder = gsk::PathBuilder::new();
+    scratch.flush();
+
+    let mut raw_path_iter = raw_path.into_iter().peekable();
+    if let Some(point) = raw_path_iter.next() {
+        let stroke_width = options.stroke_width(style);
+        if !options.validate_stroke_width(stroke_width)? {
+            return Ok(());
+        }
+
+        let path_builder = scratch.path_builder();
+        let pad = stroke_pad(stroke_width);
 
-        path_builder.move_to(x as f32, y as f32);
+        let mut is_valid = is_valid_coord(point);
+        let mut prev = point;
+        let mut is_pen_down = false;
+        let mut is_empty = true;
+        let tolerance = options.quality.path_tolerance();
 
-        for (x, y) in raw_path_iter {
-            path_builder.line_to(x as f32, y as f32);
+        // Segments outside of the cull bounds are skipped, splitting the
+        // path around them.
+        while let Some(point) = raw_path_iter.next() {
+            is_valid &= is_valid_coord(point);
+
+            // Skip points too close to the previous one, but keep the end.
+            let (dx, dy) = (
+                point.0 as i64 - prev.0 as i64,
+                point.1 as i64 - prev.1 as i64,
+            );
+            if tolerance > 0
+                && dx * dx + dy * dy <= tolerance * tolerance
+                && raw_path_iter.peek().is_some()
+            {
+                continue;
+            }
+
+            if options.is_culled([prev, point], pad) {
+                is_pen_down = false;
+            } else {
+                if !is_pen_down {
+                    let (x, y) = options.stroke_point(prev, stroke_width);
+                    path_builder.move_to(x, y);
+                    is_pen_down = true;
+                }
+                let (x, y) = options.stroke_point(point, stroke_width);
+                path_builder.line_to(x, y);
+                is_empty = false;
+            }
+
+            prev = point;
         }
 
+        // Build the path even if it is invalid, to reset the path builder.
         let path = path_builder.to_path();
+        if !options.validate(is_valid, "coordinate out of range")? || is_empty {
+            return Ok(());
+        }
+
+        let color = scratch.rgba(options.color(style.color()));
+        snapshot.append_stroke(&path, scratch.stroke(stroke_width), &color);
+        scratch.n_nodes += 1;
+    }
+
+    Ok(())
+}
+
+/// Strokes `path`, which is in the coordinates of the backend, such as a
+/// path of many shapes of one style built by the caller.
+pub fn stroke_path<S: BackendStyle>(
+    snapshot: &gtk::Snapshot,
+    options: &Options,
+    scratch: &mut Scratch,
+    path: &gsk::Path,
+    style: &S,
+) -> Result<(), DrawingErrorKind<Infallible>> {
+    scratch.flush();
+
+    let stroke_width = options.stroke_width(style);
+    if !options.validate_stroke_width(stroke_width)? {
+        return Ok(());
+    }
 
-        let stroke = gsk::Stroke::new(style.stroke_width() as f32);
-        snapshot.append_stroke(&path, &stroke, &style.color().to_rgba());
+    match path_bounds(path, stroke_width) {
+        Some((min, max)) if !options.is_culled([min, max], 0) => {}
+        _ => return Ok(()),
     }
 
+    let color = scratch.rgba(options.color(style.color()));
+    let stroke = scratch.stroke(stroke_width);
+    with_origin(snapshot, options, |snapshot| {
+        snapshot.append_stroke(path, stroke, &color);
+    });
+    scratch.n_nodes += 1;
+
     Ok(())
 }
 
+/// Fills `path`, which is in the coordinates of the backend, with the fill
+/// rule of the backend.
+pub fn fill_path<S: BackendStyle>(
+    snapshot: &gtk::Snapshot,
+    options: &Options,
+    scratch: &mut Scratch,
+    path: &gsk::Path,
+    style: &S,
+) -> Result<(), DrawingErrorKind<Infallible>> {
+    scratch.flush();
+
+    match path_bounds(path, 0.0) {
+        Some((min, max)) if !options.is_culled([min, max], 0) => {}
+        _ => return Ok(()),
+    }
+
+    let color = scratch.rgba(options.color(style.color()));
+    with_origin(snapshot, options, |snapshot| {
+        snapshot.append_fill(path, options.fill_rule, &color);
+    });
+    scratch.n_nodes += 1;
+
+    Ok(())
+}
+
+/// Returns the corners of the bounds of `path` in whole pixels, grown by
+/// the pad of `stroke_width`, or `None` if the path is empty.
+pub fn path_bounds(path: &gsk::Path, stroke_width: f32) -> Option<(BackendCoord, BackendCoord)> {
+    let bounds = path.bounds()?;
+    let pad = stroke_pad(stroke_width);
+    Some((
+        (
+            (bounds.x().floor() as i32).saturating_sub(pad),
+            (bounds.y().floor() as i32).saturating_sub(pad),
+        ),
+        (
+            ((bounds.x() + bounds.width()).ceil() as i32).saturating_add(pad),
+            ((bounds.y() + bounds.height()).ceil() as i32).saturating_add(pad),
+        ),
+    ))
+}
+
+/// Calls `f` with `snapshot` moved by the origin, so that paths in the
+/// coordinates of the backend are drawn relative to it.
+fn with_origin(snapshot: &gtk::Snapshot, options: &Options, f: impl FnOnce(&gtk::Snapshot)) {
+    if options.origin == (0, 0) {
+        f(snapshot);
+        return;
+    }
+
+    snapshot.save();
+    snapshot.translate(&Point::new(
+        -options.origin.0 as f32,
+        -options.origin.1 as f32,
+    ));
+    f(snapshot);
+    snapshot.restore();
+}
+
+pub fn draw_mapped_path<I: IntoIterator<Item = (BackendCoord, f64)>>(
+    snapshot: &gtk::Snapshot,
+    options: &Options,
+    scratch: &mut Scratch,
+    raw_path: I,
+    value_range: &Range<f64>,
+    colormap: Colormap,
+    stroke_width: u32,
+) -> Result<(), DrawingErrorKind<Infallible>> {
+    scratch.flush();
+
+    let stroke_width = options.stroke_width(&StrokeWidth(stroke_width));
+    if !options.validate_stroke_width(stroke_width)? {
+        return Ok(());
+    }
+
+    let cull_pad = stroke_pad(stroke_width);
+
+    // Round caps cover the seams between the segments.
+    let stroke = gsk::Stroke::new(stroke_width);
+    stroke.set_line_cap(gsk::LineCap::Round);
+
+    let mut prev = None;
+    for (point, value) in raw_path {
+        // Skip only the segments to invalid points, as the segments before
+        // them are already drawn.
+        if !options.validate_points([point])? {
+            prev = None;
+            continue;
+        }
+
+        let (x, y) = options.stroke_point(point, stroke_width);
+        let color = colormap.map_rgba(crate::colormaps::normalize(value, value_range));
+
+        if let Some((prev_point, (x0, y0), value0, color0)) =
+            prev.replace((point, (x, y), value, color))
+        {
+            if (x0, y0) == (x, y) || options.is_culled([prev_point, point], cull_pad) {
+                continue;
+            }
+
+            let path_builder = scratch.path_builder();
+            path_builder.move_to(x0, y0);
+            path_builder.line_to(x, y);
+            let path = path_builder.to_path();
+
+            if !options.renderer_caps.gradient_strokes {
+                let middle = crate::colormaps::normalize((value0 + value) / 2.0, value_range);
+                snapshot.append_stroke(&path, &stroke, &colormap.map_rgba(middle));
+                scratch.n_nodes += 1;
+                continue;
+            }
+
+            let pad = stroke_width;
+            let bounds = Rect::new(
+                x0.min(x) - pad,
+                y0.min(y) - pad,
+                (x - x0).abs() + pad * 2.0,
+                (y - y0).abs() + pad * 2.0,
+            );
+
+            snapshot.push_stroke(&path, &stroke);
+            snapshot.append_linear_gradient(
+                &bounds,
+                &Point::new(x0, y0),
+                &Point::new(x, y),
+                &[
+                    gsk::ColorStop::new(0.0, color0),
+                    gsk::ColorStop::new(1.0, color),
+                ],
+            );
+            snapshot.pop();
+            scratch.n_nodes += 1;
+        }
+    }
+
+    Ok(())
+}
+
+/// A style with only a stroke width, to compute the stroke width of
+/// primitives that are not drawn with a [`BackendStyle`].
+struct StrokeWidth(u32);
+
+impl BackendStyle for StrokeWidth {
+    fn color(&self) -> BackendColor {
+        BackendColor {
+            alpha: 1.0,
+            rgb: (0, 0, 0),
+        }
+    }
+
+    fn stroke_width(&self) -> u32 {
+        self.0
+    }
+}
+
 pub fn fill_polygon<S: BackendStyle, I: IntoIterator<Item = BackendCoord>>(
     snapshot: &gtk::Snapshot,
+    options: &Options,
+    scratch: &mut Scratch,
     vert: I,
     style: &S,
 ) -> Result<(), DrawingErrorKind<Infallible>> {
+    scratch.flush();
+
     let mut vert_iter = vert.into_iter();
-    if let Some((x, y)) = vert_iter.next() {
-        let path_builder = gsk::PathBuilder::new();
+    if let Some(point) = vert_iter.next() {
+        let path_builder = scratch.path_builder();
 
-        path_builder.move_to(x as f32, y as f32);
+        let mut is_valid = is_valid_coord(point);
+        let mut bounds = (point, point);
+        let (x, y) = options.point(point);
+        path_builder.move_to(x, y);
 
-        for (x, y) in vert_iter {
-            path_builder.line_to(x as f32, y as f32);
+        for point in vert_iter {
+            is_valid &= is_valid_coord(point);
+            bounds = (
+                (bounds.0 .0.min(point.0), bounds.0 .1.min(point.1)),
+                (bounds.1 .0.max(point.0), bounds.1 .1.max(point.1)),
+            );
+            let (x, y) = options.point(point);
+            path_builder.line_to(x, y);
         }
 
         path_builder.close();
+        // Build the path even if it is invalid, to reset the path builder.
         let path = path_builder.to_path();
+        if !options.validate(is_valid, "coordinate out of range")?
+            || options.is_culled([bounds.0, bounds.1], 1)
+        {
+            return Ok(());
+        }
 
-        snapshot.append_fill(&path, FILL_RULE, &style.color().to_rgba());
+        snapshot.append_fill(
+            &path,
+            options.fill_rule,
+            &scratch.rgba(options.color(style.color())),
+        );
+        scratch.n_nodes += 1;
     }
 
     Ok(())
@@ -119,50 +810,284 @@ pub fn fill_polygon<S: BackendStyle, I: IntoIterator<Item = BackendCoord>>(
 
 pub fn draw_circle<S: BackendStyle>(
     snapshot: &gtk::Snapshot,
+    options: &Options,
+    scratch: &mut Scratch,
     center: BackendCoord,
     radius: u32,
     style: &S,
     fill: bool,
 ) -> Result<(), DrawingErrorKind<Infallible>> {
-    let path_builder = gsk::PathBuilder::new();
-    path_builder.add_circle(&Point::new(center.0 as f32, center.1 as f32), radius as f32);
+    scratch.flush();
+
+    let stroke_width = options.stroke_width(style);
+    if !options.validate_points([center])?
+        || !options.validate(radius as i64 <= MAX_COORD, "radius out of range")?
+        || !options.validate_stroke_width(stroke_width)?
+        || options.is_culled([center], radius as i32 + stroke_pad(stroke_width))
+    {
+        return Ok(());
+    }
+
+    let color = scratch.rgba(options.color(style.color()));
+    let square_marker_radius = options.quality.square_marker_radius();
+    if fill && square_marker_radius.is_some_and(|max_radius| radius <= max_radius) {
+        let (x, y) = options.point(center);
+        let r = radius as f32;
+        snapshot.append_color(&color, &Rect::new(x - r, y - r, r * 2.0, r * 2.0));
+        scratch.n_nodes += 1;
+        return Ok(());
+    }
+
+    if options.quality.caches_markers() {
+        let (x, y) = if fill {
+            options.point(center)
+        } else {
+            options.stroke_point(center, stroke_width)
+        };
+        if let Some(node) = scratch.circle_marker(radius, (!fill).then_some(stroke_width), color) {
+            snapshot.save();
+            snapshot.translate(&Point::new(x, y));
+            snapshot.append_node(&node);
+            snapshot.restore();
+            scratch.n_nodes += 2;
+        }
+        return Ok(());
+    }
+
+    let path_builder = scratch.path_builder();
+    let (x, y) = if fill {
+        options.point(center)
+    } else {
+        options.stroke_point(center, stroke_width)
+    };
+    path_builder.add_circle(&Point::new(x, y), radius as f32);
     let path = path_builder.to_path();
 
     if fill {
-        snapshot.append_fill(&path, FILL_RULE, &style.color().to_rgba());
+        snapshot.append_fill(&path, options.fill_rule, &color);
     } else {
-        let stroke = gsk::Stroke::new(style.stroke_width() as f32);
-        snapshot.append_stroke(&path, &stroke, &style.color().to_rgba());
+        snapshot.append_stroke(&path, scratch.stroke(stroke_width), &color);
+    }
+    scratch.n_nodes += 1;
+
+    Ok(())
+}
+
+pub fn draw_shape<F: BackendStyle, S: BackendStyle>(
+    snapshot: &gtk::Snapshot,
+    options: &Options,
+    scratch: &mut Scratch,
+    path: &gsk::Path,
+    fill_style: &F,
+    stroke_style: &S,
+) -> Result<(), DrawingErrorKind<Infallible>> {
+    scratch.flush();
+
+    let stroke_width = options.stroke_width(stroke_style);
+    if !options.validate_stroke_width(stroke_width)? {
+        return Ok(());
     }
 
+    let fill_color = scratch.rgba(options.color(fill_style.color()));
+    let stroke_color = scratch.rgba(options.color(stroke_style.color()));
+    append_shape(
+        snapshot,
+        path,
+        options.fill_rule,
+        scratch.stroke(stroke_width),
+        &fill_color,
+        &stroke_color,
+        options.renderer_caps,
+    );
+    scratch.n_nodes += 2;
+
+    Ok(())
+}
+
+/// Fills and strokes `path` as one shape, without the stroke blending over
+/// the fill.
+///
+/// Drawing the fill and then the stroke separately darkens the inner half
+/// of a translucent stroke, where it overlaps the fill. If both colors are
+/// the same, the shape is drawn opaque and then faded as a group.
+/// Otherwise, if the stroke is translucent, the fill is masked out where
+/// the stroke covers it, so each pixel is blended once, if the renderer
+/// supports masks.
+pub fn append_shape(
+    snapshot: &gtk::Snapshot,
+    path: &gsk::Path,
+    fill_rule: gsk::FillRule,
+    stroke: &gsk::Stroke,
+    fill_color: &gdk::RGBA,
+    stroke_color: &gdk::RGBA,
+    renderer_caps: RendererCaps,
+) {
+    if fill_color == stroke_color && stroke_color.alpha() < 1.0 {
+        let opaque_color = stroke_color.with_alpha(1.0);
+        snapshot.push_opacity(stroke_color.alpha() as f64);
+        snapshot.append_fill(path, fill_rule, &opaque_color);
+        snapshot.append_stroke(path, stroke, &opaque_color);
+        snapshot.pop();
+    } else if stroke_color.alpha() < 1.0 && renderer_caps.masks {
+        // The stroke is the mask, so the fill is only drawn where the
+        // stroke is not.
+        snapshot.push_mask(gsk::MaskMode::InvertedAlpha);
+        snapshot.append_stroke(path, stroke, &gdk::RGBA::BLACK);
+        snapshot.pop();
+        snapshot.append_fill(path, fill_rule, fill_color);
+        snapshot.pop();
+        snapshot.append_stroke(path, stroke, stroke_color);
+    } else {
+        snapshot.append_fill(path, fill_rule, fill_color);
+        snapshot.append_stroke(path, stroke, stroke_color);
+    }
+}
+
+pub fn draw_paintable(
+    snapshot: &gtk::Snapshot,
+    scratch: &mut Scratch,
+    paintable: &impl IsA<gdk::Paintable>,
+    upper_left: BackendCoord,
+    bottom_right: BackendCoord,
+) -> Result<(), DrawingErrorKind<Infallible>> {
+    scratch.flush();
+
+    let x = upper_left.0.min(bottom_right.0);
+    let y = upper_left.1.min(bottom_right.1);
+    let width = upper_left.0.abs_diff(bottom_right.0) as f64;
+    let height = upper_left.1.abs_diff(bottom_right.1) as f64;
+
+    if width == 0.0 || height == 0.0 {
+        return Ok(());
+    }
+
+    snapshot.save();
+    snapshot.translate(&Point::new(x as f32, y as f32));
+    paintable.snapshot(snapshot, width, height);
+    scratch.n_nodes += 1;
+    snapshot.restore();
+
+    Ok(())
+}
+
+pub fn draw_paintable_marker(
+    snapshot: &gtk::Snapshot,
+    scratch: &mut Scratch,
+    paintable: &impl IsA<gdk::Paintable>,
+    center: BackendCoord,
+    size: u32,
+) -> Result<(), DrawingErrorKind<Infallible>> {
+    scratch.flush();
+
+    if size == 0 {
+        return Ok(());
+    }
+
+    // Fit the paintable in the square, keeping its aspect ratio.
+    let size = size as f64;
+    let aspect_ratio = paintable.intrinsic_aspect_ratio();
+    let (width, height) = if aspect_ratio <= 0.0 || aspect_ratio == 1.0 {
+        (size, size)
+    } else if aspect_ratio > 1.0 {
+        (size, size / aspect_ratio)
+    } else {
+        (size * aspect_ratio, size)
+    };
+
+    snapshot.save();
+    snapshot.translate(&Point::new(
+        (center.0 as f64 - width / 2.0) as f32,
+        (center.1 as f64 - height / 2.0) as f32,
+    ));
+    paintable.snapshot(snapshot, width, height);
+    scratch.n_nodes += 1;
+    snapshot.restore();
+
     Ok(())
 }
 
 pub fn estimate_text_size<TStyle: BackendTextStyle>(
+    options: &Options,
     layout: &pango::Layout,
     text: &str,
     style: &TStyle,
 ) -> Result<(u32, u32), DrawingErrorKind<Infallible>> {
-    layout.set_text(text);
-    layout_set_style(layout, style);
+    layout_set_text(options, layout, text, style);
+
+    let extents = text_extents(options, layout);
+    Ok((extents.width() as u32, extents.height() as u32))
+}
+
+pub fn text_coverage<TStyle: BackendTextStyle>(
+    options: &Options,
+    layout: &pango::Layout,
+    text: &str,
+    style: &TStyle,
+) -> TextCoverage {
+    layout_set_text(options, layout, text, style);
 
-    let (width, height) = layout.pixel_size();
-    Ok((width as u32, height as u32))
+    TextCoverage {
+        n_unknown_glyphs: layout.unknown_glyphs_count().max(0) as u32,
+        has_color_glyphs: has_color_glyphs(layout),
+    }
+}
+
+/// Returns whether some glyphs of the layout are drawn in their own colors,
+/// by drawing it in black and looking for pixels that are not.
+fn has_color_glyphs(layout: &pango::Layout) -> bool {
+    let (_, logical) = layout.pixel_extents();
+    let Ok(mut surface) = cairo::ImageSurface::create(
+        cairo::Format::ARgb32,
+        logical.width().max(1),
+        logical.height().max(1),
+    ) else {
+        return false;
+    };
+
+    {
+        let Ok(cr) = cairo::Context::new(&surface) else {
+            return false;
+        };
+        cr.set_source_rgb(0.0, 0.0, 0.0);
+        cr.move_to(-logical.x() as f64, -logical.y() as f64);
+        pangocairo::functions::show_layout(&cr, layout);
+    }
+
+    surface.flush();
+    let Ok(data) = surface.data() else {
+        return false;
+    };
+    // Pixels are premultiplied, so black glyphs only have alpha.
+    data.chunks_exact(4)
+        .any(|pixel| pixel[0] != 0 || pixel[1] != 0 || pixel[2] != 0)
 }
 
 pub fn draw_text<TStyle: BackendTextStyle>(
     snapshot: &gtk::Snapshot,
+    options: &Options,
+    scratch: &mut Scratch,
     layout: &pango::Layout,
     text: &str,
     style: &TStyle,
     pos: BackendCoord,
 ) -> Result<(), DrawingErrorKind<Infallible>> {
-    layout.set_text(text);
-    layout_set_style(layout, style);
+    scratch.flush();
+
+    let size = style.size();
+    if !options.validate_points([pos])?
+        || !options.validate(
+            size.is_finite() && size >= 0.0 && size <= MAX_COORD as f64,
+            "invalid font size",
+        )?
+    {
+        return Ok(());
+    }
+
+    let is_vertical = layout_set_text(options, layout, text, style);
 
     snapshot.save();
 
-    let (_, extents) = layout.pixel_extents();
+    let extents = text_extents(options, layout);
     let dx = match style.anchor().h_pos {
         HPos::Left => 0.0,
         HPos::Center => -extents.width() as f32 / 2.0,
@@ -175,43 +1100,200 @@ pub fn draw_text<TStyle: BackendTextStyle>(
     };
 
     let rotate = match style.transform() {
+        // Vertical layouts always run from top to bottom.
+        _ if is_vertical => 90.0,
         FontTransform::None => 0.0,
         FontTransform::Rotate90 => 90.0,
         FontTransform::Rotate180 => 180.0,
         FontTransform::Rotate270 => 270.0,
     };
+    let (x, y) = options.point(pos);
+    // Move the top-left corner of the extents, rather than the layout
+    // origin, to the anchored position.
+    let dx = dx - extents.x() as f32;
+    let dy = dy - extents.height() as f32 - extents.y() as f32;
     if rotate == 0.0 {
-        snapshot.translate(&Point::new(
-            pos.0 as f32 + dx,
-            pos.1 as f32 + dy - extents.height() as f32,
-        ));
+        snapshot.translate(&Point::new(x + dx, y + dy));
     } else {
-        snapshot.translate(&Point::new(pos.0 as f32, pos.1 as f32));
+        snapshot.translate(&Point::new(x, y));
         snapshot.rotate(rotate);
-        snapshot.translate(&Point::new(dx, dy - extents.height() as f32));
+        snapshot.translate(&Point::new(dx, dy));
+    }
+
+    match options.text_halo {
+        Some(TextHalo::Outline { color, width }) => {
+            let path_builder = gsk::PathBuilder::new();
+            path_builder.add_layout(layout);
+            let path = path_builder.to_path();
+
+            // Only the outer half of the stroke is visible around the glyphs.
+            let stroke = gsk::Stroke::builder(width * 2.0)
+                .line_join(gsk::LineJoin::Round)
+                .build();
+            snapshot.append_stroke(&path, &stroke, &color);
+            scratch.n_nodes += 1;
+        }
+        Some(TextHalo::Pill { color, padding }) => {
+            let bounds = Rect::new(
+                extents.x() as f32 - padding,
+                extents.y() as f32 - padding,
+                extents.width() as f32 + padding * 2.0,
+                extents.height() as f32 + padding * 2.0,
+            );
+            snapshot.push_rounded_clip(&gsk::RoundedRect::from_rect(bounds, bounds.height() / 2.0));
+            snapshot.append_color(&color, &bounds);
+            scratch.n_nodes += 1;
+            snapshot.pop();
+        }
+        Some(TextHalo::Shadow {
+            color,
+            offset: (dx, dy),
+            blur_radius,
+        }) => {
+            let is_blurred = blur_radius > 0.0 && options.renderer_caps.blurs;
+            if is_blurred {
+                snapshot.push_blur(blur_radius as f64);
+            }
+            snapshot.save();
+            snapshot.translate(&Point::new(dx, dy));
+            snapshot.append_layout(layout, &color);
+            snapshot.restore();
+            if is_blurred {
+                snapshot.pop();
+            }
+            scratch.n_nodes += 1;
+        }
+        None => {}
     }
 
-    snapshot.append_layout(layout, &style.color().to_rgba());
+    snapshot.append_layout(layout, &options.color(style.color()).to_rgba());
+    scratch.n_nodes += 1;
 
     snapshot.restore();
 
     Ok(())
 }
 
-fn layout_set_style(layout: &pango::Layout, style: &impl BackendTextStyle) {
+/// Sets the text and style of the layout, returning whether the text is laid
+/// out vertically.
+fn layout_set_text(
+    options: &Options,
+    layout: &pango::Layout,
+    text: &str,
+    style: &impl BackendTextStyle,
+) -> bool {
+    let is_vertical = options.vertical_text
+        && matches!(
+            style.transform(),
+            FontTransform::Rotate90 | FontTransform::Rotate270
+        );
+
+    // With an east gravity, vertical scripts, such as CJK, are kept upright,
+    // while other scripts are rotated along the line.
+    let gravity = if is_vertical {
+        pango::Gravity::East
+    } else {
+        pango::Gravity::South
+    };
+    let context = layout.context();
+    if context.base_gravity() != gravity {
+        context.set_base_gravity(gravity);
+        layout.context_changed();
+    }
+
+    layout.set_text(text);
+    layout_set_style(options, layout, style);
+
+    is_vertical
+}
+
+/// Returns the extents of the laid out text that is measured and anchored.
+fn text_extents(options: &Options, layout: &pango::Layout) -> pango::Rectangle {
+    let (ink, logical) = layout.pixel_extents();
+    match options.text_extents {
+        TextExtents::Logical => logical,
+        TextExtents::Ink => ink,
+        TextExtents::Baseline => {
+            let context = layout.context();
+            let metrics = context.metrics(
+                layout.font_description().as_ref(),
+                context.language().as_ref(),
+            );
+            // Wrapped text spans from the first baseline to the last.
+            let mut iter = layout.iter();
+            while iter.next_line() {}
+            let top = layout.baseline() - metrics.ascent();
+            let bottom = iter.baseline() + metrics.descent();
+
+            let to_pixels = |units: i32| (units as f64 / pango::SCALE as f64).round() as i32;
+            pango::Rectangle::new(
+                logical.x(),
+                to_pixels(top),
+                logical.width(),
+                to_pixels(bottom - top),
+            )
+        }
+    }
+}
+
+fn layout_set_style(options: &Options, layout: &pango::Layout, style: &impl BackendTextStyle) {
     let mut font_desc = pango::FontDescription::new();
-    font_desc.set_family(style.family().as_str());
-    font_desc.set_absolute_size(style.size() * pango::SCALE as f64);
-    match style.style() {
-        FontStyle::Normal => font_desc.set_style(pango::Style::Normal),
-        FontStyle::Bold => font_desc.set_weight(pango::Weight::Bold),
-        FontStyle::Italic => font_desc.set_style(pango::Style::Italic),
-        FontStyle::Oblique => font_desc.set_style(pango::Style::Oblique),
+    if options.fallback_families.is_empty() {
+        font_desc.set_family(style.family().as_str());
+    } else {
+        // Pango tries each family of a comma-separated list in order, before
+        // falling back to the font map's own fallback.
+        let families = std::iter::once(style.family().as_str())
+            .chain(options.fallback_families.iter().map(String::as_str))
+            .collect::<Vec<_>>();
+        font_desc.set_family(&families.join(","));
     }
+    font_desc.set_absolute_size(style.size() * pango::SCALE as f64);
+    let text_attributes = &options.text_attributes;
+    let (weight, font_style) = match style.style() {
+        FontStyle::Normal => (pango::Weight::Normal, pango::Style::Normal),
+        FontStyle::Bold => (pango::Weight::Bold, pango::Style::Normal),
+        FontStyle::Italic => (pango::Weight::Normal, pango::Style::Italic),
+        FontStyle::Oblique => (pango::Weight::Normal, pango::Style::Oblique),
+    };
+    font_desc.set_weight(text_attributes.weight.unwrap_or(weight));
+    font_desc.set_style(text_attributes.style.unwrap_or(font_style));
     layout.set_font_description(Some(&font_desc));
+
+    match text_attributes.max_width {
+        Some(max_width) if max_width >= 0.0 => {
+            layout.set_width((max_width * pango::SCALE as f32) as i32);
+            if let Some(wrap) = text_attributes.wrap {
+                layout.set_wrap(wrap);
+                layout.set_ellipsize(pango::EllipsizeMode::None);
+            } else {
+                layout.set_ellipsize(
+                    text_attributes
+                        .ellipsize
+                        .unwrap_or(pango::EllipsizeMode::End),
+                );
+            }
+        }
+        _ => {
+            layout.set_width(-1);
+            layout.set_ellipsize(pango::EllipsizeMode::None);
+        }
+    }
+
+    let attrs = pango::AttrList::new();
+    if text_attributes.letter_spacing != 0.0 {
+        attrs.insert(pango::AttrInt::new_letter_spacing(
+            (text_attributes.letter_spacing * pango::SCALE as f32) as i32,
+        ));
+    }
+    let font_features = text_attributes.font_features();
+    if !font_features.is_empty() {
+        attrs.insert(pango::AttrFontFeatures::new(&font_features));
+    }
+    layout.set_attributes(Some(&attrs));
 }
 
-trait BackendColorExt {
+pub trait BackendColorExt {
     fn to_rgba(&self) -> gdk::RGBA;
 }
 
@@ -225,3 +1307,246 @@ impl BackendColorExt for BackendColor {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use plotters::style::{RGBAColor, ShapeStyle};
+
+    use super::*;
+    use crate::GtkBackend;
+
+    fn rect_tuple(rect: Rect) -> (f32, f32, f32, f32) {
+        (rect.x(), rect.y(), rect.width(), rect.height())
+    }
+
+    #[test]
+    fn clamped_rect_normalizes_reversed_corners() {
+        let options = Options::default();
+
+        let rect = clamped_rect(&options, (30, 40), (10, 20), (100, 100), 0.0).unwrap();
+        assert_eq!(rect_tuple(rect), (10.0, 20.0, 20.0, 20.0));
+
+        let rect = clamped_rect(&options, (10, 40), (30, 20), (100, 100), 0.0).unwrap();
+        assert_eq!(rect_tuple(rect), (10.0, 20.0, 20.0, 20.0));
+    }
+
+    #[test]
+    fn clamped_rect_clamps_to_bounds_and_margin() {
+        let options = Options {
+            cull_bounds: Some(cull_bounds((100, 80), 0)),
+            ..Options::default()
+        };
+
+        let rect = clamped_rect(&options, (-50, -50), (150, 200), (100, 80), 0.0).unwrap();
+        assert_eq!(rect_tuple(rect), (0.0, 0.0, 100.0, 80.0));
+
+        let rect = clamped_rect(&options, (-50, -50), (150, 200), (100, 80), 2.0).unwrap();
+        assert_eq!(rect_tuple(rect), (-2.0, -2.0, 104.0, 84.0));
+
+        let rect = clamped_rect(&options, (i32::MIN, 10), (i32::MAX, 20), (100, 80), 0.0).unwrap();
+        assert_eq!(rect_tuple(rect), (0.0, 10.0, 100.0, 10.0));
+    }
+
+    #[test]
+    fn clamped_rect_rejects_empty_rects() {
+        let options = Options {
+            cull_bounds: Some(cull_bounds((100, 100), 0)),
+            ..Options::default()
+        };
+
+        assert!(clamped_rect(&options, (10, 10), (10, 20), (100, 100), 0.0).is_none());
+        assert!(clamped_rect(&options, (10, 10), (20, 10), (100, 100), 0.0).is_none());
+        assert!(clamped_rect(&options, (200, 10), (300, 20), (100, 100), 0.0).is_none());
+        assert!(clamped_rect(&options, (-30, -30), (-10, -10), (100, 100), 0.0).is_none());
+    }
+
+    #[test]
+    fn clamped_rect_is_not_clamped_without_culling() {
+        let options = Options::default();
+
+        let rect = clamped_rect(&options, (-50, -50), (150, 200), (100, 80), 0.0).unwrap();
+        assert_eq!(rect_tuple(rect), (-50.0, -50.0, 200.0, 250.0));
+
+        assert!(clamped_rect(&options, (10, 10), (10, 20), (100, 100), 0.0).is_none());
+    }
+
+    #[test]
+    fn clamped_rect_is_relative_to_origin() {
+        let options = Options {
+            origin: (1000, 2000),
+            ..Options::default()
+        };
+
+        let rect = clamped_rect(&options, (1010, 2020), (1030, 2040), (4000, 4000), 0.0).unwrap();
+        assert_eq!(rect_tuple(rect), (10.0, 20.0, 20.0, 20.0));
+    }
+
+    #[test]
+    fn draft_paths_skip_points_within_tolerance() {
+        crate::headless::test_synced(|| {
+            let style = BackendColor {
+                alpha: 1.0,
+                rgb: (0, 0, 0),
+            };
+            let n_segments = |quality| {
+                let options = Options {
+                    quality,
+                    ..Options::default()
+                };
+                let snapshot = gtk::Snapshot::new();
+                let points = [(0, 0), (1, 1), (2, 0), (10, 0), (10, 2)];
+                draw_path(&snapshot, &options, &mut Scratch::default(), points, &style).unwrap();
+
+                let node = snapshot.to_node().unwrap();
+                let path = node.downcast_ref::<gsk::StrokeNode>().unwrap().path();
+                path.to_str().matches('L').count()
+            };
+
+            assert_eq!(n_segments(Quality::Normal), 4);
+            // The end is kept even if it is within the tolerance.
+            assert_eq!(n_segments(Quality::Draft), 2);
+        });
+    }
+
+    #[test]
+    fn only_opaque_lines_are_batched() {
+        crate::headless::test_synced(|| {
+            let n_nodes = |alpha| {
+                let style = BackendColor {
+                    alpha,
+                    rgb: (0, 0, 0),
+                };
+                let snapshot = gtk::Snapshot::new();
+                let mut scratch = Scratch::default();
+                let options = Options::default();
+                for (from, to) in [((0, 0), (10, 10)), ((0, 10), (10, 0))] {
+                    draw_line(&snapshot, &options, &mut scratch, from, to, &style).unwrap();
+                }
+                scratch.flush();
+                scratch.n_nodes
+            };
+
+            assert_eq!(n_nodes(1.0), 1);
+            // Batched, the crossing of the lines would be blended once.
+            assert_eq!(n_nodes(0.5), 2);
+        });
+    }
+
+    #[test]
+    fn draft_circles_reuse_marker_nodes() {
+        crate::headless::test_synced(|| {
+            let options = Options {
+                quality: Quality::Draft,
+                ..Options::default()
+            };
+            let style = BackendColor {
+                alpha: 1.0,
+                rgb: (0, 0, 0),
+            };
+            let snapshot = gtk::Snapshot::new();
+            let mut scratch = Scratch::default();
+            for center in [(10, 10), (20, 30)] {
+                draw_circle(&snapshot, &options, &mut scratch, center, 5, &style, true).unwrap();
+            }
+            draw_circle(&snapshot, &options, &mut scratch, (40, 40), 6, &style, true).unwrap();
+
+            let node = snapshot.to_node().unwrap();
+            let container = node.downcast_ref::<gsk::ContainerNode>().unwrap();
+            let markers = (0..container.n_children())
+                .map(|index| {
+                    let child = container.child(index);
+                    child.downcast_ref::<gsk::TransformNode>().unwrap().child()
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(markers.len(), 3);
+            assert_eq!(markers[0].as_ptr(), markers[1].as_ptr());
+            assert_ne!(markers[1].as_ptr(), markers[2].as_ptr());
+        });
+    }
+
+    #[test]
+    fn shapes_blend_each_pixel_once() {
+        crate::headless::test_synced(|| {
+            let path_builder = gsk::PathBuilder::new();
+            path_builder.add_rect(&Rect::new(4.0, 4.0, 12.0, 12.0));
+            let path = path_builder.to_path();
+            let fill_style = BackendColor {
+                alpha: 1.0,
+                rgb: (255, 0, 0),
+            };
+            let stroke_style = ShapeStyle {
+                color: RGBAColor(0, 0, 255, 0.5),
+                filled: false,
+                stroke_width: 2,
+            };
+
+            let texture = crate::headless::render_to_texture((20, 20), |mut backend| {
+                backend
+                    .draw_shape(&path, &fill_style, &stroke_style)
+                    .unwrap();
+            })
+            .unwrap();
+            let mut pixels = vec![0; 20 * 20 * 4];
+            texture.download(&mut pixels, 20 * 4);
+            // The default memory format of downloads is premultiplied BGRA.
+            let pixel = |x: usize, y: usize| -> [u8; 4] {
+                pixels[(y * 20 + x) * 4..][..4].try_into().unwrap()
+            };
+
+            // The fill is drawn inside the stroke.
+            assert_eq!(pixel(10, 10), [0, 0, 255, 255]);
+            // The stroke is not blended over the fill along the inner edge,
+            // so it is as translucent as along the outer edge.
+            assert_eq!(pixel(4, 10), pixel(3, 10));
+            assert_eq!(pixel(4, 10)[2], 0);
+            assert!(pixel(4, 10)[3] < 255);
+        });
+    }
+
+    #[test]
+    fn mapped_paths_fall_back_to_solid_strokes() {
+        crate::headless::test_synced(|| {
+            let stroke_children = |renderer_caps| {
+                let options = Options {
+                    renderer_caps,
+                    ..Options::default()
+                };
+                let snapshot = gtk::Snapshot::new();
+                let points = [((0, 0), 0.0), ((10, 0), 0.5), ((20, 0), 1.0)];
+                draw_mapped_path(
+                    &snapshot,
+                    &options,
+                    &mut Scratch::default(),
+                    points,
+                    &(0.0..1.0),
+                    Colormap::Viridis,
+                    2,
+                )
+                .unwrap();
+
+                let node = snapshot.to_node().unwrap();
+                let container = node.downcast_ref::<gsk::ContainerNode>().unwrap();
+                (0..container.n_children())
+                    .map(|index| {
+                        let child = container.child(index);
+                        child.downcast_ref::<gsk::StrokeNode>().unwrap().child()
+                    })
+                    .collect::<Vec<_>>()
+            };
+
+            let children = stroke_children(RendererCaps::ALL);
+            assert_eq!(children.len(), 2);
+            assert!(children
+                .iter()
+                .all(|child| child.node_type() == gsk::RenderNodeType::LinearGradientNode));
+
+            let children = stroke_children(RendererCaps::CAIRO);
+            assert_eq!(children.len(), 2);
+            let color = children[0]
+                .downcast_ref::<gsk::ColorNode>()
+                .unwrap()
+                .color();
+            assert_eq!(color, Colormap::Viridis.map_rgba(0.25));
+        });
+    }
+}
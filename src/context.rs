@@ -0,0 +1,268 @@
+use std::cell::{Cell, RefCell};
+
+use gtk::{cairo, pango, prelude::*};
+
+use crate::Quality;
+
+thread_local! {
+    static IS_DEFAULT_CACHED: Cell<bool> = const { Cell::new(true) };
+    static DEFAULT_PANGO_CONTEXT: RefCell<Option<pango::Context>> = const { RefCell::new(None) };
+}
+
+/// Returns a Pango context of the default font map, which is created once
+/// per thread unless disabled with [`BackendContext::set_default_cached`].
+pub(crate) fn default_pango_context() -> pango::Context {
+    if !IS_DEFAULT_CACHED.get() {
+        return create_pango_context(&pangocairo::FontMap::default());
+    }
+
+    DEFAULT_PANGO_CONTEXT.with_borrow_mut(|context| {
+        context
+            .get_or_insert_with(|| create_pango_context(&pangocairo::FontMap::default()))
+            .clone()
+    })
+}
+
+/// Returns a new Pango context of `font_map` in the language of GTK, which
+/// may differ from the one Pango picks from the environment, such as when
+/// the app sets its own locale.
+fn create_pango_context(font_map: &impl IsA<pango::FontMap>) -> pango::Context {
+    let context = font_map.create_context();
+    context.set_language(Some(&gtk::default_language()));
+    context
+}
+
+/// The resources backends use to measure and draw text, which can be shared
+/// by backends drawing one after another.
+///
+/// Creating a backend with [`PaintableBackend::new`] or
+/// [`SnapshotBackend::new`] creates a new Pango context and layout, which
+/// is measurable overhead for plots redrawn on every frame. Keep a context
+/// around, such as in the widget drawing the plot, and create backends with
+/// [`PaintableBackend::with_context`] or [`SnapshotBackend::with_context`]
+/// instead.
+///
+/// Cloning a context is cheap, and the clone shares the resources of the
+/// original.
+///
+/// [`PaintableBackend::new`]: crate::PaintableBackend::new
+/// [`SnapshotBackend::new`]: crate::SnapshotBackend::new
+/// [`PaintableBackend::with_context`]: crate::PaintableBackend::with_context
+/// [`SnapshotBackend::with_context`]: crate::SnapshotBackend::with_context
+#[derive(Debug, Clone)]
+pub struct BackendContext {
+    layout: pango::Layout,
+}
+
+impl Default for BackendContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BackendContext {
+    /// Creates a new context using the default font map.
+    ///
+    /// The Pango context of the default font map is cached on the current
+    /// thread, so this only creates a new layout. See
+    /// [`BackendContext::set_default_cached`].
+    pub fn new() -> Self {
+        Self {
+            layout: pango::Layout::new(&default_pango_context()),
+        }
+    }
+
+    /// Sets whether the Pango context of the default font map is cached on
+    /// the current thread and shared by [`BackendContext::new`] and the
+    /// backends created without a context, which is the default.
+    ///
+    /// Disable this for apps that change fonts or font options at runtime,
+    /// such as by adding fonts to the default font map, so that every new
+    /// context picks up the change. Disabling this also drops the cached
+    /// context, so toggling it off and on refreshes it.
+    pub fn set_default_cached(is_cached: bool) {
+        IS_DEFAULT_CACHED.set(is_cached);
+        if !is_cached {
+            DEFAULT_PANGO_CONTEXT.take();
+        }
+    }
+
+    /// Returns whether the Pango context of the default font map is cached
+    /// on the current thread.
+    pub fn is_default_cached() -> bool {
+        IS_DEFAULT_CACHED.get()
+    }
+
+    /// Creates a new context using `font_map`, such as a font map with
+    /// custom fonts added.
+    pub fn for_font_map(font_map: &impl IsA<pango::FontMap>) -> Self {
+        let context = create_pango_context(font_map);
+        Self {
+            layout: pango::Layout::new(&context),
+        }
+    }
+
+    /// Creates a new context with the font map and font options of
+    /// `widget`, so that text looks like the labels of GTK, following the
+    /// font settings of the desktop.
+    ///
+    /// This must be created again when the font settings change, such as
+    /// with [`PlotView::bind_to_style_manager`].
+    ///
+    /// [`PlotView::bind_to_style_manager`]: crate::PlotView::bind_to_style_manager
+    pub fn for_widget(widget: &impl IsA<gtk::Widget>) -> Self {
+        let context = widget.create_pango_context();
+        context.set_language(Some(&gtk::default_language()));
+        Self {
+            layout: pango::Layout::new(&context),
+        }
+    }
+
+    /// Returns the Pango context text is laid out with.
+    pub fn pango_context(&self) -> pango::Context {
+        self.layout.context()
+    }
+
+    /// Sets the language text is shaped in, or `None` for the language of
+    /// GTK, which is the default.
+    ///
+    /// The language picks the glyphs of characters shared by several
+    /// scripts, such as Han characters, which look different in Chinese,
+    /// Japanese, and Korean, and the rules of case conversion, such as of
+    /// the dotted and dotless i in Turkish. Set this when the plot is in
+    /// another language than the rest of the app.
+    ///
+    /// This moves this context to a new Pango context, see
+    /// [`BackendContext::set_hint_style`].
+    pub fn set_language(&mut self, language: Option<&pango::Language>) {
+        let language = language.cloned().unwrap_or_else(gtk::default_language);
+        if let Some(context) = self.derive_pango_context() {
+            context.set_language(Some(&language));
+            self.layout = pango::Layout::new(&context);
+        }
+    }
+
+    /// Returns the language text is shaped in.
+    pub fn language(&self) -> pango::Language {
+        self.layout
+            .context()
+            .language()
+            .unwrap_or_else(gtk::default_language)
+    }
+
+    /// Sets how glyph outlines are fitted to the pixel grid, such as
+    /// [`cairo::HintStyle::Slight`] to match the labels of most GTK themes.
+    ///
+    /// Contexts of a font map default to the options of the font map, which
+    /// may differ from the options GTK uses for its own labels, taken from
+    /// [`gtk::Settings`]. To match those, create the context with
+    /// [`BackendContext::for_widget`] instead.
+    ///
+    /// This moves this context to a new Pango context with the same font
+    /// map and options, so that contexts it was cloned from or that share
+    /// the cached Pango context are left as is.
+    pub fn set_hint_style(&mut self, hint_style: cairo::HintStyle) {
+        self.set_font_options(|font_options| font_options.set_hint_style(hint_style));
+    }
+
+    /// Returns how glyph outlines are fitted to the pixel grid.
+    pub fn hint_style(&self) -> cairo::HintStyle {
+        self.font_options()
+            .map_or(cairo::HintStyle::Default, |font_options| {
+                font_options.hint_style()
+            })
+    }
+
+    /// Sets how the edges of glyphs are smoothed, such as
+    /// [`cairo::Antialias::Gray`] to avoid color fringes on rotated or
+    /// moving text.
+    ///
+    /// This moves this context to a new Pango context, see
+    /// [`BackendContext::set_hint_style`].
+    pub fn set_antialias(&mut self, antialias: cairo::Antialias) {
+        self.set_font_options(|font_options| font_options.set_antialias(antialias));
+    }
+
+    /// Returns how the edges of glyphs are smoothed.
+    pub fn antialias(&self) -> cairo::Antialias {
+        self.font_options()
+            .map_or(cairo::Antialias::Default, |font_options| {
+                font_options.antialias()
+            })
+    }
+
+    /// Sets the order of the color elements of the pixels of the display,
+    /// which subpixel antialiasing depends on.
+    ///
+    /// This moves this context to a new Pango context, see
+    /// [`BackendContext::set_hint_style`].
+    pub fn set_subpixel_order(&mut self, subpixel_order: cairo::SubpixelOrder) {
+        self.set_font_options(|font_options| font_options.set_subpixel_order(subpixel_order));
+    }
+
+    /// Returns the order of the color elements of the pixels of the display.
+    pub fn subpixel_order(&self) -> cairo::SubpixelOrder {
+        self.font_options()
+            .map_or(cairo::SubpixelOrder::Default, |font_options| {
+                font_options.subpixel_order()
+            })
+    }
+
+    /// Returns the layout text is measured and drawn with.
+    ///
+    /// Its text and attributes are replaced on every use.
+    pub(crate) fn layout(&self) -> &pango::Layout {
+        &self.layout
+    }
+
+    /// Returns the font options of the Pango context, if set.
+    fn font_options(&self) -> Option<cairo::FontOptions> {
+        pangocairo::functions::context_get_font_options(&self.layout.context())
+    }
+
+    /// Moves this context to a new Pango context with its font options
+    /// changed by `f`.
+    fn set_font_options(&mut self, f: impl FnOnce(&mut cairo::FontOptions)) {
+        let Some(context) = self.derive_pango_context() else {
+            return;
+        };
+
+        let font_options = pangocairo::functions::context_get_font_options(&context)
+            .or_else(|| cairo::FontOptions::new().ok());
+        if let Some(mut font_options) = font_options {
+            f(&mut font_options);
+            pangocairo::functions::context_set_font_options(&context, Some(&font_options));
+        }
+        self.layout = pango::Layout::new(&context);
+    }
+
+    /// Returns a new Pango context with the font map, language, and font
+    /// options of this context, or `None` if it has no font map.
+    fn derive_pango_context(&self) -> Option<pango::Context> {
+        let old_context = self.layout.context();
+        let context = old_context.font_map()?.create_context();
+        context.set_language(old_context.language().as_ref());
+        context.set_round_glyph_positions(old_context.round_glyph_positions());
+        if let Some(font_options) = self.font_options() {
+            pangocairo::functions::context_set_font_options(&context, Some(&font_options));
+        }
+        Some(context)
+    }
+
+    /// Returns a layout to measure and draw text with `quality`.
+    ///
+    /// This is the layout of the context for [`Quality::Normal`], and a
+    /// layout of a new Pango context derived from it otherwise, so that the
+    /// text options of this context are left as is.
+    pub(crate) fn layout_for_quality(&self, quality: Quality) -> pango::Layout {
+        let context = self
+            .derive_pango_context()
+            .filter(|_| quality != Quality::Normal);
+        let Some(context) = context else {
+            return self.layout.clone();
+        };
+
+        quality.apply_to_context(&context);
+        pango::Layout::new(&context)
+    }
+}
@@ -0,0 +1,88 @@
+use std::{error::Error, fmt};
+
+/// Error returned when creating a [`Paintable`](crate::Paintable) with an
+/// invalid size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidSizeError {
+    pub(crate) size: (u32, u32),
+}
+
+impl InvalidSizeError {
+    /// Returns the size that was rejected.
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+}
+
+impl fmt::Display for InvalidSizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (w, h) = self.size;
+        write!(
+            f,
+            "invalid size {}x{}, width and height must be between 1 and {}",
+            w,
+            h,
+            i32::MAX
+        )
+    }
+}
+
+impl Error for InvalidSizeError {}
+
+/// Error returned when drawing a primitive with invalid input, if the
+/// backend's [`InvalidInputPolicy`] is [`InvalidInputPolicy::Error`].
+///
+/// As plotters can only carry errors other than the backend error through
+/// [`DrawingErrorKind::FontError`](plotters_backend::DrawingErrorKind::FontError),
+/// this is returned in that variant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidInputError {
+    pub(crate) reason: &'static str,
+}
+
+impl InvalidInputError {
+    /// Returns a description of the invalid input.
+    pub fn reason(&self) -> &'static str {
+        self.reason
+    }
+}
+
+impl fmt::Display for InvalidInputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid input: {}", self.reason)
+    }
+}
+
+impl Error for InvalidInputError {}
+
+/// What happens when a primitive has invalid input.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidInputPolicy {
+    /// The primitive is skipped, and the rest is drawn.
+    #[default]
+    Skip,
+    /// An [`InvalidInputError`] is returned.
+    Error,
+}
+
+/// Error returned when deserializing an invalid
+/// [`Recording`](crate::recording::Recording).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidRecordingError {
+    pub(crate) offset: usize,
+}
+
+impl InvalidRecordingError {
+    /// Returns the offset of the first invalid byte.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl fmt::Display for InvalidRecordingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid recording at byte {}", self.offset)
+    }
+}
+
+impl Error for InvalidRecordingError {}
@@ -0,0 +1,233 @@
+//! Exporting of plots to image files.
+
+use std::path::Path;
+
+use gtk::{
+    cairo, gdk, glib,
+    graphene::{Point, Rect},
+    gsk,
+    prelude::*,
+};
+use plotters::{coord::CoordTranslate, drawing::DrawingArea};
+use plotters_backend::{BackendCoord, DrawingBackend};
+
+/// The part of a [`Paintable`] to export.
+///
+/// [`Paintable`]: crate::Paintable
+#[derive(Debug, Default, Clone, Copy)]
+pub enum ExportRegion {
+    /// The whole paintable.
+    #[default]
+    Full,
+    /// The plotting area, set with [`PaintableBackend::set_plot_area`],
+    /// excluding the margins, labels, and titles around it, or the whole
+    /// paintable if it was not set.
+    ///
+    /// [`PaintableBackend::set_plot_area`]: crate::PaintableBackend::set_plot_area
+    PlotArea,
+    /// The given rect, in pixels of the paintable.
+    Custom(Rect),
+}
+
+impl ExportRegion {
+    /// Returns the rect of the region within a paintable of `size` whose
+    /// plotting area is `plot_area`, or `None` if it is empty.
+    pub(crate) fn to_rect(
+        self,
+        (width, height): (u32, u32),
+        plot_area: Option<Rect>,
+    ) -> Option<Rect> {
+        let bounds = Rect::new(0.0, 0.0, width as f32, height as f32);
+        let rect = match self {
+            Self::Full => return Some(bounds),
+            Self::PlotArea => plot_area.unwrap_or(bounds),
+            Self::Custom(rect) => rect,
+        };
+        rect.intersection(&bounds)
+            .filter(|rect| rect.width() >= 1.0 && rect.height() >= 1.0)
+    }
+}
+
+/// Returns the upper left and bottom right corners of `area`, such as the
+/// plotting area of a chart given by `ChartContext::plotting_area`, to be
+/// set with [`PaintableBackend::set_plot_area`].
+///
+/// [`PaintableBackend::set_plot_area`]: crate::PaintableBackend::set_plot_area
+pub fn plot_area_of<DB: DrawingBackend, CT: CoordTranslate>(
+    area: &DrawingArea<DB, CT>,
+) -> (BackendCoord, BackendCoord) {
+    let (x_range, y_range) = area.get_pixel_range();
+    ((x_range.start, y_range.start), (x_range.end, y_range.end))
+}
+
+/// Records the frames of an animated paintable, such as a
+/// [`Paintable`](crate::Paintable) that is redrawn on every tick of a frame
+/// clock, and saves them as a PNG sequence.
+#[derive(Debug)]
+pub struct FrameRecorder {
+    paintable: gdk::Paintable,
+    renderer: gsk::CairoRenderer,
+    frames: Vec<gdk::Texture>,
+}
+
+impl FrameRecorder {
+    /// Creates a new recorder for the given paintable.
+    ///
+    /// Frames are captured at the intrinsic size of the paintable.
+    pub fn new(paintable: &impl IsA<gdk::Paintable>) -> Result<Self, glib::Error> {
+        let renderer = gsk::CairoRenderer::new();
+        renderer.realize(None)?;
+        Ok(Self {
+            paintable: paintable.clone().upcast(),
+            renderer,
+            frames: Vec::new(),
+        })
+    }
+
+    /// Captures the current contents of the paintable as a new frame.
+    pub fn capture(&mut self) {
+        let texture = render_paintable(&self.renderer, &self.paintable);
+        self.frames.push(texture);
+    }
+
+    /// Captures `n_frames` frames, calling `draw` with the frame index before
+    /// capturing each frame.
+    pub fn record(&mut self, n_frames: usize, mut draw: impl FnMut(usize)) {
+        self.frames.reserve(n_frames);
+        for index in 0..n_frames {
+            draw(index);
+            self.capture();
+        }
+    }
+
+    /// Returns the captured frames.
+    pub fn frames(&self) -> &[gdk::Texture] {
+        &self.frames
+    }
+
+    /// Removes all captured frames.
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+
+    /// Saves the captured frames to `dir` as `frame-0000.png`,
+    /// `frame-0001.png`, and so on.
+    ///
+    /// The directory must already exist.
+    pub fn save_png_sequence(&self, dir: impl AsRef<Path>) -> Result<(), glib::BoolError> {
+        let dir = dir.as_ref();
+        for (index, frame) in self.frames.iter().enumerate() {
+            frame.save_to_png(dir.join(format!("frame-{:04}.png", index)))?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for FrameRecorder {
+    fn drop(&mut self) {
+        self.renderer.unrealize();
+    }
+}
+
+/// Returns the native renderer of `surface`, or a Cairo renderer if
+/// `surface` is `None` or has no renderer.
+///
+/// The returned renderer is realized, and must be unrealized after use.
+pub(crate) fn renderer_for_surface(
+    surface: Option<&gdk::Surface>,
+) -> Result<gsk::Renderer, glib::Error> {
+    match surface.and_then(gsk::Renderer::for_surface) {
+        Some(renderer) => Ok(renderer),
+        None => {
+            let renderer = gsk::CairoRenderer::new();
+            renderer.realize(None)?;
+            Ok(renderer.upcast())
+        }
+    }
+}
+
+/// Renders the node into an SVG document of the given size.
+pub(crate) fn render_node_to_svg(
+    node: Option<&gsk::RenderNode>,
+    (width, height): (u32, u32),
+) -> Result<Vec<u8>, cairo::Error> {
+    let surface = cairo::SvgSurface::for_stream(width as f64, height as f64, Vec::<u8>::new())?;
+
+    if let Some(node) = node {
+        let cr = cairo::Context::new(&surface)?;
+        node.draw(&cr);
+    }
+
+    let stream = surface
+        .finish_output_stream()
+        .map_err(|_| cairo::Error::WriteError)?;
+    Ok(*stream.downcast::<Vec<u8>>().unwrap())
+}
+
+/// Renders the paintable at its intrinsic size.
+pub(crate) fn render_paintable(
+    renderer: &impl IsA<gsk::Renderer>,
+    paintable: &impl IsA<gdk::Paintable>,
+) -> gdk::Texture {
+    let size = (
+        paintable.intrinsic_width().max(1) as u32,
+        paintable.intrinsic_height().max(1) as u32,
+    );
+    render_paintable_at_size(renderer, paintable, size)
+}
+
+/// Renders the part of the paintable within `rect`, at its intrinsic size.
+pub(crate) fn render_paintable_region(
+    renderer: &impl IsA<gsk::Renderer>,
+    paintable: &impl IsA<gdk::Paintable>,
+    rect: &Rect,
+) -> gdk::Texture {
+    let bounds = Rect::new(0.0, 0.0, rect.width(), rect.height());
+
+    let snapshot = gtk::Snapshot::new();
+    // Ensure that empty paintables still render to a texture of the right size.
+    snapshot.append_color(&gdk::RGBA::TRANSPARENT, &bounds);
+    snapshot.translate(&Point::new(-rect.x(), -rect.y()));
+    snapshot_contents(
+        &snapshot,
+        paintable,
+        paintable.intrinsic_width().max(1) as f64,
+        paintable.intrinsic_height().max(1) as f64,
+    );
+    let node = snapshot.to_node().unwrap();
+
+    renderer.render_texture(node, Some(&bounds))
+}
+
+/// Renders the paintable scaled to the given size.
+pub(crate) fn render_paintable_at_size(
+    renderer: &impl IsA<gsk::Renderer>,
+    paintable: &impl IsA<gdk::Paintable>,
+    (width, height): (u32, u32),
+) -> gdk::Texture {
+    let width = width.max(1) as f32;
+    let height = height.max(1) as f32;
+    let bounds = Rect::new(0.0, 0.0, width, height);
+
+    let snapshot = gtk::Snapshot::new();
+    // Ensure that empty paintables still render to a texture of the right size.
+    snapshot.append_color(&gdk::RGBA::TRANSPARENT, &bounds);
+    snapshot_contents(&snapshot, paintable, width as f64, height as f64);
+    let node = snapshot.to_node().unwrap();
+
+    renderer.render_texture(node, Some(&bounds))
+}
+
+/// Draws the contents of `paintable` on `snapshot`, leaving out the
+/// checkerboard of a [`Paintable`](crate::Paintable).
+fn snapshot_contents(
+    snapshot: &gtk::Snapshot,
+    paintable: &impl IsA<gdk::Paintable>,
+    width: f64,
+    height: f64,
+) {
+    match paintable.as_ref().downcast_ref::<crate::Paintable>() {
+        Some(paintable) => paintable.snapshot_without_checkerboard(snapshot, width, height),
+        None => paintable.snapshot(snapshot, width, height),
+    }
+}
@@ -0,0 +1,159 @@
+//! Helpers for drawing gauges and meters with true arcs.
+//!
+//! Angles are in radians, as in [`polar`](crate::polar). A [`Gauge`] maps
+//! values to angles along its dial, and the functions of this module draw
+//! its parts, so a meter is drawn by calling them in order: [`draw_track`]
+//! and [`fill_zone`] for the band and its colored zones, [`draw_ticks`] for
+//! the scale, and [`draw_needle`] for the value.
+
+use std::{f64::consts::PI, ops::Range};
+
+use gtk::{graphene::Point, gsk, prelude::*};
+use plotters_backend::{BackendCoord, BackendStyle};
+
+use crate::{
+    common::BackendColorExt,
+    polar::{self, arc_to, polar_to_point},
+    GtkBackend,
+};
+
+/// The dial of a gauge, mapping values to angles.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gauge {
+    /// The center of the dial.
+    pub center: BackendCoord,
+    /// The outer radius of the dial, in pixels.
+    pub radius: f64,
+    /// The angles of the start and end of the dial, where
+    /// [`Gauge::values`] start and end.
+    pub angles: Range<f64>,
+    /// The values shown from the start to the end of the dial.
+    pub values: Range<f64>,
+}
+
+impl Gauge {
+    /// Creates a gauge with the common dial of three quarters of a turn,
+    /// from the bottom left clockwise to the bottom right.
+    pub fn new(center: BackendCoord, radius: f64, values: Range<f64>) -> Self {
+        Self {
+            center,
+            radius,
+            angles: 1.25 * PI..-0.25 * PI,
+            values,
+        }
+    }
+
+    /// Returns the angle of `value`, clamped to the dial.
+    pub fn angle_of(&self, value: f64) -> f64 {
+        let span = self.values.end - self.values.start;
+        let t = if span == 0.0 {
+            0.0
+        } else {
+            ((value - self.values.start) / span).clamp(0.0, 1.0)
+        };
+        self.angles.start + t * (self.angles.end - self.angles.start)
+    }
+}
+
+/// Fills the band of the whole dial, `width` pixels inside of its radius,
+/// as the track behind the zones or a progress arc.
+pub fn draw_track<B: GtkBackend, S: BackendStyle>(
+    backend: &mut B,
+    gauge: &Gauge,
+    width: f64,
+    style: &S,
+) {
+    fill_zone(backend, gauge, gauge.values.clone(), width, style);
+}
+
+/// Fills the band of the dial from `values.start` to `values.end`, `width`
+/// pixels inside of its radius, such as for green, yellow, and red zones.
+pub fn fill_zone<B: GtkBackend, S: BackendStyle>(
+    backend: &mut B,
+    gauge: &Gauge,
+    values: Range<f64>,
+    width: f64,
+    style: &S,
+) {
+    polar::fill_annulus_sector(
+        backend,
+        gauge.center,
+        (gauge.radius - width).max(0.0)..gauge.radius,
+        gauge.angle_of(values.start)..gauge.angle_of(values.end),
+        style,
+    );
+}
+
+/// Draws `n_ticks` evenly-spaced ticks along the dial, including at its
+/// start and end, each `length` pixels long inward from the radius.
+pub fn draw_ticks<B: GtkBackend, S: BackendStyle>(
+    backend: &mut B,
+    gauge: &Gauge,
+    n_ticks: usize,
+    length: f64,
+    style: &S,
+) {
+    let path_builder = gsk::PathBuilder::new();
+
+    for tick in 0..n_ticks {
+        let t = if n_ticks < 2 {
+            0.0
+        } else {
+            tick as f64 / (n_ticks - 1) as f64
+        };
+        let angle = gauge.angles.start + t * (gauge.angles.end - gauge.angles.start);
+
+        let (x, y) = polar_to_point(gauge.center, gauge.radius, angle);
+        path_builder.move_to(x, y);
+        let (x, y) = polar_to_point(gauge.center, (gauge.radius - length).max(0.0), angle);
+        path_builder.line_to(x, y);
+    }
+
+    let stroke = gsk::Stroke::new(backend.stroke_width(style));
+    backend.gtk_snapshot().append_stroke(
+        &path_builder.to_path(),
+        &stroke,
+        &style.color().to_rgba(),
+    );
+}
+
+/// Draws a needle pointing at `value` from the center, tapering from
+/// `width` pixels at the hub to a point `length` pixels away, with a round
+/// hub of the same width.
+pub fn draw_needle<B: GtkBackend, S: BackendStyle>(
+    backend: &mut B,
+    gauge: &Gauge,
+    value: f64,
+    length: f64,
+    width: f64,
+    style: &S,
+) {
+    let angle = gauge.angle_of(value);
+    let center = gauge.center;
+    let half_width = width / 2.0;
+
+    let path_builder = gsk::PathBuilder::new();
+
+    let (x, y) = polar_to_point(center, length, angle);
+    path_builder.move_to(x, y);
+    let (x, y) = polar_to_point(center, half_width, angle - PI / 2.0);
+    path_builder.line_to(x, y);
+    arc_to(
+        &path_builder,
+        center,
+        half_width,
+        angle - PI / 2.0,
+        angle - 1.5 * PI,
+    );
+    path_builder.close();
+    path_builder.add_circle(
+        &Point::new(center.0 as f32, center.1 as f32),
+        half_width as f32,
+    );
+
+    backend.gtk_snapshot().append_fill(
+        &path_builder.to_path(),
+        gsk::FillRule::Winding,
+        &style.color().to_rgba(),
+    );
+}
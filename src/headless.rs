@@ -0,0 +1,139 @@
+//! Rendering of plots without a display, such as in tests, command-line
+//! tools, and servers generating chart images.
+//!
+//! Drawing with [`SnapshotBackend`] and [`PaintableBackend`] and rendering
+//! with the Cairo renderer need no windowing system, but the GTK bindings
+//! refuse to be used before GTK is initialized, which fails without a
+//! display. Call [`init`] instead of [`gtk::init`] to initialize GTK for
+//! offscreen rendering either way. Widgets cannot be used without a display.
+//!
+//! [`PaintableBackend`]: crate::PaintableBackend
+
+use gtk::{cairo, gdk, glib, graphene::Rect, gsk, prelude::*};
+
+use crate::{export, SnapshotBackend};
+
+/// Initializes GTK on the current thread, falling back to offscreen
+/// rendering only if no display can be opened.
+///
+/// This does nothing if GTK is already initialized on the current thread.
+pub fn init() -> Result<(), glib::BoolError> {
+    if gtk::init().is_ok() {
+        return Ok(());
+    }
+
+    // GTK sets itself up before trying to open the display, and only tries
+    // once, so trying again completes the initialization without a display.
+    gtk::init()
+}
+
+/// Draws with a [`SnapshotBackend`] of `size` and renders it into a texture
+/// with the Cairo renderer.
+pub fn render_to_texture(
+    size: (u32, u32),
+    draw: impl FnOnce(SnapshotBackend),
+) -> Result<gdk::Texture, glib::Error> {
+    let renderer = gsk::CairoRenderer::new();
+    renderer.realize(None)?;
+
+    let (width, height) = size;
+    let bounds = Rect::new(0.0, 0.0, width.max(1) as f32, height.max(1) as f32);
+
+    // Ensure that empty drawings still render to a texture of the right size.
+    let snapshot = gtk::Snapshot::new();
+    snapshot.append_color(&gdk::RGBA::TRANSPARENT, &bounds);
+    draw(SnapshotBackend::new(&snapshot, size));
+    let texture = renderer.render_texture(snapshot.to_node().unwrap(), Some(&bounds));
+    renderer.unrealize();
+
+    Ok(texture)
+}
+
+/// Draws with a [`SnapshotBackend`] of `size` and renders it into a PNG
+/// image.
+pub fn render_to_png(
+    size: (u32, u32),
+    draw: impl FnOnce(SnapshotBackend),
+) -> Result<glib::Bytes, glib::Error> {
+    Ok(render_to_texture(size, draw)?.save_to_png_bytes())
+}
+
+/// Draws with a [`SnapshotBackend`] of `size` and renders it into an SVG
+/// document.
+pub fn render_to_svg(
+    size: (u32, u32),
+    draw: impl FnOnce(SnapshotBackend),
+) -> Result<Vec<u8>, cairo::Error> {
+    let snapshot = gtk::Snapshot::new();
+    draw(SnapshotBackend::new(&snapshot, size));
+    export::render_node_to_svg(snapshot.to_node().as_ref(), size)
+}
+
+/// Runs `f` on the thread GTK is initialized on with [`init`].
+///
+/// GTK can only be used from the thread it was initialized on, while tests
+/// run on a thread of their own.
+#[cfg(test)]
+pub(crate) fn test_synced<R: Send + 'static>(
+    f: impl FnOnce() -> R + Send + std::panic::UnwindSafe + 'static,
+) -> R {
+    use std::{
+        panic,
+        sync::{mpsc, OnceLock},
+    };
+
+    static THREAD_POOL: OnceLock<glib::ThreadPool> = OnceLock::new();
+
+    let pool = THREAD_POOL.get_or_init(|| {
+        let pool = glib::ThreadPool::exclusive(1).unwrap();
+        pool.push(|| init().expect("Failed to initialize GTK"))
+            .unwrap();
+        pool
+    });
+
+    let (tx, rx) = mpsc::sync_channel(1);
+    pool.push(move || {
+        let _ = tx.send(panic::catch_unwind(f));
+    })
+    .unwrap();
+    rx.recv()
+        .unwrap()
+        .unwrap_or_else(|err| panic::resume_unwind(err))
+}
+
+#[cfg(test)]
+mod tests {
+    use plotters::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn render_to_png_draws_chart() {
+        test_synced(|| {
+            let bytes = render_to_png((64, 48), |backend| {
+                let root = backend.into_drawing_area();
+                root.fill(&RED).unwrap();
+                root.present().unwrap();
+            })
+            .unwrap();
+
+            let texture = gdk::Texture::from_bytes(&bytes).unwrap();
+            assert_eq!((texture.width(), texture.height()), (64, 48));
+
+            let mut pixels = vec![0; 64 * 48 * 4];
+            texture.download(&mut pixels, 64 * 4);
+            // The default memory format of downloads is premultiplied BGRA.
+            assert!(pixels.chunks(4).all(|pixel| pixel == [0, 0, 255, 255]));
+        });
+    }
+
+    #[test]
+    fn render_to_png_of_empty_drawing_keeps_size() {
+        test_synced(|| {
+            let bytes = render_to_png((10, 20), |_| {}).unwrap();
+
+            let texture = gdk::Texture::from_bytes(&bytes).unwrap();
+            assert_eq!((texture.width(), texture.height()), (10, 20));
+        });
+    }
+}
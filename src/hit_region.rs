@@ -0,0 +1,302 @@
+use std::collections::{HashMap, HashSet};
+
+use gtk::{
+    gdk, glib,
+    graphene::{self, Point, Rect},
+    gsk,
+    prelude::*,
+};
+
+use crate::RendererCaps;
+
+/// The prefix of the message of the debug nodes wrapping hit regions,
+/// followed by the ID of their tag.
+const HIT_REGION_PREFIX: &str = "plotters-gtk4:hit:";
+
+/// What a clickable region of a plot does when it is clicked in a
+/// [`PlotView`](crate::PlotView).
+///
+/// This is set with [`PaintableBackend::with_link`].
+///
+/// [`PaintableBackend::with_link`]: crate::PaintableBackend::with_link
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Link {
+    /// Opens the URI with the default app for it, such as the web page of
+    /// the source of the data.
+    Uri(String),
+    /// Activates the action of the detailed name, such as
+    /// `win.configure-axis::x`, looked up in the action groups of the view
+    /// and its ancestors.
+    Action(String),
+}
+
+/// A region of a plot drawn within [`PaintableBackend::with_link`],
+/// [`PaintableBackend::with_data`], or [`PaintableBackend::with_id`].
+///
+/// This is returned by [`PlotView::pick`], [`Paintable::hit_region_at`], and
+/// [`Paintable::hit_regions`].
+///
+/// [`PaintableBackend::with_link`]: crate::PaintableBackend::with_link
+/// [`PaintableBackend::with_data`]: crate::PaintableBackend::with_data
+/// [`PaintableBackend::with_id`]: crate::PaintableBackend::with_id
+/// [`PlotView::pick`]: crate::PlotView::pick
+/// [`Paintable::hit_region_at`]: crate::Paintable::hit_region_at
+/// [`Paintable::hit_regions`]: crate::Paintable::hit_regions
+#[derive(Debug, Clone)]
+pub struct HitRegion {
+    /// The bounds of what was drawn within the region, in pixels of the
+    /// paintable.
+    pub bounds: Rect,
+    /// What the region does when it is clicked, if anything.
+    pub link: Option<Link>,
+    /// The data the region was tagged with, such as the index of a bar or
+    /// the domain object it stands for, if any.
+    pub data: Option<glib::Value>,
+    /// The ID of the element drawn within the region, such as the key of a
+    /// data point, if any.
+    pub id: Option<String>,
+    /// What was drawn within the region, to be drawn again highlighted.
+    pub(crate) node: gsk::RenderNode,
+    /// The transform and clip, in pixels of the paintable, that the node
+    /// is drawn with.
+    pub(crate) transform: gsk::Transform,
+    pub(crate) clip: Option<Rect>,
+}
+
+/// How the region under the pointer is highlighted in a
+/// [`PlotView`](crate::PlotView).
+///
+/// This is set with [`PlotView::set_hover_highlight`]. The region is drawn
+/// again above the plot with the effect, without redrawing the plot.
+///
+/// [`PlotView::set_hover_highlight`]: crate::PlotView::set_hover_highlight
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Highlight {
+    /// Mixes what was drawn with white by the amount, from `0.0` for none to
+    /// `1.0` for white.
+    Brighten(f32),
+    /// An outline of the color and width, in pixels, around what was drawn,
+    /// or around its bounds if the renderer does not support masks.
+    Outline { color: gdk::RGBA, width: f32 },
+}
+
+impl Highlight {
+    /// Appends `region` with the highlight to `snapshot`, in pixels of the
+    /// paintable.
+    pub(crate) fn append_to(
+        &self,
+        snapshot: &gtk::Snapshot,
+        region: &HitRegion,
+        renderer_caps: RendererCaps,
+    ) {
+        if let Some(clip) = &region.clip {
+            snapshot.push_clip(clip);
+        }
+        snapshot.save();
+        snapshot.transform(Some(&region.transform));
+
+        let node = &region.node;
+        match *self {
+            Self::Brighten(amount) => {
+                let amount = amount.clamp(0.0, 1.0);
+                if renderer_caps.masks {
+                    // Only the white is drawn, where the node is opaque, as
+                    // the node itself is already drawn beneath.
+                    snapshot.push_mask(gsk::MaskMode::Alpha);
+                    snapshot.append_node(node);
+                    snapshot.pop();
+                    snapshot.append_color(&gdk::RGBA::new(1.0, 1.0, 1.0, amount), &node.bounds());
+                    snapshot.pop();
+                } else {
+                    let scale = 1.0 - amount;
+                    snapshot.push_color_matrix(
+                        &graphene::Matrix::from_scale(scale, scale, scale),
+                        &graphene::Vec4::new(amount, amount, amount, 0.0),
+                    );
+                    snapshot.append_node(node);
+                    snapshot.pop();
+                }
+            }
+            Self::Outline { color, width } => {
+                let bounds = node.bounds().inset_r(-width, -width);
+                if renderer_caps.masks {
+                    // The node moved in every direction by the width, with
+                    // the node itself cut out, leaves a ring around it.
+                    snapshot.push_mask(gsk::MaskMode::InvertedAlpha);
+                    snapshot.append_node(node);
+                    snapshot.pop();
+                    snapshot.push_mask(gsk::MaskMode::Alpha);
+                    for (dx, dy) in OUTLINE_DIRECTIONS {
+                        snapshot.save();
+                        snapshot.translate(&Point::new(dx * width, dy * width));
+                        snapshot.append_node(node);
+                        snapshot.restore();
+                    }
+                    snapshot.pop();
+                    snapshot.append_color(&color, &bounds);
+                    snapshot.pop();
+                    snapshot.pop();
+                } else {
+                    let path_builder = gsk::PathBuilder::new();
+                    path_builder.add_rect(&bounds);
+                    snapshot.append_stroke(
+                        &path_builder.to_path(),
+                        &gsk::Stroke::new(width),
+                        &color,
+                    );
+                }
+            }
+        }
+
+        snapshot.restore();
+        if region.clip.is_some() {
+            snapshot.pop();
+        }
+    }
+}
+
+/// The directions the node is moved in to draw an outline around it.
+const OUTLINE_DIRECTIONS: [(f32, f32); 8] = [
+    (-1.0, -1.0),
+    (0.0, -1.0),
+    (1.0, -1.0),
+    (-1.0, 0.0),
+    (1.0, 0.0),
+    (-1.0, 1.0),
+    (0.0, 1.0),
+    (1.0, 1.0),
+];
+
+/// What is known about a hit region apart from its bounds, which are only
+/// known once the frame is drawn.
+///
+/// Regions within other regions inherit what they do not set themselves.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct HitTag {
+    pub(crate) link: Option<Link>,
+    pub(crate) data: Option<glib::Value>,
+    pub(crate) id: Option<String>,
+}
+
+/// Pushes a hit region of the tag of `id` onto `snapshot`, covering what is
+/// drawn until the matching pop.
+pub(crate) fn push_hit_region(snapshot: &gtk::Snapshot, id: u64) {
+    snapshot.push_debug(&format!("{HIT_REGION_PREFIX}{id}"));
+}
+
+/// Returns the hit regions within `node` whose tags are in `tags`, in the
+/// order they are drawn.
+///
+/// The bounds of the regions are transformed by the transforms and clipped
+/// by the clips they are drawn within.
+pub(crate) fn hit_regions(node: &gsk::RenderNode, tags: &HashMap<u64, HitTag>) -> Vec<HitRegion> {
+    let mut regions = Vec::new();
+    if !tags.is_empty() {
+        collect(node, &gsk::Transform::new(), None, tags, &mut regions);
+    }
+    regions
+}
+
+/// Returns the IDs of the tags of the hit regions within `node`.
+pub(crate) fn hit_ids(node: &gsk::RenderNode) -> HashSet<u64> {
+    let mut ids = HashSet::new();
+    collect_ids(node, &mut ids);
+    ids
+}
+
+fn collect_ids(node: &gsk::RenderNode, ids: &mut HashSet<u64>) {
+    let child = match node.node_type() {
+        gsk::RenderNodeType::ContainerNode => {
+            let container = node.downcast_ref::<gsk::ContainerNode>().unwrap();
+            for index in 0..container.n_children() {
+                collect_ids(&container.child(index), ids);
+            }
+            return;
+        }
+        gsk::RenderNodeType::TransformNode => {
+            node.downcast_ref::<gsk::TransformNode>().unwrap().child()
+        }
+        gsk::RenderNodeType::ClipNode => node.downcast_ref::<gsk::ClipNode>().unwrap().child(),
+        gsk::RenderNodeType::OpacityNode => {
+            node.downcast_ref::<gsk::OpacityNode>().unwrap().child()
+        }
+        gsk::RenderNodeType::DebugNode => {
+            let debug_node = node.downcast_ref::<gsk::DebugNode>().unwrap();
+            if let Some(id) = debug_node
+                .message()
+                .strip_prefix(HIT_REGION_PREFIX)
+                .and_then(|id| id.parse::<u64>().ok())
+            {
+                ids.insert(id);
+            }
+            debug_node.child()
+        }
+        _ => return,
+    };
+    collect_ids(&child, ids);
+}
+
+fn collect(
+    node: &gsk::RenderNode,
+    transform: &gsk::Transform,
+    clip: Option<Rect>,
+    tags: &HashMap<u64, HitTag>,
+    regions: &mut Vec<HitRegion>,
+) {
+    match node.node_type() {
+        gsk::RenderNodeType::ContainerNode => {
+            let container = node.downcast_ref::<gsk::ContainerNode>().unwrap();
+            for index in 0..container.n_children() {
+                collect(&container.child(index), transform, clip, tags, regions);
+            }
+        }
+        gsk::RenderNodeType::TransformNode => {
+            let transform_node = node.downcast_ref::<gsk::TransformNode>().unwrap();
+            let transform = transform
+                .clone()
+                .transform(Some(&transform_node.transform()));
+            collect(&transform_node.child(), &transform, clip, tags, regions);
+        }
+        gsk::RenderNodeType::ClipNode => {
+            let clip_node = node.downcast_ref::<gsk::ClipNode>().unwrap();
+            let node_clip = transform.transform_bounds(clip_node.clip());
+            let Some(clip) = clip.map_or(Some(node_clip), |clip| clip.intersection(&node_clip))
+            else {
+                return;
+            };
+            collect(&clip_node.child(), transform, Some(clip), tags, regions);
+        }
+        gsk::RenderNodeType::OpacityNode => {
+            let opacity_node = node.downcast_ref::<gsk::OpacityNode>().unwrap();
+            collect(&opacity_node.child(), transform, clip, tags, regions);
+        }
+        gsk::RenderNodeType::DebugNode => {
+            let debug_node = node.downcast_ref::<gsk::DebugNode>().unwrap();
+            let child = debug_node.child();
+
+            let tag = debug_node
+                .message()
+                .strip_prefix(HIT_REGION_PREFIX)
+                .and_then(|id| id.parse::<u64>().ok())
+                .and_then(|id| tags.get(&id));
+            if let Some(tag) = tag {
+                let bounds = transform.transform_bounds(&child.bounds());
+                let bounds = clip.map_or(Some(bounds), |clip| clip.intersection(&bounds));
+                if let Some(bounds) = bounds {
+                    regions.push(HitRegion {
+                        bounds,
+                        link: tag.link.clone(),
+                        data: tag.data.clone(),
+                        id: tag.id.clone(),
+                        node: child.clone(),
+                        transform: transform.clone(),
+                        clip,
+                    });
+                }
+            }
+
+            collect(&child, transform, clip, tags, regions);
+        }
+        _ => {}
+    }
+}
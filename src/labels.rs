@@ -0,0 +1,224 @@
+//! Tick label formatting that follows the locale of the user.
+//!
+//! The formatters returned by [`number_formatter`] and
+//! [`unix_time_formatter`] can be passed to the label formatters of a
+//! plotters chart, such as [`MeshStyle::x_label_formatter`], so that tick
+//! labels match the numbers and dates shown by the rest of the app.
+//!
+//! Dates are formatted with [`glib::DateTime`] in the local time zone.
+//! GTK sets the locale on initialization, so this must be used after
+//! [`gtk::init`].
+//!
+//! Numeric labels drawn by plotters are anchored by their measured width,
+//! which differs between numbers in fonts with proportional digits, so the
+//! labels of a y axis end up ragged. Draw them with [`draw_right_aligned`]
+//! instead, such as after disabling the labels of the axis with
+//! `y_labels(0)` and computing their positions with the coordinate
+//! transform of the chart.
+//!
+//! [`MeshStyle::x_label_formatter`]: plotters::chart::MeshStyle::x_label_formatter
+
+use std::{convert::Infallible, sync::OnceLock};
+
+use gtk::glib;
+use plotters::style::{
+    text_anchor::{HPos, Pos, VPos},
+    TextStyle,
+};
+use plotters_backend::{DrawingBackend, DrawingErrorKind};
+
+use crate::{GtkBackend, TextAttributes, TextExtents};
+
+/// The separators of formatted numbers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NumberFormat {
+    /// The separator between the integer and fractional parts, such as `.`
+    /// in English or `,` in German.
+    pub decimal_separator: String,
+    /// The separator between groups of three digits of the integer part,
+    /// such as `,` in English or `.` in German, or empty to not group
+    /// digits.
+    pub grouping_separator: String,
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        Self {
+            decimal_separator: ".".to_string(),
+            grouping_separator: String::new(),
+        }
+    }
+}
+
+impl NumberFormat {
+    /// Returns the number format of the locale of the user.
+    ///
+    /// GLib does not expose the numeric locale, so the separators are taken
+    /// from [`glib::format_size_full`], which formats with it, falling back
+    /// to the default format if they cannot be found.
+    pub fn from_locale() -> Self {
+        static LOCALE: OnceLock<NumberFormat> = OnceLock::new();
+
+        LOCALE
+            .get_or_init(|| {
+                let default = Self::default();
+
+                // "1.5" in English, or "1,5" in German.
+                let value = glib::format_size_full(1500, glib::FormatSizeFlags::ONLY_VALUE);
+                let decimal_separator = value
+                    .strip_prefix('1')
+                    .and_then(|rest| rest.strip_suffix('5'))
+                    .filter(|separator| !separator.is_empty())
+                    .map_or(default.decimal_separator, str::to_string);
+
+                // "1.5 kB (1,500 bytes)" in English, or "1,5 kB (1.500 Bytes)"
+                // in German.
+                let long = glib::format_size_full(1500, glib::FormatSizeFlags::LONG_FORMAT);
+                let grouping_separator = long
+                    .split_once('(')
+                    .and_then(|(_, rest)| rest.strip_prefix('1'))
+                    .and_then(|rest| rest.split_once("500"))
+                    .map_or(default.grouping_separator, |(separator, _)| {
+                        separator.to_string()
+                    });
+
+                Self {
+                    decimal_separator,
+                    grouping_separator,
+                }
+            })
+            .clone()
+    }
+
+    /// Formats `value` with `precision` digits after the decimal separator.
+    ///
+    /// `NaN` and infinite values are formatted as is.
+    pub fn format(&self, value: f64, precision: usize) -> String {
+        if !value.is_finite() {
+            return value.to_string();
+        }
+
+        let formatted = format!("{:.*}", precision, value.abs());
+        let (integer, fraction) = formatted
+            .split_once('.')
+            .map_or((formatted.as_str(), None), |(integer, fraction)| {
+                (integer, Some(fraction))
+            });
+
+        let mut ret = String::with_capacity(formatted.len() + integer.len() / 3);
+
+        // Avoid "-0" for values that round to zero.
+        if value.is_sign_negative() && formatted.bytes().any(|b| matches!(b, b'1'..=b'9')) {
+            ret.push('-');
+        }
+
+        for (index, digit) in integer.chars().enumerate() {
+            if index > 0 && (integer.len() - index) % 3 == 0 {
+                ret.push_str(&self.grouping_separator);
+            }
+            ret.push(digit);
+        }
+
+        if let Some(fraction) = fraction {
+            ret.push_str(&self.decimal_separator);
+            ret.push_str(fraction);
+        }
+
+        ret
+    }
+}
+
+/// A format of dates and times.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub enum DateTimeFormat {
+    /// The date in the preferred format of the locale, such as `12/31/24`.
+    Date,
+    /// The time in the preferred format of the locale, such as `23:59:59`.
+    Time,
+    /// The date and time in the preferred format of the locale.
+    #[default]
+    DateTime,
+    /// A custom format of [`glib::DateTime::format`], such as `"%b %Y"` for
+    /// the abbreviated month name and the year.
+    Custom(String),
+}
+
+impl DateTimeFormat {
+    fn as_str(&self) -> &str {
+        match self {
+            Self::Date => "%x",
+            Self::Time => "%X",
+            Self::DateTime => "%c",
+            Self::Custom(format) => format,
+        }
+    }
+}
+
+/// Formats `value` with `precision` digits after the decimal separator,
+/// using the number format of the locale of the user.
+pub fn format_number(value: f64, precision: usize) -> String {
+    NumberFormat::from_locale().format(value, precision)
+}
+
+/// Formats `seconds` since the Unix epoch in the local time zone.
+///
+/// This returns an empty string if `seconds` is out of the range of
+/// [`glib::DateTime`].
+pub fn format_unix_time(seconds: f64, format: &DateTimeFormat) -> String {
+    if !seconds.is_finite() {
+        return String::new();
+    }
+
+    glib::DateTime::from_unix_local_usec((seconds * 1_000_000.0) as i64)
+        .and_then(|date_time| date_time.format(format.as_str()))
+        .map(|formatted| formatted.to_string())
+        .unwrap_or_default()
+}
+
+/// Returns a label formatter of numbers with `precision` digits after the
+/// decimal separator, using the number format of the locale of the user.
+pub fn number_formatter(precision: usize) -> impl Fn(&f64) -> String {
+    let number_format = NumberFormat::from_locale();
+    move |value| number_format.format(*value, precision)
+}
+
+/// Returns a label formatter of seconds since the Unix epoch, such as the
+/// times of a [`TimeSeriesBuffer`](crate::TimeSeriesBuffer), in the local
+/// time zone.
+pub fn unix_time_formatter(format: DateTimeFormat) -> impl Fn(&f64) -> String {
+    move |seconds| format_unix_time(*seconds, &format)
+}
+
+/// Draws `labels`, each with the y coordinate it is vertically centered on,
+/// with their right edges at `x`, such as the tick labels of a y axis.
+///
+/// The labels are drawn with tabular figures, and aligned by their logical
+/// extents rather than the ink of their last glyph, so that the digits of
+/// numbers with as many decimals line up in columns in any font. They are
+/// centered with [`TextExtents::Baseline`], so that labels at the same y
+/// share a baseline. The text attributes and extents of the backend are
+/// restored afterwards.
+pub fn draw_right_aligned<B: GtkBackend>(
+    backend: &mut B,
+    labels: impl IntoIterator<Item = (impl AsRef<str>, i32)>,
+    x: i32,
+    style: &TextStyle<'_>,
+) -> Result<(), DrawingErrorKind<Infallible>> {
+    let text_attributes = backend.text_attributes();
+    let text_extents = backend.text_extents();
+    backend.set_text_attributes(TextAttributes {
+        tabular_figures: true,
+        ..text_attributes
+    });
+    backend.set_text_extents(TextExtents::Baseline);
+
+    let style = style.pos(Pos::new(HPos::Right, VPos::Center));
+    let res = labels
+        .into_iter()
+        .try_for_each(|(label, y)| backend.draw_text(label.as_ref(), &style, (x, y)));
+
+    backend.set_text_attributes(text_attributes);
+    backend.set_text_extents(text_extents);
+
+    res
+}
@@ -0,0 +1,269 @@
+use gtk::{
+    gio,
+    glib::{self, clone, closure_local},
+    prelude::*,
+    subclass::prelude::*,
+};
+
+use crate::{Series, SeriesMarker};
+
+const MARKER_SIZE: i32 = 16;
+
+mod imp {
+    use std::{
+        cell::{OnceCell, RefCell},
+        sync::OnceLock,
+    };
+
+    use glib::subclass::Signal;
+
+    use super::*;
+
+    #[derive(Debug, Default)]
+    pub struct Legend {
+        pub(super) list_box: OnceCell<gtk::ListBox>,
+        pub(super) model: RefCell<Option<gio::ListModel>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for Legend {
+        const NAME: &'static str = "PlottersGtk4Legend";
+        type Type = super::Legend;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.set_css_name("legend");
+            klass.set_layout_manager_type::<gtk::BinLayout>();
+        }
+    }
+
+    impl ObjectImpl for Legend {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+
+            let list_box = gtk::ListBox::new();
+            list_box.set_selection_mode(gtk::SelectionMode::None);
+            list_box.connect_row_activated(clone!(
+                #[weak]
+                obj,
+                move |_, row| {
+                    obj.toggle_series_at(row.index());
+                }
+            ));
+            list_box.set_parent(&*obj);
+
+            self.list_box.set(list_box).unwrap();
+        }
+
+        fn dispose(&self) {
+            if let Some(list_box) = self.list_box.get() {
+                list_box.unparent();
+            }
+        }
+
+        fn properties() -> &'static [glib::ParamSpec] {
+            static PROPERTIES: OnceLock<Vec<glib::ParamSpec>> = OnceLock::new();
+
+            PROPERTIES.get_or_init(|| {
+                vec![glib::ParamSpecObject::builder::<gio::ListModel>("model")
+                    .explicit_notify()
+                    .build()]
+            })
+        }
+
+        fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+            match pspec.name() {
+                "model" => {
+                    let model = value.get::<Option<gio::ListModel>>().unwrap();
+                    self.obj().set_model(model.as_ref());
+                }
+                _ => unimplemented!(),
+            }
+        }
+
+        fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            match pspec.name() {
+                "model" => self.obj().model().into(),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+
+            SIGNALS.get_or_init(|| {
+                vec![Signal::builder("series-toggled")
+                    .param_types([Series::static_type()])
+                    .build()]
+            })
+        }
+    }
+
+    impl WidgetImpl for Legend {}
+}
+
+glib::wrapper! {
+    /// A widget that lists the [`Series`] of a plot.
+    ///
+    /// Activating a row toggles the visibility of its series and emits
+    /// `series-toggled`.
+    ///
+    /// This can be used on GTK UI files using its type name `PlottersGtk4Legend`.
+    pub struct Legend(ObjectSubclass<imp::Legend>)
+        @extends gtk::Widget;
+}
+
+impl Legend {
+    /// Creates a new legend showing the series in `model`.
+    ///
+    /// This is usually the model returned by [`Paintable::series`](crate::Paintable::series).
+    pub fn new(model: Option<&impl IsA<gio::ListModel>>) -> Self {
+        let obj = glib::Object::new::<Self>();
+        obj.set_model(model);
+        obj
+    }
+
+    /// Sets the model of [`Series`] to show.
+    pub fn set_model(&self, model: Option<&impl IsA<gio::ListModel>>) {
+        let model = model.map(|model| model.as_ref().clone());
+
+        if model == self.model() {
+            return;
+        }
+
+        self.list_box().bind_model(model.as_ref(), |item| {
+            let series = item.downcast_ref::<Series>().unwrap();
+            row_for_series(series).upcast()
+        });
+
+        self.imp().model.replace(model);
+        self.notify("model");
+    }
+
+    /// Returns the model of [`Series`] shown.
+    pub fn model(&self) -> Option<gio::ListModel> {
+        self.imp().model.borrow().clone()
+    }
+
+    /// Connects to the `series-toggled` signal, emitted when a series is
+    /// toggled by activating its row.
+    pub fn connect_series_toggled<F: Fn(&Self, &Series) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "series-toggled",
+            false,
+            closure_local!(|obj: &Self, series: &Series| f(obj, series)),
+        )
+    }
+
+    fn toggle_series_at(&self, index: i32) {
+        let Some(series) = self
+            .model()
+            .and_then(|model| model.item(index as u32))
+            .and_downcast::<Series>()
+        else {
+            return;
+        };
+
+        series.set_visible(!series.is_visible());
+        self.emit_by_name::<()>("series-toggled", &[&series]);
+    }
+
+    fn list_box(&self) -> &gtk::ListBox {
+        self.imp().list_box.get().unwrap()
+    }
+}
+
+impl Default for Legend {
+    fn default() -> Self {
+        glib::Object::new()
+    }
+}
+
+fn row_for_series(series: &Series) -> gtk::Widget {
+    let marker = gtk::DrawingArea::builder()
+        .content_width(MARKER_SIZE)
+        .content_height(MARKER_SIZE)
+        .valign(gtk::Align::Center)
+        .build();
+    marker.set_draw_func(clone!(
+        #[weak]
+        series,
+        move |_, cr, width, height| {
+            draw_marker(cr, &series, width as f64, height as f64);
+        }
+    ));
+
+    let label = gtk::Label::builder()
+        .label(series.name())
+        .xalign(0.0)
+        .hexpand(true)
+        .build();
+
+    let hbox = gtk::Box::builder()
+        .orientation(gtk::Orientation::Horizontal)
+        .spacing(6)
+        .margin_top(6)
+        .margin_bottom(6)
+        .margin_start(6)
+        .margin_end(6)
+        .build();
+    hbox.append(&marker);
+    hbox.append(&label);
+
+    series
+        .bind_property("visible", &hbox, "opacity")
+        .transform_to(|_, is_visible: bool| Some(if is_visible { 1.0 } else { 0.4 }))
+        .sync_create()
+        .build();
+
+    hbox.upcast()
+}
+
+fn draw_marker(cr: &gtk::cairo::Context, series: &Series, width: f64, height: f64) {
+    let color = series.color();
+    cr.set_source_rgba(
+        color.red() as f64,
+        color.green() as f64,
+        color.blue() as f64,
+        color.alpha() as f64,
+    );
+    cr.set_line_width(2.0);
+
+    let (cx, cy) = (width / 2.0, height / 2.0);
+    let r = width.min(height) / 2.0 - 2.0;
+
+    match series.marker() {
+        SeriesMarker::Line => {
+            cr.move_to(0.0, cy);
+            cr.line_to(width, cy);
+            let _ = cr.stroke();
+        }
+        SeriesMarker::Circle => {
+            cr.arc(cx, cy, r, 0.0, std::f64::consts::TAU);
+            let _ = cr.fill();
+        }
+        SeriesMarker::Square => {
+            cr.rectangle(cx - r, cy - r, 2.0 * r, 2.0 * r);
+            let _ = cr.fill();
+        }
+        SeriesMarker::Triangle => {
+            cr.move_to(cx, cy - r);
+            cr.line_to(cx + r, cy + r);
+            cr.line_to(cx - r, cy + r);
+            cr.close_path();
+            let _ = cr.fill();
+        }
+        SeriesMarker::Cross => {
+            cr.move_to(cx - r, cy - r);
+            cr.line_to(cx + r, cy + r);
+            cr.move_to(cx + r, cy - r);
+            cr.line_to(cx - r, cy + r);
+            let _ = cr.stroke();
+        }
+    }
+}
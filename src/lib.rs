@@ -2,11 +2,83 @@
 #![deny(rustdoc::broken_intra_doc_links)]
 #![doc = include_str!("../README.md")]
 
+mod annotation;
+pub mod appearance;
+pub mod axis_break;
+mod backend;
+pub mod bulk;
+pub mod colormaps;
 mod common;
+mod context;
+mod error;
+pub mod export;
+pub mod gauges;
+pub mod headless;
+mod hit_region;
+pub mod labels;
+mod legend;
+mod live_paintable;
+mod minimap;
+mod optimize;
 mod paintable;
+pub mod palette;
+#[cfg(feature = "parity")]
+pub mod parity;
+#[cfg(feature = "pdf")]
+pub mod pdf;
+mod plot_factory;
+mod plot_menu;
+mod plot_source;
+mod plot_view;
+pub mod polar;
+mod quality;
+mod range_model;
+pub mod recording;
+mod renderer_caps;
+mod secondary_axis;
+mod selection_model;
+mod series;
 mod snapshot;
+mod sparkline;
+mod strip_chart;
+mod style;
+mod tile_cache;
+mod time_series;
+mod transition;
+#[cfg(feature = "gst")]
+pub mod video;
+mod watermark;
 
 pub use self::{
-    paintable::{Paintable, PaintableBackend},
+    annotation::{Annotation, AnnotationId, Annotations},
+    backend::{BoxedBackend, GtkBackend, SnapshotScope},
+    context::BackendContext,
+    error::{InvalidInputError, InvalidInputPolicy, InvalidRecordingError, InvalidSizeError},
+    hit_region::{Highlight, HitRegion, Link},
+    legend::Legend,
+    live_paintable::LivePaintable,
+    minimap::Minimap,
+    optimize::OptimizationStats,
+    paintable::{Paintable, PaintableBackend, RenderStats},
+    plot_factory::PlotFactory,
+    plot_menu::PlotMenu,
+    plot_source::{PlotSource, Points},
+    plot_view::{BrushMode, PlotView, TrackingPoint},
+    quality::Quality,
+    range_model::RangeModel,
+    renderer_caps::RendererCaps,
+    secondary_axis::SecondaryAxisLayout,
+    selection_model::PlotSelectionModel,
+    series::{Series, SeriesMarker},
     snapshot::SnapshotBackend,
+    sparkline::Sparkline,
+    strip_chart::StripChart,
+    style::{
+        Background, ClassStyle, Hairline, HairlineStyle, StyleClasses, TextAttributes,
+        TextCoverage, TextExtents, TextHalo,
+    },
+    tile_cache::TileCache,
+    time_series::TimeSeriesBuffer,
+    transition::Easing,
+    watermark::{Watermark, WatermarkContent},
 };
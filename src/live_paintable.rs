@@ -0,0 +1,296 @@
+use std::{rc::Rc, time::Duration};
+
+use gtk::{
+    gdk,
+    glib::{self, clone},
+    prelude::*,
+    subclass::prelude::*,
+};
+
+use crate::Paintable;
+
+/// The default interval between redraws, about 30 per second.
+const DEFAULT_INTERVAL: Duration = Duration::from_millis(33);
+
+type DrawFunc = Rc<dyn Fn(&Paintable, Duration)>;
+
+mod imp {
+    use std::{
+        cell::{Cell, OnceCell, RefCell},
+        fmt,
+        sync::OnceLock,
+    };
+
+    use super::*;
+
+    pub struct LivePaintable {
+        pub(super) paintable: OnceCell<Paintable>,
+        pub(super) paintable_handler: RefCell<Option<glib::SignalHandlerId>>,
+        pub(super) draw_func: RefCell<Option<DrawFunc>>,
+        pub(super) interval: Cell<Duration>,
+        pub(super) tick_source: RefCell<Option<glib::SourceId>>,
+        /// The elapsed time when last paused.
+        pub(super) elapsed: Cell<Duration>,
+        /// The monotonic time when last played.
+        pub(super) play_time: Cell<Option<i64>>,
+    }
+
+    impl Default for LivePaintable {
+        fn default() -> Self {
+            Self {
+                paintable: OnceCell::default(),
+                paintable_handler: RefCell::default(),
+                draw_func: RefCell::default(),
+                interval: Cell::new(DEFAULT_INTERVAL),
+                tick_source: RefCell::default(),
+                elapsed: Cell::default(),
+                play_time: Cell::default(),
+            }
+        }
+    }
+
+    impl fmt::Debug for LivePaintable {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("LivePaintable")
+                .field("paintable", &self.paintable)
+                .field("interval", &self.interval)
+                .field("is_playing", &self.play_time.get().is_some())
+                .finish_non_exhaustive()
+        }
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for LivePaintable {
+        const NAME: &'static str = "PlottersGtk4LivePaintable";
+        type Type = super::LivePaintable;
+        type Interfaces = (gdk::Paintable,);
+    }
+
+    impl ObjectImpl for LivePaintable {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+            let handler_id = obj.paintable().connect_invalidate_contents(clone!(
+                #[weak]
+                obj,
+                move |_| {
+                    obj.invalidate_contents();
+                }
+            ));
+            self.paintable_handler.replace(Some(handler_id));
+        }
+
+        fn dispose(&self) {
+            let obj = self.obj();
+            obj.pause();
+
+            if let Some(handler_id) = self.paintable_handler.take() {
+                obj.paintable().disconnect(handler_id);
+            }
+        }
+
+        fn properties() -> &'static [glib::ParamSpec] {
+            static PROPERTIES: OnceLock<Vec<glib::ParamSpec>> = OnceLock::new();
+
+            PROPERTIES.get_or_init(|| {
+                vec![
+                    glib::ParamSpecObject::builder::<Paintable>("paintable")
+                        .construct_only()
+                        .build(),
+                    glib::ParamSpecBoolean::builder("playing")
+                        .explicit_notify()
+                        .build(),
+                ]
+            })
+        }
+
+        fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+            match pspec.name() {
+                "paintable" => {
+                    let paintable = value.get().unwrap();
+                    self.paintable.set(paintable).unwrap();
+                }
+                "playing" => {
+                    let is_playing = value.get().unwrap();
+                    self.obj().set_playing(is_playing);
+                }
+                _ => unimplemented!(),
+            }
+        }
+
+        fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            match pspec.name() {
+                "paintable" => self.obj().paintable().into(),
+                "playing" => self.obj().is_playing().into(),
+                _ => unimplemented!(),
+            }
+        }
+    }
+
+    impl PaintableImpl for LivePaintable {
+        fn snapshot(&self, snapshot: &gdk::Snapshot, width: f64, height: f64) {
+            self.obj().paintable().snapshot(snapshot, width, height);
+        }
+
+        fn flags(&self) -> gdk::PaintableFlags {
+            gdk::PaintableFlags::SIZE
+        }
+
+        fn intrinsic_width(&self) -> i32 {
+            self.obj().paintable().intrinsic_width()
+        }
+
+        fn intrinsic_height(&self) -> i32 {
+            self.obj().paintable().intrinsic_height()
+        }
+    }
+}
+
+glib::wrapper! {
+    /// A paintable that redraws a [`Paintable`] periodically while playing,
+    /// like the paintable of a video.
+    ///
+    /// This is for plots that change continuously rather than on events,
+    /// such as meters and gauges polling a data source. The draw function is
+    /// called on every tick with the time played so far, and is expected to
+    /// read the latest data and draw it on the paintable.
+    ///
+    /// This can be used on GTK UI files using its type name
+    /// `PlottersGtk4LivePaintable`.
+    pub struct LivePaintable(ObjectSubclass<imp::LivePaintable>)
+        @implements gdk::Paintable;
+}
+
+impl LivePaintable {
+    /// Creates a new live paintable that redraws `paintable`.
+    ///
+    /// This is paused until [`LivePaintable::play`] is called.
+    pub fn new(paintable: &Paintable) -> Self {
+        glib::Object::builder()
+            .property("paintable", paintable)
+            .build()
+    }
+
+    /// Returns the paintable that is redrawn.
+    pub fn paintable(&self) -> &Paintable {
+        self.imp().paintable.get().unwrap()
+    }
+
+    /// Sets the function called on every tick to redraw the paintable, with
+    /// the time played so far.
+    pub fn set_draw_func(&self, draw_func: impl Fn(&Paintable, Duration) + 'static) {
+        self.imp().draw_func.replace(Some(Rc::new(draw_func)));
+    }
+
+    /// Sets the interval between redraws.
+    ///
+    /// This defaults to 33 ms, about 30 redraws per second.
+    pub fn set_interval(&self, interval: Duration) {
+        let imp = self.imp();
+
+        if interval == imp.interval.get() {
+            return;
+        }
+
+        imp.interval.set(interval);
+
+        if self.is_playing() {
+            self.start_ticking();
+        }
+    }
+
+    /// Returns the interval between redraws.
+    pub fn interval(&self) -> Duration {
+        self.imp().interval.get()
+    }
+
+    /// Starts redrawing periodically, drawing the first frame right away.
+    pub fn play(&self) {
+        self.set_playing(true);
+    }
+
+    /// Stops redrawing, keeping the last drawn frame.
+    pub fn pause(&self) {
+        self.set_playing(false);
+    }
+
+    /// Sets whether the paintable is redrawn periodically.
+    pub fn set_playing(&self, is_playing: bool) {
+        let imp = self.imp();
+
+        if is_playing == self.is_playing() {
+            return;
+        }
+
+        if is_playing {
+            imp.play_time.set(Some(glib::monotonic_time()));
+            self.start_ticking();
+            self.tick();
+        } else {
+            imp.elapsed.set(self.elapsed());
+            imp.play_time.set(None);
+            if let Some(source_id) = imp.tick_source.take() {
+                source_id.remove();
+            }
+        }
+
+        self.notify("playing");
+    }
+
+    /// Returns whether the paintable is redrawn periodically.
+    pub fn is_playing(&self) -> bool {
+        self.imp().play_time.get().is_some()
+    }
+
+    /// Returns the time played so far, excluding the time paused.
+    pub fn elapsed(&self) -> Duration {
+        let imp = self.imp();
+
+        let since_play = imp.play_time.get().map_or(Duration::ZERO, |play_time| {
+            Duration::from_micros((glib::monotonic_time() - play_time).max(0) as u64)
+        });
+        imp.elapsed.get() + since_play
+    }
+
+    /// Resets the time played so far to zero.
+    pub fn reset_elapsed(&self) {
+        let imp = self.imp();
+
+        imp.elapsed.set(Duration::ZERO);
+        if imp.play_time.get().is_some() {
+            imp.play_time.set(Some(glib::monotonic_time()));
+        }
+    }
+
+    fn start_ticking(&self) {
+        let imp = self.imp();
+
+        if let Some(source_id) = imp.tick_source.take() {
+            source_id.remove();
+        }
+
+        let source_id = glib::timeout_add_local(
+            imp.interval.get(),
+            clone!(
+                #[weak(rename_to = obj)]
+                self,
+                #[upgrade_or]
+                glib::ControlFlow::Break,
+                move || {
+                    obj.tick();
+                    glib::ControlFlow::Continue
+                }
+            ),
+        );
+        imp.tick_source.replace(Some(source_id));
+    }
+
+    fn tick(&self) {
+        // Clone to allow the draw function to replace itself.
+        let draw_func = self.imp().draw_func.borrow().clone();
+        if let Some(draw_func) = draw_func {
+            draw_func(self.paintable(), self.elapsed());
+        }
+    }
+}
@@ -0,0 +1,410 @@
+use std::ops::Range;
+
+use gtk::{
+    glib::{self, clone},
+    graphene::{Point, Rect},
+    gsk,
+    prelude::*,
+    subclass::prelude::*,
+};
+
+use crate::{Paintable, RangeModel};
+
+/// The opacity of the shade drawn outside the viewport.
+const SHADE_ALPHA: f32 = 0.2;
+
+/// The width of the border of the viewport.
+const VIEWPORT_BORDER_WIDTH: f32 = 1.0;
+
+mod imp {
+    use std::{cell::RefCell, sync::OnceLock};
+
+    use super::*;
+
+    #[derive(Debug)]
+    pub struct Minimap {
+        pub(super) paintable: RefCell<Option<Paintable>>,
+        pub(super) paintable_handler: RefCell<Option<glib::SignalHandlerId>>,
+        pub(super) range_model: RefCell<Option<RangeModel>>,
+        pub(super) range_model_handler: RefCell<Option<glib::SignalHandlerId>>,
+        pub(super) data_ranges: RefCell<(Range<f64>, Range<f64>)>,
+
+        pub(super) drag_start_ranges: RefCell<Option<(Range<f64>, Range<f64>)>>,
+    }
+
+    impl Default for Minimap {
+        fn default() -> Self {
+            Self {
+                paintable: RefCell::default(),
+                paintable_handler: RefCell::default(),
+                range_model: RefCell::default(),
+                range_model_handler: RefCell::default(),
+                data_ranges: RefCell::new((0.0..1.0, 0.0..1.0)),
+                drag_start_ranges: RefCell::default(),
+            }
+        }
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for Minimap {
+        const NAME: &'static str = "PlottersGtk4Minimap";
+        type Type = super::Minimap;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.set_css_name("minimap");
+        }
+    }
+
+    impl ObjectImpl for Minimap {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+
+            let drag = gtk::GestureDrag::new();
+            drag.connect_drag_begin(clone!(
+                #[weak]
+                obj,
+                move |_, x, y| {
+                    obj.handle_drag_begin(x, y);
+                }
+            ));
+            drag.connect_drag_update(clone!(
+                #[weak]
+                obj,
+                move |_, offset_x, offset_y| {
+                    obj.handle_drag_update(offset_x, offset_y);
+                }
+            ));
+            drag.connect_drag_end(clone!(
+                #[weak]
+                obj,
+                move |_, _, _| {
+                    obj.imp().drag_start_ranges.replace(None);
+                }
+            ));
+            obj.add_controller(drag);
+        }
+
+        fn dispose(&self) {
+            let obj = self.obj();
+            obj.disconnect_paintable();
+            obj.disconnect_range_model();
+        }
+
+        fn properties() -> &'static [glib::ParamSpec] {
+            static PROPERTIES: OnceLock<Vec<glib::ParamSpec>> = OnceLock::new();
+
+            PROPERTIES.get_or_init(|| {
+                vec![
+                    glib::ParamSpecObject::builder::<Paintable>("paintable")
+                        .explicit_notify()
+                        .build(),
+                    glib::ParamSpecObject::builder::<RangeModel>("range-model")
+                        .explicit_notify()
+                        .build(),
+                ]
+            })
+        }
+
+        fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+            match pspec.name() {
+                "paintable" => {
+                    let paintable = value.get::<Option<Paintable>>().unwrap();
+                    self.obj().set_paintable(paintable.as_ref());
+                }
+                "range-model" => {
+                    let range_model = value.get::<Option<RangeModel>>().unwrap();
+                    self.obj().set_range_model(range_model.as_ref());
+                }
+                _ => unimplemented!(),
+            }
+        }
+
+        fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            match pspec.name() {
+                "paintable" => self.obj().paintable().into(),
+                "range-model" => self.imp().range_model.borrow().to_value(),
+                _ => unimplemented!(),
+            }
+        }
+    }
+
+    impl WidgetImpl for Minimap {
+        fn request_mode(&self) -> gtk::SizeRequestMode {
+            gtk::SizeRequestMode::HeightForWidth
+        }
+
+        fn measure(&self, orientation: gtk::Orientation, for_size: i32) -> (i32, i32, i32, i32) {
+            let Some(paintable) = self.paintable.borrow().clone() else {
+                return (0, 0, -1, -1);
+            };
+
+            // Keep the aspect ratio of the paintable, as a strip that is as
+            // wide as it is allocated.
+            let natural = match orientation {
+                gtk::Orientation::Horizontal => 0,
+                _ => {
+                    if for_size < 0 {
+                        paintable.height() as i32
+                    } else {
+                        (for_size as f64 * paintable.height() as f64 / paintable.width() as f64)
+                            .round() as i32
+                    }
+                }
+            };
+            (0, natural, -1, -1)
+        }
+
+        fn snapshot(&self, snapshot: &gtk::Snapshot) {
+            let obj = self.obj();
+            let (width, height) = (obj.width() as f32, obj.height() as f32);
+
+            // The recorded node is reused as is, only scaled down.
+            if let Some(paintable) = self.paintable.borrow().as_ref() {
+                paintable.snapshot(snapshot, width as f64, height as f64);
+            }
+
+            let Some(viewport) = obj.viewport() else {
+                return;
+            };
+
+            let mut shade = obj.color();
+            shade.set_alpha(shade.alpha() * SHADE_ALPHA);
+            for rect in [
+                Rect::new(0.0, 0.0, viewport.x(), height),
+                Rect::new(
+                    viewport.x() + viewport.width(),
+                    0.0,
+                    width - viewport.x() - viewport.width(),
+                    height,
+                ),
+                Rect::new(viewport.x(), 0.0, viewport.width(), viewport.y()),
+                Rect::new(
+                    viewport.x(),
+                    viewport.y() + viewport.height(),
+                    viewport.width(),
+                    height - viewport.y() - viewport.height(),
+                ),
+            ] {
+                if rect.width() > 0.0 && rect.height() > 0.0 {
+                    snapshot.append_color(&shade, &rect);
+                }
+            }
+
+            let border_color = obj.color();
+            snapshot.append_border(
+                &gsk::RoundedRect::from_rect(viewport, 0.0),
+                &[VIEWPORT_BORDER_WIDTH; 4],
+                &[border_color; 4],
+            );
+        }
+    }
+}
+
+glib::wrapper! {
+    /// An overview of a whole plot, with the visible ranges of a
+    /// [`RangeModel`] outlined as a viewport.
+    ///
+    /// The paintable is drawn once with the whole data, typically as a strip
+    /// under a [`PlotView`](crate::PlotView) showing part of a long time
+    /// series, and its recorded contents are reused at the size of the
+    /// minimap. Dragging the viewport pans the range model, and pressing
+    /// outside of it centers it there.
+    ///
+    /// This can be used on GTK UI files using its type name `PlottersGtk4Minimap`.
+    pub struct Minimap(ObjectSubclass<imp::Minimap>)
+        @extends gtk::Widget;
+}
+
+impl Minimap {
+    /// Creates a new minimap showing `paintable` with the ranges of
+    /// `range_model`.
+    pub fn new(paintable: &Paintable, range_model: &RangeModel) -> Self {
+        glib::Object::builder()
+            .property("paintable", paintable)
+            .property("range-model", range_model)
+            .build()
+    }
+
+    /// Sets the paintable with the whole data.
+    pub fn set_paintable(&self, paintable: Option<&Paintable>) {
+        if paintable == self.paintable().as_ref() {
+            return;
+        }
+
+        self.disconnect_paintable();
+
+        if let Some(paintable) = paintable {
+            let handler_id = paintable.connect_invalidate_contents(clone!(
+                #[weak(rename_to = obj)]
+                self,
+                move |_| {
+                    obj.queue_draw();
+                }
+            ));
+            self.imp().paintable_handler.replace(Some(handler_id));
+        }
+
+        self.imp().paintable.replace(paintable.cloned());
+        self.queue_resize();
+        self.notify("paintable");
+    }
+
+    /// Returns the paintable with the whole data.
+    pub fn paintable(&self) -> Option<Paintable> {
+        self.imp().paintable.borrow().clone()
+    }
+
+    /// Sets the range model whose ranges are outlined and panned.
+    ///
+    /// This is typically the range model of the main
+    /// [`PlotView`](crate::PlotView).
+    pub fn set_range_model(&self, range_model: Option<&RangeModel>) {
+        if range_model == self.imp().range_model.borrow().as_ref() {
+            return;
+        }
+
+        self.disconnect_range_model();
+
+        if let Some(range_model) = range_model {
+            let handler_id = range_model.connect_changed(clone!(
+                #[weak(rename_to = obj)]
+                self,
+                move |_| {
+                    obj.queue_draw();
+                }
+            ));
+            self.imp().range_model_handler.replace(Some(handler_id));
+        }
+
+        self.imp().range_model.replace(range_model.cloned());
+        self.queue_draw();
+        self.notify("range-model");
+    }
+
+    /// Returns the range model whose ranges are outlined and panned.
+    pub fn range_model(&self) -> Option<RangeModel> {
+        self.imp().range_model.borrow().clone()
+    }
+
+    /// Sets the data ranges the paintable spans, which defaults to `0..1`
+    /// for both axes.
+    ///
+    /// For a minimap of the x axis only, set the y range to the y range of
+    /// the range model, so that the viewport spans the whole height.
+    pub fn set_data_ranges(&self, x_range: Range<f64>, y_range: Range<f64>) {
+        self.imp().data_ranges.replace((x_range, y_range));
+        self.queue_draw();
+    }
+
+    /// Returns the data ranges the paintable spans.
+    pub fn data_ranges(&self) -> (Range<f64>, Range<f64>) {
+        self.imp().data_ranges.borrow().clone()
+    }
+
+    /// Returns the rect of the ranges of the range model, in widget
+    /// coordinates and clamped to the widget.
+    fn viewport(&self) -> Option<Rect> {
+        let range_model = self.range_model()?;
+        let (x_data, y_data) = self.data_ranges();
+        let (width, height) = (self.width() as f64, self.height() as f64);
+
+        let to_x = |x: f64| (x - x_data.start) / (x_data.end - x_data.start) * width;
+        let to_y = |y: f64| (y_data.end - y) / (y_data.end - y_data.start) * height;
+
+        let x0 = to_x(range_model.x_min()).clamp(0.0, width);
+        let x1 = to_x(range_model.x_max()).clamp(0.0, width);
+        let y0 = to_y(range_model.y_max()).clamp(0.0, height);
+        let y1 = to_y(range_model.y_min()).clamp(0.0, height);
+
+        if !(x0.is_finite() && x1.is_finite() && y0.is_finite() && y1.is_finite()) {
+            return None;
+        }
+
+        Some(Rect::new(
+            x0.min(x1) as f32,
+            y0.min(y1) as f32,
+            (x1 - x0).abs() as f32,
+            (y1 - y0).abs() as f32,
+        ))
+    }
+
+    /// Returns the size of a pixel of the widget, in data units.
+    fn pixel_size(&self) -> Option<(f64, f64)> {
+        let (x_data, y_data) = self.data_ranges();
+        let (width, height) = (self.width() as f64, self.height() as f64);
+        if width <= 0.0 || height <= 0.0 {
+            return None;
+        }
+
+        Some((
+            (x_data.end - x_data.start) / width,
+            (y_data.end - y_data.start) / height,
+        ))
+    }
+
+    fn handle_drag_begin(&self, x: f64, y: f64) {
+        let (Some(range_model), Some(viewport)) = (self.range_model(), self.viewport()) else {
+            return;
+        };
+
+        // Center the viewport on presses outside of it, so that it can be
+        // dragged from there.
+        if !viewport.contains_point(&Point::new(x as f32, y as f32)) {
+            if let Some((x_scale, y_scale)) = self.pixel_size() {
+                let center_x = (viewport.x() + viewport.width() / 2.0) as f64;
+                let center_y = (viewport.y() + viewport.height() / 2.0) as f64;
+                range_model.pan((x - center_x) * x_scale, (center_y - y) * y_scale);
+            }
+        }
+
+        self.imp()
+            .drag_start_ranges
+            .replace(Some((range_model.x_range(), range_model.y_range())));
+    }
+
+    fn handle_drag_update(&self, offset_x: f64, offset_y: f64) {
+        let Some((x_range, y_range)) = self.imp().drag_start_ranges.borrow().clone() else {
+            return;
+        };
+        let (Some(range_model), Some((x_scale, y_scale))) = (self.range_model(), self.pixel_size())
+        else {
+            return;
+        };
+
+        let dx = offset_x * x_scale;
+        let dy = -offset_y * y_scale;
+        range_model.set_ranges(
+            x_range.start + dx..x_range.end + dx,
+            y_range.start + dy..y_range.end + dy,
+        );
+    }
+
+    fn disconnect_paintable(&self) {
+        let handler_id = self.imp().paintable_handler.take();
+
+        if let (Some(paintable), Some(handler_id)) =
+            (self.imp().paintable.borrow().as_ref(), handler_id)
+        {
+            paintable.disconnect(handler_id);
+        }
+    }
+
+    fn disconnect_range_model(&self) {
+        let handler_id = self.imp().range_model_handler.take();
+
+        if let (Some(range_model), Some(handler_id)) =
+            (self.imp().range_model.borrow().as_ref(), handler_id)
+        {
+            range_model.disconnect(handler_id);
+        }
+    }
+}
+
+impl Default for Minimap {
+    fn default() -> Self {
+        glib::Object::new()
+    }
+}
@@ -0,0 +1,307 @@
+use gtk::{graphene::Rect, gsk, pango, prelude::*};
+
+/// The node counts of a frame before and after it was optimized.
+///
+/// This is part of the [`RenderStats`](crate::RenderStats) of frames drawn
+/// with [`PaintableBackend::set_optimizes_nodes`] enabled.
+///
+/// [`PaintableBackend::set_optimizes_nodes`]: crate::PaintableBackend::set_optimizes_nodes
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct OptimizationStats {
+    /// The number of nodes of the frame as drawn, including nested nodes.
+    pub n_nodes_before: usize,
+    /// The number of nodes of the frame after optimizing it, including
+    /// nested nodes.
+    pub n_nodes_after: usize,
+}
+
+/// Returns an equivalent of `node` with fewer nodes when drawn within
+/// `bounds`, and the node counts before and after.
+///
+/// Nested containers are flattened, nodes outside of the visible bounds or
+/// fully transparent are dropped, clips that clip nothing are removed, and
+/// adjacent color nodes of the same color that form a rect are merged.
+pub(crate) fn optimize(
+    node: &gsk::RenderNode,
+    bounds: &Rect,
+) -> (Option<gsk::RenderNode>, OptimizationStats) {
+    let optimized = single(optimize_node(node, Some(bounds)));
+    let stats = OptimizationStats {
+        n_nodes_before: count_nodes(node),
+        n_nodes_after: optimized.as_ref().map_or(0, count_nodes),
+    };
+    (optimized, stats)
+}
+
+/// Returns the optimized nodes equivalent to `node` when drawn within
+/// `visible`, or anywhere if `None`.
+///
+/// This returns a list, so that the children of containers are flattened
+/// into the parent container.
+fn optimize_node(node: &gsk::RenderNode, visible: Option<&Rect>) -> Vec<gsk::RenderNode> {
+    if visible.is_some_and(|visible| node.bounds().intersection(visible).is_none()) {
+        return Vec::new();
+    }
+
+    match node.node_type() {
+        gsk::RenderNodeType::ContainerNode => {
+            let container = node.downcast_ref::<gsk::ContainerNode>().unwrap();
+            let mut children = Vec::new();
+            for index in 0..container.n_children() {
+                for child in optimize_node(&container.child(index), visible) {
+                    push_merged(&mut children, child);
+                }
+            }
+            children
+        }
+        gsk::RenderNodeType::ColorNode => {
+            let color_node = node.downcast_ref::<gsk::ColorNode>().unwrap();
+            if color_node.color().alpha() <= 0.0 {
+                Vec::new()
+            } else {
+                vec![node.clone()]
+            }
+        }
+        gsk::RenderNodeType::OpacityNode => {
+            let opacity_node = node.downcast_ref::<gsk::OpacityNode>().unwrap();
+            let opacity = opacity_node.opacity();
+            if opacity <= 0.0 {
+                return Vec::new();
+            }
+
+            let children = optimize_node(&opacity_node.child(), visible);
+            if opacity >= 1.0 {
+                return children;
+            }
+
+            single(children)
+                .map(|child| gsk::OpacityNode::new(&child, opacity).upcast())
+                .into_iter()
+                .collect()
+        }
+        gsk::RenderNodeType::ClipNode => {
+            let clip_node = node.downcast_ref::<gsk::ClipNode>().unwrap();
+            let clip = clip_node.clip();
+            let Some(visible_clip) =
+                visible.map_or(Some(*clip), |visible| visible.intersection(clip))
+            else {
+                return Vec::new();
+            };
+
+            let children = optimize_node(&clip_node.child(), Some(&visible_clip));
+            if children
+                .iter()
+                .all(|child| clip.contains_rect(&child.bounds()))
+            {
+                return children;
+            }
+
+            single(children)
+                .map(|child| gsk::ClipNode::new(&child, clip).upcast())
+                .into_iter()
+                .collect()
+        }
+        gsk::RenderNodeType::TransformNode => {
+            let transform_node = node.downcast_ref::<gsk::TransformNode>().unwrap();
+
+            // The visible bounds are not transformed into the space of the
+            // child, so nothing within is culled.
+            single(optimize_node(&transform_node.child(), None))
+                .map(|child| gsk::TransformNode::new(&child, &transform_node.transform()).upcast())
+                .into_iter()
+                .collect()
+        }
+        _ => vec![node.clone()],
+    }
+}
+
+/// The prefix of the message of the debug nodes wrapping level-of-detail
+/// groups, followed by their minimum scale.
+const LOD_GROUP_PREFIX: &str = "plotters-gtk4:lod:";
+
+/// Pushes a level-of-detail group onto `snapshot`, which is only drawn by
+/// [`for_scale`] at `min_scale` or more, until the matching pop.
+pub(crate) fn push_lod_group(snapshot: &gtk::Snapshot, min_scale: f32) {
+    snapshot.push_debug(&format!("{LOD_GROUP_PREFIX}{min_scale}"));
+}
+
+/// How content is adapted to the scale it is drawn at, in [`for_scale`].
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct ScaleOptions {
+    /// The size below which text is faded out, in pixels.
+    pub(crate) min_text_size: Option<f32>,
+    /// Whether strokes keep their width regardless of the scale.
+    pub(crate) is_non_scaling_stroke: bool,
+}
+
+/// Returns `node` as drawn at `scale`, or `None` if nothing is left.
+///
+/// Level-of-detail groups whose minimum scale is above `scale` are dropped.
+/// If the minimum text size is set, text is faded out where the font size
+/// times `scale` is below it, linearly from fully opaque at the minimum
+/// size to fully transparent at half of it, below which it is dropped. If
+/// strokes are non-scaling, their widths are divided by `scale`.
+pub(crate) fn for_scale(
+    node: &gsk::RenderNode,
+    scale: f32,
+    options: ScaleOptions,
+) -> Option<gsk::RenderNode> {
+    single(for_scale_node(node, scale, options))
+}
+
+fn for_scale_node(
+    node: &gsk::RenderNode,
+    scale: f32,
+    options: ScaleOptions,
+) -> Vec<gsk::RenderNode> {
+    let for_child = |child: &gsk::RenderNode| single(for_scale_node(child, scale, options));
+
+    match node.node_type() {
+        gsk::RenderNodeType::TextNode => {
+            let Some(min_text_size) = options.min_text_size else {
+                return vec![node.clone()];
+            };
+
+            let text_node = node.downcast_ref::<gsk::TextNode>().unwrap();
+            let font_size =
+                text_node.font().describe_with_absolute_size().size() as f32 / pango::SCALE as f32;
+            let opacity = ((font_size * scale / min_text_size - 0.5) * 2.0).min(1.0);
+            if opacity <= 0.0 {
+                Vec::new()
+            } else if opacity >= 1.0 {
+                vec![node.clone()]
+            } else {
+                vec![gsk::OpacityNode::new(node, opacity).upcast()]
+            }
+        }
+        gsk::RenderNodeType::StrokeNode if options.is_non_scaling_stroke && scale > 0.0 => {
+            let stroke_node = node.downcast_ref::<gsk::StrokeNode>().unwrap();
+            let path = stroke_node.path();
+            let stroke = stroke_node.stroke().clone();
+            stroke.set_line_width(stroke.line_width() / scale);
+
+            // The child is usually a color node covering the stroke, which
+            // has to cover the stroke of the new width instead.
+            let child = stroke_node.child();
+            let child = match (
+                child.downcast_ref::<gsk::ColorNode>(),
+                path.stroke_bounds(&stroke),
+            ) {
+                (Some(color_node), Some(bounds)) => {
+                    gsk::ColorNode::new(color_node.color(), &bounds).upcast()
+                }
+                _ => child,
+            };
+
+            vec![gsk::StrokeNode::new(&child, &path, &stroke).upcast()]
+        }
+        gsk::RenderNodeType::DebugNode => {
+            let debug_node = node.downcast_ref::<gsk::DebugNode>().unwrap();
+            let message = debug_node.message();
+            match message
+                .strip_prefix(LOD_GROUP_PREFIX)
+                .map(str::parse::<f32>)
+            {
+                Some(Ok(min_scale)) if scale < min_scale => Vec::new(),
+                // The group is no longer needed once it is shown.
+                Some(_) => for_scale_node(&debug_node.child(), scale, options),
+                None => for_child(&debug_node.child())
+                    .map(|child| gsk::DebugNode::new(&child, &message).upcast())
+                    .into_iter()
+                    .collect(),
+            }
+        }
+        gsk::RenderNodeType::ContainerNode => {
+            let container = node.downcast_ref::<gsk::ContainerNode>().unwrap();
+            (0..container.n_children())
+                .flat_map(|index| for_scale_node(&container.child(index), scale, options))
+                .collect()
+        }
+        gsk::RenderNodeType::OpacityNode => {
+            let opacity_node = node.downcast_ref::<gsk::OpacityNode>().unwrap();
+            for_child(&opacity_node.child())
+                .map(|child| gsk::OpacityNode::new(&child, opacity_node.opacity()).upcast())
+                .into_iter()
+                .collect()
+        }
+        gsk::RenderNodeType::ClipNode => {
+            let clip_node = node.downcast_ref::<gsk::ClipNode>().unwrap();
+            for_child(&clip_node.child())
+                .map(|child| gsk::ClipNode::new(&child, clip_node.clip()).upcast())
+                .into_iter()
+                .collect()
+        }
+        gsk::RenderNodeType::TransformNode => {
+            // Content is mostly only translated or rotated within, so the
+            // scale of the transform is not taken into account.
+            let transform_node = node.downcast_ref::<gsk::TransformNode>().unwrap();
+            for_child(&transform_node.child())
+                .map(|child| gsk::TransformNode::new(&child, &transform_node.transform()).upcast())
+                .into_iter()
+                .collect()
+        }
+        _ => vec![node.clone()],
+    }
+}
+
+/// Appends `node` to `nodes`, merging it into the last node if both are
+/// color nodes of the same color whose rects share an edge.
+fn push_merged(nodes: &mut Vec<gsk::RenderNode>, node: gsk::RenderNode) {
+    let merged = nodes.last().and_then(|last| {
+        let last = last.downcast_ref::<gsk::ColorNode>()?;
+        let next = node.downcast_ref::<gsk::ColorNode>()?;
+        if last.color() != next.color() {
+            return None;
+        }
+
+        let (a, b) = (last.bounds(), next.bounds());
+        let is_row = a.y() == b.y()
+            && a.height() == b.height()
+            && (a.x() + a.width() == b.x() || b.x() + b.width() == a.x());
+        let is_column = a.x() == b.x()
+            && a.width() == b.width()
+            && (a.y() + a.height() == b.y() || b.y() + b.height() == a.y());
+        (is_row || is_column).then(|| gsk::ColorNode::new(last.color(), &a.union(&b)))
+    });
+
+    match merged {
+        Some(merged) => *nodes.last_mut().unwrap() = merged.upcast(),
+        None => nodes.push(node),
+    }
+}
+
+/// Returns the nodes as a single node.
+fn single(mut nodes: Vec<gsk::RenderNode>) -> Option<gsk::RenderNode> {
+    match nodes.len() {
+        0 => None,
+        1 => nodes.pop(),
+        _ => Some(gsk::ContainerNode::new(&nodes).upcast()),
+    }
+}
+
+/// Returns the number of nodes of `node`, including nested nodes.
+fn count_nodes(node: &gsk::RenderNode) -> usize {
+    let children = match node.node_type() {
+        gsk::RenderNodeType::ContainerNode => {
+            let container = node.downcast_ref::<gsk::ContainerNode>().unwrap();
+            (0..container.n_children())
+                .map(|index| count_nodes(&container.child(index)))
+                .sum()
+        }
+        gsk::RenderNodeType::TransformNode => {
+            count_nodes(&node.downcast_ref::<gsk::TransformNode>().unwrap().child())
+        }
+        gsk::RenderNodeType::ClipNode => {
+            count_nodes(&node.downcast_ref::<gsk::ClipNode>().unwrap().child())
+        }
+        gsk::RenderNodeType::RoundedClipNode => {
+            count_nodes(&node.downcast_ref::<gsk::RoundedClipNode>().unwrap().child())
+        }
+        gsk::RenderNodeType::OpacityNode => {
+            count_nodes(&node.downcast_ref::<gsk::OpacityNode>().unwrap().child())
+        }
+        _ => 0,
+    };
+
+    1 + children
+}
@@ -1,15 +1,45 @@
-use std::convert::Infallible;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    convert::Infallible,
+    ops::Range,
+    path::Path,
+    time::{Duration, Instant},
+};
 
-use gtk::{gdk, glib, graphene::Rect, gsk, pango, prelude::*, subclass::prelude::*};
+use gtk::{
+    cairo, gdk, gio,
+    glib::{self, clone},
+    graphene::{Point, Rect},
+    gsk, pango,
+    prelude::*,
+    subclass::prelude::*,
+};
+use plotters::style::Color;
 use plotters_backend::{
     BackendColor, BackendCoord, BackendStyle, BackendTextStyle, DrawingBackend, DrawingErrorKind,
 };
 
-use crate::common;
+use crate::{
+    annotation::{self, Annotation, AnnotationId, Annotations},
+    backend::ScopeState,
+    colormaps::Colormap,
+    common,
+    export::{self, ExportRegion},
+    hit_region::{self, HitRegion, HitTag},
+    optimize,
+    recording::Recording,
+    transition::{Transition, TRANSITION_FRAME_INTERVAL},
+    BackendContext, Background, Easing, GtkBackend, InvalidInputPolicy, InvalidSizeError, Link,
+    OptimizationStats, Quality, RendererCaps, Series, SeriesMarker, SnapshotScope, StyleClasses,
+    TextAttributes, TextCoverage, TextExtents, TextHalo, Watermark,
+};
+
+/// The size of the squares of the transparency checkerboard, in pixels.
+const CHECKERBOARD_SQUARE_SIZE: f32 = 8.0;
 
 mod imp {
     use std::{
-        cell::{OnceCell, RefCell},
+        cell::{Cell, OnceCell, RefCell},
         sync::OnceLock,
     };
 
@@ -20,6 +50,48 @@ mod imp {
         pub(super) width: OnceCell<u32>,
         pub(super) height: OnceCell<u32>,
         pub(super) node: RefCell<Option<gsk::RenderNode>>,
+        pub(super) render_stats: Cell<RenderStats>,
+        pub(super) thumbnail: RefCell<Option<(u32, gdk::Texture)>>,
+        pub(super) eviction_timeout: Cell<Option<Duration>>,
+        pub(super) eviction_source: RefCell<Option<glib::SourceId>>,
+        pub(super) evicted_node: RefCell<Option<glib::Bytes>>,
+        pub(super) series: OnceCell<gio::ListStore>,
+        pub(super) annotations: RefCell<Vec<(AnnotationId, Annotation)>>,
+        pub(super) next_annotation_id: Cell<u64>,
+        pub(super) annotations_node: RefCell<Option<gsk::RenderNode>>,
+        pub(super) pending_cancellable: RefCell<Option<gio::Cancellable>>,
+        pub(super) max_fps: Cell<Option<u32>>,
+        pub(super) last_invalidation: Cell<Option<i64>>,
+        pub(super) invalidation_source: RefCell<Option<FrameSource>>,
+        pub(super) background: Cell<Option<Background>>,
+        pub(super) shows_checkerboard: Cell<bool>,
+        pub(super) quality: Cell<Quality>,
+        pub(super) renderer_caps: Cell<RendererCaps>,
+        /// The scale factor, or zero for the default of one.
+        pub(super) scale_factor: Cell<i32>,
+        pub(super) min_text_size: Cell<Option<f32>>,
+        pub(super) is_non_scaling_stroke: Cell<bool>,
+        /// The last node drawn at a scale, with the node and scale it was
+        /// derived from.
+        pub(super) scaled_node: RefCell<Option<(gsk::RenderNode, f32, Option<gsk::RenderNode>)>>,
+        /// Whether the node may have level-of-detail groups, which is
+        /// assumed for nodes not drawn with `PaintableBackend`.
+        pub(super) has_lod_groups: Cell<bool>,
+        pub(super) watermark: RefCell<Option<Watermark>>,
+        pub(super) transition: RefCell<Option<Transition>>,
+        pub(super) transition_source: RefCell<Option<FrameSource>>,
+        pub(super) recording: RefCell<Option<Recording>>,
+        pub(super) recording_nodes: RefCell<HashMap<Vec<u8>, gsk::RenderNode>>,
+        pub(super) regions: RefCell<Vec<Region>>,
+        pub(super) plot_area: Cell<Option<(BackendCoord, BackendCoord)>>,
+        /// The tags of the hit regions of the presented frames, by ID.
+        pub(super) hit_tags: RefCell<HashMap<u64, HitTag>>,
+        pub(super) next_hit_id: Cell<u64>,
+        /// The hit regions of the node, found when first needed.
+        pub(super) hit_regions: RefCell<Option<Vec<HitRegion>>>,
+        /// The widget attached with `attach_to_widget`, whose frame clock
+        /// drives the invalidations.
+        pub(super) widget: glib::WeakRef<gtk::Widget>,
     }
 
     #[glib::object_subclass]
@@ -36,13 +108,25 @@ mod imp {
             PROPERTIES.get_or_init(|| {
                 vec![
                     glib::ParamSpecUInt::builder("width")
+                        .minimum(1)
                         .maximum(i32::MAX as u32)
+                        .default_value(1)
                         .construct_only()
                         .build(),
                     glib::ParamSpecUInt::builder("height")
+                        .minimum(1)
                         .maximum(i32::MAX as u32)
+                        .default_value(1)
                         .construct_only()
                         .build(),
+                    glib::ParamSpecBoolean::builder("shows-checkerboard")
+                        .explicit_notify()
+                        .build(),
+                    glib::ParamSpecInt::builder("scale-factor")
+                        .minimum(1)
+                        .default_value(1)
+                        .explicit_notify()
+                        .build(),
                 ]
             })
         }
@@ -57,6 +141,14 @@ mod imp {
                     let height = value.get().unwrap();
                     self.height.set(height).unwrap();
                 }
+                "shows-checkerboard" => {
+                    let shows_checkerboard = value.get().unwrap();
+                    self.obj().set_shows_checkerboard(shows_checkerboard);
+                }
+                "scale-factor" => {
+                    let scale_factor = value.get().unwrap();
+                    self.obj().set_scale_factor(scale_factor);
+                }
                 _ => unimplemented!(),
             }
         }
@@ -65,6 +157,8 @@ mod imp {
             match pspec.name() {
                 "width" => self.obj().width().into(),
                 "height" => self.obj().height().into(),
+                "shows-checkerboard" => self.obj().shows_checkerboard().into(),
+                "scale-factor" => self.obj().scale_factor().into(),
                 _ => unimplemented!(),
             }
         }
@@ -72,23 +166,78 @@ mod imp {
 
     impl PaintableImpl for Paintable {
         fn snapshot(&self, snapshot: &gdk::Snapshot, width: f64, height: f64) {
+            let obj = self.obj();
+            obj.restore_evicted_node();
+            obj.schedule_eviction();
+
             let node = self.node.borrow();
+            let annotations_node = obj.annotations_node();
+            let background = self.background.get();
+            let watermark = self.watermark.borrow();
+
+            // The checkerboard is drawn unscaled, so that its squares keep
+            // their size regardless of the size the paintable is drawn at.
+            if self.shows_checkerboard.get() {
+                append_checkerboard(snapshot, &Rect::new(0.0, 0.0, width as f32, height as f32));
+            }
 
-            let Some(node) = node.as_ref() else {
+            if node.is_none()
+                && annotations_node.is_none()
+                && background.is_none()
+                && watermark.is_none()
+            {
                 return;
-            };
+            }
 
             snapshot.save();
 
-            let (this_width, this_height) = self.obj().size();
-            snapshot.scale(
+            let (this_width, this_height) = obj.size();
+            let (scale_x, scale_y) = (
                 width as f32 / this_width as f32,
                 height as f32 / this_height as f32,
             );
+            snapshot.scale(scale_x, scale_y);
 
-            snapshot.push_clip(&Rect::new(0.0, 0.0, this_width as f32, this_height as f32));
+            let scale = scale_x.min(scale_y);
+            let node = node
+                .as_ref()
+                .and_then(|node| obj.node_for_scale(node, scale));
+            let annotations_node = annotations_node
+                .and_then(|node| optimize::for_scale(&node, scale, obj.scale_options()));
 
-            snapshot.append_node(node);
+            let bounds = Rect::new(0.0, 0.0, this_width as f32, this_height as f32);
+            snapshot.push_clip(&bounds);
+
+            if let Some(background) = background {
+                background.append_to(snapshot, &bounds);
+            }
+
+            if let Some(transition) = self
+                .transition
+                .borrow()
+                .as_ref()
+                .filter(|transition| transition.is_cross_fade())
+            {
+                snapshot.push_cross_fade(transition.progress());
+                if let Some(previous_node) = transition.previous_node() {
+                    snapshot.append_node(previous_node);
+                }
+                snapshot.pop();
+                if let Some(node) = node.as_ref() {
+                    snapshot.append_node(node);
+                }
+                snapshot.pop();
+            } else if let Some(node) = node.as_ref() {
+                snapshot.append_node(node);
+            }
+
+            if let Some(annotations_node) = annotations_node {
+                snapshot.append_node(&annotations_node);
+            }
+
+            if let Some(watermark) = watermark.as_ref() {
+                watermark.append_to(snapshot.upcast_ref(), (this_width, this_height));
+            }
 
             snapshot.pop();
 
@@ -107,6 +256,20 @@ mod imp {
             self.obj().height() as i32
         }
     }
+
+    /// Fills `bounds` with a checkerboard, the conventional backdrop for
+    /// previewing transparency.
+    fn append_checkerboard(snapshot: &gdk::Snapshot, bounds: &Rect) {
+        let size = CHECKERBOARD_SQUARE_SIZE;
+        let light = gdk::RGBA::new(0.8, 0.8, 0.8, 1.0);
+        let dark = gdk::RGBA::new(0.6, 0.6, 0.6, 1.0);
+
+        snapshot.push_repeat(bounds, Some(&Rect::new(0.0, 0.0, size * 2.0, size * 2.0)));
+        snapshot.append_color(&light, &Rect::new(0.0, 0.0, size * 2.0, size * 2.0));
+        snapshot.append_color(&dark, &Rect::new(size, 0.0, size, size));
+        snapshot.append_color(&dark, &Rect::new(0.0, size, size, size));
+        snapshot.pop();
+    }
 }
 
 glib::wrapper! {
@@ -119,11 +282,48 @@ glib::wrapper! {
 
 impl Paintable {
     /// Creates a new paintable with the given width and height.
-    pub fn new((w, h): (u32, u32)) -> Self {
-        glib::Object::builder()
+    ///
+    /// This returns an error if the width or height is zero or larger than
+    /// [`i32::MAX`].
+    pub fn new((w, h): (u32, u32)) -> Result<Self, InvalidSizeError> {
+        let is_valid = |v: u32| (1..=i32::MAX as u32).contains(&v);
+        if !is_valid(w) || !is_valid(h) {
+            return Err(InvalidSizeError { size: (w, h) });
+        }
+
+        Ok(glib::Object::builder()
             .property("width", w)
             .property("height", h)
-            .build()
+            .build())
+    }
+
+    /// Creates a new paintable from the contents serialized with
+    /// [`Paintable::serialize`].
+    ///
+    /// This returns an error if `bytes` is not a valid serialized paintable.
+    pub fn from_node_bytes(bytes: &glib::Bytes) -> Result<Self, glib::Error> {
+        let mut error = None;
+        let node = gsk::RenderNode::deserialize_with_error_func(bytes, |_, _, err| {
+            error.get_or_insert_with(|| err.clone());
+        });
+
+        let Some(node) = node else {
+            return Err(error.unwrap_or_else(|| {
+                glib::Error::new(gio::IOErrorEnum::InvalidData, "Invalid render node")
+            }));
+        };
+
+        // The bounds of serialized nodes are the bounds of the paintable.
+        let bounds = node.bounds();
+        let size = (
+            bounds.width().round() as u32,
+            bounds.height().round() as u32,
+        );
+        let this = Self::new(size)
+            .map_err(|err| glib::Error::new(gio::IOErrorEnum::InvalidData, &err.to_string()))?;
+        this.set_node(Some(node));
+
+        Ok(this)
     }
 
     /// Returns the width of the paintable.
@@ -143,150 +343,2916 @@ impl Paintable {
 
     /// Clears the contents of the paintable.
     pub fn clear(&self) {
+        self.imp().recording.replace(None);
+        self.imp().recording_nodes.borrow_mut().clear();
         self.set_node(None);
+        self.imp().render_stats.set(RenderStats::default());
     }
 
-    fn set_node(&self, node: Option<gsk::RenderNode>) {
-        self.imp().node.replace(node);
-        self.invalidate_contents();
+    /// Renders the contents of the paintable offscreen into a texture of the
+    /// given size, scaling the contents to fit.
+    ///
+    /// The texture is rendered with the native renderer of `surface`, such
+    /// as the surface of the window the plot is shown in, or with a Cairo
+    /// renderer if `surface` is `None` or has no renderer. This is useful for
+    /// thumbnails, previews, and comparing plots in tests.
+    pub fn render_to_texture(
+        &self,
+        size: (u32, u32),
+        surface: Option<&gdk::Surface>,
+    ) -> Result<gdk::Texture, glib::Error> {
+        let renderer = export::renderer_for_surface(surface)?;
+        let texture = export::render_paintable_at_size(&renderer, self, size);
+        renderer.unrealize();
+
+        Ok(texture)
     }
-}
 
-/// Backend that draws to an object that implements [`gdk::Paintable`].
-#[derive(Debug)]
-pub struct PaintableBackend<'a> {
-    snapshot: Option<gtk::Snapshot>,
-    paintable: &'a Paintable,
-    layout: pango::Layout,
-    size: (u32, u32),
-}
+    /// Draws the paintable on `snapshot` at `width` and `height`, without the
+    /// checkerboard, as it is only a preview and must not end up in rendered
+    /// contents.
+    pub(crate) fn snapshot_without_checkerboard(
+        &self,
+        snapshot: &impl IsA<gdk::Snapshot>,
+        width: f64,
+        height: f64,
+    ) {
+        let shows_checkerboard = self.imp().shows_checkerboard.replace(false);
+        self.snapshot(snapshot, width, height);
+        self.imp().shows_checkerboard.set(shows_checkerboard);
+    }
 
-impl<'a> PaintableBackend<'a> {
-    /// Creates a new drawing backend backed with an object that implements
-    /// [`gdk::Paintable`].
-    pub fn new(paintable: &'a Paintable) -> Self {
-        let font_map = pangocairo::FontMap::default();
-        let context = font_map.create_context();
-        let layout = pango::Layout::new(&context);
-        Self {
-            snapshot: None,
-            paintable,
-            layout,
-            size: paintable.size(),
+    /// Returns the contents of the paintable downscaled to fit in a square of
+    /// `max_size`, keeping the aspect ratio.
+    ///
+    /// The contents are rendered at the thumbnail size, rather than scaling
+    /// down a full-size rendering, so thin strokes and text stay smooth. The
+    /// thumbnail is cached until the contents change, so this is cheap to
+    /// call from the factory of a [`gtk::ListView`] showing many previews.
+    /// The returned texture does not keep the full-size nodes alive.
+    pub fn thumbnail(&self, max_size: u32) -> Result<gdk::Texture, glib::Error> {
+        if let Some((cached_max_size, texture)) = self.imp().thumbnail.borrow().as_ref() {
+            if *cached_max_size == max_size {
+                return Ok(texture.clone());
+            }
         }
+
+        let (width, height) = self.size();
+        let scale = (max_size as f64 / width.max(height) as f64).min(1.0);
+        let size = (
+            (width as f64 * scale).round().max(1.0) as u32,
+            (height as f64 * scale).round().max(1.0) as u32,
+        );
+        let texture = self.render_to_texture(size, None)?;
+
+        self.imp()
+            .thumbnail
+            .replace(Some((max_size, texture.clone())));
+
+        Ok(texture)
     }
 
-    #[inline]
-    fn snapshot(&self) -> &gtk::Snapshot {
-        self.snapshot.as_ref().expect("backend was not prepared")
+    /// Returns a content provider of the contents of the paintable as a PNG
+    /// image and an SVG document.
+    ///
+    /// This can be used with a [`gtk::DragSource`] or the clipboard to let
+    /// users drag or copy plots into other applications, such as email or
+    /// office applications. [`PlotView`](crate::PlotView) provides this
+    /// when dragged with <kbd>Ctrl</kbd> held.
+    pub fn as_drag_content(&self) -> gdk::ContentProvider {
+        self.content_for_region(ExportRegion::Full)
     }
-}
 
-impl Drop for PaintableBackend<'_> {
-    fn drop(&mut self) {
-        if let Some(snapshot) = self.snapshot.take() {
-            self.paintable.set_node(snapshot.to_node());
-        }
+    /// Returns the upper left and bottom right corners of the plotting
+    /// area, set with [`PaintableBackend::set_plot_area`].
+    pub fn plot_area(&self) -> Option<(BackendCoord, BackendCoord)> {
+        self.imp().plot_area.get()
     }
-}
 
-impl DrawingBackend for PaintableBackend<'_> {
-    type ErrorType = Infallible;
+    /// Renders the part of the contents of the paintable within `region`
+    /// into a texture, at the intrinsic size of the paintable.
+    ///
+    /// See [`Paintable::render_to_texture`] for `surface`.
+    pub fn render_region_to_texture(
+        &self,
+        region: ExportRegion,
+        surface: Option<&gdk::Surface>,
+    ) -> Result<gdk::Texture, glib::Error> {
+        let rect = self.region_rect(region)?;
+        let renderer = export::renderer_for_surface(surface)?;
+        let texture = export::render_paintable_region(&renderer, self, &rect);
+        renderer.unrealize();
 
-    #[inline]
-    fn get_size(&self) -> (u32, u32) {
-        self.size
+        Ok(texture)
     }
 
-    fn ensure_prepared(&mut self) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
-        if self.snapshot.is_none() {
-            self.snapshot.replace(gtk::Snapshot::new());
-        }
+    /// Saves the part of the contents of the paintable within `region` as a
+    /// PNG image at `path`.
+    pub fn save_png(
+        &self,
+        path: impl AsRef<Path>,
+        region: ExportRegion,
+    ) -> Result<(), glib::Error> {
+        let bytes = self
+            .render_region_to_texture(region, None)?
+            .save_to_png_bytes();
+        gio::File::for_path(path).replace_contents(
+            &bytes,
+            None,
+            false,
+            gio::FileCreateFlags::REPLACE_DESTINATION,
+            None::<&gio::Cancellable>,
+        )?;
         Ok(())
     }
 
-    fn present(&mut self) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
-        if let Some(snapshot) = self.snapshot.take() {
-            self.paintable.set_node(snapshot.to_node());
+    /// Copies the part of the contents of the paintable within `region` to
+    /// `clipboard`, as a PNG image and an SVG document.
+    pub fn copy_to_clipboard(
+        &self,
+        clipboard: &gdk::Clipboard,
+        region: ExportRegion,
+    ) -> Result<(), glib::BoolError> {
+        clipboard.set_content(Some(&self.content_for_region(region)))
+    }
+
+    /// Serializes the contents of the paintable, along with its size.
+    ///
+    /// The contents can be restored with [`Paintable::from_node_bytes`], such
+    /// as to cache plots on disk between runs or to send them from another
+    /// process. The format is the one of [`gsk::RenderNode::serialize`],
+    /// which is only guaranteed to be understood by the same GTK version.
+    ///
+    /// [Annotations](Paintable::annotations), the
+    /// [background](Paintable::set_background), and the
+    /// [watermark](Paintable::set_watermark) are not serialized.
+    pub fn serialize(&self) -> glib::Bytes {
+        self.restore_evicted_node();
+
+        let (width, height) = self.size();
+        let bounds = Rect::new(0.0, 0.0, width as f32, height as f32);
+
+        // Ensure that the bounds of the node are exactly the bounds of the
+        // paintable.
+        let snapshot = gtk::Snapshot::new();
+        snapshot.append_color(&gdk::RGBA::TRANSPARENT, &bounds);
+        if let Some(node) = self.imp().node.borrow().as_ref() {
+            snapshot.push_clip(&bounds);
+            snapshot.append_node(node);
+            snapshot.pop();
         }
-        Ok(())
+
+        snapshot.to_node().unwrap().serialize()
     }
 
-    #[inline]
-    fn draw_pixel(
-        &mut self,
-        point: BackendCoord,
-        color: BackendColor,
-    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
-        common::draw_pixel(self.snapshot(), point, color)
+    /// Returns a rough estimate of the memory used by the drawn nodes, in
+    /// bytes.
+    ///
+    /// This includes the pixels of textures, such as rasterized frames, but
+    /// not the resources the renderer creates from the nodes, such as glyph
+    /// caches. If the nodes are evicted, this is the size of their serialized
+    /// form.
+    pub fn estimated_node_memory(&self) -> usize {
+        if let Some(bytes) = self.imp().evicted_node.borrow().as_ref() {
+            return bytes.len();
+        }
+
+        self.imp()
+            .node
+            .borrow()
+            .as_ref()
+            .map_or(0, estimated_node_memory)
     }
 
-    #[inline]
-    fn draw_line<S: BackendStyle>(
-        &mut self,
-        from: BackendCoord,
-        to: BackendCoord,
-        style: &S,
-    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
-        common::draw_line(self.snapshot(), from, to, style)
+    /// Sets the duration after which the drawn nodes are evicted if the
+    /// paintable is not drawn, or `None` to never evict them, which is the
+    /// default.
+    ///
+    /// Evicted nodes are serialized and dropped, along with the resources
+    /// held by them, such as textures. They are restored the next time the
+    /// paintable is drawn. This is useful for apps holding many paintables
+    /// that are rarely shown at once, such as dashboards with many tabs.
+    pub fn set_eviction_timeout(&self, timeout: Option<Duration>) {
+        self.imp().eviction_timeout.set(timeout);
+        self.schedule_eviction();
     }
 
-    #[inline]
-    fn draw_rect<S: BackendStyle>(
-        &mut self,
-        upper_left: BackendCoord,
-        bottom_right: BackendCoord,
-        style: &S,
-        fill: bool,
-    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
-        common::draw_rect(self.snapshot(), upper_left, bottom_right, style, fill)
+    /// Returns the duration after which the drawn nodes are evicted if the
+    /// paintable is not drawn.
+    pub fn eviction_timeout(&self) -> Option<Duration> {
+        self.imp().eviction_timeout.get()
     }
 
-    #[inline]
-    fn draw_path<S: BackendStyle, I: IntoIterator<Item = BackendCoord>>(
-        &mut self,
-        raw_path: I,
-        style: &S,
-    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
-        common::draw_path(self.snapshot(), raw_path, style)
+    /// Sets the maximum number of times per second the contents are
+    /// invalidated, or `None` to invalidate them on every change, which is
+    /// the default.
+    ///
+    /// Frames presented faster than this, such as from streaming data, are
+    /// coalesced, so that only the latest one is drawn once the interval
+    /// elapses. This keeps GTK from being flooded with redraws it cannot
+    /// show faster than the display refreshes anyway.
+    pub fn set_max_fps(&self, max_fps: Option<u32>) {
+        self.imp()
+            .max_fps
+            .set(max_fps.filter(|&max_fps| max_fps > 0));
     }
 
-    #[inline]
-    fn fill_polygon<S: BackendStyle, I: IntoIterator<Item = BackendCoord>>(
-        &mut self,
-        vert: I,
-        style: &S,
-    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
-        common::fill_polygon(self.snapshot(), vert, style)
+    /// Returns the maximum number of times per second the contents are
+    /// invalidated.
+    pub fn max_fps(&self) -> Option<u32> {
+        self.imp().max_fps.get()
     }
 
-    #[inline]
-    fn draw_circle<S: BackendStyle>(
-        &mut self,
-        center: BackendCoord,
-        radius: u32,
-        style: &S,
-        fill: bool,
-    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
-        common::draw_circle(self.snapshot(), center, radius, style, fill)
+    /// Cancels the frame being drawn with a [`PaintableBackend`], if any.
+    ///
+    /// The cancelled frame is not presented, and the primitives drawn after
+    /// cancelling it are skipped. Creating a new backend for the paintable
+    /// cancels the pending frame as well, so a superseded render, such as one
+    /// of ranges the user has since zoomed away from, is abandoned.
+    pub fn cancel_pending(&self) {
+        if let Some(cancellable) = self.imp().pending_cancellable.take() {
+            cancellable.cancel();
+        }
     }
 
-    #[inline]
-    fn estimate_text_size<TStyle: BackendTextStyle>(
+    /// Sets what is drawn beneath the plot, or `None` to leave it
+    /// transparent, which is the default.
+    ///
+    /// The background is kept when the plot is redrawn or cleared, and is
+    /// included in textures and SVG documents rendered from the paintable,
+    /// but is not [serialized](Paintable::serialize).
+    pub fn set_background(&self, background: Option<Background>) {
+        let imp = self.imp();
+
+        if imp.background.get() == background {
+            return;
+        }
+
+        imp.background.set(background);
+        imp.thumbnail.replace(None);
+        self.queue_invalidate_contents();
+    }
+
+    /// Returns what is drawn beneath the plot.
+    pub fn background(&self) -> Option<Background> {
+        self.imp().background.get()
+    }
+
+    /// Calls `draw` to draw new contents, then cross-fades from the previous
+    /// contents to them over `duration`, with the progress eased by
+    /// `easing`.
+    ///
+    /// `draw` is given a new backend for the paintable, and must present it,
+    /// such as by calling [`DrawingArea::present`] on the drawing area
+    /// created from it. This gives polished updates when the dataset
+    /// changes. Starting another transition while one is running continues
+    /// from the contents presented last.
+    ///
+    /// [`DrawingArea::present`]: plotters::drawing::DrawingArea::present
+    pub fn transition_to(
         &self,
-        text: &str,
-        style: &TStyle,
-    ) -> Result<(u32, u32), DrawingErrorKind<Self::ErrorType>> {
-        common::estimate_text_size(&self.layout, text, style)
+        draw: impl FnOnce(PaintableBackend<'_>),
+        duration: Duration,
+        easing: Easing,
+    ) {
+        self.restore_evicted_node();
+        let from = self.imp().node.borrow().clone();
+
+        draw(PaintableBackend::new(self));
+
+        self.start_transition(Transition::cross_fade(from, duration, easing));
     }
 
-    #[inline]
-    fn draw_text<TStyle: BackendTextStyle>(
-        &mut self,
-        text: &str,
-        style: &TStyle,
-        pos: BackendCoord,
-    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
-        common::draw_text(self.snapshot(), &self.layout, text, style, pos)
+    /// Shows `recording`, rebuilding only the nodes of the parts of it that
+    /// changed since the recording last shown with this.
+    ///
+    /// The display list is split into its top-level groups, recorded with
+    /// [`RecordingBackend::with_group`], and runs of primitives between them,
+    /// and the nodes of the parts that are the same as before are kept. This
+    /// makes periodic redraws of mostly static charts, such as dashboards
+    /// where a single series changes, much cheaper than redrawing
+    /// everything with a [`PaintableBackend`].
+    ///
+    /// [`RecordingBackend::with_group`]: crate::recording::RecordingBackend::with_group
+    pub fn present_recording(&self, recording: Recording) {
+        let imp = self.imp();
+
+        let nodes = recording.chunk_nodes(&mut imp.recording_nodes.borrow_mut());
+        imp.recording.replace(Some(recording));
+
+        self.set_node(Some(gsk::ContainerNode::new(&nodes).upcast()));
+    }
+
+    /// Shows `recording`, animating the primitives of the recording shown
+    /// previously with this into the matching primitives of `recording` over
+    /// `duration`, with the progress eased by `easing`.
+    ///
+    /// Primitives are matched by their kind and color, or by their text for
+    /// text, in the order they were drawn, so that bar heights and line
+    /// shapes of the same series animate smoothly when values change, while
+    /// primitives without a match fade in or out. Series of the same color
+    /// can be kept apart with [`RecordingBackend::with_group`].
+    ///
+    /// If no recording was shown with this since the paintable was last drawn
+    /// with a [`PaintableBackend`], or it was of a different size, this
+    /// cross-fades to `recording` instead. Starting another morph while one
+    /// is running continues from the recording it was morphing into.
+    ///
+    /// [`RecordingBackend::with_group`]: crate::recording::RecordingBackend::with_group
+    pub fn morph_to(&self, recording: Recording, duration: Duration, easing: Easing) {
+        let imp = self.imp();
+
+        let previous = imp.recording.replace(Some(recording.clone()));
+        let transition = match previous {
+            Some(previous) if previous.size() == recording.size() => {
+                Transition::morph(previous, recording, duration, easing)
+            }
+            _ => {
+                self.restore_evicted_node();
+                let previous_node = imp.node.borrow().clone();
+                self.set_node(recording.to_node());
+                Transition::cross_fade(previous_node, duration, easing)
+            }
+        };
+
+        self.start_transition(transition);
+    }
+
+    /// Shows `transition` until it is finished, invalidating the contents on
+    /// every frame.
+    ///
+    /// The frames are driven by the frame clock of the attached widget while
+    /// it is mapped, or by a timeout otherwise.
+    fn start_transition(&self, transition: Transition) {
+        let imp = self.imp();
+
+        if let Some(source) = imp.transition_source.take() {
+            source.remove();
+        }
+
+        if transition.is_finished() {
+            imp.transition.replace(None);
+            if let Some(frame) = transition.morph_frame() {
+                self.set_node(frame.to_node());
+            }
+            self.invalidate_contents();
+            return;
+        }
+
+        imp.transition.replace(Some(transition));
+
+        let source = if let Some(widget) = imp.widget.upgrade().filter(|widget| widget.is_mapped())
+        {
+            FrameSource::Tick(widget.add_tick_callback(clone!(
+                #[weak(rename_to = obj)]
+                self,
+                #[upgrade_or]
+                glib::ControlFlow::Break,
+                move |_, _| obj.advance_transition()
+            )))
+        } else {
+            FrameSource::Timeout(glib::timeout_add_local(
+                TRANSITION_FRAME_INTERVAL,
+                clone!(
+                    #[weak(rename_to = obj)]
+                    self,
+                    #[upgrade_or]
+                    glib::ControlFlow::Break,
+                    move || obj.advance_transition()
+                ),
+            ))
+        };
+        imp.transition_source.replace(Some(source));
+    }
+
+    /// Shows the current frame of the running transition, returning whether
+    /// to keep calling this.
+    fn advance_transition(&self) -> glib::ControlFlow {
+        let imp = self.imp();
+
+        let (is_finished, morph_frame) = match imp.transition.borrow().as_ref() {
+            Some(transition) => (transition.is_finished(), transition.morph_frame()),
+            None => (true, None),
+        };
+
+        if let Some(frame) = morph_frame {
+            self.set_node(frame.to_node());
+        }
+
+        if is_finished {
+            imp.transition.replace(None);
+            imp.transition_source.replace(None);
+            self.invalidate_contents();
+            return glib::ControlFlow::Break;
+        }
+
+        self.invalidate_contents();
+        glib::ControlFlow::Continue
+    }
+
+    /// Sets the mark drawn above the plot and annotations, or `None` to draw
+    /// none, which is the default.
+    ///
+    /// The watermark is kept when the plot is redrawn or cleared, and is
+    /// included in textures and SVG documents rendered from the paintable,
+    /// so apps can stamp exported charts, such as with an attribution or a
+    /// "DRAFT" label, without changing the code that draws them. Like the
+    /// [background](Paintable::set_background), it is not
+    /// [serialized](Paintable::serialize).
+    pub fn set_watermark(&self, watermark: Option<Watermark>) {
+        let imp = self.imp();
+
+        if *imp.watermark.borrow() == watermark {
+            return;
+        }
+
+        imp.watermark.replace(watermark);
+        imp.thumbnail.replace(None);
+        self.queue_invalidate_contents();
+    }
+
+    /// Returns the mark drawn above the plot and annotations.
+    pub fn watermark(&self) -> Option<Watermark> {
+        self.imp().watermark.borrow().clone()
+    }
+
+    /// Sets whether a checkerboard is shown beneath the plot, to preview
+    /// transparent areas, such as when designing chart assets meant to be
+    /// overlaid on other content.
+    ///
+    /// The checkerboard is only shown where the paintable is drawn, and is
+    /// not included in textures and SVG documents rendered from it.
+    pub fn set_shows_checkerboard(&self, shows_checkerboard: bool) {
+        if shows_checkerboard == self.shows_checkerboard() {
+            return;
+        }
+
+        self.imp().shows_checkerboard.set(shows_checkerboard);
+        self.invalidate_contents();
+        self.notify("shows-checkerboard");
+    }
+
+    /// Returns whether a checkerboard is shown beneath the plot.
+    pub fn shows_checkerboard(&self) -> bool {
+        self.imp().shows_checkerboard.get()
+    }
+
+    /// Sets the quality backends drawing on this paintable start with.
+    ///
+    /// This lets whatever schedules the drawing pick the quality without
+    /// changing the drawing code, such as [`PlotView`](crate::PlotView)
+    /// drafting while the user pans or zooms. Backends can still override
+    /// it with [`PaintableBackend::set_quality`].
+    pub fn set_quality(&self, quality: Quality) {
+        self.imp().quality.set(quality);
+    }
+
+    /// Returns the quality backends drawing on this paintable start with.
+    pub fn quality(&self) -> Quality {
+        self.imp().quality.get()
+    }
+
+    /// Sets the number of device pixels per pixel of the paintable, which
+    /// content rasterized by backends is rendered at.
+    ///
+    /// Vector content is sharp at any density, but frames rasterized for
+    /// exceeding [`PaintableBackend::set_node_budget`] must be drawn again
+    /// when this changes, such as when notified of the `scale-factor`
+    /// property. This defaults to `1`, and is usually kept in sync with a
+    /// widget with [`Paintable::attach_to_widget`].
+    pub fn set_scale_factor(&self, scale_factor: i32) {
+        let scale_factor = scale_factor.max(1);
+        if scale_factor == self.scale_factor() {
+            return;
+        }
+
+        self.imp().scale_factor.set(scale_factor);
+        self.notify("scale-factor");
+    }
+
+    /// Returns the number of device pixels per pixel of the paintable.
+    pub fn scale_factor(&self) -> i32 {
+        self.imp().scale_factor.get().max(1)
+    }
+
+    /// Keeps the scale factor of the paintable in sync with the one of
+    /// `widget`, such as when its window moves to a monitor of another
+    /// density.
+    ///
+    /// Invalidations limited by [`Paintable::set_max_fps`] are scheduled on
+    /// the frame clock of `widget` as well, while it is mapped.
+    ///
+    /// The returned handler can be disconnected from `widget` to stop
+    /// syncing. [`PlotView`](crate::PlotView) does this for its paintable,
+    /// redrawing it when the scale factor changes.
+    pub fn attach_to_widget(&self, widget: &impl IsA<gtk::Widget>) -> glib::SignalHandlerId {
+        self.imp().widget.set(Some(widget.upcast_ref()));
+        self.set_scale_factor(widget.scale_factor());
+        widget.connect_scale_factor_notify(clone!(
+            #[weak(rename_to = obj)]
+            self,
+            move |widget| {
+                obj.set_scale_factor(widget.scale_factor());
+            }
+        ))
+    }
+
+    /// Sets the capabilities of the renderer the paintable is drawn with,
+    /// which backends drawing on this paintable start with.
+    ///
+    /// [`PlotView`](crate::PlotView) sets this to the capabilities of the
+    /// renderer of its window. Backends can still override it with
+    /// [`PaintableBackend::set_renderer_caps`].
+    pub fn set_renderer_caps(&self, renderer_caps: RendererCaps) {
+        self.imp().renderer_caps.set(renderer_caps);
+    }
+
+    /// Returns the capabilities of the renderer the paintable is drawn with.
+    pub fn renderer_caps(&self) -> RendererCaps {
+        self.imp().renderer_caps.get()
+    }
+
+    /// Sets the size below which text is faded out when the paintable is
+    /// drawn scaled down, in pixels, or `None` to always draw text as is.
+    ///
+    /// Text too small to be legible, such as in thumbnails, only clutters
+    /// the plot. Text is faded out as its font size times the scale the
+    /// paintable is drawn at goes from this size to half of it, below which
+    /// it is not drawn at all. Outline and pill halos are kept.
+    pub fn set_min_text_size(&self, min_text_size: Option<f32>) {
+        let imp = self.imp();
+        imp.min_text_size.set(min_text_size);
+        imp.scaled_node.replace(None);
+        imp.thumbnail.replace(None);
+        self.invalidate_contents();
+    }
+
+    /// Returns the size below which text is faded out when the paintable is
+    /// drawn scaled down, in pixels.
+    pub fn min_text_size(&self) -> Option<f32> {
+        self.imp().min_text_size.get()
+    }
+
+    /// Sets whether strokes keep their width when the paintable is drawn
+    /// scaled, rather than getting thicker or thinner with it.
+    ///
+    /// This keeps the hairlines of line charts crisp at any size the
+    /// paintable is shown at, such as in a [`gtk::Picture`] that stretches
+    /// with the window. Only the widths of strokes are compensated, by the
+    /// smaller of the horizontal and vertical scale, so fills and text keep
+    /// scaling. For pixel-exact strokes, draw on a paintable of the size it
+    /// is shown at instead.
+    pub fn set_non_scaling_stroke(&self, is_non_scaling_stroke: bool) {
+        let imp = self.imp();
+        imp.is_non_scaling_stroke.set(is_non_scaling_stroke);
+        imp.scaled_node.replace(None);
+        imp.thumbnail.replace(None);
+        self.invalidate_contents();
+    }
+
+    /// Returns whether strokes keep their width when the paintable is drawn
+    /// scaled.
+    pub fn is_non_scaling_stroke(&self) -> bool {
+        self.imp().is_non_scaling_stroke.get()
+    }
+
+    /// Returns the statistics of the last presented frame.
+    ///
+    /// This can be used to tune [`PaintableBackend::set_node_budget`].
+    pub fn render_stats(&self) -> RenderStats {
+        self.imp().render_stats.get()
+    }
+
+    /// Registers a series to be shown in a [`Legend`](crate::Legend).
+    ///
+    /// If a series with the same name was already registered, that series
+    /// is returned instead, so this can be called every time the plot is
+    /// drawn without losing its visibility.
+    pub fn register_series(&self, name: &str, color: &impl Color, marker: SeriesMarker) -> Series {
+        let store = self.series_store();
+
+        if let Some(series) = store
+            .iter::<Series>()
+            .filter_map(Result::ok)
+            .find(|series| series.name() == name)
+        {
+            return series;
+        }
+
+        let series = Series::new(name, color, marker);
+        store.append(&series);
+        series
+    }
+
+    /// Returns the registered series as a list model of [`Series`].
+    pub fn series(&self) -> gio::ListModel {
+        self.series_store().clone().upcast()
+    }
+
+    /// Removes all registered series.
+    pub fn clear_series(&self) {
+        self.series_store().remove_all();
+    }
+
+    /// Returns the annotations drawn above the plot.
+    ///
+    /// Annotations are kept when the plot is redrawn or cleared, and
+    /// changing them does not redraw the plot.
+    pub fn annotations(&self) -> Annotations {
+        Annotations {
+            paintable: self.clone(),
+        }
+    }
+
+    /// Calls `f` with the annotations and the next ID, then redraws them.
+    pub(crate) fn update_annotations<T>(
+        &self,
+        f: impl FnOnce(&mut Vec<(AnnotationId, Annotation)>, &mut u64) -> T,
+    ) -> T {
+        let imp = self.imp();
+
+        let mut next_id = imp.next_annotation_id.get();
+        let ret = f(&mut imp.annotations.borrow_mut(), &mut next_id);
+        imp.next_annotation_id.set(next_id);
+
+        imp.annotations_node.replace(None);
+        imp.thumbnail.replace(None);
+        self.queue_invalidate_contents();
+
+        ret
+    }
+
+    pub(crate) fn annotations_list(&self) -> Vec<(AnnotationId, Annotation)> {
+        self.imp().annotations.borrow().clone()
+    }
+
+    /// Returns the node of the annotations, creating it if they changed.
+    fn annotations_node(&self) -> Option<gsk::RenderNode> {
+        let imp = self.imp();
+
+        if imp.annotations.borrow().is_empty() {
+            return None;
+        }
+
+        let node = imp
+            .annotations_node
+            .borrow_mut()
+            .get_or_insert_with(|| {
+                annotation::annotations_node(&imp.annotations.borrow(), self.size())
+                    .unwrap_or_else(|| gsk::ContainerNode::new(&[]).upcast())
+            })
+            .clone();
+        Some(node)
+    }
+
+    /// Returns the topmost region at `(x, y)`, in pixels of the paintable,
+    /// drawn within [`PaintableBackend::with_link`],
+    /// [`PaintableBackend::with_data`], or [`PaintableBackend::with_id`].
+    pub fn hit_region_at(&self, x: f32, y: f32) -> Option<HitRegion> {
+        let point = Point::new(x, y);
+        self.with_hit_regions(|regions| {
+            regions
+                .iter()
+                .rev()
+                .find(|region| region.bounds.contains_point(&point))
+                .cloned()
+        })
+    }
+
+    /// Returns the regions drawn within [`PaintableBackend::with_link`],
+    /// [`PaintableBackend::with_data`], or [`PaintableBackend::with_id`], in
+    /// the order they are drawn.
+    ///
+    /// Regions are not kept in frames rasterized for exceeding the node
+    /// budget, see [`PaintableBackend::set_node_budget`].
+    pub fn hit_regions(&self) -> Vec<HitRegion> {
+        self.with_hit_regions(<[HitRegion]>::to_vec)
+    }
+
+    /// Calls `f` with the hit regions of the node, finding them first if
+    /// the node changed.
+    fn with_hit_regions<T>(&self, f: impl FnOnce(&[HitRegion]) -> T) -> T {
+        let imp = self.imp();
+
+        if imp.hit_regions.borrow().is_none() {
+            self.restore_evicted_node();
+            self.schedule_eviction();
+
+            let regions = imp.node.borrow().as_ref().map_or_else(Vec::new, |node| {
+                hit_region::hit_regions(node, &imp.hit_tags.borrow())
+            });
+            imp.hit_regions.replace(Some(regions));
+        }
+
+        f(imp.hit_regions.borrow().as_deref().unwrap_or_default())
+    }
+
+    fn series_store(&self) -> &gio::ListStore {
+        self.imp().series.get_or_init(gio::ListStore::new::<Series>)
+    }
+
+    pub(crate) fn set_node(&self, node: Option<gsk::RenderNode>) {
+        self.imp().regions.borrow_mut().clear();
+        self.imp().has_lod_groups.set(true);
+        self.replace_node(node);
+    }
+
+    /// Replaces the part of the contents within `rect` with `node`, after
+    /// moving the previous contents left by `scroll_x` pixels.
+    fn merge_region(&self, node: Option<gsk::RenderNode>, rect: Rect, scroll_x: f32) {
+        let imp = self.imp();
+        self.restore_evicted_node();
+
+        let (width, height) = self.size();
+        let bounds = Rect::new(0.0, 0.0, width as f32, height as f32);
+
+        let mut regions = imp.regions.take();
+        if regions.is_empty() {
+            if let Some(node) = imp.node.borrow().clone() {
+                regions.push(Region::new(bounds, node));
+            }
+        }
+
+        if scroll_x != 0.0 {
+            regions = regions
+                .into_iter()
+                .filter_map(|region| region.scrolled(scroll_x, &bounds))
+                .collect();
+        }
+
+        let mut regions = regions
+            .into_iter()
+            .flat_map(|region| region.subtract(&rect))
+            .collect::<Vec<_>>();
+        if let Some(node) = node {
+            if let Some(rect) = rect.intersection(&bounds) {
+                regions.push(Region::new(rect, node));
+            }
+        }
+
+        let nodes = regions.iter().map(Region::to_node).collect::<Vec<_>>();
+        imp.regions.replace(regions);
+        self.replace_node(Some(gsk::ContainerNode::new(&nodes).upcast()));
+    }
+
+    /// Replaces the series groups of the contents with the groups of the
+    /// same names in `groups`, returning whether all of them were found.
+    fn merge_series(&self, groups: &HashMap<String, gsk::RenderNode>) -> bool {
+        let imp = self.imp();
+        self.restore_evicted_node();
+
+        let node = imp.node.borrow().clone();
+        let mut replaced = HashSet::new();
+        if let Some(node) =
+            node.and_then(|node| replace_series_groups(&node, groups, &mut replaced))
+        {
+            // The regions presented previously are part of the node now.
+            imp.regions.borrow_mut().clear();
+            self.replace_node(Some(node));
+        }
+        replaced.len() == groups.len()
+    }
+
+    /// Drops the tags of the hit regions that are no longer in the node,
+    /// as they were drawn over.
+    fn retain_presented_hit_tags(&self) {
+        let imp = self.imp();
+
+        if imp.hit_tags.borrow().is_empty() {
+            return;
+        }
+
+        let ids = imp
+            .node
+            .borrow()
+            .as_ref()
+            .map(hit_region::hit_ids)
+            .unwrap_or_default();
+        imp.hit_tags.borrow_mut().retain(|id, _| ids.contains(id));
+    }
+
+    fn replace_node(&self, node: Option<gsk::RenderNode>) {
+        self.imp().node.replace(node);
+        self.imp().hit_regions.replace(None);
+        self.imp().evicted_node.replace(None);
+        self.imp().thumbnail.replace(None);
+        self.schedule_eviction();
+        self.queue_invalidate_contents();
+    }
+
+    /// Invalidates the contents, or schedules it if they were invalidated
+    /// too recently for the maximum FPS.
+    fn queue_invalidate_contents(&self) {
+        let imp = self.imp();
+
+        let Some(max_fps) = imp.max_fps.get() else {
+            self.invalidate_contents();
+            return;
+        };
+
+        // A pending invalidation draws the latest contents anyway, unless it
+        // waits for the frames of a widget that is no longer shown.
+        if let Some(source) = imp.invalidation_source.take() {
+            if !matches!(source, FrameSource::Tick(_))
+                || imp
+                    .widget
+                    .upgrade()
+                    .is_some_and(|widget| widget.is_mapped())
+            {
+                imp.invalidation_source.replace(Some(source));
+                return;
+            }
+            source.remove();
+        }
+
+        let interval = 1_000_000 / max_fps as i64;
+        let now = glib::monotonic_time();
+        let elapsed = imp
+            .last_invalidation
+            .get()
+            .map_or(interval, |last| now - last);
+
+        if elapsed >= interval {
+            imp.last_invalidation.set(Some(now));
+            self.invalidate_contents();
+            return;
+        }
+
+        // Invalidating on the frame clock of the attached widget draws the
+        // latest contents on the first frame after the interval. Paintables
+        // without one visible have to make do with a timeout.
+        if let Some(widget) = imp.widget.upgrade().filter(|widget| widget.is_mapped()) {
+            let tick_id = widget.add_tick_callback(clone!(
+                #[weak(rename_to = obj)]
+                self,
+                #[upgrade_or]
+                glib::ControlFlow::Break,
+                move |_, frame_clock| {
+                    let imp = obj.imp();
+                    let frame_time = frame_clock.frame_time();
+                    if imp
+                        .last_invalidation
+                        .get()
+                        .is_some_and(|last| frame_time - last < interval)
+                    {
+                        return glib::ControlFlow::Continue;
+                    }
+
+                    imp.invalidation_source.replace(None);
+                    imp.last_invalidation.set(Some(frame_time));
+                    obj.invalidate_contents();
+                    glib::ControlFlow::Break
+                }
+            ));
+            imp.invalidation_source
+                .replace(Some(FrameSource::Tick(tick_id)));
+            return;
+        }
+
+        let source_id = glib::timeout_add_local_once(
+            Duration::from_micros((interval - elapsed) as u64),
+            clone!(
+                #[weak(rename_to = obj)]
+                self,
+                move || {
+                    let imp = obj.imp();
+                    imp.invalidation_source.replace(None);
+                    imp.last_invalidation.set(Some(glib::monotonic_time()));
+                    obj.invalidate_contents();
+                }
+            ),
+        );
+        imp.invalidation_source
+            .replace(Some(FrameSource::Timeout(source_id)));
+    }
+
+    /// Renders the part of the contents of the paintable within `region`
+    /// into an SVG document.
+    pub(crate) fn to_svg(&self, region: ExportRegion) -> Result<Vec<u8>, cairo::Error> {
+        self.restore_evicted_node();
+
+        let rect = self
+            .region_rect(region)
+            .map_err(|_| cairo::Error::InvalidSize)?;
+        let (width, height) = self.size();
+        let snapshot = gtk::Snapshot::new();
+        snapshot.translate(&Point::new(-rect.x(), -rect.y()));
+        if let Some(background) = self.imp().background.get() {
+            background.append_to(&snapshot, &Rect::new(0.0, 0.0, width as f32, height as f32));
+        }
+        if let Some(node) = self.imp().node.borrow().as_ref() {
+            snapshot.append_node(node);
+        }
+        if let Some(annotations_node) = self.annotations_node() {
+            snapshot.append_node(&annotations_node);
+        }
+        if let Some(watermark) = self.imp().watermark.borrow().as_ref() {
+            watermark.append_to(&snapshot, (width, height));
+        }
+        export::render_node_to_svg(
+            snapshot.to_node().as_ref(),
+            (rect.width().ceil() as u32, rect.height().ceil() as u32),
+        )
+    }
+
+    /// Returns a content provider of the part of the contents of the
+    /// paintable within `region` as a PNG image and an SVG document.
+    fn content_for_region(&self, region: ExportRegion) -> gdk::ContentProvider {
+        let mut providers = Vec::new();
+
+        if let Ok(texture) = self.render_region_to_texture(region, None) {
+            // Textures are serialized as PNG, among other formats.
+            providers.push(gdk::ContentProvider::for_value(&texture.to_value()));
+        }
+
+        if let Ok(svg) = self.to_svg(region) {
+            providers.push(gdk::ContentProvider::for_bytes(
+                "image/svg+xml",
+                &glib::Bytes::from_owned(svg),
+            ));
+        }
+
+        gdk::ContentProvider::new_union(&providers)
+    }
+
+    /// Returns the rect of `region`, or an error if it is empty.
+    fn region_rect(&self, region: ExportRegion) -> Result<Rect, glib::Error> {
+        let plot_area = self.plot_area().map(|(upper_left, bottom_right)| {
+            Rect::new(
+                upper_left.0 as f32,
+                upper_left.1 as f32,
+                (bottom_right.0 - upper_left.0) as f32,
+                (bottom_right.1 - upper_left.1) as f32,
+            )
+        });
+        region.to_rect(self.size(), plot_area).ok_or_else(|| {
+            glib::Error::new(
+                gio::IOErrorEnum::InvalidArgument,
+                "Export region is outside of the paintable",
+            )
+        })
+    }
+
+    /// Restarts the countdown to evict the node, if eviction is enabled.
+    fn schedule_eviction(&self) {
+        let imp = self.imp();
+
+        if let Some(source_id) = imp.eviction_source.take() {
+            source_id.remove();
+        }
+
+        let Some(timeout) = imp.eviction_timeout.get() else {
+            return;
+        };
+
+        if imp.node.borrow().is_none() {
+            return;
+        }
+
+        let source_id = glib::timeout_add_local_once(
+            timeout,
+            clone!(
+                #[weak(rename_to = obj)]
+                self,
+                move || {
+                    obj.imp().eviction_source.replace(None);
+                    obj.evict_node();
+                }
+            ),
+        );
+        imp.eviction_source.replace(Some(source_id));
+    }
+
+    fn scale_options(&self) -> optimize::ScaleOptions {
+        let imp = self.imp();
+        optimize::ScaleOptions {
+            min_text_size: imp.min_text_size.get(),
+            is_non_scaling_stroke: imp.is_non_scaling_stroke.get(),
+        }
+    }
+
+    /// Returns `node` as drawn at `scale`, reusing the last result if
+    /// neither changed.
+    fn node_for_scale(&self, node: &gsk::RenderNode, scale: f32) -> Option<gsk::RenderNode> {
+        let imp = self.imp();
+
+        let options = self.scale_options();
+        if !imp.has_lod_groups.get()
+            && options.min_text_size.is_none()
+            && !options.is_non_scaling_stroke
+        {
+            return Some(node.clone());
+        }
+
+        if let Some((scaled_from, scaled_scale, scaled_node)) = imp.scaled_node.borrow().as_ref() {
+            if scaled_from == node && *scaled_scale == scale {
+                return scaled_node.clone();
+            }
+        }
+
+        let scaled_node = optimize::for_scale(node, scale, options);
+        imp.scaled_node
+            .replace(Some((node.clone(), scale, scaled_node.clone())));
+        scaled_node
+    }
+
+    fn evict_node(&self) {
+        let imp = self.imp();
+
+        if let Some(node) = imp.node.take() {
+            imp.evicted_node.replace(Some(node.serialize()));
+            imp.scaled_node.replace(None);
+        }
+    }
+
+    fn restore_evicted_node(&self) {
+        let imp = self.imp();
+
+        if let Some(bytes) = imp.evicted_node.take() {
+            imp.node.replace(gsk::RenderNode::deserialize(&bytes));
+        }
+    }
+}
+
+/// Statistics of a frame drawn with [`PaintableBackend`].
+///
+/// This is returned by [`Paintable::render_stats`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RenderStats {
+    /// The number of nodes appended by the drawn primitives.
+    ///
+    /// This excludes nodes appended directly through [`GtkBackend`].
+    pub n_nodes: usize,
+    /// Whether the frame was rasterized into a texture, as it exceeded the
+    /// node budget.
+    pub is_rasterized: bool,
+    /// The node counts before and after optimizing the frame, if it was
+    /// optimized with [`PaintableBackend::set_optimizes_nodes`].
+    pub optimization: Option<OptimizationStats>,
+}
+
+/// Backend that draws to an object that implements [`gdk::Paintable`].
+#[derive(Debug)]
+pub struct PaintableBackend<'a> {
+    snapshot: Option<gtk::Snapshot>,
+    tiles: BTreeMap<(i32, i32), gtk::Snapshot>,
+    tile_size: Option<u32>,
+    node_budget: Option<usize>,
+    optimizes_nodes: bool,
+    progressive_interval: Option<Duration>,
+    last_partial_flush: Option<Instant>,
+    partial_nodes: Vec<gsk::RenderNode>,
+    scope_depth: usize,
+    z_layers: BTreeMap<i32, Vec<gsk::RenderNode>>,
+    cancellable: gio::Cancellable,
+    paintable: &'a Paintable,
+    context: BackendContext,
+    layout: pango::Layout,
+    size: (u32, u32),
+    cull_margin: Option<u32>,
+    options: common::Options,
+    style_classes: StyleClasses,
+    scratch: common::Scratch,
+    /// The tags of the hit regions drawn in the frame, by ID.
+    hit_tags: HashMap<u64, HitTag>,
+    /// The tag of the hit region being drawn, if any.
+    hit_tag: Option<HitTag>,
+    /// Whether the frame has level-of-detail groups.
+    has_lod_groups: bool,
+    /// The plotting area set for the frame, if any.
+    plot_area: Option<(BackendCoord, BackendCoord)>,
+}
+
+impl<'a> PaintableBackend<'a> {
+    /// Creates a new drawing backend backed with an object that implements
+    /// [`gdk::Paintable`].
+    ///
+    /// This creates new resources for drawing text. For plots redrawn often,
+    /// use [`PaintableBackend::with_context`] with a [`BackendContext`] that
+    /// is kept across redraws instead.
+    pub fn new(paintable: &'a Paintable) -> Self {
+        Self::with_context(paintable, &BackendContext::new())
+    }
+
+    /// Creates a new drawing backend backed with an object that implements
+    /// [`gdk::Paintable`], reusing the resources of `context`.
+    pub fn with_context(paintable: &'a Paintable, context: &BackendContext) -> Self {
+        paintable.cancel_pending();
+        // What is drawn no longer matches the recording to morph from.
+        paintable.imp().recording.replace(None);
+        paintable.imp().recording_nodes.borrow_mut().clear();
+        let cancellable = gio::Cancellable::new();
+        paintable
+            .imp()
+            .pending_cancellable
+            .replace(Some(cancellable.clone()));
+
+        let mut backend = Self {
+            snapshot: None,
+            tiles: BTreeMap::new(),
+            tile_size: None,
+            node_budget: None,
+            optimizes_nodes: false,
+            progressive_interval: None,
+            last_partial_flush: None,
+            partial_nodes: Vec::new(),
+            scope_depth: 0,
+            z_layers: BTreeMap::new(),
+            cancellable,
+            paintable,
+            context: context.clone(),
+            layout: context.layout().clone(),
+            size: paintable.size(),
+            cull_margin: None,
+            options: common::Options::default(),
+            style_classes: StyleClasses::default(),
+            scratch: common::Scratch::default(),
+            hit_tags: HashMap::new(),
+            hit_tag: None,
+            has_lod_groups: false,
+            plot_area: None,
+        };
+        backend.set_quality(paintable.quality());
+        backend.set_renderer_caps(paintable.renderer_caps());
+        backend
+    }
+
+    /// Sets the multiplier applied to the stroke width of every drawn style.
+    ///
+    /// This is useful for drawing thinner or thicker strokes than what
+    /// [`BackendStyle::stroke_width`] allows, as it is only an integer.
+    pub fn set_stroke_width_scale(&mut self, scale: f32) {
+        self.options.stroke_width_scale = scale;
+    }
+
+    /// Returns the multiplier applied to the stroke width of every drawn style.
+    pub fn stroke_width_scale(&self) -> f32 {
+        self.options.stroke_width_scale
+    }
+
+    /// Sets the width used to draw strokes with a stroke width of zero, or
+    /// `None` to not draw them, as other plotters backends do.
+    ///
+    /// This defaults to `None`. See [`HairlineStyle`](crate::HairlineStyle)
+    /// for more details.
+    pub fn set_hairline_width(&mut self, width: Option<f32>) {
+        self.options.hairline_width = width;
+    }
+
+    /// Returns the width used to draw strokes with a stroke width of zero,
+    /// or `None` if they are not drawn.
+    pub fn hairline_width(&self) -> Option<f32> {
+        self.options.hairline_width
+    }
+
+    /// Sets the rule used to determine the interior of filled polygons.
+    ///
+    /// This defaults to [`gsk::FillRule::Winding`]. Use
+    /// [`gsk::FillRule::EvenOdd`] for self-intersecting polygons that should
+    /// have holes, such as star shapes.
+    pub fn set_fill_rule(&mut self, fill_rule: gsk::FillRule) {
+        self.options.fill_rule = fill_rule;
+    }
+
+    /// Returns the rule used to determine the interior of filled polygons.
+    pub fn fill_rule(&self) -> gsk::FillRule {
+        self.options.fill_rule
+    }
+
+    /// Sets the decoration drawn behind text, or `None` to draw text as is.
+    pub fn set_text_halo(&mut self, text_halo: Option<TextHalo>) {
+        self.options.text_halo = text_halo;
+    }
+
+    /// Returns the decoration drawn behind text.
+    pub fn text_halo(&self) -> Option<TextHalo> {
+        self.options.text_halo
+    }
+
+    /// Sets the extents of text that are measured and aligned to the text
+    /// anchor.
+    ///
+    /// Both measuring and drawing text use the same extents, so labels are
+    /// positioned consistently with their measured size.
+    pub fn set_text_extents(&mut self, text_extents: TextExtents) {
+        self.options.text_extents = text_extents;
+    }
+
+    /// Returns the extents of text that are measured and aligned to the text
+    /// anchor.
+    pub fn text_extents(&self) -> TextExtents {
+        self.options.text_extents
+    }
+
+    /// Sets whether text rotated by 90° or 270° is laid out vertically.
+    ///
+    /// Vertical layouts keep the glyphs of vertical scripts, such as CJK,
+    /// upright, as is appropriate for y-axis labels in these scripts, while
+    /// rotating other scripts along the line. The text always runs from top
+    /// to bottom.
+    pub fn set_vertical_text(&mut self, is_vertical_text: bool) {
+        self.options.vertical_text = is_vertical_text;
+    }
+
+    /// Returns whether text rotated by 90° or 270° is laid out vertically.
+    pub fn is_vertical_text(&self) -> bool {
+        self.options.vertical_text
+    }
+
+    /// Sets whether strokes are snapped to the pixel grid.
+    ///
+    /// With pixel snapping, stroke widths of at least one pixel are rounded
+    /// to whole pixels, and odd-width strokes are offset by half a pixel, so
+    /// that axes, grids, and rect borders are drawn crisp, matching the look
+    /// of the bitmap backend. This is meant for displays with a scale factor
+    /// of 1, where the logical pixels are the device pixels.
+    pub fn set_pixel_snap(&mut self, is_pixel_snap: bool) {
+        self.options.pixel_snap = is_pixel_snap;
+    }
+
+    /// Returns whether strokes are snapped to the pixel grid.
+    pub fn is_pixel_snap(&self) -> bool {
+        self.options.pixel_snap
+    }
+
+    /// Sets how far outside of the backend bounds primitives are still
+    /// drawn, in pixels, or `None` to draw all primitives, which is the
+    /// default.
+    ///
+    /// With a margin, pixels, lines, circles, and polygons entirely beyond
+    /// it are skipped, and segments of paths beyond it are left out, instead
+    /// of being turned into nodes that are only clipped when composited.
+    /// This reduces the node count of zoomed-in views of large datasets. The
+    /// margin should cover anything drawn past the points, such as markers
+    /// drawn with [`plotters`] elements around them.
+    ///
+    /// Nothing is skipped within [`Self::with_transform`], as the transform
+    /// may move primitives into view.
+    pub fn set_cull_margin(&mut self, margin: Option<u32>) {
+        self.cull_margin = margin;
+        self.update_cull_bounds();
+    }
+
+    /// Returns how far outside of the backend bounds primitives are still
+    /// drawn.
+    pub fn cull_margin(&self) -> Option<u32> {
+        self.cull_margin
+    }
+
+    fn update_cull_bounds(&mut self) {
+        self.options.cull_bounds = self
+            .cull_margin
+            .map(|margin| common::cull_bounds(self.size, margin));
+    }
+
+    /// Starts a scope of state pushed on the snapshot, such as a clip,
+    /// returning the snapshot to push it on and the cull bounds to restore
+    /// when leaving it.
+    ///
+    /// Culling is disabled within the scope, as it may move primitives into
+    /// view, and so is tiling, as tiles are drawn outside of the scope.
+    pub(crate) fn enter_scope(&mut self) -> (gtk::Snapshot, ScopeState) {
+        let _ = self.ensure_prepared();
+        self.scratch.flush();
+        self.scope_depth += 1;
+        let scope_state = ScopeState {
+            cull_bounds: self.options.cull_bounds.take(),
+            tile_size: self.tile_size.take(),
+        };
+        (self.snapshot().clone(), scope_state)
+    }
+
+    /// Ends a scope started with [`Self::enter_scope`], before the state
+    /// pushed on the snapshot is popped.
+    pub(crate) fn leave_scope(&mut self, scope_state: ScopeState) {
+        self.options.cull_bounds = scope_state.cull_bounds;
+        self.tile_size = scope_state.tile_size;
+        self.scope_depth -= 1;
+        self.scratch.flush();
+    }
+
+    /// Sets the trade-off between rendering speed and quality.
+    ///
+    /// See [`Quality`] for what each preset changes. This defaults to
+    /// [`Quality::Normal`].
+    pub fn set_quality(&mut self, quality: Quality) {
+        if quality == self.options.quality {
+            return;
+        }
+
+        self.scratch.flush();
+        self.options.quality = quality;
+        self.layout = self.context.layout_for_quality(quality);
+    }
+
+    /// Returns the trade-off between rendering speed and quality.
+    pub fn quality(&self) -> Quality {
+        self.options.quality
+    }
+
+    /// Sets the capabilities of the renderer the drawing is rendered with,
+    /// falling back to simpler nodes for what it does not support.
+    ///
+    /// This defaults to [`RendererCaps::ALL`].
+    pub fn set_renderer_caps(&mut self, renderer_caps: RendererCaps) {
+        self.options.renderer_caps = renderer_caps;
+    }
+
+    /// Returns the capabilities of the renderer the drawing is rendered
+    /// with.
+    pub fn renderer_caps(&self) -> RendererCaps {
+        self.options.renderer_caps
+    }
+
+    /// Records the plotting area of the frame on the paintable, from
+    /// `upper_left` to `bottom_right`, to be exported alone with
+    /// [`ExportRegion::PlotArea`].
+    ///
+    /// The plotting area of a chart can be found with
+    /// [`export::plot_area_of`]. It is unset when a frame is presented in
+    /// full without setting it, while frames presented with
+    /// [`PaintableBackend::present_region`] keep the previous one.
+    pub fn set_plot_area(&mut self, upper_left: BackendCoord, bottom_right: BackendCoord) {
+        self.plot_area = Some((upper_left, bottom_right));
+        self.paintable.imp().plot_area.set(self.plot_area);
+    }
+
+    /// Sets what happens when a primitive has invalid input, such as
+    /// coordinates, radii, stroke widths, or font sizes that are out of range
+    /// because of `NaN` or infinite values in the plotted data.
+    ///
+    /// By default, such primitives are skipped.
+    pub fn set_invalid_input_policy(&mut self, policy: InvalidInputPolicy) {
+        self.options.invalid_input_policy = policy;
+    }
+
+    /// Returns what happens when a primitive has invalid input.
+    pub fn invalid_input_policy(&self) -> InvalidInputPolicy {
+        self.options.invalid_input_policy
+    }
+
+    /// Sets the typographic attributes applied to all drawn text.
+    ///
+    /// For example, enabling [`TextAttributes::tabular_figures`] aligns
+    /// numeric axis tick labels in columns.
+    pub fn set_text_attributes(&mut self, text_attributes: TextAttributes) {
+        self.options.text_attributes = text_attributes;
+    }
+
+    /// Returns the typographic attributes applied to all drawn text.
+    pub fn text_attributes(&self) -> TextAttributes {
+        self.options.text_attributes
+    }
+
+    /// Sets the font families that are tried, in order, for characters that
+    /// the family of the text style does not have.
+    ///
+    /// Which font is picked for such characters otherwise depends on the
+    /// system's font configuration, which may end up with monochrome emoji or
+    /// missing glyphs. For example, `&["Noto Color Emoji"]` makes sure emoji in
+    /// category labels are drawn in color. Color glyphs are drawn with their
+    /// own colors rather than the text style's color. Whether they are can be
+    /// checked with [`PaintableBackend::text_coverage`].
+    pub fn set_fallback_families(&mut self, families: &[&str]) {
+        self.options.fallback_families = families.iter().map(|family| family.to_string()).collect();
+    }
+
+    /// Returns the font families that are tried for characters that the
+    /// family of the text style does not have.
+    pub fn fallback_families(&self) -> &[String] {
+        &self.options.fallback_families
+    }
+
+    /// Returns how `text` is covered by the fonts it is drawn with in
+    /// `style`, with the fallback families of this backend.
+    ///
+    /// This verifies that the fonts of the system draw labels as intended,
+    /// such as emoji in color rather than in monochrome or as boxes, so
+    /// that fallback families can be set or text replaced otherwise.
+    pub fn text_coverage<TStyle: BackendTextStyle>(
+        &self,
+        text: &str,
+        style: &TStyle,
+    ) -> TextCoverage {
+        common::text_coverage(&self.options, &self.layout, text, style)
+    }
+
+    /// Sets the size of the square tiles the scene is split into, or `None`
+    /// to not split the scene, which is the default.
+    ///
+    /// Graphene and GSK use `f32` coordinates, which lose precision in very
+    /// large plots, such as 100000×2000 plots of long signals. `f32` represents
+    /// every integer up to 2²⁴ exactly, but sub-pixel positions, such as
+    /// stroke edges, degrade from around 10⁵ pixels onwards. With tiling, each
+    /// primitive is drawn into every tile it overlaps, with coordinates
+    /// relative to the tile, and each tile is wrapped in a translated and
+    /// clipped node. A tile size of 4096 keeps sub-pixel precision at any plot
+    /// size.
+    ///
+    /// Content drawn with [`PaintableBackend::draw_paintable`],
+    /// [`PaintableBackend::with_transform`], and [`GtkBackend`] is not tiled,
+    /// and is drawn beneath the tiles.
+    pub fn set_tile_size(&mut self, tile_size: Option<u32>) {
+        self.tile_size = tile_size.filter(|&tile_size| tile_size > 0);
+    }
+
+    /// Returns the size of the tiles the scene is split into.
+    pub fn tile_size(&self) -> Option<u32> {
+        self.tile_size
+    }
+
+    /// Sets the maximum number of nodes a frame may have before it is
+    /// rasterized, or `None` to never rasterize, which is the default.
+    ///
+    /// Scenes with many thousands of nodes, such as dense scatter plots, are
+    /// slow to render on every frame of the GTK render thread. Once a
+    /// presented frame exceeds the budget, the whole scene is rendered
+    /// offscreen into a texture at the size of the paintable, which is cheap
+    /// to draw, but is not as sharp when scaled or on high-DPI displays. See
+    /// [`Paintable::render_stats`] for the node count of the last frame.
+    pub fn set_node_budget(&mut self, node_budget: Option<usize>) {
+        self.node_budget = node_budget;
+    }
+
+    /// Returns the maximum number of nodes a frame may have before it is
+    /// rasterized.
+    pub fn node_budget(&self) -> Option<usize> {
+        self.node_budget
+    }
+
+    /// Sets whether presented frames are optimized, which is disabled by
+    /// default.
+    ///
+    /// The optimization flattens nested containers, drops nodes that are
+    /// fully transparent or outside of the paintable, removes clips that
+    /// clip nothing, and merges adjacent rects of the same color, such as
+    /// the cells of grids and heatmaps, which reduces the work of the GTK
+    /// renderer on every frame at the cost of a pass over the nodes when
+    /// presenting. See [`RenderStats::optimization`] for its effect on the
+    /// last frame.
+    pub fn set_optimizes_nodes(&mut self, optimizes_nodes: bool) {
+        self.optimizes_nodes = optimizes_nodes;
+    }
+
+    /// Returns whether presented frames are optimized.
+    pub fn optimizes_nodes(&self) -> bool {
+        self.optimizes_nodes
+    }
+
+    /// Sets the interval at which the primitives drawn so far are shown on
+    /// the paintable, or `None` to only show them when presented, which is
+    /// the default.
+    ///
+    /// This lets plots that take seconds to draw appear progressively. The
+    /// partial frames are drawn whenever the main loop gets to run, such as
+    /// when drawing from an async function that yields between chunks of
+    /// data. See also [`PaintableBackend::flush_partial`].
+    pub fn set_progressive_interval(&mut self, interval: Option<Duration>) {
+        self.progressive_interval = interval;
+    }
+
+    /// Returns the interval at which the primitives drawn so far are shown
+    /// on the paintable.
+    pub fn progressive_interval(&self) -> Option<Duration> {
+        self.progressive_interval
+    }
+
+    /// Shows the primitives drawn so far on the paintable, without ending
+    /// the frame.
+    ///
+    /// The node budget and render stats still apply to the whole frame when
+    /// it is presented. This does nothing within scopes, such as
+    /// [`PaintableBackend::with_transform`], [`PaintableBackend::with_z`], and
+    /// [`PaintableBackend::with_class`].
+    pub fn flush_partial(&mut self) {
+        if self.scope_depth > 0 {
+            return;
+        }
+
+        self.last_partial_flush = Some(Instant::now());
+
+        let Some(node) = self.take_node() else {
+            return;
+        };
+        self.partial_nodes.push(node);
+        let _ = self.ensure_prepared();
+
+        self.paintable.imp().hit_tags.replace(self.hit_tags.clone());
+        self.paintable
+            .set_node(Some(gsk::ContainerNode::new(&self.partial_nodes).upcast()));
+        self.paintable.imp().has_lod_groups.set(self.has_lod_groups);
+    }
+
+    /// Presents the primitives drawn within the rect from `upper_left` to
+    /// `bottom_right`, keeping the contents presented previously outside of
+    /// it.
+    ///
+    /// This is for frames where only part of the plot changes, such as a
+    /// single bar or the legend. The unchanged parts keep the same render
+    /// nodes, which GTK compares to the previous frame when drawing, so only
+    /// the changed region is redrawn on screen. Primitives drawn outside of
+    /// the rect are discarded.
+    ///
+    /// A frame presented with [`DrawingBackend::present`] replaces all of the
+    /// contents again.
+    pub fn present_region(&mut self, upper_left: BackendCoord, bottom_right: BackendCoord) {
+        self.present_damage(upper_left, bottom_right, 0);
+    }
+
+    /// Moves the contents presented previously left by `dx` pixels, or right
+    /// if negative, and then presents the primitives drawn within the rect
+    /// from `upper_left` to `bottom_right` as with
+    /// [`PaintableBackend::present_region`].
+    ///
+    /// This is for scrolling charts, where the plot shifts by the width of
+    /// the newest samples and only the strip they are drawn in, usually at
+    /// the right edge, has to be drawn. Contents moved out of the paintable
+    /// are dropped.
+    pub fn present_scrolled(
+        &mut self,
+        dx: i32,
+        upper_left: BackendCoord,
+        bottom_right: BackendCoord,
+    ) {
+        self.present_damage(upper_left, bottom_right, dx);
+    }
+
+    /// Presents the series drawn within [`PaintableBackend::with_series`],
+    /// replacing the ones of the same names presented previously and
+    /// keeping the rest of the contents.
+    ///
+    /// This is for frames where only the points of some series change, such
+    /// as those of [`PlotView::changed_sources`], as the axes, the legend,
+    /// and the other series are not drawn again. Primitives drawn outside of
+    /// `with_series` are discarded.
+    ///
+    /// This returns `false` if a series was not found in the contents, such
+    /// as when it was not drawn within `with_series` before or the frame was
+    /// rasterized, in which case the frame has to be drawn again in full.
+    ///
+    /// [`PlotView::changed_sources`]: crate::PlotView::changed_sources
+    pub fn present_series(&mut self) -> bool {
+        self.present_node(Damage::Series)
+    }
+
+    fn present_damage(&mut self, upper_left: BackendCoord, bottom_right: BackendCoord, dx: i32) {
+        let (x0, x1) = (
+            upper_left.0.min(bottom_right.0),
+            upper_left.0.max(bottom_right.0),
+        );
+        let (y0, y1) = (
+            upper_left.1.min(bottom_right.1),
+            upper_left.1.max(bottom_right.1),
+        );
+        self.present_node(Damage::Region {
+            rect: Rect::new(x0 as f32, y0 as f32, (x1 - x0) as f32, (y1 - y0) as f32),
+            scroll_x: -dx as f32,
+        });
+    }
+
+    /// Returns the cancellable of the frame, which is cancelled by
+    /// [`Paintable::cancel_pending`] or when another backend is created for
+    /// the paintable.
+    ///
+    /// Drawing is skipped once cancelled, but long-running work before
+    /// drawing, such as fetching or decimating data in an async function,
+    /// should check [`PaintableBackend::is_cancelled`] or pass the
+    /// cancellable to the asynchronous operations to be abandoned quickly.
+    pub fn cancellable(&self) -> &gio::Cancellable {
+        &self.cancellable
+    }
+
+    /// Returns whether the frame was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellable.is_cancelled()
+    }
+
+    /// Draws the current contents of `paintable`, scaled to fit the rect
+    /// from `upper_left` to `bottom_right`.
+    ///
+    /// This can be used to compose multiple independently-updated plots,
+    /// such as other [`Paintable`](crate::Paintable)s, into one scene.
+    pub fn draw_paintable(
+        &mut self,
+        paintable: &impl IsA<gdk::Paintable>,
+        upper_left: BackendCoord,
+        bottom_right: BackendCoord,
+    ) -> Result<(), DrawingErrorKind<Infallible>> {
+        self.ensure_prepared()?;
+        let (snapshot, _, scratch) = self.untiled();
+        common::draw_paintable(snapshot, scratch, paintable, upper_left, bottom_right)
+    }
+
+    /// Strokes a path colored by the values of its points, mapping `value_range`
+    /// onto `colormap`.
+    ///
+    /// The color is interpolated along each segment, such as for sensor data
+    /// colored by magnitude. Each segment is a separate node, so prefer
+    /// [`DrawingBackend::draw_path`] for paths of a single color.
+    ///
+    /// The path is not tiled, see [`PaintableBackend::set_tile_size`].
+    pub fn draw_mapped_path<I: IntoIterator<Item = (BackendCoord, f64)>>(
+        &mut self,
+        raw_path: I,
+        value_range: Range<f64>,
+        colormap: Colormap,
+        stroke_width: u32,
+    ) -> Result<(), DrawingErrorKind<Infallible>> {
+        self.ensure_prepared()?;
+        if self.is_cancelled() {
+            return Ok(());
+        }
+        self.progress();
+        let (snapshot, options, scratch) = self.untiled();
+        common::draw_mapped_path(
+            snapshot,
+            options,
+            scratch,
+            raw_path,
+            &value_range,
+            colormap,
+            stroke_width,
+        )
+    }
+
+    /// Draws `paintable` as a marker centered on `center`, scaled to fit a
+    /// square of `size` while keeping its aspect ratio.
+    ///
+    /// This can be used to draw data points as icons, such as a
+    /// [`gtk::IconPaintable`] of weather symbols, or avatars loaded as
+    /// [`gdk::Texture`]s.
+    pub fn draw_paintable_marker(
+        &mut self,
+        paintable: &impl IsA<gdk::Paintable>,
+        center: BackendCoord,
+        size: u32,
+    ) -> Result<(), DrawingErrorKind<Infallible>> {
+        self.ensure_prepared()?;
+        if self.is_cancelled() {
+            return Ok(());
+        }
+        let (snapshot, _, scratch) = self.untiled();
+        common::draw_paintable_marker(snapshot, scratch, paintable, center, size)
+    }
+
+    /// Returns the snapshot being drawn to, to draw with GTK APIs in between
+    /// plotters primitives, such as [`gtk::IconPaintable`]s or badges at
+    /// exact plot coordinates.
+    ///
+    /// The coordinates are the same as the ones of the primitives, in pixels
+    /// of the paintable. What is drawn on the returned scope is appended when
+    /// it is dropped, without leaking the state of the snapshot, see
+    /// [`SnapshotScope`]. Content drawn this way is not tiled, see
+    /// [`PaintableBackend::set_tile_size`].
+    pub fn snapshot_mut(&mut self) -> SnapshotScope<'_> {
+        let _ = self.ensure_prepared();
+        self.scratch.flush();
+        SnapshotScope::new(self.snapshot())
+    }
+
+    /// Calls `f` with the drawing transformed by `transform`.
+    ///
+    /// Everything drawn within `f` is wrapped in a [`gsk::TransformNode`],
+    /// which is useful for custom elements that rotate, skew, or scale a
+    /// group of primitives.
+    ///
+    /// Content drawn within `f` is not tiled, see
+    /// [`PaintableBackend::set_tile_size`].
+    pub fn with_transform<R>(
+        &mut self,
+        transform: &gsk::Transform,
+        f: impl FnOnce(&mut Self) -> R,
+    ) -> R {
+        let _ = self.ensure_prepared();
+        let snapshot = self.snapshot().clone();
+
+        self.scratch.flush();
+        snapshot.save();
+        snapshot.transform(Some(transform));
+        let tile_size = self.tile_size.take();
+        self.scope_depth += 1;
+        let cull_bounds = self.options.cull_bounds.take();
+        let ret = f(self);
+        self.options.cull_bounds = cull_bounds;
+        self.scope_depth -= 1;
+        self.tile_size = tile_size;
+        self.scratch.flush();
+        snapshot.restore();
+
+        ret
+    }
+
+    /// Calls `f` with everything drawn within it only shown when the
+    /// paintable is drawn at `min_scale` of its size or more.
+    ///
+    /// This lets one paintable serve both full-size views and thumbnails,
+    /// such as by drawing the minor grid lines and tick labels within a
+    /// group with a minimum scale of `0.5`, so that they are dropped where
+    /// they would only be clutter.
+    ///
+    /// Content drawn within `f` is not tiled, see
+    /// [`PaintableBackend::set_tile_size`].
+    pub fn with_min_scale<R>(&mut self, min_scale: f32, f: impl FnOnce(&mut Self) -> R) -> R {
+        let _ = self.ensure_prepared();
+        let snapshot = self.snapshot().clone();
+
+        self.scratch.flush();
+        optimize::push_lod_group(&snapshot, min_scale);
+        self.has_lod_groups = true;
+        let tile_size = self.tile_size.take();
+        self.scope_depth += 1;
+        let ret = f(self);
+        self.scope_depth -= 1;
+        self.tile_size = tile_size;
+        self.scratch.flush();
+        snapshot.pop();
+
+        ret
+    }
+
+    /// Calls `f` with everything drawn within it grouped as `series`, so
+    /// that it can be drawn again alone and presented with
+    /// [`PaintableBackend::present_series`].
+    ///
+    /// Content drawn within `f` is not tiled, see
+    /// [`PaintableBackend::set_tile_size`].
+    pub fn with_series<R>(&mut self, series: &Series, f: impl FnOnce(&mut Self) -> R) -> R {
+        let _ = self.ensure_prepared();
+        let snapshot = self.snapshot().clone();
+
+        self.scratch.flush();
+        snapshot.push_debug(&format!("{SERIES_GROUP_PREFIX}{}", series.name()));
+        let tile_size = self.tile_size.take();
+        self.scope_depth += 1;
+        let ret = f(self);
+        self.scope_depth -= 1;
+        self.tile_size = tile_size;
+        self.scratch.flush();
+        snapshot.pop();
+
+        ret
+    }
+
+    /// Calls `f` with the drawing inset by `margins`, such as the padding of
+    /// the widget the plot is shown in.
+    ///
+    /// Within `f`, the origin is at the top left corner of the inner rect,
+    /// the size of the backend is the size of the inner rect, and everything
+    /// drawn is clipped to it. This keeps room around the chart for GTK
+    /// widgets overlaid on the plot, such as buttons or a legend, while the
+    /// coordinates of the chart stay relative to its own area.
+    ///
+    /// Content drawn within `f` is not tiled, see
+    /// [`PaintableBackend::set_tile_size`].
+    pub fn with_inset<R>(&mut self, margins: &gtk::Border, f: impl FnOnce(&mut Self) -> R) -> R {
+        let _ = self.ensure_prepared();
+        let snapshot = self.snapshot().clone();
+        let (width, height) = self.size;
+        let left = margins.left().max(0) as u32;
+        let top = margins.top().max(0) as u32;
+        let inner_size = (
+            width.saturating_sub(left + margins.right().max(0) as u32),
+            height.saturating_sub(top + margins.bottom().max(0) as u32),
+        );
+
+        self.scratch.flush();
+        snapshot.save();
+        snapshot.translate(&Point::new(left as f32, top as f32));
+        snapshot.push_clip(&Rect::new(
+            0.0,
+            0.0,
+            inner_size.0 as f32,
+            inner_size.1 as f32,
+        ));
+        let outer_size = std::mem::replace(&mut self.size, inner_size);
+        let cull_bounds = self.options.cull_bounds;
+        // Keep culling disabled within transforms.
+        if cull_bounds.is_some() {
+            self.update_cull_bounds();
+        }
+        let tile_size = self.tile_size.take();
+        self.scope_depth += 1;
+        let ret = f(self);
+        self.scope_depth -= 1;
+        self.tile_size = tile_size;
+        self.size = outer_size;
+        self.options.cull_bounds = cull_bounds;
+        self.scratch.flush();
+        snapshot.pop();
+        snapshot.restore();
+
+        ret
+    }
+
+    /// Calls `f` with everything drawn within it placed at `z` when the frame
+    /// is presented.
+    ///
+    /// Content drawn outside of any scope is at a z of zero. Content with a
+    /// greater z is drawn above content with a lower z, and content with the
+    /// same z is drawn in the order it was drawn. This allows drawing
+    /// annotations before the data, but having them appear above it,
+    /// without reordering the plotting code.
+    ///
+    /// Scopes within [`PaintableBackend::with_transform`] are not
+    /// transformed.
+    pub fn with_z<R>(&mut self, z: i32, f: impl FnOnce(&mut Self) -> R) -> R {
+        let _ = self.ensure_prepared();
+        self.scratch.flush();
+
+        let outer_snapshot = self.snapshot.replace(gtk::Snapshot::new());
+        let outer_tiles = std::mem::take(&mut self.tiles);
+
+        self.scope_depth += 1;
+        let ret = f(self);
+        self.scope_depth -= 1;
+
+        let node = self.take_node();
+        self.snapshot = outer_snapshot;
+        self.tiles = outer_tiles;
+
+        if let Some(node) = node {
+            self.z_layers.entry(z).or_default().push(node);
+        }
+
+        ret
+    }
+
+    /// Calls `f` with the text drawn and measured within it using
+    /// `text_attributes`, instead of the attributes set with
+    /// [`Self::set_text_attributes`].
+    pub fn with_text_attributes<R>(
+        &mut self,
+        text_attributes: TextAttributes,
+        f: impl FnOnce(&mut Self) -> R,
+    ) -> R {
+        let prev = std::mem::replace(&mut self.options.text_attributes, text_attributes);
+        let ret = f(self);
+        self.options.text_attributes = prev;
+
+        ret
+    }
+
+    /// Sets the styles of the classes of [`Self::with_class`].
+    pub fn set_style_classes(&mut self, style_classes: StyleClasses) {
+        self.style_classes = style_classes;
+    }
+
+    /// Returns the styles of the classes of [`Self::with_class`].
+    pub fn style_classes(&self) -> &StyleClasses {
+        &self.style_classes
+    }
+
+    /// Calls `f` with the primitives drawn within it using the style of
+    /// `class`, set with [`Self::set_style_classes`].
+    ///
+    /// The style of the class overrides the color and stroke width of the
+    /// drawn primitives. Classes without a style keep the style of the
+    /// enclosing class, if any.
+    pub fn with_class<R>(&mut self, class: &str, f: impl FnOnce(&mut Self) -> R) -> R {
+        let Some(class_style) = self.style_classes.get(class).copied() else {
+            return f(self);
+        };
+
+        let prev = std::mem::replace(&mut self.options.class_style, class_style);
+        self.scope_depth += 1;
+        let ret = f(self);
+        self.scope_depth -= 1;
+        self.options.class_style = prev;
+
+        ret
+    }
+
+    /// Calls `f` with everything drawn within it made a region that
+    /// activates `link` when it is clicked in a [`PlotView`], such as the
+    /// title of an axis that opens the settings of the axis.
+    ///
+    /// The region covers the bounds of what is drawn, and is returned by
+    /// [`Paintable::hit_region_at`]. Content drawn within `f` is not tiled,
+    /// see [`PaintableBackend::set_tile_size`].
+    ///
+    /// [`PlotView`]: crate::PlotView
+    pub fn with_link<R>(&mut self, link: Link, f: impl FnOnce(&mut Self) -> R) -> R {
+        let mut tag = self.hit_tag.clone().unwrap_or_default();
+        tag.link = Some(link);
+        self.with_hit_tag(tag, f)
+    }
+
+    /// Calls `f` with everything drawn within it, such as a bar, tagged
+    /// with `data`, such as its index or the domain object it stands for.
+    ///
+    /// The data is returned with the region by [`PlotView::pick`] and
+    /// [`Paintable::hit_region_at`], so that a clicked element can be
+    /// mapped back to what it shows without keeping its geometry around.
+    /// Types that are not [`glib::Value`]s can be wrapped in a
+    /// [`glib::BoxedAnyObject`].
+    ///
+    /// Regions within other regions keep the link, data, or ID of the
+    /// outer region that they do not set. Content drawn within `f` is not
+    /// tiled, see [`PaintableBackend::set_tile_size`].
+    ///
+    /// [`PlotView::pick`]: crate::PlotView::pick
+    pub fn with_data<R>(&mut self, data: impl ToValue, f: impl FnOnce(&mut Self) -> R) -> R {
+        let mut tag = self.hit_tag.clone().unwrap_or_default();
+        tag.data = Some(data.to_value());
+        self.with_hit_tag(tag, f)
+    }
+
+    /// Calls `f` with everything drawn within it, such as the marker of a
+    /// data point, marked as the element of `id`, such as the key of the
+    /// data point.
+    ///
+    /// Elements are selected by their IDs in a [`PlotView`], such as by
+    /// clicking them or brushing over them with
+    /// [`PlotView::set_brush_mode`], and the selected elements are drawn
+    /// with the selection highlight. Plots of the same data should use the
+    /// same IDs, so that elements selected in one plot are highlighted in
+    /// the others when they share a [`PlotSelectionModel`].
+    ///
+    /// Nested regions inherit what they do not set, as with
+    /// [`PaintableBackend::with_data`].
+    ///
+    /// [`PlotView`]: crate::PlotView
+    /// [`PlotView::set_brush_mode`]: crate::PlotView::set_brush_mode
+    /// [`PlotSelectionModel`]: crate::PlotSelectionModel
+    pub fn with_id<R>(&mut self, id: &str, f: impl FnOnce(&mut Self) -> R) -> R {
+        let mut tag = self.hit_tag.clone().unwrap_or_default();
+        tag.id = Some(id.to_string());
+        self.with_hit_tag(tag, f)
+    }
+
+    fn with_hit_tag<R>(&mut self, tag: HitTag, f: impl FnOnce(&mut Self) -> R) -> R {
+        let _ = self.ensure_prepared();
+        let snapshot = self.snapshot().clone();
+
+        // IDs are unique across frames, as regions presented with
+        // `present_region` keep the regions of previous frames.
+        let next_hit_id = &self.paintable.imp().next_hit_id;
+        let id = next_hit_id.get();
+        next_hit_id.set(id + 1);
+        self.hit_tags.insert(id, tag.clone());
+
+        self.scratch.flush();
+        hit_region::push_hit_region(&snapshot, id);
+        let outer_tag = self.hit_tag.replace(tag);
+        let tile_size = self.tile_size.take();
+        self.scope_depth += 1;
+        let ret = f(self);
+        self.scope_depth -= 1;
+        self.tile_size = tile_size;
+        self.hit_tag = outer_tag;
+        self.scratch.flush();
+        snapshot.pop();
+
+        ret
+    }
+
+    #[inline]
+    fn snapshot(&self) -> &gtk::Snapshot {
+        self.snapshot.as_ref().expect("backend was not prepared")
+    }
+
+    /// Flushes the primitives drawn so far if the progressive interval has
+    /// elapsed since the last flush.
+    fn progress(&mut self) {
+        let Some(interval) = self.progressive_interval else {
+            return;
+        };
+
+        let last_partial_flush = *self.last_partial_flush.get_or_insert_with(Instant::now);
+        if last_partial_flush.elapsed() >= interval {
+            self.flush_partial();
+        }
+    }
+
+    /// Returns the snapshot, options, and scratch state to draw a primitive
+    /// without tiling.
+    fn untiled(&mut self) -> (&gtk::Snapshot, &common::Options, &mut common::Scratch) {
+        let snapshot = self.snapshot.as_ref().expect("backend was not prepared");
+        (snapshot, &self.options, &mut self.scratch)
+    }
+
+    /// Calls `f` with the snapshot, options, and scratch state to draw a
+    /// primitive within `(min, max)`, once for every overlapping tile if
+    /// tiling is enabled.
+    fn draw_tiled(
+        &mut self,
+        (min, max): (BackendCoord, BackendCoord),
+        f: impl Fn(
+            &gtk::Snapshot,
+            &common::Options,
+            &mut common::Scratch,
+        ) -> Result<(), DrawingErrorKind<Infallible>>,
+    ) -> Result<(), DrawingErrorKind<Infallible>> {
+        if self.is_cancelled() {
+            return Ok(());
+        }
+
+        self.progress();
+
+        let Some(tile_size) = self.tile_size else {
+            let (snapshot, options, scratch) = self.untiled();
+            return f(snapshot, options, scratch);
+        };
+
+        let tile_size = tile_size as i32;
+        let (width, height) = (self.size.0 as i32, self.size.1 as i32);
+        let tile_range = |min: i32, max: i32, len: i32| {
+            min.clamp(0, len).div_euclid(tile_size)..=max.clamp(0, len).div_euclid(tile_size)
+        };
+
+        for ty in tile_range(min.1, max.1, height) {
+            for tx in tile_range(min.0, max.0, width) {
+                let snapshot = self.tiles.entry((tx, ty)).or_default();
+                let options = common::Options {
+                    origin: (tx * tile_size, ty * tile_size),
+                    ..self.options.clone()
+                };
+                f(snapshot, &options, &mut self.scratch)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the bounds of the points grown by the stroke width of `style`.
+    fn stroke_bounds<S: BackendStyle>(
+        &self,
+        points: impl IntoIterator<Item = BackendCoord>,
+        style: &S,
+    ) -> (BackendCoord, BackendCoord) {
+        let pad = self.options.stroke_width(style).ceil() as i32 + 1;
+        let (min, max) = points.into_iter().fold(
+            ((i32::MAX, i32::MAX), (i32::MIN, i32::MIN)),
+            |(min, max), (x, y)| ((min.0.min(x), min.1.min(y)), (max.0.max(x), max.1.max(y))),
+        );
+        (
+            (min.0.saturating_sub(pad), min.1.saturating_sub(pad)),
+            (max.0.saturating_add(pad), max.1.saturating_add(pad)),
+        )
+    }
+
+    /// Presents the drawn frame to the paintable, replacing the part of the
+    /// contents given by `damage`, and returns whether it could be replaced.
+    fn present_node(&mut self, damage: Damage) -> bool {
+        let node = self.take_node();
+
+        if self.is_cancelled() {
+            self.partial_nodes.clear();
+            self.z_layers.clear();
+            self.scratch.n_nodes = 0;
+            self.hit_tags.clear();
+            self.has_lod_groups = false;
+            return true;
+        }
+
+        let pending_cancellable = &self.paintable.imp().pending_cancellable;
+        if pending_cancellable.borrow().as_ref() == Some(&self.cancellable) {
+            pending_cancellable.replace(None);
+        }
+
+        let mut z_layers = std::mem::take(&mut self.z_layers);
+        let node = if self.partial_nodes.is_empty() && z_layers.is_empty() {
+            node
+        } else {
+            // The content drawn outside of scopes is below the scopes at a z
+            // of zero.
+            let mut unscoped = std::mem::take(&mut self.partial_nodes);
+            unscoped.extend(node);
+            let layer = z_layers.entry(0).or_default();
+            layer.splice(0..0, unscoped);
+
+            let nodes = z_layers.into_values().flatten().collect::<Vec<_>>();
+            Some(gsk::ContainerNode::new(&nodes).upcast())
+        };
+        self.last_partial_flush = None;
+        let n_nodes = std::mem::take(&mut self.scratch.n_nodes);
+
+        let (node, optimization) = match node {
+            Some(node) if self.optimizes_nodes => {
+                let (width, height) = self.size;
+                let bounds = Rect::new(0.0, 0.0, width as f32, height as f32);
+                let (node, stats) = optimize::optimize(&node, &bounds);
+                (node, Some(stats))
+            }
+            node => (node, None),
+        };
+
+        let is_rasterized = self.node_budget.is_some_and(|budget| n_nodes > budget);
+        let node = if is_rasterized {
+            node.map(|node| {
+                rasterize(&node, self.size, self.paintable.scale_factor()).unwrap_or(node)
+            })
+        } else {
+            node
+        };
+
+        self.paintable.imp().render_stats.set(RenderStats {
+            n_nodes,
+            is_rasterized,
+            optimization,
+        });
+        let hit_tags = std::mem::take(&mut self.hit_tags);
+        let has_lod_groups = std::mem::take(&mut self.has_lod_groups);
+        let imp = self.paintable.imp();
+        match damage {
+            Damage::Full => {
+                imp.hit_tags.replace(hit_tags);
+                imp.plot_area.set(self.plot_area);
+                self.paintable.set_node(node);
+                imp.has_lod_groups.set(has_lod_groups);
+            }
+            Damage::Region { rect, scroll_x } => {
+                // The regions of previous frames outside of the rect are
+                // kept.
+                imp.hit_tags.borrow_mut().extend(hit_tags);
+                self.paintable.merge_region(node, rect, scroll_x);
+                self.paintable.retain_presented_hit_tags();
+                imp.has_lod_groups
+                    .set(imp.has_lod_groups.get() || has_lod_groups);
+            }
+            Damage::Series => {
+                let mut groups = HashMap::new();
+                if let Some(node) = &node {
+                    series_groups(node, &mut groups);
+                }
+
+                imp.hit_tags.borrow_mut().extend(hit_tags);
+                let is_merged = self.paintable.merge_series(&groups);
+                self.paintable.retain_presented_hit_tags();
+                imp.has_lod_groups
+                    .set(imp.has_lod_groups.get() || has_lod_groups);
+                return is_merged;
+            }
+        }
+        true
+    }
+
+    /// Takes the node of the drawn frame, composing the tiles on top.
+    fn take_node(&mut self) -> Option<gsk::RenderNode> {
+        self.scratch.flush();
+        let snapshot = self.snapshot.take()?;
+
+        let tile_size = self.tile_size.unwrap_or_default() as f32;
+        for ((tx, ty), tile) in std::mem::take(&mut self.tiles) {
+            let Some(node) = tile.to_node() else {
+                continue;
+            };
+
+            snapshot.save();
+            snapshot.translate(&Point::new(tx as f32 * tile_size, ty as f32 * tile_size));
+            snapshot.push_clip(&Rect::new(0.0, 0.0, tile_size, tile_size));
+            snapshot.append_node(node);
+            snapshot.pop();
+            snapshot.restore();
+        }
+
+        snapshot.to_node()
+    }
+}
+
+/// What calls back to draw the next frame of a paintable.
+#[derive(Debug)]
+enum FrameSource {
+    /// A tick callback of the attached widget, called on every frame.
+    Tick(gtk::TickCallbackId),
+    /// A timeout, for when no widget is attached.
+    Timeout(glib::SourceId),
+}
+
+impl FrameSource {
+    fn remove(self) {
+        match self {
+            Self::Tick(tick_id) => tick_id.remove(),
+            Self::Timeout(source_id) => source_id.remove(),
+        }
+    }
+}
+
+/// The part of the contents of a paintable replaced by a presented frame.
+#[derive(Debug, Clone, Copy)]
+enum Damage {
+    Full,
+    Region {
+        rect: Rect,
+        scroll_x: f32,
+    },
+    /// The series groups of the frame.
+    Series,
+}
+
+/// A part of the contents of a paintable presented with
+/// [`PaintableBackend::present_region`], shown clipped to `rect`.
+#[derive(Debug)]
+struct Region {
+    rect: Rect,
+    node: gsk::RenderNode,
+    /// How far the node was moved since it was presented.
+    offset_x: f32,
+}
+
+impl Region {
+    fn new(rect: Rect, node: gsk::RenderNode) -> Self {
+        Self {
+            rect,
+            node,
+            offset_x: 0.0,
+        }
+    }
+
+    /// Returns the region moved horizontally by `dx` and clipped to
+    /// `bounds`, or `None` if it was moved out of them.
+    fn scrolled(self, dx: f32, bounds: &Rect) -> Option<Self> {
+        let rect = self.rect.offset_r(dx, 0.0).intersection(bounds)?;
+        Some(Self {
+            rect,
+            node: self.node,
+            offset_x: self.offset_x + dx,
+        })
+    }
+
+    /// Returns the parts of the region outside of `rect`.
+    fn subtract(self, rect: &Rect) -> Vec<Self> {
+        let Some(overlap) = self.rect.intersection(rect) else {
+            return vec![self];
+        };
+
+        let (x0, y0) = (self.rect.x(), self.rect.y());
+        let (x1, y1) = (x0 + self.rect.width(), y0 + self.rect.height());
+        let (ox0, oy0) = (overlap.x(), overlap.y());
+        let (ox1, oy1) = (ox0 + overlap.width(), oy0 + overlap.height());
+
+        // The bands above and below the overlap, and the parts left and
+        // right of it in between.
+        [
+            Rect::new(x0, y0, x1 - x0, oy0 - y0),
+            Rect::new(x0, oy1, x1 - x0, y1 - oy1),
+            Rect::new(x0, oy0, ox0 - x0, oy1 - oy0),
+            Rect::new(ox1, oy0, x1 - ox1, oy1 - oy0),
+        ]
+        .into_iter()
+        .filter(|part| part.width() > 0.0 && part.height() > 0.0)
+        .map(|part| Self {
+            rect: part,
+            node: self.node.clone(),
+            offset_x: self.offset_x,
+        })
+        .collect()
+    }
+
+    fn to_node(&self) -> gsk::RenderNode {
+        let node = if self.offset_x == 0.0 {
+            self.node.clone()
+        } else {
+            let transform = gsk::Transform::new().translate(&Point::new(self.offset_x, 0.0));
+            gsk::TransformNode::new(&self.node, &transform).upcast()
+        };
+        gsk::ClipNode::new(&node, &self.rect).upcast()
+    }
+}
+
+/// The prefix of the message of the debug nodes wrapping the groups drawn
+/// with [`PaintableBackend::with_series`], followed by the series name.
+const SERIES_GROUP_PREFIX: &str = "plotters-gtk4:series:";
+
+/// Collects the series groups within `node` by the series name.
+fn series_groups(node: &gsk::RenderNode, groups: &mut HashMap<String, gsk::RenderNode>) {
+    let child = match node.node_type() {
+        gsk::RenderNodeType::ContainerNode => {
+            let container = node.downcast_ref::<gsk::ContainerNode>().unwrap();
+            for index in 0..container.n_children() {
+                series_groups(&container.child(index), groups);
+            }
+            return;
+        }
+        gsk::RenderNodeType::TransformNode => {
+            node.downcast_ref::<gsk::TransformNode>().unwrap().child()
+        }
+        gsk::RenderNodeType::ClipNode => node.downcast_ref::<gsk::ClipNode>().unwrap().child(),
+        gsk::RenderNodeType::OpacityNode => {
+            node.downcast_ref::<gsk::OpacityNode>().unwrap().child()
+        }
+        gsk::RenderNodeType::DebugNode => {
+            let debug_node = node.downcast_ref::<gsk::DebugNode>().unwrap();
+            if let Some(name) = debug_node.message().strip_prefix(SERIES_GROUP_PREFIX) {
+                groups.insert(name.to_string(), node.clone());
+                return;
+            }
+            debug_node.child()
+        }
+        _ => return,
+    };
+    series_groups(&child, groups);
+}
+
+/// Returns `node` with its series groups replaced by the groups of the same
+/// names in `groups`, adding the names to `replaced`, or `None` if none of
+/// them are within it.
+///
+/// The groups are replaced where they are, so they keep the transforms and
+/// clips of the previous frame, and everything else keeps the same nodes.
+fn replace_series_groups(
+    node: &gsk::RenderNode,
+    groups: &HashMap<String, gsk::RenderNode>,
+    replaced: &mut HashSet<String>,
+) -> Option<gsk::RenderNode> {
+    match node.node_type() {
+        gsk::RenderNodeType::ContainerNode => {
+            let container = node.downcast_ref::<gsk::ContainerNode>().unwrap();
+            let mut is_replaced = false;
+            let children = (0..container.n_children())
+                .map(|index| {
+                    let child = container.child(index);
+                    match replace_series_groups(&child, groups, replaced) {
+                        Some(child) => {
+                            is_replaced = true;
+                            child
+                        }
+                        None => child,
+                    }
+                })
+                .collect::<Vec<_>>();
+            is_replaced.then(|| gsk::ContainerNode::new(&children).upcast())
+        }
+        gsk::RenderNodeType::TransformNode => {
+            let transform_node = node.downcast_ref::<gsk::TransformNode>().unwrap();
+            replace_series_groups(&transform_node.child(), groups, replaced)
+                .map(|child| gsk::TransformNode::new(&child, &transform_node.transform()).upcast())
+        }
+        gsk::RenderNodeType::ClipNode => {
+            let clip_node = node.downcast_ref::<gsk::ClipNode>().unwrap();
+            replace_series_groups(&clip_node.child(), groups, replaced)
+                .map(|child| gsk::ClipNode::new(&child, clip_node.clip()).upcast())
+        }
+        gsk::RenderNodeType::OpacityNode => {
+            let opacity_node = node.downcast_ref::<gsk::OpacityNode>().unwrap();
+            replace_series_groups(&opacity_node.child(), groups, replaced)
+                .map(|child| gsk::OpacityNode::new(&child, opacity_node.opacity()).upcast())
+        }
+        gsk::RenderNodeType::DebugNode => {
+            let debug_node = node.downcast_ref::<gsk::DebugNode>().unwrap();
+            let message = debug_node.message();
+            if let Some((name, group)) = message
+                .strip_prefix(SERIES_GROUP_PREFIX)
+                .and_then(|name| groups.get_key_value(name))
+            {
+                replaced.insert(name.clone());
+                return Some(group.clone());
+            }
+            replace_series_groups(&debug_node.child(), groups, replaced)
+                .map(|child| gsk::DebugNode::new(&child, &message).upcast())
+        }
+        _ => None,
+    }
+}
+
+/// Returns a rough estimate of the memory used by the node and its children,
+/// in bytes.
+fn estimated_node_memory(node: &gsk::RenderNode) -> usize {
+    /// The rough size of a node without its children and data.
+    const NODE_SIZE: usize = 128;
+
+    let child = |child: gsk::RenderNode| estimated_node_memory(&child);
+
+    let data = match node.node_type() {
+        gsk::RenderNodeType::ContainerNode => {
+            let container = node.downcast_ref::<gsk::ContainerNode>().unwrap();
+            (0..container.n_children())
+                .map(|index| child(container.child(index)))
+                .sum()
+        }
+        gsk::RenderNodeType::TransformNode => {
+            child(node.downcast_ref::<gsk::TransformNode>().unwrap().child())
+        }
+        gsk::RenderNodeType::ClipNode => {
+            child(node.downcast_ref::<gsk::ClipNode>().unwrap().child())
+        }
+        gsk::RenderNodeType::RoundedClipNode => {
+            child(node.downcast_ref::<gsk::RoundedClipNode>().unwrap().child())
+        }
+        gsk::RenderNodeType::OpacityNode => {
+            child(node.downcast_ref::<gsk::OpacityNode>().unwrap().child())
+        }
+        gsk::RenderNodeType::StrokeNode => {
+            child(node.downcast_ref::<gsk::StrokeNode>().unwrap().child())
+        }
+        gsk::RenderNodeType::FillNode => {
+            child(node.downcast_ref::<gsk::FillNode>().unwrap().child())
+        }
+        gsk::RenderNodeType::TextureNode => {
+            let texture = node.downcast_ref::<gsk::TextureNode>().unwrap().texture();
+            texture.width() as usize * texture.height() as usize * 4
+        }
+        gsk::RenderNodeType::TextNode => {
+            let text = node.downcast_ref::<gsk::TextNode>().unwrap();
+            text.num_glyphs() as usize * std::mem::size_of::<pango::GlyphInfo>()
+        }
+        _ => 0,
+    };
+
+    NODE_SIZE + data
+}
+
+/// Renders the node offscreen into a texture node of the given size, with
+/// `scale_factor` texels per pixel.
+fn rasterize(
+    node: &gsk::RenderNode,
+    (width, height): (u32, u32),
+    scale_factor: i32,
+) -> Option<gsk::RenderNode> {
+    let renderer = gsk::CairoRenderer::new();
+    renderer.realize(None).ok()?;
+
+    let scale = scale_factor as f32;
+    let bounds = Rect::new(0.0, 0.0, width as f32, height as f32);
+    let device_bounds = Rect::new(0.0, 0.0, width as f32 * scale, height as f32 * scale);
+    let scaled_node = gsk::TransformNode::new(node, &gsk::Transform::new().scale(scale, scale));
+    let texture = renderer.render_texture(scaled_node, Some(&device_bounds));
+    renderer.unrealize();
+
+    Some(gsk::TextureNode::new(&texture, &bounds).upcast())
+}
+
+impl Drop for PaintableBackend<'_> {
+    fn drop(&mut self) {
+        if self.snapshot.is_some() {
+            self.present_node(Damage::Full);
+        }
+    }
+}
+
+impl DrawingBackend for PaintableBackend<'_> {
+    type ErrorType = Infallible;
+
+    #[inline]
+    fn get_size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    fn ensure_prepared(&mut self) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        if self.snapshot.is_none() {
+            self.snapshot.replace(gtk::Snapshot::new());
+        }
+        Ok(())
+    }
+
+    fn present(&mut self) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        if self.snapshot.is_some() {
+            self.present_node(Damage::Full);
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn draw_pixel(
+        &mut self,
+        point: BackendCoord,
+        color: BackendColor,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        self.draw_tiled((point, point), |snapshot, options, scratch| {
+            common::draw_pixel(snapshot, options, scratch, point, color)
+        })
+    }
+
+    #[inline]
+    fn draw_line<S: BackendStyle>(
+        &mut self,
+        from: BackendCoord,
+        to: BackendCoord,
+        style: &S,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        let bounds = self.stroke_bounds([from, to], style);
+        self.draw_tiled(bounds, |snapshot, options, scratch| {
+            common::draw_line(snapshot, options, scratch, from, to, style)
+        })
+    }
+
+    #[inline]
+    fn draw_rect<S: BackendStyle>(
+        &mut self,
+        upper_left: BackendCoord,
+        bottom_right: BackendCoord,
+        style: &S,
+        fill: bool,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        let size = self.size;
+        let bounds = self.stroke_bounds([upper_left, bottom_right], style);
+        self.draw_tiled(bounds, |snapshot, options, scratch| {
+            common::draw_rect(
+                snapshot,
+                options,
+                scratch,
+                size,
+                upper_left,
+                bottom_right,
+                style,
+                fill,
+            )
+        })
+    }
+
+    #[inline]
+    fn draw_path<S: BackendStyle, I: IntoIterator<Item = BackendCoord>>(
+        &mut self,
+        raw_path: I,
+        style: &S,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        if self.is_cancelled() {
+            return Ok(());
+        }
+
+        if self.tile_size.is_none() {
+            self.progress();
+            let (snapshot, options, scratch) = self.untiled();
+            return common::draw_path(snapshot, options, scratch, raw_path, style);
+        }
+
+        let mut points = std::mem::take(&mut self.scratch.points);
+        points.clear();
+        points.extend(raw_path);
+        let bounds = self.stroke_bounds(points.iter().copied(), style);
+        let ret = self.draw_tiled(bounds, |snapshot, options, scratch| {
+            common::draw_path(snapshot, options, scratch, points.iter().copied(), style)
+        });
+        self.scratch.points = points;
+        ret
+    }
+
+    #[inline]
+    fn fill_polygon<S: BackendStyle, I: IntoIterator<Item = BackendCoord>>(
+        &mut self,
+        vert: I,
+        style: &S,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        if self.is_cancelled() {
+            return Ok(());
+        }
+
+        if self.tile_size.is_none() {
+            self.progress();
+            let (snapshot, options, scratch) = self.untiled();
+            return common::fill_polygon(snapshot, options, scratch, vert, style);
+        }
+
+        let mut points = std::mem::take(&mut self.scratch.points);
+        points.clear();
+        points.extend(vert);
+        let bounds = self.stroke_bounds(points.iter().copied(), style);
+        let ret = self.draw_tiled(bounds, |snapshot, options, scratch| {
+            common::fill_polygon(snapshot, options, scratch, points.iter().copied(), style)
+        });
+        self.scratch.points = points;
+        ret
+    }
+
+    #[inline]
+    fn draw_circle<S: BackendStyle>(
+        &mut self,
+        center: BackendCoord,
+        radius: u32,
+        style: &S,
+        fill: bool,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        let r = radius as i32;
+        let bounds = self.stroke_bounds(
+            [(center.0 - r, center.1 - r), (center.0 + r, center.1 + r)],
+            style,
+        );
+        self.draw_tiled(bounds, |snapshot, options, scratch| {
+            common::draw_circle(snapshot, options, scratch, center, radius, style, fill)
+        })
+    }
+
+    #[inline]
+    fn estimate_text_size<TStyle: BackendTextStyle>(
+        &self,
+        text: &str,
+        style: &TStyle,
+    ) -> Result<(u32, u32), DrawingErrorKind<Self::ErrorType>> {
+        common::estimate_text_size(&self.options, &self.layout, text, style)
+    }
+
+    #[inline]
+    fn draw_text<TStyle: BackendTextStyle>(
+        &mut self,
+        text: &str,
+        style: &TStyle,
+        pos: BackendCoord,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        let bounds = if self.tile_size.is_some() {
+            // The text may be anchored and rotated in any direction.
+            let (w, h) = common::estimate_text_size(&self.options, &self.layout, text, style)?;
+            let extent = w.max(h) as i32 + 1;
+            (
+                (pos.0 - extent, pos.1 - extent),
+                (pos.0 + extent, pos.1 + extent),
+            )
+        } else {
+            (pos, pos)
+        };
+
+        let layout = self.layout.clone();
+        self.draw_tiled(bounds, |snapshot, options, scratch| {
+            common::draw_text(snapshot, options, scratch, &layout, text, style, pos)
+        })
+    }
+}
+
+impl GtkBackend for PaintableBackend<'_> {
+    fn gtk_snapshot(&mut self) -> &gtk::Snapshot {
+        let _ = self.ensure_prepared();
+        self.scratch.flush();
+        self.snapshot()
+    }
+
+    fn stroke_width<S: BackendStyle>(&self, style: &S) -> f32 {
+        self.options.stroke_width(style)
+    }
+
+    fn draw_shape<F: BackendStyle, S: BackendStyle>(
+        &mut self,
+        path: &gsk::Path,
+        fill_style: &F,
+        stroke_style: &S,
+    ) -> Result<(), DrawingErrorKind<Infallible>> {
+        let _ = self.ensure_prepared();
+        let snapshot = self.snapshot().clone();
+        common::draw_shape(
+            &snapshot,
+            &self.options,
+            &mut self.scratch,
+            path,
+            fill_style,
+            stroke_style,
+        )
+    }
+
+    fn stroke_path<S: BackendStyle>(
+        &mut self,
+        path: &gsk::Path,
+        style: &S,
+    ) -> Result<(), DrawingErrorKind<Infallible>> {
+        self.ensure_prepared()?;
+        let stroke_width = self.options.stroke_width(style);
+        let Some(bounds) = common::path_bounds(path, stroke_width) else {
+            return Ok(());
+        };
+        self.draw_tiled(bounds, |snapshot, options, scratch| {
+            common::stroke_path(snapshot, options, scratch, path, style)
+        })
+    }
+
+    fn fill_path<S: BackendStyle>(
+        &mut self,
+        path: &gsk::Path,
+        style: &S,
+    ) -> Result<(), DrawingErrorKind<Infallible>> {
+        self.ensure_prepared()?;
+        let Some(bounds) = common::path_bounds(path, 0.0) else {
+            return Ok(());
+        };
+        self.draw_tiled(bounds, |snapshot, options, scratch| {
+            common::fill_path(snapshot, options, scratch, path, style)
+        })
+    }
+
+    fn set_text_attributes(&mut self, text_attributes: TextAttributes) {
+        PaintableBackend::set_text_attributes(self, text_attributes);
+    }
+
+    fn text_attributes(&self) -> TextAttributes {
+        PaintableBackend::text_attributes(self)
+    }
+
+    fn set_text_extents(&mut self, text_extents: TextExtents) {
+        PaintableBackend::set_text_extents(self, text_extents);
+    }
+
+    fn text_extents(&self) -> TextExtents {
+        PaintableBackend::text_extents(self)
+    }
+
+    fn with_clip<R>(
+        &mut self,
+        clip: &Rect,
+        transform: Option<&gsk::Transform>,
+        f: impl FnOnce(&mut Self) -> R,
+    ) -> R {
+        let (snapshot, scope_state) = self.enter_scope();
+        snapshot.push_clip(clip);
+        snapshot.save();
+        snapshot.transform(transform);
+        let ret = f(self);
+        self.leave_scope(scope_state);
+        snapshot.restore();
+        snapshot.pop();
+
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headless;
+
+    #[test]
+    fn new_rejects_invalid_sizes() {
+        for size in [
+            (0, 10),
+            (10, 0),
+            (0, 0),
+            (i32::MAX as u32 + 1, 10),
+            (10, u32::MAX),
+        ] {
+            assert_eq!(Paintable::new(size).unwrap_err().size(), size);
+        }
+    }
+
+    #[test]
+    fn new_accepts_valid_sizes() {
+        headless::test_synced(|| {
+            let paintable = Paintable::new((1, i32::MAX as u32)).unwrap();
+            assert_eq!(paintable.intrinsic_width(), 1);
+            assert_eq!(paintable.intrinsic_height(), i32::MAX);
+        });
+    }
+
+    /// Returns the bounds of the color nodes within `node`, and the
+    /// translation of each of them.
+    fn color_nodes(node: &gsk::RenderNode, offset: (f32, f32), out: &mut Vec<(Rect, (f32, f32))>) {
+        if let Some(node) = node.downcast_ref::<gsk::ContainerNode>() {
+            for index in 0..node.n_children() {
+                color_nodes(&node.child(index), offset, out);
+            }
+        } else if let Some(node) = node.downcast_ref::<gsk::TransformNode>() {
+            let (dx, dy) = node.transform().to_translate();
+            color_nodes(&node.child(), (offset.0 + dx, offset.1 + dy), out);
+        } else if let Some(node) = node.downcast_ref::<gsk::ClipNode>() {
+            color_nodes(&node.child(), offset, out);
+        } else if let Some(node) = node.downcast_ref::<gsk::DebugNode>() {
+            color_nodes(&node.child(), offset, out);
+        } else if let Some(node) = node.downcast_ref::<gsk::ColorNode>() {
+            out.push((node.bounds(), offset));
+        }
+    }
+
+    #[test]
+    fn set_tile_size_ignores_zero() {
+        headless::test_synced(|| {
+            let paintable = Paintable::new((100, 100)).unwrap();
+            let mut backend = PaintableBackend::new(&paintable);
+
+            assert_eq!(backend.tile_size(), None);
+            backend.set_tile_size(Some(0));
+            assert_eq!(backend.tile_size(), None);
+            backend.set_tile_size(Some(4096));
+            assert_eq!(backend.tile_size(), Some(4096));
+        });
+    }
+
+    #[test]
+    fn tiled_primitives_have_coordinates_relative_to_their_tile() {
+        headless::test_synced(|| {
+            let paintable = Paintable::new((100_000, 2000)).unwrap();
+            let mut backend = PaintableBackend::new(&paintable);
+            backend.set_tile_size(Some(4096));
+
+            // Spans the tiles at x = 94208 and x = 98304.
+            backend
+                .draw_rect((98_000, 10), (98_500, 20), &plotters::style::RED, true)
+                .unwrap();
+            backend.present().unwrap();
+
+            let mut nodes = Vec::new();
+            color_nodes(
+                paintable.imp().node.borrow().as_ref().unwrap(),
+                (0.0, 0.0),
+                &mut nodes,
+            );
+            nodes.sort_by(|(_, a), (_, b)| a.0.total_cmp(&b.0));
+
+            assert_eq!(nodes.len(), 2);
+            assert_eq!(nodes[0].1, (94_208.0, 0.0));
+            assert_eq!(nodes[1].1, (98_304.0, 0.0));
+            for (bounds, (dx, _)) in &nodes {
+                // Small enough for sub-pixel precision, and clipped to the
+                // tile when rendered.
+                assert!(bounds.x().abs() < 4096.0);
+                assert_eq!(bounds.x() + dx, 98_000.0);
+                assert_eq!(bounds.width(), 500.0);
+            }
+        });
+    }
+
+    #[test]
+    fn transformed_primitives_are_not_tiled() {
+        headless::test_synced(|| {
+            let paintable = Paintable::new((10_000, 100)).unwrap();
+            let mut backend = PaintableBackend::new(&paintable);
+            backend.set_tile_size(Some(4096));
+
+            backend.with_transform(
+                &gsk::Transform::new().translate(&Point::new(10.0, 5.0)),
+                |backend| {
+                    backend
+                        .draw_rect((5000, 10), (5100, 20), &plotters::style::RED, true)
+                        .unwrap();
+                },
+            );
+            backend.present().unwrap();
+
+            let mut nodes = Vec::new();
+            color_nodes(
+                paintable.imp().node.borrow().as_ref().unwrap(),
+                (0.0, 0.0),
+                &mut nodes,
+            );
+
+            // Moved by the transform, rather than drawn in the tile at
+            // x = 4096 outside of it.
+            assert_eq!(nodes.len(), 1);
+            let (bounds, (dx, dy)) = nodes[0];
+            assert_eq!((bounds.x() + dx, bounds.y() + dy), (5010.0, 15.0));
+            assert_eq!((bounds.width(), bounds.height()), (100.0, 10.0));
+        });
+    }
+
+    /// Backs the precision limits documented on
+    /// [`PaintableBackend::set_tile_size`].
+    #[test]
+    fn tile_size_keeps_sub_pixel_precision() {
+        let is_exact = |x: f64| x as f32 as f64 == x;
+
+        // Every integer up to 2²⁴ is exact...
+        assert!(is_exact((1 << 24) as f64));
+        assert!(!is_exact(((1 << 24) + 1) as f64));
+
+        // ...but sub-pixel positions are not, from around 10⁵ pixels onwards.
+        for x in [100_000, 1_000_000, 10_000_000] {
+            assert!(!is_exact(x as f64 + 1.0 / 256.0));
+
+            // Relative to a tile of 4096 pixels, they are.
+            let x = (x % 4096) as f64;
+            assert!(is_exact(x + 1.0 / 256.0));
+            assert!(is_exact(x + 0.5));
+        }
+    }
+
+    #[test]
+    fn present_series_replaces_only_the_series() {
+        use plotters::style::{BLUE, GREEN, RED, WHITE};
+
+        headless::test_synced(|| {
+            let paintable = Paintable::new((100, 100)).unwrap();
+            let a = paintable.register_series("a", &RED, SeriesMarker::Line);
+            let b = paintable.register_series("b", &BLUE, SeriesMarker::Line);
+
+            let mut backend = PaintableBackend::new(&paintable);
+            backend.draw_rect((0, 0), (100, 100), &WHITE, true).unwrap();
+            backend.with_series(&a, |backend| {
+                backend.draw_rect((10, 10), (20, 20), &RED, true).unwrap();
+            });
+            backend.with_series(&b, |backend| {
+                backend.draw_rect((30, 30), (40, 40), &BLUE, true).unwrap();
+            });
+            backend.present().unwrap();
+            drop(backend);
+
+            let mut backend = PaintableBackend::new(&paintable);
+            // Discarded, as it is outside of the series.
+            backend.draw_rect((0, 0), (5, 5), &GREEN, true).unwrap();
+            backend.with_series(&a, |backend| {
+                backend.draw_rect((50, 50), (60, 60), &RED, true).unwrap();
+            });
+            assert!(backend.present_series());
+            drop(backend);
+
+            let mut nodes = Vec::new();
+            color_nodes(
+                paintable.imp().node.borrow().as_ref().unwrap(),
+                (0.0, 0.0),
+                &mut nodes,
+            );
+            let bounds = nodes
+                .iter()
+                .map(|(bounds, _)| (bounds.x(), bounds.y(), bounds.width(), bounds.height()))
+                .collect::<Vec<_>>();
+            assert_eq!(
+                bounds,
+                [
+                    (0.0, 0.0, 100.0, 100.0),
+                    (50.0, 50.0, 10.0, 10.0),
+                    (30.0, 30.0, 10.0, 10.0),
+                ]
+            );
+
+            // Series not presented before cannot be replaced.
+            let c = paintable.register_series("c", &GREEN, SeriesMarker::Line);
+            let mut backend = PaintableBackend::new(&paintable);
+            backend.with_series(&c, |backend| {
+                backend.draw_rect((70, 70), (80, 80), &GREEN, true).unwrap();
+            });
+            assert!(!backend.present_series());
+        });
     }
 }
@@ -0,0 +1,178 @@
+//! Colors from the [GNOME HIG palette](https://developer.gnome.org/hig/reference/palette.html).
+//!
+//! The colors are exposed as [`RGBColor`] so they can be used directly with
+//! plotters, and the [`Gnome`] and [`GnomeDark`] palettes implement
+//! [`Palette`] for picking series colors by index.
+
+use gtk::{gdk, prelude::*};
+use plotters::style::{Palette, RGBColor};
+
+pub const BLUE_1: RGBColor = RGBColor(0x99, 0xc1, 0xf1);
+pub const BLUE_2: RGBColor = RGBColor(0x62, 0xa0, 0xea);
+pub const BLUE_3: RGBColor = RGBColor(0x35, 0x84, 0xe4);
+pub const BLUE_4: RGBColor = RGBColor(0x1c, 0x71, 0xd8);
+pub const BLUE_5: RGBColor = RGBColor(0x1a, 0x5f, 0xb4);
+
+pub const GREEN_1: RGBColor = RGBColor(0x8f, 0xf0, 0xa4);
+pub const GREEN_2: RGBColor = RGBColor(0x57, 0xe3, 0x89);
+pub const GREEN_3: RGBColor = RGBColor(0x33, 0xd1, 0x7a);
+pub const GREEN_4: RGBColor = RGBColor(0x2e, 0xc2, 0x7e);
+pub const GREEN_5: RGBColor = RGBColor(0x26, 0xa2, 0x69);
+
+pub const YELLOW_1: RGBColor = RGBColor(0xf9, 0xf0, 0x6b);
+pub const YELLOW_2: RGBColor = RGBColor(0xf8, 0xe4, 0x5c);
+pub const YELLOW_3: RGBColor = RGBColor(0xf6, 0xd3, 0x2d);
+pub const YELLOW_4: RGBColor = RGBColor(0xf5, 0xc2, 0x11);
+pub const YELLOW_5: RGBColor = RGBColor(0xe5, 0xa5, 0x0a);
+
+pub const ORANGE_1: RGBColor = RGBColor(0xff, 0xbe, 0x6f);
+pub const ORANGE_2: RGBColor = RGBColor(0xff, 0xa3, 0x48);
+pub const ORANGE_3: RGBColor = RGBColor(0xff, 0x78, 0x00);
+pub const ORANGE_4: RGBColor = RGBColor(0xe6, 0x61, 0x00);
+pub const ORANGE_5: RGBColor = RGBColor(0xc6, 0x46, 0x00);
+
+pub const RED_1: RGBColor = RGBColor(0xf6, 0x61, 0x51);
+pub const RED_2: RGBColor = RGBColor(0xed, 0x33, 0x3b);
+pub const RED_3: RGBColor = RGBColor(0xe0, 0x1b, 0x24);
+pub const RED_4: RGBColor = RGBColor(0xc0, 0x1c, 0x28);
+pub const RED_5: RGBColor = RGBColor(0xa5, 0x1d, 0x2d);
+
+pub const PURPLE_1: RGBColor = RGBColor(0xdc, 0x8a, 0xdd);
+pub const PURPLE_2: RGBColor = RGBColor(0xc0, 0x61, 0xcb);
+pub const PURPLE_3: RGBColor = RGBColor(0x91, 0x41, 0xac);
+pub const PURPLE_4: RGBColor = RGBColor(0x81, 0x3d, 0x9c);
+pub const PURPLE_5: RGBColor = RGBColor(0x61, 0x35, 0x83);
+
+pub const BROWN_1: RGBColor = RGBColor(0xcd, 0xab, 0x8f);
+pub const BROWN_2: RGBColor = RGBColor(0xb5, 0x83, 0x5a);
+pub const BROWN_3: RGBColor = RGBColor(0x98, 0x6a, 0x44);
+pub const BROWN_4: RGBColor = RGBColor(0x86, 0x5e, 0x3c);
+pub const BROWN_5: RGBColor = RGBColor(0x63, 0x45, 0x2c);
+
+pub const LIGHT_1: RGBColor = RGBColor(0xff, 0xff, 0xff);
+pub const LIGHT_2: RGBColor = RGBColor(0xf6, 0xf5, 0xf4);
+pub const LIGHT_3: RGBColor = RGBColor(0xde, 0xdd, 0xda);
+pub const LIGHT_4: RGBColor = RGBColor(0xc0, 0xbf, 0xbc);
+pub const LIGHT_5: RGBColor = RGBColor(0x9a, 0x99, 0x96);
+
+pub const DARK_1: RGBColor = RGBColor(0x77, 0x76, 0x7b);
+pub const DARK_2: RGBColor = RGBColor(0x5e, 0x5c, 0x64);
+pub const DARK_3: RGBColor = RGBColor(0x3d, 0x38, 0x46);
+pub const DARK_4: RGBColor = RGBColor(0x24, 0x1f, 0x31);
+pub const DARK_5: RGBColor = RGBColor(0x00, 0x00, 0x00);
+
+/// Palette of GNOME HIG colors suited for light backgrounds.
+#[derive(Debug)]
+pub struct Gnome;
+
+impl Palette for Gnome {
+    const COLORS: &'static [(u8, u8, u8)] = &[
+        rgb(BLUE_3),
+        rgb(ORANGE_3),
+        rgb(GREEN_5),
+        rgb(RED_3),
+        rgb(PURPLE_3),
+        rgb(YELLOW_5),
+        rgb(BROWN_3),
+        rgb(DARK_2),
+    ];
+}
+
+/// Palette of GNOME HIG colors suited for dark backgrounds.
+#[derive(Debug)]
+pub struct GnomeDark;
+
+impl Palette for GnomeDark {
+    const COLORS: &'static [(u8, u8, u8)] = &[
+        rgb(BLUE_2),
+        rgb(ORANGE_2),
+        rgb(GREEN_3),
+        rgb(RED_1),
+        rgb(PURPLE_2),
+        rgb(YELLOW_3),
+        rgb(BROWN_2),
+        rgb(LIGHT_4),
+    ];
+}
+
+/// Palette that follows the accent color and color scheme of a widget.
+///
+/// The accent color is always the first color, followed by the colors of
+/// [`Gnome`] or [`GnomeDark`], depending on whether the widget uses a dark
+/// color scheme.
+#[derive(Debug, Clone, Copy)]
+pub struct AdaptivePalette {
+    accent: RGBColor,
+    is_dark: bool,
+}
+
+impl AdaptivePalette {
+    /// Creates a palette from the current style of the widget.
+    ///
+    /// This must be called again when the style of the widget changes, for
+    /// example, when switching between light and dark color schemes.
+    pub fn for_widget(widget: &impl IsA<gtk::Widget>) -> Self {
+        let is_dark = luminance(&widget.color()) > 0.5;
+        let accent = accent_color(widget).unwrap_or(if is_dark { BLUE_2 } else { BLUE_3 });
+        Self { accent, is_dark }
+    }
+
+    /// Returns the accent color.
+    pub fn accent(&self) -> RGBColor {
+        self.accent
+    }
+
+    /// Returns whether the palette is for a dark color scheme.
+    pub fn is_dark(&self) -> bool {
+        self.is_dark
+    }
+
+    /// Returns the colors of the palette, starting with the accent color.
+    pub fn colors(&self) -> Vec<RGBColor> {
+        let base = if self.is_dark {
+            GnomeDark::COLORS
+        } else {
+            Gnome::COLORS
+        };
+
+        let accent = self.accent;
+        std::iter::once(accent)
+            .chain(
+                base.iter()
+                    .map(|&(r, g, b)| RGBColor(r, g, b))
+                    .filter(|&color| color != accent),
+            )
+            .collect()
+    }
+
+    /// Picks a color from the palette, wrapping around when the index is out
+    /// of range.
+    pub fn pick(&self, idx: usize) -> RGBColor {
+        let colors = self.colors();
+        colors[idx % colors.len()]
+    }
+}
+
+/// Returns the accent color of the widget, as defined by the
+/// `accent_bg_color` named color of the current theme.
+pub fn accent_color(widget: &impl IsA<gtk::Widget>) -> Option<RGBColor> {
+    #[allow(deprecated)]
+    let color = widget.style_context().lookup_color("accent_bg_color")?;
+    Some(to_rgb_color(&color))
+}
+
+fn to_rgb_color(color: &gdk::RGBA) -> RGBColor {
+    RGBColor(
+        (color.red() * 255.0).round() as u8,
+        (color.green() * 255.0).round() as u8,
+        (color.blue() * 255.0).round() as u8,
+    )
+}
+
+fn luminance(color: &gdk::RGBA) -> f32 {
+    0.2126 * color.red() + 0.7152 * color.green() + 0.0722 * color.blue()
+}
+
+const fn rgb(color: RGBColor) -> (u8, u8, u8) {
+    (color.0, color.1, color.2)
+}
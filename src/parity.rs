@@ -0,0 +1,259 @@
+//! Utilities for comparing the output of this backend against
+//! [`plotters_bitmap`], to verify visual fidelity in tests.
+//!
+//! This requires the `parity` feature, which also enables the `ttf` feature
+//! of plotters, so that [`BitMapBackend`] draws text.
+//!
+//! Antialiasing and text rendering differ between both backends, so the
+//! comparison is a score rather than an exact match. A chart that renders
+//! the same on both typically has a small [`ParityReport::mean_difference`],
+//! while missing or misplaced elements show up as a large
+//! [`ParityReport::differing_fraction`].
+
+use std::error::Error;
+
+use gtk::gdk;
+use plotters::{
+    coord::Shift,
+    drawing::{DrawingArea, DrawingAreaErrorKind, IntoDrawingArea},
+};
+use plotters_backend::DrawingBackend;
+use plotters_bitmap::BitMapBackend;
+
+use crate::{Paintable, PaintableBackend};
+
+/// The difference a channel must exceed for a pixel to be counted as
+/// differing, out of 255.
+const DEFAULT_TOLERANCE: u8 = 32;
+
+/// A chart that can be drawn on any backend.
+///
+/// This is implemented by the charts compared with [`compare`], as they are
+/// drawn once on each backend.
+pub trait ParityChart {
+    /// Draws the chart on `root`.
+    fn draw<DB: DrawingBackend>(
+        &self,
+        root: &DrawingArea<DB, Shift>,
+    ) -> Result<(), DrawingAreaErrorKind<DB::ErrorType>>;
+}
+
+/// The result of comparing the output of both backends.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParityReport {
+    /// The mean difference of all channels of all pixels, from `0.0` for
+    /// identical images to `1.0`.
+    pub mean_difference: f64,
+    /// The largest difference of a channel, out of 255.
+    pub max_difference: u8,
+    /// The fraction of pixels with a channel that differs by more than the
+    /// tolerance, from `0.0` to `1.0`.
+    pub differing_fraction: f64,
+}
+
+/// Draws `chart` through this backend and through [`BitMapBackend`] at
+/// `size`, and compares the results, counting pixels as differing if a
+/// channel differs by more than 32 out of 255.
+///
+/// The output of this backend is rasterized with an offscreen Cairo
+/// renderer, and both outputs are composited over black.
+pub fn compare(chart: &impl ParityChart, size: (u32, u32)) -> Result<ParityReport, Box<dyn Error>> {
+    compare_with_tolerance(chart, size, DEFAULT_TOLERANCE)
+}
+
+/// Like [`compare`], but counting pixels as differing if a channel differs
+/// by more than `tolerance` out of 255.
+pub fn compare_with_tolerance(
+    chart: &impl ParityChart,
+    size: (u32, u32),
+    tolerance: u8,
+) -> Result<ParityReport, Box<dyn Error>> {
+    let gtk_pixels = render_gtk(chart, size)?;
+    let bitmap_pixels = render_bitmap(chart, size)?;
+
+    Ok(diff(&gtk_pixels, &bitmap_pixels, tolerance))
+}
+
+/// Returns the RGB pixels of `chart` drawn through this backend.
+fn render_gtk(chart: &impl ParityChart, size: (u32, u32)) -> Result<Vec<u8>, Box<dyn Error>> {
+    let paintable = Paintable::new(size)?;
+    {
+        let root = PaintableBackend::new(&paintable).into_drawing_area();
+        chart.draw(&root)?;
+        root.present()?;
+    }
+
+    let texture = paintable.render_to_texture(size, None)?;
+
+    // Premultiplied colors are the colors composited over black.
+    let mut downloader = gdk::TextureDownloader::new(&texture);
+    downloader.set_format(gdk::MemoryFormat::R8g8b8a8Premultiplied);
+    let (bytes, stride) = downloader.download_bytes();
+
+    let (width, height) = (size.0 as usize, size.1 as usize);
+    let mut pixels = Vec::with_capacity(width * height * 3);
+    for row in bytes.chunks(stride).take(height) {
+        for pixel in row[..width * 4].chunks_exact(4) {
+            pixels.extend_from_slice(&pixel[..3]);
+        }
+    }
+
+    Ok(pixels)
+}
+
+/// Returns the RGB pixels of `chart` drawn through [`BitMapBackend`].
+fn render_bitmap(chart: &impl ParityChart, size: (u32, u32)) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut pixels = vec![0; size.0 as usize * size.1 as usize * 3];
+    {
+        let root = BitMapBackend::with_buffer(&mut pixels, size).into_drawing_area();
+        chart.draw(&root)?;
+        root.present()?;
+    }
+
+    Ok(pixels)
+}
+
+fn diff(a: &[u8], b: &[u8], tolerance: u8) -> ParityReport {
+    let mut sum = 0u64;
+    let mut max_difference = 0;
+    let mut n_differing = 0usize;
+
+    for (pixel_a, pixel_b) in a.chunks_exact(3).zip(b.chunks_exact(3)) {
+        let mut is_differing = false;
+        for (&channel_a, &channel_b) in pixel_a.iter().zip(pixel_b) {
+            let difference = channel_a.abs_diff(channel_b);
+            sum += difference as u64;
+            max_difference = max_difference.max(difference);
+            is_differing |= difference > tolerance;
+        }
+        if is_differing {
+            n_differing += 1;
+        }
+    }
+
+    let n_pixels = (a.len().min(b.len()) / 3).max(1);
+    ParityReport {
+        mean_difference: sum as f64 / (n_pixels * 3 * 255) as f64,
+        max_difference,
+        differing_fraction: n_differing as f64 / n_pixels as f64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use plotters::prelude::*;
+
+    use super::*;
+    use crate::headless;
+
+    struct Chart;
+
+    impl ParityChart for Chart {
+        fn draw<DB: DrawingBackend>(
+            &self,
+            root: &DrawingArea<DB, Shift>,
+        ) -> Result<(), DrawingAreaErrorKind<DB::ErrorType>> {
+            root.fill(&WHITE)?;
+            root.draw(&Rectangle::new([(20, 20), (80, 60)], BLUE.filled()))?;
+            root.draw(&PathElement::new(vec![(10, 90), (190, 70)], BLACK))?;
+            root.draw(&Text::new(
+                "Parity",
+                (110, 20),
+                ("sans-serif", 24).into_font().color(&BLACK),
+            ))?;
+            Ok(())
+        }
+    }
+
+    struct BlankChart;
+
+    impl ParityChart for BlankChart {
+        fn draw<DB: DrawingBackend>(
+            &self,
+            root: &DrawingArea<DB, Shift>,
+        ) -> Result<(), DrawingAreaErrorKind<DB::ErrorType>> {
+            root.fill(&WHITE)
+        }
+    }
+
+    #[test]
+    fn diff_of_identical_pixels_is_zero() {
+        let pixels = [10, 20, 30, 40, 50, 60];
+        let report = diff(&pixels, &pixels, 0);
+        assert_eq!(report.mean_difference, 0.0);
+        assert_eq!(report.max_difference, 0);
+        assert_eq!(report.differing_fraction, 0.0);
+    }
+
+    #[test]
+    fn diff_counts_pixels_beyond_tolerance() {
+        let report = diff(&[0, 0, 0, 0, 0, 0], &[255, 0, 0, 10, 0, 0], 32);
+        assert_eq!(report.max_difference, 255);
+        assert_eq!(report.differing_fraction, 0.5);
+        assert_eq!(report.mean_difference, 265.0 / (2.0 * 3.0 * 255.0));
+    }
+
+    #[test]
+    fn compare_matches_same_chart() {
+        headless::test_synced(|| {
+            let report = compare(&Chart, (200, 100)).unwrap();
+            assert!(report.differing_fraction < 0.05, "{:?}", report);
+
+            let report = compare(&BlankChart, (200, 100)).unwrap();
+            assert_eq!(report.differing_fraction, 0.0, "{:?}", report);
+        });
+    }
+
+    #[test]
+    fn compare_draws_text_on_both_backends() {
+        headless::test_synced(|| {
+            let text_pixels = render_bitmap(&Chart, (200, 100)).unwrap();
+            let blank_pixels = render_bitmap(&BlankChart, (200, 100)).unwrap();
+            // Only the text is drawn right of the rect and above the line.
+            let is_text_drawn = |pixels: &[u8]| {
+                (10..40).any(|y| {
+                    (110..200).any(|x| {
+                        let offset = (y * 200 + x) * 3;
+                        pixels[offset..offset + 3] != [255, 255, 255]
+                    })
+                })
+            };
+            assert!(is_text_drawn(&text_pixels));
+            assert!(!is_text_drawn(&blank_pixels));
+
+            let text_pixels = render_gtk(&Chart, (200, 100)).unwrap();
+            assert!(is_text_drawn(&text_pixels));
+        });
+    }
+
+    #[test]
+    fn ink_text_extents_match_bitmap_backend() {
+        headless::test_synced(|| {
+            for size in [12.0, 20.0, 32.0] {
+                let style = TextStyle::from(("sans-serif", size).into_font());
+                for text in ["Plot", "jpg", "1,234.5", "Temperature (°C)"] {
+                    let mut pixels = vec![0; 3];
+                    let bitmap = BitMapBackend::with_buffer(&mut pixels, (1, 1));
+                    let expected = bitmap.estimate_text_size(text, &style).unwrap();
+
+                    let snapshot = gtk::Snapshot::new();
+                    let mut backend = crate::SnapshotBackend::new(&snapshot, (1, 1));
+                    backend.set_text_extents(crate::TextExtents::Ink);
+                    let actual = backend.estimate_text_size(text, &style).unwrap();
+
+                    // Both backends may pick different fonts for the family,
+                    // and hint glyphs differently.
+                    let is_close = |a: u32, b: u32| a.abs_diff(b) <= (b / 10).max(2);
+                    assert!(
+                        is_close(actual.0, expected.0) && is_close(actual.1, expected.1),
+                        "{:?} at {}: {:?} != {:?}",
+                        text,
+                        size,
+                        actual,
+                        expected
+                    );
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,170 @@
+//! Exporting of recorded plots to multi-page PDF documents, such as for
+//! reports.
+
+use std::{io::Write, path::Path};
+
+use gtk::cairo;
+
+use crate::recording::Recording;
+
+/// The size of the pages and the margins of a [`PdfDocument`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PageSetup {
+    /// The width and height of the pages, in points.
+    pub size: (f64, f64),
+    /// The distance between the edges of the pages and the plots, in points.
+    pub margin: f64,
+}
+
+impl PageSetup {
+    /// A4 portrait, 210 × 297 mm, with half-inch margins.
+    pub const A4: Self = Self {
+        size: (595.0, 842.0),
+        margin: 36.0,
+    };
+
+    /// US Letter portrait, 8.5 × 11 in, with half-inch margins.
+    pub const LETTER: Self = Self {
+        size: (612.0, 792.0),
+        margin: 36.0,
+    };
+
+    /// Returns the page setup rotated to landscape.
+    pub fn landscape(self) -> Self {
+        let (width, height) = self.size;
+        Self {
+            size: (width.max(height), width.min(height)),
+            ..self
+        }
+    }
+}
+
+impl Default for PageSetup {
+    fn default() -> Self {
+        Self::A4
+    }
+}
+
+/// A PDF document with a recorded plot on each page.
+///
+/// Each plot is scaled to fit within the margins of its page, keeping its
+/// aspect ratio, and centered. The primitives stay vectors, so the plots
+/// are sharp when printed or zoomed.
+#[derive(Debug)]
+pub struct PdfDocument {
+    surface: cairo::PdfSurface,
+    page_setup: PageSetup,
+    n_pages: usize,
+}
+
+impl PdfDocument {
+    /// Creates a document that is written to `stream` as pages are added,
+    /// with pages of `page_setup`.
+    pub fn for_stream(
+        stream: impl Write + 'static,
+        page_setup: PageSetup,
+    ) -> Result<Self, cairo::Error> {
+        let (width, height) = page_setup.size;
+        let surface = cairo::PdfSurface::for_stream(width, height, stream)?;
+        Ok(Self {
+            surface,
+            page_setup,
+            n_pages: 0,
+        })
+    }
+
+    /// Creates a document that is written to the file at `path`, with pages
+    /// of `page_setup`.
+    pub fn create(path: impl AsRef<Path>, page_setup: PageSetup) -> Result<Self, cairo::Error> {
+        let (width, height) = page_setup.size;
+        let surface = cairo::PdfSurface::new(width, height, path)?;
+        Ok(Self {
+            surface,
+            page_setup,
+            n_pages: 0,
+        })
+    }
+
+    /// Sets the page setup of the pages added from now on.
+    pub fn set_page_setup(&mut self, page_setup: PageSetup) -> Result<(), cairo::Error> {
+        let (width, height) = page_setup.size;
+        self.surface.set_size(width, height)?;
+        self.page_setup = page_setup;
+        Ok(())
+    }
+
+    /// Returns the page setup of the pages added from now on.
+    pub fn page_setup(&self) -> PageSetup {
+        self.page_setup
+    }
+
+    /// Returns the number of pages added.
+    pub fn n_pages(&self) -> usize {
+        self.n_pages
+    }
+
+    /// Adds a page with `recording`.
+    pub fn add_page(&mut self, recording: &Recording) -> Result<(), cairo::Error> {
+        let cr = cairo::Context::new(&self.surface)?;
+
+        let PageSetup {
+            size: (page_width, page_height),
+            margin,
+        } = self.page_setup;
+        let available_width = (page_width - 2.0 * margin).max(0.0);
+        let available_height = (page_height - 2.0 * margin).max(0.0);
+
+        let (width, height) = recording.size();
+        if width > 0 && height > 0 {
+            let scale = (available_width / width as f64).min(available_height / height as f64);
+            cr.translate(
+                margin + (available_width - width as f64 * scale) / 2.0,
+                margin + (available_height - height as f64 * scale) / 2.0,
+            );
+            cr.scale(scale, scale);
+            cr.rectangle(0.0, 0.0, width as f64, height as f64);
+            cr.clip();
+
+            if let Some(node) = recording.to_node() {
+                node.draw(&cr);
+            }
+        }
+
+        cr.show_page()?;
+        self.n_pages += 1;
+        Ok(())
+    }
+
+    /// Finishes the document, writing the remaining output.
+    ///
+    /// Dropping the document also finishes it, but ignores errors.
+    pub fn finish(self) -> Result<(), cairo::Error> {
+        self.surface.finish();
+        self.surface.status()
+    }
+}
+
+/// Renders each of `recordings` on its own page of a PDF document with
+/// pages of `page_setup`.
+pub fn render_to_pdf<'a>(
+    recordings: impl IntoIterator<Item = &'a Recording>,
+    page_setup: PageSetup,
+) -> Result<Vec<u8>, cairo::Error> {
+    let (width, height) = page_setup.size;
+    let surface = cairo::PdfSurface::for_stream(width, height, Vec::<u8>::new())?;
+
+    let mut document = PdfDocument {
+        surface,
+        page_setup,
+        n_pages: 0,
+    };
+    for recording in recordings {
+        document.add_page(recording)?;
+    }
+
+    let stream = document
+        .surface
+        .finish_output_stream()
+        .map_err(|_| cairo::Error::WriteError)?;
+    Ok(*stream.downcast::<Vec<u8>>().unwrap())
+}
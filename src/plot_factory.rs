@@ -0,0 +1,172 @@
+use std::{cell::RefCell, collections::VecDeque, fmt, rc::Rc};
+
+use gtk::{
+    gdk,
+    glib::{self, clone},
+    prelude::*,
+};
+
+use crate::{InvalidSizeError, Paintable};
+
+/// The number of items drawn per idle iteration of the rendering queue.
+const ITEMS_PER_IDLE: usize = 4;
+
+type DrawFunc = Box<dyn Fn(&glib::Object, &Paintable)>;
+
+struct Inner {
+    size: (u32, u32),
+    draw_func: DrawFunc,
+    pool: RefCell<Vec<Paintable>>,
+    queue: RefCell<VecDeque<(gtk::ListItem, Paintable)>>,
+    idle_source: RefCell<Option<glib::SourceId>>,
+}
+
+/// Item factory of [`gtk::ListView`]s and [`gtk::GridView`]s that shows a
+/// plot for each item, such as a list of sparklines.
+///
+/// Each list item shows a [`Paintable`] in a [`gtk::Picture`], drawn with
+/// the draw function given the item of the model. Items are drawn from an
+/// idle queue after they are bound rather than while binding, so that
+/// scrolling stays smooth, and rows scrolled out of view before they were
+/// drawn are never drawn. When an item is unbound, the frame being drawn
+/// for it is [cancelled](Paintable::cancel_pending) and its paintable is
+/// cleared and kept for reuse by the next bound item.
+pub struct PlotFactory {
+    factory: gtk::SignalListItemFactory,
+    inner: Rc<Inner>,
+}
+
+impl fmt::Debug for PlotFactory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PlotFactory")
+            .field("factory", &self.factory)
+            .field("size", &self.inner.size)
+            .field("n_pending", &self.n_pending())
+            .finish_non_exhaustive()
+    }
+}
+
+impl PlotFactory {
+    /// Creates a factory of plots of `size`, drawn by calling `draw_func`
+    /// with the item of the model and the paintable to draw it on, usually
+    /// with a [`PaintableBackend`](crate::PaintableBackend).
+    pub fn new(
+        size: (u32, u32),
+        draw_func: impl Fn(&glib::Object, &Paintable) + 'static,
+    ) -> Result<Self, InvalidSizeError> {
+        // Fail early rather than on the first bound item.
+        let paintable = Paintable::new(size)?;
+
+        let inner = Rc::new(Inner {
+            size,
+            draw_func: Box::new(draw_func),
+            pool: RefCell::new(vec![paintable]),
+            queue: RefCell::default(),
+            idle_source: RefCell::default(),
+        });
+
+        let factory = gtk::SignalListItemFactory::new();
+        factory.connect_setup(|_, list_item| {
+            let list_item = list_item.downcast_ref::<gtk::ListItem>().unwrap();
+            let picture = gtk::Picture::new();
+            picture.set_can_shrink(false);
+            list_item.set_child(Some(&picture));
+        });
+        factory.connect_bind(clone!(
+            #[strong]
+            inner,
+            move |_, list_item| {
+                let list_item = list_item.downcast_ref::<gtk::ListItem>().unwrap();
+                let paintable = inner
+                    .pool
+                    .borrow_mut()
+                    .pop()
+                    .unwrap_or_else(|| Paintable::new(inner.size).unwrap());
+                picture(list_item).set_paintable(Some(&paintable));
+
+                inner
+                    .queue
+                    .borrow_mut()
+                    .push_back((list_item.clone(), paintable));
+                Inner::ensure_idle(&inner);
+            }
+        ));
+        factory.connect_unbind(clone!(
+            #[strong]
+            inner,
+            move |_, list_item| {
+                let list_item = list_item.downcast_ref::<gtk::ListItem>().unwrap();
+                let picture = picture(list_item);
+                let Some(paintable) = picture.paintable().and_downcast::<Paintable>() else {
+                    return;
+                };
+                picture.set_paintable(gdk::Paintable::NONE);
+
+                inner
+                    .queue
+                    .borrow_mut()
+                    .retain(|(_, queued)| queued != &paintable);
+                paintable.cancel_pending();
+                paintable.clear();
+                inner.pool.borrow_mut().push(paintable);
+            }
+        ));
+
+        Ok(Self { factory, inner })
+    }
+
+    /// Returns the factory to set on a [`gtk::ListView`] or
+    /// [`gtk::GridView`].
+    pub fn factory(&self) -> &gtk::ListItemFactory {
+        self.factory.upcast_ref()
+    }
+
+    /// Returns the size of the plots.
+    pub fn size(&self) -> (u32, u32) {
+        self.inner.size
+    }
+
+    /// Returns the number of bound items waiting to be drawn.
+    pub fn n_pending(&self) -> usize {
+        self.inner.queue.borrow().len()
+    }
+}
+
+impl Inner {
+    /// Starts draining the queue when the main loop is idle, if it is not
+    /// already being drained.
+    fn ensure_idle(this: &Rc<Self>) {
+        if this.idle_source.borrow().is_some() {
+            return;
+        }
+
+        let source_id = glib::idle_add_local(clone!(
+            #[weak]
+            this,
+            #[upgrade_or]
+            glib::ControlFlow::Break,
+            move || {
+                for _ in 0..ITEMS_PER_IDLE {
+                    let Some((list_item, paintable)) = this.queue.borrow_mut().pop_front() else {
+                        break;
+                    };
+                    if let Some(item) = list_item.item() {
+                        (this.draw_func)(&item, &paintable);
+                    }
+                }
+
+                if this.queue.borrow().is_empty() {
+                    this.idle_source.replace(None);
+                    glib::ControlFlow::Break
+                } else {
+                    glib::ControlFlow::Continue
+                }
+            }
+        ));
+        this.idle_source.replace(Some(source_id));
+    }
+}
+
+fn picture(list_item: &gtk::ListItem) -> gtk::Picture {
+    list_item.child().and_downcast::<gtk::Picture>().unwrap()
+}
@@ -0,0 +1,253 @@
+use gtk::{
+    gdk, gio,
+    glib::{self, clone},
+    prelude::*,
+    subclass::prelude::*,
+};
+
+use crate::{export::ExportRegion, PlotView};
+
+mod imp {
+    use std::{
+        cell::{Cell, OnceCell},
+        sync::OnceLock,
+    };
+
+    use super::*;
+
+    #[derive(Debug, Default)]
+    pub struct PlotMenu {
+        pub(super) plot_view: glib::WeakRef<PlotView>,
+        pub(super) popover: OnceCell<gtk::PopoverMenu>,
+        pub(super) is_grid_hidden: Cell<bool>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PlotMenu {
+        const NAME: &'static str = "PlottersGtk4PlotMenu";
+        type Type = super::PlotMenu;
+    }
+
+    impl ObjectImpl for PlotMenu {
+        fn properties() -> &'static [glib::ParamSpec] {
+            static PROPERTIES: OnceLock<Vec<glib::ParamSpec>> = OnceLock::new();
+
+            PROPERTIES.get_or_init(|| {
+                vec![glib::ParamSpecBoolean::builder("show-grid")
+                    .default_value(true)
+                    .explicit_notify()
+                    .build()]
+            })
+        }
+
+        fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+            match pspec.name() {
+                "show-grid" => {
+                    let show_grid = value.get().unwrap();
+                    self.obj().set_show_grid(show_grid);
+                }
+                _ => unimplemented!(),
+            }
+        }
+
+        fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            match pspec.name() {
+                "show-grid" => self.obj().shows_grid().into(),
+                _ => unimplemented!(),
+            }
+        }
+    }
+}
+
+glib::wrapper! {
+    /// A context menu with the standard actions of a [`PlotView`].
+    ///
+    /// The menu is shown by right-clicking the view, and has the following
+    /// actions, which are also available in the `plot` action group of the
+    /// view:
+    ///
+    /// - `plot.copy-image` copies the plot to the clipboard.
+    /// - `plot.save-as` saves the plot as a PNG image, or as an SVG document
+    ///   if the chosen file name ends with `.svg`.
+    /// - `plot.reset-zoom` resets the ranges to the home ranges of the view,
+    ///   see [`PlotView::set_home_ranges`].
+    /// - `plot.show-grid` toggles [`PlotMenu::shows_grid`], which the draw
+    ///   function can use to skip drawing the grid.
+    pub struct PlotMenu(ObjectSubclass<imp::PlotMenu>);
+}
+
+impl PlotMenu {
+    /// Installs a new menu on `plot_view`.
+    ///
+    /// The menu is kept alive by the view, so the returned menu does not
+    /// have to be stored.
+    pub fn new(plot_view: &PlotView) -> Self {
+        let this = glib::Object::new::<Self>();
+        let imp = this.imp();
+
+        imp.plot_view.set(Some(plot_view));
+
+        let action_group = gio::SimpleActionGroup::new();
+
+        let copy_image_action = gio::SimpleAction::new("copy-image", None);
+        copy_image_action.connect_activate(clone!(
+            #[strong(rename_to = obj)]
+            this,
+            move |_, _| {
+                obj.copy_image();
+            }
+        ));
+        action_group.add_action(&copy_image_action);
+
+        let save_as_action = gio::SimpleAction::new("save-as", None);
+        save_as_action.connect_activate(clone!(
+            #[strong(rename_to = obj)]
+            this,
+            move |_, _| {
+                obj.save_as();
+            }
+        ));
+        action_group.add_action(&save_as_action);
+
+        let reset_zoom_action = gio::SimpleAction::new("reset-zoom", None);
+        reset_zoom_action.connect_activate(clone!(
+            #[strong(rename_to = obj)]
+            this,
+            move |_, _| {
+                obj.reset_zoom();
+            }
+        ));
+        action_group.add_action(&reset_zoom_action);
+
+        let show_grid_action = gio::PropertyAction::new("show-grid", &this, "show-grid");
+        action_group.add_action(&show_grid_action);
+
+        plot_view.insert_action_group("plot", Some(&action_group));
+
+        let menu = gio::Menu::new();
+        let section = gio::Menu::new();
+        section.append(Some("Copy Image"), Some("plot.copy-image"));
+        section.append(Some("Save As…"), Some("plot.save-as"));
+        menu.append_section(None, &section);
+        let section = gio::Menu::new();
+        section.append(Some("Reset Zoom"), Some("plot.reset-zoom"));
+        section.append(Some("Show Grid"), Some("plot.show-grid"));
+        menu.append_section(None, &section);
+
+        let popover = gtk::PopoverMenu::from_model(Some(&menu));
+        popover.set_has_arrow(false);
+        popover.set_halign(gtk::Align::Start);
+        popover.set_parent(plot_view);
+        imp.popover.set(popover).unwrap();
+
+        let click = gtk::GestureClick::new();
+        click.set_button(gdk::BUTTON_SECONDARY);
+        click.connect_pressed(clone!(
+            #[strong(rename_to = obj)]
+            this,
+            move |gesture, _, x, y| {
+                gesture.set_state(gtk::EventSequenceState::Claimed);
+                obj.popup_at(x, y);
+            }
+        ));
+        plot_view.add_controller(click);
+
+        this
+    }
+
+    /// Sets whether the grid should be drawn, redrawing the view.
+    ///
+    /// It is up to the draw function to skip drawing the grid.
+    pub fn set_show_grid(&self, show_grid: bool) {
+        if show_grid == self.shows_grid() {
+            return;
+        }
+
+        self.imp().is_grid_hidden.set(!show_grid);
+        self.notify("show-grid");
+
+        if let Some(plot_view) = self.imp().plot_view.upgrade() {
+            plot_view.redraw();
+        }
+    }
+
+    /// Returns whether the grid should be drawn.
+    pub fn shows_grid(&self) -> bool {
+        !self.imp().is_grid_hidden.get()
+    }
+
+    fn popup_at(&self, x: f64, y: f64) {
+        let popover = self.imp().popover.get().unwrap();
+        popover.set_pointing_to(Some(&gdk::Rectangle::new(x as i32, y as i32, 1, 1)));
+        popover.popup();
+    }
+
+    fn copy_image(&self) {
+        let Some(plot_view) = self.imp().plot_view.upgrade() else {
+            return;
+        };
+        let Some(paintable) = plot_view.paintable() else {
+            return;
+        };
+
+        if let Err(err) = paintable.copy_to_clipboard(&plot_view.clipboard(), ExportRegion::Full) {
+            glib::g_warning!("plotters-gtk4", "Failed to copy image: {:?}", err);
+        }
+    }
+
+    fn save_as(&self) {
+        let Some(plot_view) = self.imp().plot_view.upgrade() else {
+            return;
+        };
+        let Some(paintable) = plot_view.paintable() else {
+            return;
+        };
+
+        let dialog = gtk::FileDialog::builder()
+            .title("Save As")
+            .initial_name("plot.png")
+            .modal(true)
+            .build();
+        let window = plot_view.root().and_downcast::<gtk::Window>();
+        dialog.save(window.as_ref(), None::<&gio::Cancellable>, move |res| {
+            // The dialog returns an error if it is dismissed.
+            let Ok(file) = res else {
+                return;
+            };
+
+            let is_svg = file
+                .path()
+                .and_then(|path| path.extension().map(|ext| ext.eq_ignore_ascii_case("svg")))
+                .unwrap_or(false);
+            let bytes = if is_svg {
+                paintable
+                    .to_svg(ExportRegion::Full)
+                    .map(glib::Bytes::from_owned)
+                    .map_err(|err| glib::Error::new(gio::IOErrorEnum::Failed, &err.to_string()))
+            } else {
+                paintable
+                    .render_to_texture(paintable.size(), None)
+                    .map(|texture| texture.save_to_png_bytes())
+            };
+
+            let res = bytes.and_then(|bytes| {
+                file.replace_contents(
+                    &bytes,
+                    None,
+                    false,
+                    gio::FileCreateFlags::REPLACE_DESTINATION,
+                    None::<&gio::Cancellable>,
+                )
+            });
+            if let Err(err) = res {
+                glib::g_warning!("plotters-gtk4", "Failed to save image: {:?}", err);
+            }
+        });
+    }
+
+    fn reset_zoom(&self) {
+        if let Some(plot_view) = self.imp().plot_view.upgrade() {
+            plot_view.reset_ranges();
+        }
+    }
+}
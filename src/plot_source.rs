@@ -0,0 +1,230 @@
+use std::{
+    cell::{Cell, Ref, RefCell},
+    fmt,
+    rc::{Rc, Weak},
+};
+
+use gtk::{gio, glib, prelude::*};
+
+use crate::Series;
+
+type PointFunc = Box<dyn Fn(&glib::Object) -> Option<(f64, f64)>>;
+
+struct Inner {
+    series: Series,
+    model: gio::ListModel,
+    point_func: PointFunc,
+    /// The point of each item of the model, or `None` for items without one.
+    points: RefCell<Vec<Option<(f64, f64)>>>,
+    /// The number of items with a point.
+    n_points: Cell<usize>,
+    items_changed_handler: RefCell<Option<glib::SignalHandlerId>>,
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        if let Some(handler_id) = self.items_changed_handler.take() {
+            self.model.disconnect(handler_id);
+        }
+    }
+}
+
+/// The data points of a [`Series`], read from the items of a
+/// [`gio::ListModel`].
+///
+/// Each item is mapped to a point with the point function, such as from the
+/// properties of a domain object, and the points are kept up to date as the
+/// model changes. Only the items within the range reported by
+/// `items-changed` are mapped again, so appending to a large model is cheap.
+///
+/// A [`PlotView`] that a source is added to with [`PlotView::add_source`]
+/// redraws whenever the points of the source change, so the draw function
+/// only has to draw [`PlotSource::points`], or only the series of
+/// [`PlotView::changed_sources`].
+///
+/// [`PlotView`]: crate::PlotView
+/// [`PlotView::add_source`]: crate::PlotView::add_source
+/// [`PlotView::changed_sources`]: crate::PlotView::changed_sources
+#[derive(Clone)]
+pub struct PlotSource {
+    inner: Rc<Inner>,
+}
+
+impl fmt::Debug for PlotSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PlotSource")
+            .field("series", &self.inner.series)
+            .field("model", &self.inner.model)
+            .field("n_points", &self.n_points())
+            .finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for PlotSource {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+impl Eq for PlotSource {}
+
+impl PlotSource {
+    /// Creates a source of the points of `series`, mapped from the items of
+    /// `model` with `point_func`.
+    ///
+    /// Items for which `point_func` returns `None` are skipped, such as
+    /// items of another type or with missing values.
+    pub fn new(
+        series: &Series,
+        model: &impl IsA<gio::ListModel>,
+        point_func: impl Fn(&glib::Object) -> Option<(f64, f64)> + 'static,
+    ) -> Self {
+        let model = model.upcast_ref::<gio::ListModel>().clone();
+        let points = (0..model.n_items())
+            .map(|position| model.item(position).and_then(|item| point_func(&item)))
+            .collect::<Vec<_>>();
+        let n_points = points.iter().flatten().count();
+
+        let inner = Rc::new(Inner {
+            series: series.clone(),
+            model,
+            point_func: Box::new(point_func),
+            points: RefCell::new(points),
+            n_points: Cell::new(n_points),
+            items_changed_handler: RefCell::default(),
+        });
+
+        let weak_inner = Rc::downgrade(&inner);
+        let handler_id =
+            inner
+                .model
+                .connect_items_changed(move |model, position, n_removed, n_added| {
+                    if let Some(inner) = Weak::upgrade(&weak_inner) {
+                        inner.update_points(model, position, n_removed, n_added);
+                    }
+                });
+        inner.items_changed_handler.replace(Some(handler_id));
+
+        Self { inner }
+    }
+
+    /// Returns the series the points are drawn as.
+    pub fn series(&self) -> &Series {
+        &self.inner.series
+    }
+
+    /// Returns the model the points are read from.
+    pub fn model(&self) -> &gio::ListModel {
+        &self.inner.model
+    }
+
+    /// Returns the number of points, not counting skipped items.
+    pub fn n_points(&self) -> usize {
+        self.inner.n_points.get()
+    }
+
+    /// Returns an iterator over the points, in the order of the items of the
+    /// model.
+    ///
+    /// The points are borrowed rather than copied, so the model must not be
+    /// changed while the iterator is alive.
+    pub fn points(&self) -> Points<'_> {
+        Points {
+            points: Ref::map(self.inner.points.borrow(), Vec::as_slice),
+            index: 0,
+            n_remaining: self.n_points(),
+        }
+    }
+}
+
+/// An iterator over the points of a [`PlotSource`].
+///
+/// This is returned by [`PlotSource::points`].
+#[derive(Debug)]
+pub struct Points<'a> {
+    points: Ref<'a, [Option<(f64, f64)>]>,
+    index: usize,
+    n_remaining: usize,
+}
+
+impl Iterator for Points<'_> {
+    type Item = (f64, f64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(point) = self.points.get(self.index) {
+            self.index += 1;
+            if let Some(point) = point {
+                self.n_remaining -= 1;
+                return Some(*point);
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.n_remaining, Some(self.n_remaining))
+    }
+}
+
+impl ExactSizeIterator for Points<'_> {}
+
+impl Inner {
+    /// Maps the items that changed again, as reported by `items-changed`.
+    fn update_points(&self, model: &gio::ListModel, position: u32, n_removed: u32, n_added: u32) {
+        let added = (position..position + n_added)
+            .map(|position| {
+                model
+                    .item(position)
+                    .and_then(|item| (self.point_func)(&item))
+            })
+            .collect::<Vec<_>>();
+        let n_added_points = added.iter().flatten().count();
+
+        let mut points = self.points.borrow_mut();
+        let start = (position as usize).min(points.len());
+        let end = (start + n_removed as usize).min(points.len());
+        let n_removed_points = points.splice(start..end, added).flatten().count();
+        self.n_points
+            .set(self.n_points.get() - n_removed_points + n_added_points);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use plotters::style::RED;
+
+    use super::*;
+    use crate::{headless, Paintable, SeriesMarker};
+
+    #[test]
+    fn points_follow_the_model() {
+        headless::test_synced(|| {
+            let paintable = Paintable::new((10, 10)).unwrap();
+            let series = paintable.register_series("values", &RED, SeriesMarker::Line);
+            let model = gtk::StringList::new(&["1", "x", "3"]);
+            let source = PlotSource::new(&series, &model, |item| {
+                let string = item.downcast_ref::<gtk::StringObject>()?.string();
+                let value = string.parse::<f64>().ok()?;
+                Some((value, value * 2.0))
+            });
+
+            assert_eq!(source.n_points(), 2);
+            assert_eq!(source.points().len(), 2);
+            assert_eq!(
+                source.points().collect::<Vec<_>>(),
+                [(1.0, 2.0), (3.0, 6.0)]
+            );
+
+            model.splice(1, 2, &["2", "4", "y"]);
+            assert_eq!(source.n_points(), 3);
+            assert_eq!(
+                source.points().collect::<Vec<_>>(),
+                [(1.0, 2.0), (2.0, 4.0), (4.0, 8.0)]
+            );
+
+            model.remove(0);
+            assert_eq!(source.n_points(), 2);
+            assert_eq!(source.points().len(), 2);
+        });
+    }
+}
@@ -0,0 +1,2226 @@
+use std::{
+    cell::Cell,
+    collections::{HashMap, HashSet},
+    fmt,
+    ops::Range,
+    rc::Rc,
+    time::Duration,
+};
+
+use gtk::{
+    gdk, gio,
+    glib::{self, clone, closure_local},
+    graphene::{Point, Rect},
+    gsk,
+    prelude::*,
+    subclass::prelude::*,
+};
+
+use crate::{
+    common, Annotation, Highlight, HitRegion, Link, Paintable, PlotSelectionModel, PlotSource,
+    Quality, RangeModel, RendererCaps, Series, TileCache,
+};
+
+/// The properties of [`gtk::Settings`] and `adw::StyleManager` that change
+/// the style of plots.
+const STYLE_PROPERTIES: &[&str] = &[
+    "gtk-application-prefer-dark-theme",
+    "gtk-theme-name",
+    "gtk-font-name",
+    "gtk-xft-antialias",
+    "gtk-xft-dpi",
+    "gtk-xft-hinting",
+    "gtk-xft-hintstyle",
+    "gtk-xft-rgba",
+    "gtk-hint-font-metrics",
+    "dark",
+    "high-contrast",
+    "accent-color",
+];
+
+/// The factor the ranges are zoomed by per scroll step.
+const ZOOM_STEP: f64 = 1.1;
+
+/// The rate kinetic panning slows down at, per second.
+const KINETIC_FRICTION: f64 = 4.0;
+
+/// The speed kinetic panning stops at, in pixels per second.
+const KINETIC_MIN_SPEED: f64 = 20.0;
+
+/// The default fraction of the ranges panned per arrow key press.
+const DEFAULT_KEYBOARD_PAN_STEP: f64 = 0.1;
+
+/// How long after the last scroll step zooming is considered done.
+const SCROLL_SETTLE_DELAY: Duration = Duration::from_millis(200);
+
+/// How selected elements are highlighted by default, in the blue accent
+/// color of GNOME.
+const DEFAULT_SELECTION_HIGHLIGHT: Highlight = Highlight::Outline {
+    color: gdk::RGBA::new(0.21, 0.52, 0.89, 1.0),
+    width: 2.0,
+};
+
+/// The radius of the dots where the tracking line intersects series.
+const TRACKING_DOT_RADIUS: f32 = 3.0;
+
+/// The space between the tracking dots and their value labels.
+const TRACKING_LABEL_SPACING: f32 = 6.0;
+
+/// The version of the state returned by [`PlotView::save_state`], which is
+/// increased when the state changes in a way older versions cannot restore.
+const STATE_VERSION: u32 = 1;
+
+type DrawFunc = Rc<dyn Fn(&PlotView, &Paintable)>;
+
+type CoordMapper = Rc<dyn Fn((i32, i32)) -> Option<(f64, f64)>>;
+
+type TrackingFunc = Rc<dyn Fn(&Series, f64) -> Option<TrackingPoint>>;
+
+/// Where the tracking line of a [`PlotView`] intersects a series.
+///
+/// This is returned by the function set with
+/// [`PlotView::set_tracking_func`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrackingPoint {
+    /// The value of the series, shown in the label.
+    pub value: f64,
+    /// The y coordinate the value is drawn at, in pixels of the paintable.
+    pub y: i32,
+}
+
+/// The shape of the area selected by dragging with <kbd>Shift</kbd> held in
+/// a [`PlotView`].
+///
+/// This is set with [`PlotView::set_brush_mode`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BrushMode {
+    /// Dragging with <kbd>Shift</kbd> held pans as without it.
+    #[default]
+    None,
+    /// A rectangle from where the drag started to the pointer.
+    Rectangle,
+    /// A freehand shape following the pointer, closed back to where the drag
+    /// started.
+    Lasso,
+}
+
+mod imp {
+    use std::{
+        cell::{OnceCell, RefCell},
+        sync::OnceLock,
+    };
+
+    use glib::subclass::Signal;
+
+    use super::*;
+
+    #[derive(Default)]
+    pub struct PlotView {
+        pub(super) paintable: RefCell<Option<Paintable>>,
+        pub(super) paintable_handlers: RefCell<Vec<glib::SignalHandlerId>>,
+        /// The handler of the scale factor of the view syncing the one of
+        /// the paintable.
+        pub(super) scale_factor_handler: RefCell<Option<glib::SignalHandlerId>>,
+        pub(super) range_model: RefCell<Option<RangeModel>>,
+        pub(super) range_model_handler: RefCell<Option<glib::SignalHandlerId>>,
+        pub(super) draw_func: RefCell<Option<DrawFunc>>,
+        pub(super) coord_mapper: RefCell<Option<CoordMapper>>,
+        pub(super) readout_label: OnceCell<gtk::Label>,
+        pub(super) shows_readout: Cell<bool>,
+        pub(super) shows_tracking_line: Cell<bool>,
+        pub(super) palette_index: Cell<u32>,
+        pub(super) tracking_func: RefCell<Option<TrackingFunc>>,
+        pub(super) home_ranges: RefCell<Option<(Range<f64>, Range<f64>)>>,
+        pub(super) keyboard_pan_step: Cell<Option<f64>>,
+        pub(super) keyboard_zoom_step: Cell<Option<f64>>,
+        pub(super) drafts_during_gestures: Cell<bool>,
+        /// The quality of the paintable before a gesture drafted it.
+        pub(super) quality_before_gesture: Cell<Option<Quality>>,
+        /// Whether the paintable was redrawn since a gesture drafted it.
+        pub(super) is_draft_drawn: Cell<bool>,
+        pub(super) tile_cache: RefCell<Option<TileCache>>,
+
+        pub(super) drag_start_ranges: RefCell<Option<(Range<f64>, Range<f64>)>>,
+        pub(super) drag_sample: Cell<Option<(u32, f64, f64)>>,
+        pub(super) drag_velocity: Cell<(f64, f64)>,
+        pub(super) kinetic_tick: RefCell<Option<gtk::TickCallbackId>>,
+        pub(super) zoom_start: RefCell<Option<(Range<f64>, Range<f64>, (f64, f64))>>,
+        pub(super) pointer_position: Cell<Option<(f64, f64)>>,
+        pub(super) scroll_settle_source: RefCell<Option<glib::SourceId>>,
+        pub(super) idle_redraw_source: RefCell<Option<glib::SourceId>>,
+        /// Whether the idle redraw has to draw everything, rather than only
+        /// the changed sources.
+        pub(super) is_full_redraw_queued: Cell<bool>,
+        /// The sources whose points changed since the last draw.
+        pub(super) pending_changed_sources: RefCell<Vec<PlotSource>>,
+        /// The changed sources of the draw in progress.
+        pub(super) changed_sources: RefCell<Vec<PlotSource>>,
+        pub(super) hover_highlight: Cell<Option<Highlight>>,
+        /// The bounds of the hit region under the pointer, if any.
+        pub(super) hovered_bounds: Cell<Option<Rect>>,
+        /// The cursor replaced by the pointer cursor while over a link, if
+        /// the pointer cursor is set.
+        pub(super) cursor_before_link: RefCell<Option<Option<gdk::Cursor>>>,
+        pub(super) brush_mode: Cell<BrushMode>,
+        /// The points of the brush being dragged, in widget coordinates,
+        /// starting with where the drag started.
+        pub(super) brush_points: RefCell<Option<Vec<(f64, f64)>>>,
+        pub(super) selection_model: RefCell<Option<PlotSelectionModel>>,
+        pub(super) selection_model_handler: RefCell<Option<glib::SignalHandlerId>>,
+        pub(super) selection_highlight: Cell<Option<Highlight>>,
+        /// The sources added to the view, with the handlers of their models
+        /// and series.
+        pub(super) sources:
+            RefCell<Vec<(PlotSource, glib::SignalHandlerId, glib::SignalHandlerId)>>,
+        /// The style managers bound with `bind_to_style_manager`, with the
+        /// handlers of their style properties.
+        pub(super) style_manager_handlers:
+            RefCell<Vec<(glib::WeakRef<glib::Object>, glib::SignalHandlerId)>>,
+    }
+
+    impl fmt::Debug for PlotView {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("PlotView")
+                .field("paintable", &self.paintable)
+                .field("range_model", &self.range_model)
+                .finish_non_exhaustive()
+        }
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PlotView {
+        const NAME: &'static str = "PlottersGtk4PlotView";
+        type Type = super::PlotView;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.set_css_name("plotview");
+        }
+    }
+
+    impl ObjectImpl for PlotView {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+
+            if self.range_model.borrow().is_none() {
+                obj.set_range_model(&RangeModel::default());
+            }
+            if self.selection_model.borrow().is_none() {
+                obj.set_selection_model(&PlotSelectionModel::default());
+            }
+
+            obj.set_focusable(true);
+            obj.set_selection_highlight(Some(DEFAULT_SELECTION_HIGHLIGHT));
+
+            let key = gtk::EventControllerKey::new();
+            key.connect_key_pressed(clone!(
+                #[weak]
+                obj,
+                #[upgrade_or]
+                glib::Propagation::Proceed,
+                move |_, keyval, _, state| obj.handle_key_pressed(keyval, state)
+            ));
+            obj.add_controller(key);
+
+            let drag = gtk::GestureDrag::new();
+            drag.connect_drag_begin(clone!(
+                #[weak]
+                obj,
+                move |gesture, start_x, start_y| {
+                    if obj.brush_mode() != BrushMode::None
+                        && gesture
+                            .current_event_state()
+                            .contains(gdk::ModifierType::SHIFT_MASK)
+                    {
+                        obj.imp()
+                            .brush_points
+                            .replace(Some(vec![(start_x, start_y)]));
+                        return;
+                    }
+
+                    // Dragging with Ctrl held drags the plot itself instead.
+                    if gesture
+                        .current_event_state()
+                        .contains(gdk::ModifierType::CONTROL_MASK)
+                    {
+                        return;
+                    }
+
+                    obj.stop_kinetic();
+                    obj.grab_focus();
+
+                    let range_model = obj.range_model();
+                    obj.imp()
+                        .drag_start_ranges
+                        .replace(Some((range_model.x_range(), range_model.y_range())));
+                    obj.imp()
+                        .drag_sample
+                        .set(Some((gesture.current_event_time(), 0.0, 0.0)));
+                    obj.imp().drag_velocity.set((0.0, 0.0));
+                    obj.update_gesture_quality();
+                }
+            ));
+            drag.connect_drag_update(clone!(
+                #[weak]
+                obj,
+                move |gesture, offset_x, offset_y| {
+                    if obj.imp().brush_points.borrow().is_some() {
+                        obj.update_brush(offset_x, offset_y);
+                        return;
+                    }
+
+                    obj.track_drag_velocity(gesture.current_event_time(), offset_x, offset_y);
+                    obj.handle_drag_update(offset_x, offset_y);
+                }
+            ));
+            drag.connect_drag_end(clone!(
+                #[weak]
+                obj,
+                move |gesture, _, _| {
+                    if obj.imp().brush_points.borrow().is_some() {
+                        obj.finish_brush();
+                        return;
+                    }
+
+                    let was_dragging = obj.imp().drag_start_ranges.take().is_some();
+                    obj.imp().drag_sample.set(None);
+
+                    // Only touch drags keep moving after being released, as
+                    // is usual for touchscreens.
+                    let is_touch = gesture
+                        .device()
+                        .is_some_and(|device| device.source() == gdk::InputSource::Touchscreen);
+                    if was_dragging && is_touch {
+                        obj.start_kinetic();
+                    }
+                    obj.update_gesture_quality();
+                }
+            ));
+            obj.add_controller(drag);
+
+            let zoom = gtk::GestureZoom::new();
+            zoom.connect_begin(clone!(
+                #[weak]
+                obj,
+                move |gesture, _| {
+                    obj.stop_kinetic();
+
+                    let range_model = obj.range_model();
+                    let center = gesture
+                        .bounding_box_center()
+                        .map(|center| obj.to_data(center))
+                        .unwrap_or_else(|| {
+                            obj.to_data((obj.width() as f64 / 2.0, obj.height() as f64 / 2.0))
+                        });
+                    obj.imp().zoom_start.replace(Some((
+                        range_model.x_range(),
+                        range_model.y_range(),
+                        center,
+                    )));
+                    obj.update_gesture_quality();
+                }
+            ));
+            zoom.connect_scale_changed(clone!(
+                #[weak]
+                obj,
+                move |_, scale| {
+                    obj.handle_zoom_scale_changed(scale);
+                }
+            ));
+            zoom.connect_end(clone!(
+                #[weak]
+                obj,
+                move |_, _| {
+                    obj.imp().zoom_start.replace(None);
+                    obj.update_gesture_quality();
+                }
+            ));
+            obj.add_controller(zoom);
+
+            let click = gtk::GestureClick::new();
+            click.connect_released(clone!(
+                #[weak]
+                obj,
+                move |gesture, n_press, x, y| {
+                    if n_press != 1 {
+                        return;
+                    }
+
+                    let Some(region) = obj.pick(x, y) else {
+                        return;
+                    };
+                    if let Some(link) = region.link {
+                        gesture.set_state(gtk::EventSequenceState::Claimed);
+                        obj.activate_link(&link);
+                    } else if let Some(id) = region.id {
+                        gesture.set_state(gtk::EventSequenceState::Claimed);
+                        obj.select_clicked(&id, gesture.current_event_state());
+                    }
+                }
+            ));
+            obj.add_controller(click);
+
+            let drag_source = gtk::DragSource::new();
+            drag_source.set_actions(gdk::DragAction::COPY);
+            drag_source.connect_prepare(clone!(
+                #[weak]
+                obj,
+                #[upgrade_or]
+                None,
+                move |source, _, _| {
+                    if !source
+                        .current_event_state()
+                        .contains(gdk::ModifierType::CONTROL_MASK)
+                    {
+                        return None;
+                    }
+
+                    obj.paintable().map(|paintable| paintable.as_drag_content())
+                }
+            ));
+            obj.add_controller(drag_source);
+
+            let motion = gtk::EventControllerMotion::new();
+            motion.connect_motion(clone!(
+                #[weak]
+                obj,
+                move |_, x, y| {
+                    obj.handle_motion(x, y);
+                }
+            ));
+            motion.connect_leave(clone!(
+                #[weak]
+                obj,
+                move |_| {
+                    obj.imp().pointer_position.set(None);
+
+                    if obj.imp().hovered_bounds.take().is_some() && obj.hover_highlight().is_some()
+                    {
+                        obj.queue_draw();
+                    }
+
+                    if let Some(label) = obj.imp().readout_label.get() {
+                        label.set_visible(false);
+                    }
+
+                    if obj.shows_tracking_line() {
+                        obj.queue_draw();
+                    }
+                }
+            ));
+            obj.add_controller(motion);
+
+            let scroll = gtk::EventControllerScroll::new(gtk::EventControllerScrollFlags::VERTICAL);
+            scroll.connect_scroll(clone!(
+                #[weak]
+                obj,
+                #[upgrade_or]
+                glib::Propagation::Proceed,
+                move |_, _, dy| {
+                    obj.handle_scroll(dy);
+                    glib::Propagation::Stop
+                }
+            ));
+            obj.add_controller(scroll);
+        }
+
+        fn dispose(&self) {
+            let obj = self.obj();
+            obj.stop_kinetic();
+            if let Some(source_id) = self.scroll_settle_source.take() {
+                source_id.remove();
+            }
+            if let Some(source_id) = self.idle_redraw_source.take() {
+                source_id.remove();
+            }
+            obj.disconnect_paintable();
+            obj.disconnect_range_model();
+            obj.disconnect_selection_model();
+            for (source, items_changed_handler, visible_handler) in self.sources.take() {
+                source.model().disconnect(items_changed_handler);
+                source.series().disconnect(visible_handler);
+            }
+            for (style_manager, handler_id) in self.style_manager_handlers.take() {
+                if let Some(style_manager) = style_manager.upgrade() {
+                    style_manager.disconnect(handler_id);
+                }
+            }
+
+            while let Some(child) = obj.first_child() {
+                child.unparent();
+            }
+        }
+
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+
+            SIGNALS.get_or_init(|| {
+                vec![
+                    Signal::builder("data-hovered")
+                        .param_types([f64::static_type(), f64::static_type()])
+                        .build(),
+                    Signal::builder("brushed")
+                        .param_types([Vec::<String>::static_type()])
+                        .build(),
+                ]
+            })
+        }
+
+        fn properties() -> &'static [glib::ParamSpec] {
+            static PROPERTIES: OnceLock<Vec<glib::ParamSpec>> = OnceLock::new();
+
+            PROPERTIES.get_or_init(|| {
+                vec![
+                    glib::ParamSpecObject::builder::<Paintable>("paintable")
+                        .explicit_notify()
+                        .build(),
+                    glib::ParamSpecObject::builder::<RangeModel>("range-model")
+                        .explicit_notify()
+                        .build(),
+                    glib::ParamSpecObject::builder::<PlotSelectionModel>("selection-model")
+                        .explicit_notify()
+                        .build(),
+                    glib::ParamSpecUInt::builder("palette-index")
+                        .explicit_notify()
+                        .build(),
+                    glib::ParamSpecBoolean::builder("shows-readout")
+                        .explicit_notify()
+                        .build(),
+                    glib::ParamSpecBoolean::builder("shows-tracking-line")
+                        .explicit_notify()
+                        .build(),
+                    glib::ParamSpecBoolean::builder("drafts-during-gestures")
+                        .explicit_notify()
+                        .build(),
+                ]
+            })
+        }
+
+        fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+            match pspec.name() {
+                "paintable" => {
+                    let paintable = value.get::<Option<Paintable>>().unwrap();
+                    self.obj().set_paintable(paintable.as_ref());
+                }
+                "range-model" => {
+                    let range_model = value.get::<Option<RangeModel>>().unwrap();
+                    self.obj().set_range_model(&range_model.unwrap_or_default());
+                }
+                "selection-model" => {
+                    let selection_model = value.get::<Option<PlotSelectionModel>>().unwrap();
+                    self.obj()
+                        .set_selection_model(&selection_model.unwrap_or_default());
+                }
+                "palette-index" => {
+                    let palette_index = value.get().unwrap();
+                    self.obj().set_palette_index(palette_index);
+                }
+                "shows-readout" => {
+                    let shows_readout = value.get().unwrap();
+                    self.obj().set_shows_readout(shows_readout);
+                }
+                "shows-tracking-line" => {
+                    let shows_tracking_line = value.get().unwrap();
+                    self.obj().set_shows_tracking_line(shows_tracking_line);
+                }
+                "drafts-during-gestures" => {
+                    let drafts_during_gestures = value.get().unwrap();
+                    self.obj()
+                        .set_drafts_during_gestures(drafts_during_gestures);
+                }
+                _ => unimplemented!(),
+            }
+        }
+
+        fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            let obj = self.obj();
+
+            match pspec.name() {
+                "paintable" => obj.paintable().into(),
+                "range-model" => obj.range_model().into(),
+                "selection-model" => obj.selection_model().into(),
+                "palette-index" => obj.palette_index().into(),
+                "shows-readout" => obj.shows_readout().into(),
+                "shows-tracking-line" => obj.shows_tracking_line().into(),
+                "drafts-during-gestures" => obj.drafts_during_gestures().into(),
+                _ => unimplemented!(),
+            }
+        }
+    }
+
+    impl WidgetImpl for PlotView {
+        fn realize(&self) {
+            self.parent_realize();
+
+            let obj = self.obj();
+            if obj.update_renderer_caps() {
+                obj.redraw();
+            }
+        }
+
+        fn measure(&self, orientation: gtk::Orientation, _for_size: i32) -> (i32, i32, i32, i32) {
+            let natural = self.paintable.borrow().as_ref().map_or(0, |paintable| {
+                if orientation == gtk::Orientation::Horizontal {
+                    paintable.intrinsic_width()
+                } else {
+                    paintable.intrinsic_height()
+                }
+            });
+            (0, natural, -1, -1)
+        }
+
+        fn size_allocate(&self, width: i32, height: i32, baseline: i32) {
+            self.parent_size_allocate(width, height, baseline);
+
+            if let Some(label) = self.readout_label.get() {
+                let (_, natural) = label.preferred_size();
+                let x = (width - natural.width()).max(0);
+                label.allocate(
+                    natural.width().min(width),
+                    natural.height().min(height),
+                    -1,
+                    Some(gsk::Transform::new().translate(&Point::new(x as f32, 0.0))),
+                );
+            }
+
+            // Popovers, such as the one of a `PlotMenu`, must be presented
+            // whenever their parent is allocated.
+            let mut child = self.obj().first_child();
+            while let Some(widget) = child {
+                if let Some(popover) = widget.downcast_ref::<gtk::Popover>() {
+                    popover.present();
+                }
+                child = widget.next_sibling();
+            }
+        }
+
+        fn snapshot(&self, snapshot: &gtk::Snapshot) {
+            let obj = self.obj();
+
+            if let Some(tile_cache) = self.tile_cache.borrow().as_ref() {
+                obj.snapshot_tiles(tile_cache, snapshot);
+            }
+
+            if let Some(paintable) = self.paintable.borrow().as_ref() {
+                paintable.snapshot(snapshot, obj.width() as f64, obj.height() as f64);
+                obj.snapshot_selection(paintable, snapshot);
+                obj.snapshot_hover_highlight(paintable, snapshot);
+            }
+
+            obj.snapshot_brush(snapshot);
+
+            if obj.shows_tracking_line() {
+                obj.snapshot_tracking_line(snapshot);
+            }
+
+            if let Some(label) = self.readout_label.get() {
+                obj.snapshot_child(label, snapshot);
+            }
+        }
+    }
+}
+
+glib::wrapper! {
+    /// An interactive widget that displays a [`Paintable`].
+    ///
+    /// The visible data ranges are held by a [`RangeModel`], which is panned
+    /// by dragging and zoomed by scrolling. Whenever the ranges change, the
+    /// draw function is called to redraw the paintable.
+    ///
+    /// Dragging with <kbd>Ctrl</kbd> held drags the plot to other
+    /// applications as an image, using [`Paintable::as_drag_content`].
+    /// Clicking content drawn within [`PaintableBackend::with_link`]
+    /// activates its link. Content drawn within [`PaintableBackend::with_id`]
+    /// is selected in the [`PlotSelectionModel`] by clicking it, with
+    /// <kbd>Ctrl</kbd> held to add to the selection, or by dragging over it
+    /// with <kbd>Shift</kbd> held if a [`BrushMode`] is set.
+    ///
+    /// When focused, the arrow keys pan, <kbd>+</kbd> and <kbd>-</kbd> zoom,
+    /// and <kbd>Home</kbd> resets the ranges, with the resulting ranges
+    /// announced to assistive technologies.
+    ///
+    /// The parameters of the plot are properties that can be bound to
+    /// controls with [`glib::Binding`], redrawing the view when they change:
+    /// the ranges through the `x-min`, `x-max`, `y-min`, and `y-max`
+    /// properties of the range model, and `palette-index`, `shows-readout`,
+    /// and `shows-tracking-line` of the view. Properties of other objects,
+    /// such as the `visible` property of a [`Series`], can redraw the view
+    /// with [`PlotView::redraw_on_notify`], and data read from a
+    /// [`gio::ListModel`] with [`PlotView::add_source`].
+    ///
+    /// This can be used on GTK UI files using its type name `PlottersGtk4PlotView`.
+    ///
+    /// [`PaintableBackend::with_link`]: crate::PaintableBackend::with_link
+    /// [`PaintableBackend::with_id`]: crate::PaintableBackend::with_id
+    pub struct PlotView(ObjectSubclass<imp::PlotView>)
+        @extends gtk::Widget;
+}
+
+impl PlotView {
+    /// Creates a new plot view that displays `paintable`.
+    pub fn new(paintable: &Paintable) -> Self {
+        glib::Object::builder()
+            .property("paintable", paintable)
+            .build()
+    }
+
+    /// Sets the paintable to display.
+    pub fn set_paintable(&self, paintable: Option<&Paintable>) {
+        if paintable == self.paintable().as_ref() {
+            return;
+        }
+
+        self.disconnect_paintable();
+
+        if let Some(paintable) = paintable {
+            let handlers = vec![
+                paintable.connect_invalidate_contents(clone!(
+                    #[weak(rename_to = obj)]
+                    self,
+                    move |_| {
+                        obj.queue_draw();
+                    }
+                )),
+                paintable.connect_invalidate_size(clone!(
+                    #[weak(rename_to = obj)]
+                    self,
+                    move |_| {
+                        obj.queue_resize();
+                    }
+                )),
+                self.redraw_on_notify(paintable, "scale-factor"),
+            ];
+            self.imp().paintable_handlers.replace(handlers);
+        }
+
+        self.imp().paintable.replace(paintable.cloned());
+        if let Some(paintable) = paintable {
+            self.imp()
+                .scale_factor_handler
+                .replace(Some(paintable.attach_to_widget(self)));
+        }
+        self.update_renderer_caps();
+        self.notify("paintable");
+
+        self.queue_resize();
+        self.redraw();
+    }
+
+    /// Returns the paintable displayed.
+    pub fn paintable(&self) -> Option<Paintable> {
+        self.imp().paintable.borrow().clone()
+    }
+
+    /// Sets the range model that holds the visible data ranges.
+    ///
+    /// Sharing a range model between multiple views synchronizes their
+    /// ranges.
+    pub fn set_range_model(&self, range_model: &RangeModel) {
+        if self.imp().range_model.borrow().as_ref() == Some(range_model) {
+            return;
+        }
+
+        self.disconnect_range_model();
+
+        let handler_id = range_model.connect_changed(clone!(
+            #[weak(rename_to = obj)]
+            self,
+            move |_| {
+                obj.redraw();
+
+                if obj.imp().tile_cache.borrow().is_some() {
+                    obj.queue_draw();
+                }
+            }
+        ));
+        self.imp().range_model_handler.replace(Some(handler_id));
+
+        self.imp().range_model.replace(Some(range_model.clone()));
+        self.notify("range-model");
+
+        if self.imp().home_ranges.borrow().is_none() {
+            self.set_home_ranges(range_model.x_range(), range_model.y_range());
+        }
+
+        self.redraw();
+    }
+
+    /// Returns the range model that holds the visible data ranges.
+    pub fn range_model(&self) -> RangeModel {
+        self.imp().range_model.borrow().clone().unwrap()
+    }
+
+    /// Sets the ranges that [`PlotView::reset_ranges`] resets to.
+    ///
+    /// This defaults to the ranges of the first range model set.
+    pub fn set_home_ranges(&self, x_range: Range<f64>, y_range: Range<f64>) {
+        self.imp().home_ranges.replace(Some((x_range, y_range)));
+    }
+
+    /// Returns the ranges that [`PlotView::reset_ranges`] resets to.
+    pub fn home_ranges(&self) -> (Range<f64>, Range<f64>) {
+        self.imp().home_ranges.borrow().clone().unwrap()
+    }
+
+    /// Resets the ranges of the range model to the home ranges.
+    ///
+    /// This is also done by pressing <kbd>Home</kbd>.
+    pub fn reset_ranges(&self) {
+        let (x_range, y_range) = self.home_ranges();
+        self.range_model().set_ranges(x_range, y_range);
+    }
+
+    /// Returns the state of the view the user can change, to be restored
+    /// with [`PlotView::restore_state`], such as in another session.
+    ///
+    /// The state is an `a{sv}` dictionary, which can be stored in a
+    /// GSettings key of that type, with the version of the state, the
+    /// ranges of the range model, the visibility of the series registered on
+    /// the paintable by name, and the annotations of the paintable.
+    pub fn save_state(&self) -> glib::Variant {
+        let range_model = self.range_model();
+        let state = glib::VariantDict::new(None);
+        state.insert_value("version", &STATE_VERSION.to_variant());
+        state.insert_value(
+            "x-range",
+            &(range_model.x_min(), range_model.x_max()).to_variant(),
+        );
+        state.insert_value(
+            "y-range",
+            &(range_model.y_min(), range_model.y_max()).to_variant(),
+        );
+
+        if let Some(paintable) = self.paintable() {
+            let series_visibility = paintable
+                .series()
+                .iter::<Series>()
+                .filter_map(Result::ok)
+                .map(|series| (series.name(), series.is_visible()))
+                .collect::<HashMap<_, _>>();
+            state.insert_value("series-visibility", &series_visibility.to_variant());
+
+            let annotations = paintable.annotations();
+            let annotations = annotations
+                .ids()
+                .into_iter()
+                .filter_map(|id| annotations.get(id))
+                .collect::<Vec<_>>();
+            state.insert_value("annotations", &annotations.to_variant());
+        }
+
+        state.end()
+    }
+
+    /// Restores the state returned by [`PlotView::save_state`].
+    ///
+    /// Only the parts of the state that are present are restored. The
+    /// visibility is restored for the series already registered on the
+    /// paintable, so this should be called after the first draw. This
+    /// returns an error if the state is not a dictionary, has a part of the
+    /// wrong type, or was saved by a newer version of this crate that it
+    /// cannot be restored by, in which case nothing is restored.
+    pub fn restore_state(&self, state: &glib::Variant) -> Result<(), glib::BoolError> {
+        if !state.is_type(glib::VariantTy::VARDICT) {
+            return Err(glib::bool_error!(
+                "Expected state of type `a{{sv}}`, got `{}`",
+                state.type_()
+            ));
+        }
+
+        let state = glib::VariantDict::new(Some(state));
+        let lookup_err = |err| glib::bool_error!("Invalid state: {}", err);
+        // States saved before the version was added are of the first one.
+        let version = state
+            .lookup::<u32>("version")
+            .map_err(lookup_err)?
+            .unwrap_or(1);
+        if version > STATE_VERSION {
+            return Err(glib::bool_error!("Unsupported state version {}", version));
+        }
+        let x_range = state.lookup::<(f64, f64)>("x-range").map_err(lookup_err)?;
+        let y_range = state.lookup::<(f64, f64)>("y-range").map_err(lookup_err)?;
+        let series_visibility = state
+            .lookup::<HashMap<String, bool>>("series-visibility")
+            .map_err(lookup_err)?;
+        let annotations = match state.lookup_value("annotations", None) {
+            Some(value) => Some(
+                value
+                    .get::<Vec<Annotation>>()
+                    .ok_or_else(|| glib::bool_error!("Invalid state: invalid annotations"))?,
+            ),
+            None => None,
+        };
+
+        if x_range.is_some() || y_range.is_some() {
+            let range_model = self.range_model();
+            let (x_min, x_max) = x_range.unwrap_or((range_model.x_min(), range_model.x_max()));
+            let (y_min, y_max) = y_range.unwrap_or((range_model.y_min(), range_model.y_max()));
+            range_model.set_ranges(x_min..x_max, y_min..y_max);
+        }
+
+        if let Some(paintable) = self.paintable() {
+            if let Some(series_visibility) = series_visibility {
+                for series in paintable.series().iter::<Series>().filter_map(Result::ok) {
+                    if let Some(&is_visible) = series_visibility.get(&series.name()) {
+                        series.set_visible(is_visible);
+                    }
+                }
+            }
+
+            if let Some(annotations) = annotations {
+                let paintable_annotations = paintable.annotations();
+                paintable_annotations.clear();
+                for annotation in annotations {
+                    paintable_annotations.add(annotation);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sets the fraction of the ranges panned per arrow key press.
+    ///
+    /// This defaults to `0.1`.
+    pub fn set_keyboard_pan_step(&self, step: f64) {
+        self.imp().keyboard_pan_step.set(Some(step));
+    }
+
+    /// Returns the fraction of the ranges panned per arrow key press.
+    pub fn keyboard_pan_step(&self) -> f64 {
+        self.imp()
+            .keyboard_pan_step
+            .get()
+            .unwrap_or(DEFAULT_KEYBOARD_PAN_STEP)
+    }
+
+    /// Sets the factor the ranges are zoomed by per <kbd>+</kbd> or
+    /// <kbd>-</kbd> key press.
+    ///
+    /// This defaults to the factor of a scroll step, `1.1`.
+    pub fn set_keyboard_zoom_step(&self, step: f64) {
+        self.imp().keyboard_zoom_step.set(Some(step));
+    }
+
+    /// Returns the factor the ranges are zoomed by per <kbd>+</kbd> or
+    /// <kbd>-</kbd> key press.
+    pub fn keyboard_zoom_step(&self) -> f64 {
+        self.imp().keyboard_zoom_step.get().unwrap_or(ZOOM_STEP)
+    }
+
+    /// Sets the function called to draw on the paintable.
+    ///
+    /// This is called whenever the ranges of the range model change, with
+    /// the ranges to draw available from [`PlotView::range_model`].
+    pub fn set_draw_func(&self, draw_func: impl Fn(&Self, &Paintable) + 'static) {
+        self.imp().draw_func.replace(Some(Rc::new(draw_func)));
+        self.redraw();
+    }
+
+    /// Sets the function that maps a point of the paintable, in pixels, to
+    /// data coordinates, or `None` if the point is outside the plotted area.
+    ///
+    /// This is typically the coordinate transform of the chart, such as the
+    /// function returned by `ChartContext::into_coord_trans` in plotters.
+    /// Without a mapper, the ranges of the range model are assumed to span
+    /// the whole view.
+    pub fn set_coord_mapper(
+        &self,
+        coord_mapper: impl Fn((i32, i32)) -> Option<(f64, f64)> + 'static,
+    ) {
+        self.imp().coord_mapper.replace(Some(Rc::new(coord_mapper)));
+    }
+
+    /// Sets whether a label showing the data coordinates under the pointer
+    /// is shown in the top-right corner.
+    pub fn set_shows_readout(&self, shows_readout: bool) {
+        if shows_readout == self.shows_readout() {
+            return;
+        }
+
+        let imp = self.imp();
+        imp.shows_readout.set(shows_readout);
+        self.notify("shows-readout");
+
+        if !shows_readout {
+            if let Some(label) = imp.readout_label.get() {
+                label.set_visible(false);
+            }
+            return;
+        }
+
+        imp.readout_label.get_or_init(|| {
+            let label = gtk::Label::builder()
+                .visible(false)
+                .margin_top(6)
+                .margin_end(6)
+                .css_classes(["osd", "numeric"])
+                .build();
+            label.set_parent(self);
+            label
+        });
+    }
+
+    /// Returns whether a label showing the data coordinates under the
+    /// pointer is shown.
+    pub fn shows_readout(&self) -> bool {
+        self.imp().shows_readout.get()
+    }
+
+    /// Sets the index of the palette color the draw function should start
+    /// picking series colors from, redrawing the view.
+    ///
+    /// This is a parameter for the draw function, such as to offset the
+    /// colors of plots sharing a legend, and can be bound to a
+    /// [`gtk::SpinButton`] like the ranges of the range model can be bound
+    /// to [`gtk::Scale`]s.
+    pub fn set_palette_index(&self, palette_index: u32) {
+        if palette_index == self.palette_index() {
+            return;
+        }
+
+        self.imp().palette_index.set(palette_index);
+        self.notify("palette-index");
+        self.redraw();
+    }
+
+    /// Returns the index of the palette color the draw function should
+    /// start picking series colors from.
+    pub fn palette_index(&self) -> u32 {
+        self.imp().palette_index.get()
+    }
+
+    /// Sets whether a vertical line is drawn at the pointer while it is
+    /// within the plotted area.
+    ///
+    /// The values of the visible series of the paintable where the line
+    /// intersects them are labeled, as returned by the function set with
+    /// [`PlotView::set_tracking_func`].
+    pub fn set_shows_tracking_line(&self, shows_tracking_line: bool) {
+        if shows_tracking_line == self.shows_tracking_line() {
+            return;
+        }
+
+        self.imp().shows_tracking_line.set(shows_tracking_line);
+        self.notify("shows-tracking-line");
+        self.queue_draw();
+    }
+
+    /// Returns whether a vertical line is drawn at the pointer.
+    pub fn shows_tracking_line(&self) -> bool {
+        self.imp().shows_tracking_line.get()
+    }
+
+    /// Sets whether the paintable is drawn with [`Quality::Draft`] while the
+    /// plot is panned or zoomed, and redrawn with the quality it had before
+    /// once the gesture ends.
+    ///
+    /// This keeps interactions smooth on large datasets, with the quality
+    /// set on the paintable with [`Paintable::set_quality`], which the
+    /// backends created in the draw function start with.
+    pub fn set_drafts_during_gestures(&self, drafts_during_gestures: bool) {
+        if drafts_during_gestures == self.drafts_during_gestures() {
+            return;
+        }
+
+        self.imp()
+            .drafts_during_gestures
+            .set(drafts_during_gestures);
+        self.notify("drafts-during-gestures");
+
+        // Do not leave the paintable drafted when disabled mid-gesture.
+        self.restore_gesture_quality();
+    }
+
+    /// Returns whether the paintable is drawn with [`Quality::Draft`] while
+    /// the plot is panned or zoomed.
+    pub fn drafts_during_gestures(&self) -> bool {
+        self.imp().drafts_during_gestures.get()
+    }
+
+    /// Sets the cache of tiles drawn beneath the paintable, or `None` to
+    /// draw the paintable only.
+    ///
+    /// The tiles are drawn with the ranges of the range model spanning the
+    /// whole view, and follow panning and zooming without calling the draw
+    /// function. The draw function should then only draw what is not in the
+    /// tiles, such as axes and overlays, on a transparent background.
+    pub fn set_tile_cache(&self, tile_cache: Option<&TileCache>) {
+        self.imp().tile_cache.replace(tile_cache.cloned());
+        self.queue_draw();
+    }
+
+    /// Returns the cache of tiles drawn beneath the paintable.
+    pub fn tile_cache(&self) -> Option<TileCache> {
+        self.imp().tile_cache.borrow().clone()
+    }
+
+    /// Sets the function that returns where a series intersects the
+    /// tracking line at the given data x coordinate, or `None` if it has no
+    /// value there.
+    ///
+    /// This is called for each visible series registered with
+    /// [`Paintable::register_series`] whenever the tracking line is drawn.
+    pub fn set_tracking_func(
+        &self,
+        tracking_func: impl Fn(&Series, f64) -> Option<TrackingPoint> + 'static,
+    ) {
+        self.imp()
+            .tracking_func
+            .replace(Some(Rc::new(tracking_func)));
+        self.queue_draw();
+    }
+
+    /// Connects to the `data-hovered` signal, emitted with the data
+    /// coordinates under the pointer whenever it moves within the plotted
+    /// area.
+    pub fn connect_data_hovered<F: Fn(&Self, f64, f64) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "data-hovered",
+            false,
+            closure_local!(|obj: &Self, x: f64, y: f64| f(obj, x, y)),
+        )
+    }
+
+    /// Redraws the view whenever `property_name` of `object` changes.
+    ///
+    /// This is useful for parameters of the draw function held by other
+    /// objects and bound to controls, such as a [`gtk::Switch`] bound to the
+    /// `visible` property of a [`Series`]. The returned handler can be
+    /// disconnected from `object` to stop redrawing.
+    pub fn redraw_on_notify(
+        &self,
+        object: &impl IsA<glib::Object>,
+        property_name: &str,
+    ) -> glib::SignalHandlerId {
+        object.connect_notify_local(
+            Some(property_name),
+            clone!(
+                #[weak(rename_to = obj)]
+                self,
+                move |_, _| {
+                    obj.redraw();
+                }
+            ),
+        )
+    }
+
+    /// Adds `source` to the view, redrawing the view whenever its points
+    /// change or its series is shown or hidden.
+    ///
+    /// Changes to the points of hidden series do not redraw the view, and
+    /// all the changes made until the view is idle, such as points appended
+    /// in a loop, are drawn in a single redraw. The draw function can draw
+    /// the points of the sources from [`PlotView::sources`], or only those
+    /// of [`PlotView::changed_sources`] when only points changed.
+    pub fn add_source(&self, source: &PlotSource) {
+        if self.sources().contains(source) {
+            return;
+        }
+
+        let items_changed_handler = source.model().connect_items_changed(clone!(
+            #[weak(rename_to = obj)]
+            self,
+            move |model, _, _, _| {
+                obj.queue_sources_redraw(model);
+            }
+        ));
+        let visible_handler = source.series().connect_notify_local(
+            Some("visible"),
+            clone!(
+                #[weak(rename_to = obj)]
+                self,
+                move |_, _| {
+                    obj.queue_idle_redraw();
+                }
+            ),
+        );
+        self.imp().sources.borrow_mut().push((
+            source.clone(),
+            items_changed_handler,
+            visible_handler,
+        ));
+
+        self.queue_idle_redraw();
+    }
+
+    /// Removes `source` from the view, so that its changes no longer redraw
+    /// the view.
+    pub fn remove_source(&self, source: &PlotSource) {
+        let removed = {
+            let mut sources = self.imp().sources.borrow_mut();
+            let Some(index) = sources.iter().position(|(s, _, _)| s == source) else {
+                return;
+            };
+            sources.remove(index)
+        };
+
+        let (source, items_changed_handler, visible_handler) = removed;
+        source.model().disconnect(items_changed_handler);
+        source.series().disconnect(visible_handler);
+
+        self.queue_idle_redraw();
+    }
+
+    /// Returns the sources whose points changed since the view was last
+    /// drawn, if only they have to be drawn again, or an empty list if
+    /// everything has to be.
+    ///
+    /// This is for the draw function, which can draw only the series of
+    /// these sources, each within [`PaintableBackend::with_series`], and
+    /// present them with [`PaintableBackend::present_series`], so that the
+    /// axes and the other series are not drawn again, or everything if it
+    /// returns `false`. The sources are only listed if nothing else, such as
+    /// the ranges or the style, changed since the last draw.
+    ///
+    /// [`PaintableBackend::with_series`]: crate::PaintableBackend::with_series
+    /// [`PaintableBackend::present_series`]: crate::PaintableBackend::present_series
+    pub fn changed_sources(&self) -> Vec<PlotSource> {
+        self.imp().changed_sources.borrow().clone()
+    }
+
+    /// Returns the sources added to the view, in the order they were added.
+    pub fn sources(&self) -> Vec<PlotSource> {
+        self.imp()
+            .sources
+            .borrow()
+            .iter()
+            .map(|(source, _, _)| source.clone())
+            .collect()
+    }
+
+    /// Redraws the view whenever the color scheme, theme, or fonts change,
+    /// as notified by `style_manager`.
+    ///
+    /// This can be a [`gtk::Settings`], such as the default settings, or an
+    /// `adw::StyleManager`, whose style properties are watched if it has
+    /// them, so that colors picked by the draw function, such as from an
+    /// [`AdaptivePalette`], follow the app. The view is redrawn once the
+    /// new style is applied, after all the changes notified together.
+    ///
+    /// Both a [`gtk::Settings`] and an `adw::StyleManager` can be bound.
+    /// They usually outlive the view, so the handlers are disconnected when
+    /// the view is disposed.
+    ///
+    /// [`AdaptivePalette`]: crate::palette::AdaptivePalette
+    pub fn bind_to_style_manager(&self, style_manager: &impl IsA<glib::Object>) {
+        let style_manager = style_manager.upcast_ref::<glib::Object>();
+        let handlers = STYLE_PROPERTIES
+            .iter()
+            .copied()
+            .filter(|name| style_manager.find_property(name).is_some())
+            .map(|name| {
+                let handler_id = style_manager.connect_notify_local(
+                    Some(name),
+                    clone!(
+                        #[weak(rename_to = obj)]
+                        self,
+                        move |_, _| {
+                            obj.queue_idle_redraw();
+                        }
+                    ),
+                );
+                (style_manager.downgrade(), handler_id)
+            });
+        self.imp()
+            .style_manager_handlers
+            .borrow_mut()
+            .extend(handlers);
+    }
+
+    /// Sets how the region under the pointer drawn within
+    /// [`PaintableBackend::with_link`] or [`PaintableBackend::with_data`] is
+    /// highlighted, or `None` to not highlight it, which is the default.
+    ///
+    /// [`PaintableBackend::with_link`]: crate::PaintableBackend::with_link
+    /// [`PaintableBackend::with_data`]: crate::PaintableBackend::with_data
+    pub fn set_hover_highlight(&self, highlight: Option<Highlight>) {
+        self.imp().hover_highlight.set(highlight);
+        self.queue_draw();
+    }
+
+    /// Returns how the region under the pointer is highlighted.
+    pub fn hover_highlight(&self) -> Option<Highlight> {
+        self.imp().hover_highlight.get()
+    }
+
+    /// Sets the shape of the area selected by dragging with <kbd>Shift</kbd>
+    /// held, or [`BrushMode::None`] to not select by dragging, which is the
+    /// default.
+    ///
+    /// When the drag ends, the elements drawn within
+    /// [`PaintableBackend::with_id`] whose centers are within the area
+    /// become the selected elements, and `brushed` is emitted with their
+    /// IDs.
+    ///
+    /// [`PaintableBackend::with_id`]: crate::PaintableBackend::with_id
+    pub fn set_brush_mode(&self, brush_mode: BrushMode) {
+        self.imp().brush_mode.set(brush_mode);
+    }
+
+    /// Returns the shape of the area selected by dragging.
+    pub fn brush_mode(&self) -> BrushMode {
+        self.imp().brush_mode.get()
+    }
+
+    /// Sets the selection model that holds the IDs of the selected
+    /// elements, drawn with the selection highlight.
+    ///
+    /// Sharing a selection model between multiple views highlights the
+    /// elements selected in one of them in the others.
+    pub fn set_selection_model(&self, selection_model: &PlotSelectionModel) {
+        if self.imp().selection_model.borrow().as_ref() == Some(selection_model) {
+            return;
+        }
+
+        self.disconnect_selection_model();
+
+        let handler_id = selection_model.connect_items_changed(clone!(
+            #[weak(rename_to = obj)]
+            self,
+            move |_, _, _, _| {
+                obj.queue_draw();
+            }
+        ));
+        self.imp().selection_model_handler.replace(Some(handler_id));
+
+        self.imp()
+            .selection_model
+            .replace(Some(selection_model.clone()));
+        self.notify("selection-model");
+
+        self.queue_draw();
+    }
+
+    /// Returns the selection model that holds the IDs of the selected
+    /// elements.
+    pub fn selection_model(&self) -> PlotSelectionModel {
+        self.imp().selection_model.borrow().clone().unwrap()
+    }
+
+    /// Selects only the elements of `ids` in the selection model.
+    ///
+    /// See [`PlotSelectionModel::set_selected_ids`].
+    pub fn set_selected_ids(&self, ids: &[impl AsRef<str>]) {
+        self.selection_model().set_selected_ids(ids);
+    }
+
+    /// Returns the IDs of the selected elements in the selection model.
+    pub fn selected_ids(&self) -> Vec<String> {
+        self.selection_model().selected_ids()
+    }
+
+    /// Sets how the selected elements are highlighted, or `None` to not
+    /// highlight them.
+    ///
+    /// This defaults to a blue outline.
+    pub fn set_selection_highlight(&self, highlight: Option<Highlight>) {
+        self.imp().selection_highlight.set(highlight);
+        self.queue_draw();
+    }
+
+    /// Returns how the selected elements are highlighted.
+    pub fn selection_highlight(&self) -> Option<Highlight> {
+        self.imp().selection_highlight.get()
+    }
+
+    /// Connects to when elements are selected by brushing, with the IDs of
+    /// the selected elements.
+    ///
+    /// See [`PlotView::set_brush_mode`].
+    pub fn connect_brushed<F: Fn(&Self, &[String]) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "brushed",
+            false,
+            closure_local!(|obj: &Self, ids: Vec<String>| f(obj, &ids)),
+        )
+    }
+
+    /// Returns the topmost region of the paintable at `(x, y)`, in widget
+    /// coordinates, such as the position of a click, drawn within
+    /// [`PaintableBackend::with_link`] or [`PaintableBackend::with_data`].
+    ///
+    /// The bounds of the region are in pixels of the paintable.
+    ///
+    /// [`PaintableBackend::with_link`]: crate::PaintableBackend::with_link
+    /// [`PaintableBackend::with_data`]: crate::PaintableBackend::with_data
+    pub fn pick(&self, x: f64, y: f64) -> Option<HitRegion> {
+        let paintable = self.paintable()?;
+        let (px, py) = self.to_paintable(&paintable, (x, y))?;
+        paintable.hit_region_at(px as f32, py as f32)
+    }
+
+    /// Calls the draw function to redraw the paintable.
+    pub fn redraw(&self) {
+        // The points of the changed sources are drawn with everything else.
+        self.imp().pending_changed_sources.take();
+        self.redraw_sources(Vec::new());
+    }
+
+    /// Calls the draw function with `changed_sources` returned by
+    /// [`PlotView::changed_sources`], or everything to be drawn if empty.
+    fn redraw_sources(&self, changed_sources: Vec<PlotSource>) {
+        let imp = self.imp();
+        if imp.quality_before_gesture.get().is_some() {
+            imp.is_draft_drawn.set(true);
+        }
+
+        let draw_func = imp.draw_func.borrow().clone();
+
+        if let (Some(draw_func), Some(paintable)) = (draw_func, self.paintable()) {
+            imp.changed_sources.replace(changed_sources);
+            draw_func(self, &paintable);
+            imp.changed_sources.take();
+        }
+    }
+
+    /// Sets the renderer capabilities of the paintable to those of the
+    /// renderer of the window, returning whether they changed.
+    fn update_renderer_caps(&self) -> bool {
+        let (Some(paintable), Some(renderer_caps)) =
+            (self.paintable(), RendererCaps::for_widget(self))
+        else {
+            return false;
+        };
+
+        if paintable.renderer_caps() == renderer_caps {
+            return false;
+        }
+
+        paintable.set_renderer_caps(renderer_caps);
+        true
+    }
+
+    /// Redraws the view when idle, once for all the changes made until then,
+    /// such as style changes notified together or points added in a loop.
+    fn queue_idle_redraw(&self) {
+        self.imp().is_full_redraw_queued.set(true);
+        self.queue_idle_draw();
+    }
+
+    /// Redraws the visible series of the sources of `model` when idle,
+    /// along with the other sources changed until then.
+    fn queue_sources_redraw(&self, model: &gio::ListModel) {
+        let imp = self.imp();
+
+        let changed_sources = imp
+            .sources
+            .borrow()
+            .iter()
+            .map(|(source, _, _)| source)
+            .filter(|source| source.model() == model && source.series().is_visible())
+            .cloned()
+            .collect::<Vec<_>>();
+        if changed_sources.is_empty() {
+            return;
+        }
+
+        let mut pending_changed_sources = imp.pending_changed_sources.borrow_mut();
+        for source in changed_sources {
+            if !pending_changed_sources.contains(&source) {
+                pending_changed_sources.push(source);
+            }
+        }
+        drop(pending_changed_sources);
+
+        self.queue_idle_draw();
+    }
+
+    fn queue_idle_draw(&self) {
+        let imp = self.imp();
+
+        if imp.idle_redraw_source.borrow().is_some() {
+            return;
+        }
+
+        let source_id = glib::idle_add_local_once(clone!(
+            #[weak(rename_to = obj)]
+            self,
+            move || {
+                let imp = obj.imp();
+                imp.idle_redraw_source.replace(None);
+
+                let changed_sources = imp.pending_changed_sources.take();
+                if imp.is_full_redraw_queued.take() {
+                    obj.redraw();
+                } else if !changed_sources.is_empty() {
+                    obj.redraw_sources(changed_sources);
+                }
+            }
+        ));
+        imp.idle_redraw_source.replace(Some(source_id));
+    }
+
+    /// Converts a point in widget coordinates to data coordinates, assuming
+    /// the ranges span the whole widget.
+    fn to_data(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        let range_model = self.range_model();
+        let x_span = range_model.x_max() - range_model.x_min();
+        let y_span = range_model.y_max() - range_model.y_min();
+        (
+            range_model.x_min() + x / self.width() as f64 * x_span,
+            range_model.y_max() - y / self.height() as f64 * y_span,
+        )
+    }
+
+    fn handle_drag_update(&self, offset_x: f64, offset_y: f64) {
+        let Some((x_range, y_range)) = self.imp().drag_start_ranges.borrow().clone() else {
+            return;
+        };
+
+        let (width, height) = (self.width() as f64, self.height() as f64);
+        if width <= 0.0 || height <= 0.0 {
+            return;
+        }
+
+        let dx = -offset_x / width * (x_range.end - x_range.start);
+        let dy = offset_y / height * (y_range.end - y_range.start);
+        self.range_model().set_ranges(
+            x_range.start + dx..x_range.end + dx,
+            y_range.start + dy..y_range.end + dy,
+        );
+    }
+
+    /// Updates the velocity of the drag from the offset at `time`, in
+    /// milliseconds.
+    fn track_drag_velocity(&self, time: u32, offset_x: f64, offset_y: f64) {
+        let imp = self.imp();
+
+        if let Some((prev_time, prev_x, prev_y)) = imp.drag_sample.get() {
+            let dt = time.wrapping_sub(prev_time) as f64 / 1000.0;
+            if dt > 0.0 {
+                imp.drag_velocity
+                    .set(((offset_x - prev_x) / dt, (offset_y - prev_y) / dt));
+            }
+        }
+
+        imp.drag_sample.set(Some((time, offset_x, offset_y)));
+    }
+
+    /// Keeps panning with the velocity of the released drag, slowing down
+    /// until it stops.
+    fn start_kinetic(&self) {
+        let (vx, vy) = self.imp().drag_velocity.get();
+        if vx.hypot(vy) < KINETIC_MIN_SPEED {
+            return;
+        }
+
+        let last_frame_time = Cell::new(None::<i64>);
+        let tick_id = self.add_tick_callback(move |obj, frame_clock| {
+            let frame_time = frame_clock.frame_time();
+            let dt = last_frame_time
+                .replace(Some(frame_time))
+                .map_or(0.0, |prev| (frame_time - prev) as f64 / 1_000_000.0);
+
+            let imp = obj.imp();
+            let (vx, vy) = imp.drag_velocity.get();
+
+            let (width, height) = (obj.width() as f64, obj.height() as f64);
+            if width > 0.0 && height > 0.0 {
+                let range_model = obj.range_model();
+                let x_span = range_model.x_max() - range_model.x_min();
+                let y_span = range_model.y_max() - range_model.y_min();
+                range_model.pan(-vx * dt / width * x_span, vy * dt / height * y_span);
+            }
+
+            let decay = (-KINETIC_FRICTION * dt).exp();
+            let (vx, vy) = (vx * decay, vy * decay);
+            imp.drag_velocity.set((vx, vy));
+
+            if vx.hypot(vy) < KINETIC_MIN_SPEED {
+                imp.kinetic_tick.replace(None);
+                obj.update_gesture_quality();
+                glib::ControlFlow::Break
+            } else {
+                glib::ControlFlow::Continue
+            }
+        });
+        self.imp().kinetic_tick.replace(Some(tick_id));
+    }
+
+    fn stop_kinetic(&self) {
+        if let Some(tick_id) = self.imp().kinetic_tick.take() {
+            tick_id.remove();
+        }
+    }
+
+    fn handle_zoom_scale_changed(&self, scale: f64) {
+        let Some((x_range, y_range, (cx, cy))) = self.imp().zoom_start.borrow().clone() else {
+            return;
+        };
+
+        if scale <= 0.0 {
+            return;
+        }
+
+        // Zoom relative to the ranges at the start of the gesture, as the
+        // scale is relative to the initial distance of the touch points.
+        let scale_range = |Range { start, end }: Range<f64>, center: f64| {
+            center - (center - start) / scale..center + (end - center) / scale
+        };
+        self.range_model()
+            .set_ranges(scale_range(x_range, cx), scale_range(y_range, cy));
+    }
+
+    /// Converts a point in widget coordinates to pixels of the paintable.
+    fn to_paintable(&self, paintable: &Paintable, (x, y): (f64, f64)) -> Option<(f64, f64)> {
+        let (width, height) = (self.width() as f64, self.height() as f64);
+        if width <= 0.0 || height <= 0.0 {
+            return None;
+        }
+
+        // The paintable is scaled to the size of the view.
+        Some((
+            x / width * paintable.width() as f64,
+            y / height * paintable.height() as f64,
+        ))
+    }
+
+    /// Returns the data coordinates of a point in widget coordinates.
+    fn pointer_data(&self, (x, y): (f64, f64)) -> Option<(f64, f64)> {
+        let coord_mapper = self.imp().coord_mapper.borrow().clone();
+        let Some(coord_mapper) = coord_mapper else {
+            return Some(self.to_data((x, y)));
+        };
+
+        let paintable = self.paintable()?;
+        let (px, py) = self.to_paintable(&paintable, (x, y))?;
+        coord_mapper((px.round() as i32, py.round() as i32))
+    }
+
+    /// Selects only the clicked element of `id`, or toggles whether it is
+    /// selected if <kbd>Ctrl</kbd> is held.
+    fn select_clicked(&self, id: &str, state: gdk::ModifierType) {
+        let selection_model = self.selection_model();
+        if !state.contains(gdk::ModifierType::CONTROL_MASK) {
+            selection_model.set_selected_ids(&[id]);
+        } else if selection_model.is_selected(id) {
+            selection_model.unselect(id);
+        } else {
+            selection_model.select(id);
+        }
+    }
+
+    fn activate_link(&self, link: &Link) {
+        match link {
+            Link::Uri(uri) => {
+                gtk::UriLauncher::new(uri).launch(
+                    self.root().and_downcast_ref::<gtk::Window>(),
+                    gio::Cancellable::NONE,
+                    |res| {
+                        if let Err(err) = res {
+                            glib::g_warning!("plotters-gtk4", "Failed to open link: {:?}", err);
+                        }
+                    },
+                );
+            }
+            Link::Action(detailed_name) => {
+                let (name, target) = match gio::Action::parse_detailed_name(detailed_name) {
+                    Ok(parsed) => parsed,
+                    Err(err) => {
+                        glib::g_warning!("plotters-gtk4", "Invalid link action: {:?}", err);
+                        return;
+                    }
+                };
+                if let Err(err) = self.activate_action(&name, target.as_ref()) {
+                    glib::g_warning!("plotters-gtk4", "Failed to activate link: {:?}", err);
+                }
+            }
+        }
+    }
+
+    fn handle_motion(&self, x: f64, y: f64) {
+        let imp = self.imp();
+        imp.pointer_position.set(Some((x, y)));
+
+        let region = self.pick(x, y);
+
+        // Only the cursor set here is replaced, so that the cursor set on
+        // the view is kept elsewhere.
+        let is_over_link = region.as_ref().is_some_and(|region| region.link.is_some());
+        let is_link_cursor_set = imp.cursor_before_link.borrow().is_some();
+        if is_over_link && !is_link_cursor_set {
+            imp.cursor_before_link.replace(Some(self.cursor()));
+            self.set_cursor_from_name(Some("pointer"));
+        } else if !is_over_link && is_link_cursor_set {
+            let cursor = imp.cursor_before_link.take().flatten();
+            self.set_cursor(cursor.as_ref());
+        }
+
+        let hovered_bounds = region.map(|region| region.bounds);
+        if imp.hovered_bounds.replace(hovered_bounds) != hovered_bounds
+            && self.hover_highlight().is_some()
+        {
+            self.queue_draw();
+        }
+
+        if self.shows_tracking_line() {
+            self.queue_draw();
+        }
+
+        let data = self.pointer_data((x, y));
+
+        if let Some((data_x, data_y)) = data {
+            self.emit_by_name::<()>("data-hovered", &[&data_x, &data_y]);
+        }
+
+        if let Some(label) = imp.readout_label.get() {
+            match data {
+                Some((data_x, data_y)) if self.shows_readout() => {
+                    label.set_label(&format!("x: {:.4}, y: {:.4}", data_x, data_y));
+                    label.set_visible(true);
+                }
+                _ => label.set_visible(false),
+            }
+        }
+    }
+
+    fn update_brush(&self, offset_x: f64, offset_y: f64) {
+        let mut brush_points = self.imp().brush_points.borrow_mut();
+        let Some(points) = brush_points.as_mut() else {
+            return;
+        };
+
+        let (start_x, start_y) = points[0];
+        let point = (start_x + offset_x, start_y + offset_y);
+        if self.brush_mode() == BrushMode::Lasso {
+            points.push(point);
+        } else {
+            points.truncate(1);
+            points.push(point);
+        }
+        drop(brush_points);
+
+        self.queue_draw();
+    }
+
+    fn finish_brush(&self) {
+        let Some(points) = self.imp().brush_points.take() else {
+            return;
+        };
+
+        let ids = self.brushed_ids(&points);
+        self.set_selected_ids(&ids);
+        self.emit_by_name::<()>("brushed", &[&ids]);
+    }
+
+    /// Returns the IDs of the elements whose centers are within the brush of
+    /// `points`, in widget coordinates.
+    fn brushed_ids(&self, points: &[(f64, f64)]) -> Vec<String> {
+        let Some(paintable) = self.paintable() else {
+            return Vec::new();
+        };
+        let Some(polygon) = self
+            .brush_polygon(points)
+            .into_iter()
+            .map(|point| self.to_paintable(&paintable, point))
+            .collect::<Option<Vec<_>>>()
+        else {
+            return Vec::new();
+        };
+
+        let mut ids = Vec::<String>::new();
+        let mut id_set = HashSet::new();
+        for region in paintable.hit_regions() {
+            let Some(id) = region.id else {
+                continue;
+            };
+
+            let center = region.bounds.center();
+            if polygon_contains(&polygon, (center.x() as f64, center.y() as f64))
+                && id_set.insert(id.clone())
+            {
+                ids.push(id);
+            }
+        }
+        ids
+    }
+
+    /// Returns the corners of the brush of `points`.
+    fn brush_polygon(&self, points: &[(f64, f64)]) -> Vec<(f64, f64)> {
+        match (self.brush_mode(), points) {
+            (BrushMode::Lasso, _) => points.to_vec(),
+            (_, &[(x0, y0), .., (x1, y1)]) => vec![(x0, y0), (x1, y0), (x1, y1), (x0, y1)],
+            _ => Vec::new(),
+        }
+    }
+
+    fn snapshot_brush(&self, snapshot: &gtk::Snapshot) {
+        let brush_points = self.imp().brush_points.borrow();
+        let Some(points) = brush_points.as_ref() else {
+            return;
+        };
+        let polygon = self.brush_polygon(points);
+        let Some(((x0, y0), rest)) = polygon.split_first() else {
+            return;
+        };
+
+        let path_builder = gsk::PathBuilder::new();
+        path_builder.move_to(*x0 as f32, *y0 as f32);
+        for (x, y) in rest {
+            path_builder.line_to(*x as f32, *y as f32);
+        }
+        path_builder.close();
+        let path = path_builder.to_path();
+
+        let color = self.color();
+        let mut fill_color = color;
+        fill_color.set_alpha(color.alpha() * 0.15);
+        let mut stroke_color = color;
+        stroke_color.set_alpha(color.alpha() * 0.6);
+        common::append_shape(
+            snapshot,
+            &path,
+            gsk::FillRule::EvenOdd,
+            &gsk::Stroke::new(1.0),
+            &fill_color,
+            &stroke_color,
+            RendererCaps::for_widget(self).unwrap_or(RendererCaps::ALL),
+        );
+    }
+
+    /// Draws the selected elements again with the selection highlight.
+    fn snapshot_selection(&self, paintable: &Paintable, snapshot: &gtk::Snapshot) {
+        let Some(highlight) = self.selection_highlight() else {
+            return;
+        };
+        let selection_model = self.selection_model();
+        if selection_model.n_items() == 0 {
+            return;
+        }
+
+        // The paintable is scaled to the size of the view.
+        snapshot.save();
+        snapshot.scale(
+            self.width() as f32 / paintable.width() as f32,
+            self.height() as f32 / paintable.height() as f32,
+        );
+        for region in paintable.hit_regions() {
+            if region
+                .id
+                .as_ref()
+                .is_some_and(|id| selection_model.is_selected(id))
+            {
+                highlight.append_to(snapshot, &region, paintable.renderer_caps());
+            }
+        }
+        snapshot.restore();
+    }
+
+    /// Draws the hit region under the pointer again with the hover
+    /// highlight.
+    fn snapshot_hover_highlight(&self, paintable: &Paintable, snapshot: &gtk::Snapshot) {
+        let Some(highlight) = self.hover_highlight() else {
+            return;
+        };
+        let Some((x, y)) = self.imp().pointer_position.get() else {
+            return;
+        };
+        let Some(region) = self.pick(x, y) else {
+            return;
+        };
+
+        // The paintable is scaled to the size of the view.
+        snapshot.save();
+        snapshot.scale(
+            self.width() as f32 / paintable.width() as f32,
+            self.height() as f32 / paintable.height() as f32,
+        );
+        highlight.append_to(snapshot, &region, paintable.renderer_caps());
+        snapshot.restore();
+    }
+
+    fn snapshot_tiles(&self, tile_cache: &TileCache, snapshot: &gtk::Snapshot) {
+        let range_model = self.range_model();
+        let renderer = self.native().and_then(|native| native.renderer());
+        let is_complete = tile_cache.snapshot(
+            snapshot,
+            range_model.x_range(),
+            range_model.y_range(),
+            (self.width() as f64, self.height() as f64),
+            self.scale_factor() as f64,
+            renderer.as_ref(),
+        );
+
+        // Render the remaining tiles on the next frames.
+        if !is_complete {
+            glib::idle_add_local_once(clone!(
+                #[weak(rename_to = obj)]
+                self,
+                move || {
+                    obj.queue_draw();
+                }
+            ));
+        }
+    }
+
+    fn snapshot_tracking_line(&self, snapshot: &gtk::Snapshot) {
+        let Some((x, y)) = self.imp().pointer_position.get() else {
+            return;
+        };
+        let Some((data_x, _)) = self.pointer_data((x, y)) else {
+            return;
+        };
+
+        let (width, height) = (self.width() as f32, self.height() as f32);
+        let x = x.round() as f32 + 0.5;
+
+        let mut line_color = self.color();
+        line_color.set_alpha(line_color.alpha() * 0.5);
+        snapshot.append_color(&line_color, &Rect::new(x - 0.5, 0.0, 1.0, height));
+
+        let tracking_func = self.imp().tracking_func.borrow().clone();
+        let (Some(tracking_func), Some(paintable)) = (tracking_func, self.paintable()) else {
+            return;
+        };
+
+        let layout = self.create_pango_layout(None);
+        for series in paintable
+            .series()
+            .iter::<Series>()
+            .filter_map(Result::ok)
+            .filter(|series| series.is_visible())
+        {
+            let Some(point) = tracking_func(&series, data_x) else {
+                continue;
+            };
+
+            // The paintable is scaled to the size of the view.
+            let y = point.y as f32 / paintable.height() as f32 * height;
+            let color = series.color();
+
+            let path_builder = gsk::PathBuilder::new();
+            path_builder.add_circle(&Point::new(x, y), TRACKING_DOT_RADIUS);
+            snapshot.append_fill(&path_builder.to_path(), gsk::FillRule::Winding, &color);
+
+            layout.set_text(&format!("{:.4}", point.value));
+            let (_, logical) = layout.pixel_extents();
+
+            // Keep the label within the view, flipping it to the left of the
+            // line near the right edge.
+            let mut label_x = x + TRACKING_LABEL_SPACING;
+            if label_x + logical.width() as f32 > width {
+                label_x = x - TRACKING_LABEL_SPACING - logical.width() as f32;
+            }
+            let label_y = y - logical.height() as f32 / 2.0;
+
+            snapshot.save();
+            snapshot.translate(&Point::new(label_x, label_y));
+            snapshot.append_layout(&layout, &color);
+            snapshot.restore();
+        }
+    }
+
+    fn handle_key_pressed(&self, keyval: gdk::Key, state: gdk::ModifierType) -> glib::Propagation {
+        // Leave shortcuts with modifiers, such as for moving the focus, to
+        // the rest of the app.
+        if state.intersects(gdk::ModifierType::CONTROL_MASK | gdk::ModifierType::ALT_MASK) {
+            return glib::Propagation::Proceed;
+        }
+
+        let range_model = self.range_model();
+        let pan_step = self.keyboard_pan_step();
+        let x_step = (range_model.x_max() - range_model.x_min()) * pan_step;
+        let y_step = (range_model.y_max() - range_model.y_min()) * pan_step;
+        let center = (
+            (range_model.x_min() + range_model.x_max()) / 2.0,
+            (range_model.y_min() + range_model.y_max()) / 2.0,
+        );
+
+        match keyval {
+            gdk::Key::Left | gdk::Key::KP_Left => range_model.pan(-x_step, 0.0),
+            gdk::Key::Right | gdk::Key::KP_Right => range_model.pan(x_step, 0.0),
+            gdk::Key::Up | gdk::Key::KP_Up => range_model.pan(0.0, y_step),
+            gdk::Key::Down | gdk::Key::KP_Down => range_model.pan(0.0, -y_step),
+            gdk::Key::plus | gdk::Key::equal | gdk::Key::KP_Add => {
+                range_model.zoom(self.keyboard_zoom_step(), center)
+            }
+            gdk::Key::minus | gdk::Key::KP_Subtract => {
+                range_model.zoom(self.keyboard_zoom_step().recip(), center)
+            }
+            gdk::Key::Home | gdk::Key::KP_Home => self.reset_ranges(),
+            _ => return glib::Propagation::Proceed,
+        }
+
+        self.announce_ranges();
+
+        glib::Propagation::Stop
+    }
+
+    /// Announces the current ranges to assistive technologies.
+    fn announce_ranges(&self) {
+        let range_model = self.range_model();
+        let description = format!(
+            "X from {:.4} to {:.4}, Y from {:.4} to {:.4}",
+            range_model.x_min(),
+            range_model.x_max(),
+            range_model.y_min(),
+            range_model.y_max()
+        );
+
+        self.update_property(&[gtk::accessible::Property::Description(&description)]);
+        self.announce(&description, gtk::AccessibleAnnouncementPriority::Medium);
+    }
+
+    fn handle_scroll(&self, dy: f64) {
+        if self.drafts_during_gestures() {
+            let imp = self.imp();
+            if let Some(source_id) = imp.scroll_settle_source.take() {
+                source_id.remove();
+            }
+            let source_id = glib::timeout_add_local_once(
+                SCROLL_SETTLE_DELAY,
+                clone!(
+                    #[weak(rename_to = obj)]
+                    self,
+                    move || {
+                        obj.imp().scroll_settle_source.replace(None);
+                        obj.update_gesture_quality();
+                    }
+                ),
+            );
+            imp.scroll_settle_source.replace(Some(source_id));
+            self.update_gesture_quality();
+        }
+
+        let center = self
+            .imp()
+            .pointer_position
+            .get()
+            .unwrap_or((self.width() as f64 / 2.0, self.height() as f64 / 2.0));
+        self.range_model()
+            .zoom(ZOOM_STEP.powf(-dy), self.to_data(center));
+    }
+
+    /// Sets the quality of the paintable to draft while a gesture is active,
+    /// and back to the quality it had before, redrawing, once all are done.
+    fn update_gesture_quality(&self) {
+        if !self.drafts_during_gestures() {
+            return;
+        }
+
+        let Some(paintable) = self.paintable() else {
+            return;
+        };
+
+        let imp = self.imp();
+        let is_interacting = imp.drag_start_ranges.borrow().is_some()
+            || imp.zoom_start.borrow().is_some()
+            || imp.kinetic_tick.borrow().is_some()
+            || imp.scroll_settle_source.borrow().is_some();
+
+        if !is_interacting {
+            self.restore_gesture_quality();
+        } else if imp.quality_before_gesture.get().is_none() {
+            // The ranges changed by the gesture trigger the redraw.
+            imp.quality_before_gesture.set(Some(paintable.quality()));
+            paintable.set_quality(Quality::Draft);
+        }
+    }
+
+    /// Restores the quality the paintable had before a gesture drafted it,
+    /// redrawing it if it was drawn as a draft.
+    ///
+    /// Gestures that did not change the ranges, such as clicks, are not
+    /// redrawn, as the paintable still shows what was drawn before.
+    fn restore_gesture_quality(&self) {
+        let imp = self.imp();
+
+        let Some(quality) = imp.quality_before_gesture.take() else {
+            return;
+        };
+
+        if let Some(paintable) = self.paintable() {
+            paintable.set_quality(quality);
+        }
+
+        if imp.is_draft_drawn.replace(false) {
+            self.redraw();
+        }
+    }
+
+    fn disconnect_paintable(&self) {
+        let handlers = self.imp().paintable_handlers.take();
+
+        if let Some(handler_id) = self.imp().scale_factor_handler.take() {
+            self.disconnect(handler_id);
+        }
+
+        if let Some(paintable) = self.imp().paintable.borrow().as_ref() {
+            for handler_id in handlers {
+                paintable.disconnect(handler_id);
+            }
+        }
+    }
+
+    fn disconnect_range_model(&self) {
+        let handler_id = self.imp().range_model_handler.take();
+
+        if let (Some(range_model), Some(handler_id)) =
+            (self.imp().range_model.borrow().as_ref(), handler_id)
+        {
+            range_model.disconnect(handler_id);
+        }
+    }
+
+    fn disconnect_selection_model(&self) {
+        let handler_id = self.imp().selection_model_handler.take();
+
+        if let (Some(selection_model), Some(handler_id)) =
+            (self.imp().selection_model.borrow().as_ref(), handler_id)
+        {
+            selection_model.disconnect(handler_id);
+        }
+    }
+}
+
+/// Returns whether `point` is within `polygon`, by the even-odd rule.
+fn polygon_contains(polygon: &[(f64, f64)], (x, y): (f64, f64)) -> bool {
+    let mut is_inside = false;
+    let mut prev = match polygon.last() {
+        Some(&last) => last,
+        None => return false,
+    };
+    for &(x1, y1) in polygon {
+        let (x0, y0) = prev;
+        if (y0 > y) != (y1 > y) && x < x0 + (y - y0) / (y1 - y0) * (x1 - x0) {
+            is_inside = !is_inside;
+        }
+        prev = (x1, y1);
+    }
+    is_inside
+}
+
+impl Default for PlotView {
+    fn default() -> Self {
+        glib::Object::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use plotters::style::{BLUE, RED};
+
+    use super::*;
+    use crate::{colormaps::Colormap, headless, SeriesMarker};
+
+    fn view() -> PlotView {
+        let paintable = Paintable::new((200, 100)).unwrap();
+        paintable.register_series("temperature", &RED, SeriesMarker::Line);
+        paintable.register_series("humidity", &BLUE, SeriesMarker::Circle);
+        PlotView::new(&paintable)
+    }
+
+    fn series_visibility(view: &PlotView) -> Vec<(String, bool)> {
+        view.paintable()
+            .unwrap()
+            .series()
+            .iter::<Series>()
+            .map(|series| {
+                let series = series.unwrap();
+                (series.name(), series.is_visible())
+            })
+            .collect()
+    }
+
+    fn annotations(view: &PlotView) -> Vec<Annotation> {
+        let annotations = view.paintable().unwrap().annotations();
+        annotations
+            .ids()
+            .into_iter()
+            .filter_map(|id| annotations.get(id))
+            .collect()
+    }
+
+    #[test]
+    fn state_round_trips() {
+        headless::test_synced(|| {
+            let view = view();
+            view.range_model().set_ranges(-1.5..2.5, 10.0..20.0);
+            view.paintable()
+                .unwrap()
+                .series()
+                .item(1)
+                .and_downcast::<Series>()
+                .unwrap()
+                .set_visible(false);
+            let paintable_annotations = view.paintable().unwrap().annotations();
+            paintable_annotations.add(Annotation::HorizontalLine {
+                y: 50.0,
+                color: gdk::RGBA::RED,
+                width: 2.0,
+            });
+            paintable_annotations.add(Annotation::Colorbar {
+                upper_left: (10.0, 10.0),
+                bottom_right: (20.0, 90.0),
+                colormap: Colormap::Viridis,
+            });
+
+            let state = view.save_state();
+            assert!(state.is_type(glib::VariantTy::VARDICT));
+
+            let restored = self::view();
+            restored.restore_state(&state).unwrap();
+
+            let range_model = restored.range_model();
+            assert_eq!(range_model.x_range(), -1.5..2.5);
+            assert_eq!(range_model.y_range(), 10.0..20.0);
+            assert_eq!(series_visibility(&restored), series_visibility(&view));
+            assert_eq!(annotations(&restored), annotations(&view));
+
+            // Restoring again replaces the annotations rather than adding
+            // to them.
+            restored.restore_state(&state).unwrap();
+            assert_eq!(annotations(&restored).len(), 2);
+        });
+    }
+
+    #[test]
+    fn restore_state_restores_only_present_parts() {
+        headless::test_synced(|| {
+            let view = view();
+            view.range_model().set_ranges(0.0..1.0, 2.0..3.0);
+
+            let state = glib::VariantDict::new(None);
+            state.insert_value("x-range", &(5.0, 6.0).to_variant());
+            view.restore_state(&state.end()).unwrap();
+
+            assert_eq!(view.range_model().x_range(), 5.0..6.0);
+            assert_eq!(view.range_model().y_range(), 2.0..3.0);
+            assert!(series_visibility(&view)
+                .iter()
+                .all(|(_, is_visible)| *is_visible));
+        });
+    }
+
+    #[test]
+    fn restore_state_rejects_invalid_states() {
+        headless::test_synced(|| {
+            let view = view();
+            view.range_model().set_ranges(0.0..1.0, 2.0..3.0);
+
+            assert!(view.restore_state(&(1.0, 2.0).to_variant()).is_err());
+
+            let wrong_type = glib::VariantDict::new(None);
+            wrong_type.insert_value("x-range", &(5.0, 6.0).to_variant());
+            wrong_type.insert_value("y-range", &"7..8".to_variant());
+            assert!(view.restore_state(&wrong_type.end()).is_err());
+
+            let newer = glib::VariantDict::new(Some(&view.save_state()));
+            newer.insert_value("version", &(STATE_VERSION + 1).to_variant());
+            newer.insert_value("x-range", &(5.0, 6.0).to_variant());
+            assert!(view.restore_state(&newer.end()).is_err());
+
+            // Nothing is restored from invalid states.
+            assert_eq!(view.range_model().x_range(), 0.0..1.0);
+        });
+    }
+}
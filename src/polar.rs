@@ -0,0 +1,131 @@
+//! Helpers for drawing polar plots and radar charts with true arcs.
+//!
+//! Angles are in radians, starting from the positive x axis and increasing
+//! counter-clockwise, as in the usual mathematical convention.
+
+use std::{
+    f64::consts::{PI, TAU},
+    ops::Range,
+};
+
+use gtk::{graphene::Point, gsk, prelude::*};
+use plotters_backend::{BackendCoord, BackendStyle};
+
+use crate::{common::BackendColorExt, GtkBackend};
+
+/// Draws `n_rings` evenly-spaced concentric circles up to `radius`, and
+/// `n_spokes` evenly-spaced radial lines from `center`.
+pub fn draw_polar_grid<B: GtkBackend, S: BackendStyle>(
+    backend: &mut B,
+    center: BackendCoord,
+    radius: f64,
+    n_rings: usize,
+    n_spokes: usize,
+    style: &S,
+) {
+    let path_builder = gsk::PathBuilder::new();
+
+    for ring in 1..=n_rings {
+        let r = radius * ring as f64 / n_rings as f64;
+        path_builder.add_circle(&Point::new(center.0 as f32, center.1 as f32), r as f32);
+    }
+
+    for spoke in 0..n_spokes {
+        let angle = TAU * spoke as f64 / n_spokes as f64;
+        let (x, y) = polar_to_point(center, radius, angle);
+        path_builder.move_to(center.0 as f32, center.1 as f32);
+        path_builder.line_to(x, y);
+    }
+
+    stroke(backend, &path_builder.to_path(), style);
+}
+
+/// Draws a line at `angle` from `radii.start` to `radii.end` away from
+/// `center`.
+pub fn draw_radial_line<B: GtkBackend, S: BackendStyle>(
+    backend: &mut B,
+    center: BackendCoord,
+    angle: f64,
+    radii: Range<f64>,
+    style: &S,
+) {
+    let path_builder = gsk::PathBuilder::new();
+
+    let (x, y) = polar_to_point(center, radii.start, angle);
+    path_builder.move_to(x, y);
+    let (x, y) = polar_to_point(center, radii.end, angle);
+    path_builder.line_to(x, y);
+
+    stroke(backend, &path_builder.to_path(), style);
+}
+
+/// Fills the region between `radii.start` and `radii.end` away from
+/// `center`, from `angles.start` to `angles.end`.
+///
+/// An inner radius of zero fills a circular sector, and an angle span of a
+/// full turn or more fills an annulus.
+pub fn fill_annulus_sector<B: GtkBackend, S: BackendStyle>(
+    backend: &mut B,
+    center: BackendCoord,
+    radii: Range<f64>,
+    angles: Range<f64>,
+    style: &S,
+) {
+    let path_builder = gsk::PathBuilder::new();
+
+    let end_angle = angles.end.min(angles.start + TAU);
+
+    let (x, y) = polar_to_point(center, radii.end, angles.start);
+    path_builder.move_to(x, y);
+    arc_to(&path_builder, center, radii.end, angles.start, end_angle);
+
+    if radii.start > 0.0 {
+        let (x, y) = polar_to_point(center, radii.start, end_angle);
+        path_builder.line_to(x, y);
+        arc_to(&path_builder, center, radii.start, end_angle, angles.start);
+    } else {
+        path_builder.line_to(center.0 as f32, center.1 as f32);
+    }
+
+    path_builder.close();
+
+    backend.gtk_snapshot().append_fill(
+        &path_builder.to_path(),
+        gsk::FillRule::EvenOdd,
+        &style.color().to_rgba(),
+    );
+}
+
+/// Adds an arc from the current point, which must be at `from` on the
+/// circle, to `to`.
+pub(crate) fn arc_to(
+    path_builder: &gsk::PathBuilder,
+    center: BackendCoord,
+    radius: f64,
+    from: f64,
+    to: f64,
+) {
+    // Split the arc, so each piece spans at most half a turn, as SVG arcs
+    // are ambiguous otherwise.
+    let n_pieces = ((to - from).abs() / PI).ceil().max(1.0) as usize;
+    let step = (to - from) / n_pieces as f64;
+
+    for piece in 1..=n_pieces {
+        let (x, y) = polar_to_point(center, radius, from + step * piece as f64);
+        path_builder.svg_arc_to(radius as f32, radius as f32, 0.0, false, step < 0.0, x, y);
+    }
+}
+
+pub(crate) fn polar_to_point(center: BackendCoord, radius: f64, angle: f64) -> (f32, f32) {
+    (
+        (center.0 as f64 + radius * angle.cos()) as f32,
+        (center.1 as f64 - radius * angle.sin()) as f32,
+    )
+}
+
+fn stroke<B: GtkBackend, S: BackendStyle>(backend: &mut B, path: &gsk::Path, style: &S) {
+    let stroke = gsk::Stroke::new(backend.stroke_width(style));
+    backend
+        .gtk_snapshot()
+        .append_stroke(path, &stroke, &style.color().to_rgba());
+}
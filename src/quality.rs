@@ -0,0 +1,84 @@
+use gtk::{cairo, pango};
+
+/// A preset of the trade-offs between rendering speed and quality.
+///
+/// This is set with [`PaintableBackend::set_quality`] or
+/// [`SnapshotBackend::set_quality`], such as [`Quality::Draft`] while the
+/// user pans or zooms, and [`Quality::High`] for the final still.
+///
+/// [`PaintableBackend::set_quality`]: crate::PaintableBackend::set_quality
+/// [`SnapshotBackend::set_quality`]: crate::SnapshotBackend::set_quality
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Quality {
+    /// Fast rendering for interactive use, with visible shortcuts.
+    ///
+    /// Points of paths within two pixels of the previous point are skipped,
+    /// pixels of the same color are batched into a single node, small filled
+    /// circles are drawn as squares, the nodes of other circles are reused
+    /// for the next circles of the same size and style, and text is hinted
+    /// with its glyphs placed on whole pixels.
+    Draft,
+    /// Everything is drawn as given, with the text options of the
+    /// [`BackendContext`](crate::BackendContext).
+    #[default]
+    Normal,
+    /// Everything is drawn as given, with text unhinted and its glyphs
+    /// placed at subpixel positions, for the most faithful glyph shapes and
+    /// spacing.
+    High,
+}
+
+impl Quality {
+    /// Returns the distance in pixels within which points of a path are
+    /// skipped, or zero to keep every point.
+    pub(crate) fn path_tolerance(self) -> i64 {
+        match self {
+            Self::Draft => 2,
+            Self::Normal | Self::High => 0,
+        }
+    }
+
+    /// Returns whether the node of a circle is reused for the next circles
+    /// of the same radius, stroke, and color, moved in place.
+    ///
+    /// Renderers that cache the rasterization of paths can then reuse it for
+    /// every marker of a scatter plot.
+    pub(crate) fn caches_markers(self) -> bool {
+        self == Self::Draft
+    }
+
+    /// Returns whether pixels of the same color are batched into one node.
+    pub(crate) fn batches_pixels(self) -> bool {
+        self == Self::Draft
+    }
+
+    /// Returns the radius up to which filled circles are drawn as squares,
+    /// if they are.
+    pub(crate) fn square_marker_radius(self) -> Option<u32> {
+        match self {
+            Self::Draft => Some(2),
+            Self::Normal | Self::High => None,
+        }
+    }
+
+    /// Sets up `context` to lay out text with this quality.
+    ///
+    /// This does nothing for [`Quality::Normal`].
+    pub(crate) fn apply_to_context(self, context: &pango::Context) {
+        let (hint_style, hint_metrics, rounds_glyph_positions) = match self {
+            Self::Draft => (cairo::HintStyle::Full, cairo::HintMetrics::On, true),
+            Self::Normal => return,
+            Self::High => (cairo::HintStyle::None, cairo::HintMetrics::Off, false),
+        };
+
+        // Keep the other options of the context, such as the antialiasing.
+        let font_options = pangocairo::functions::context_get_font_options(context)
+            .or_else(|| cairo::FontOptions::new().ok());
+        if let Some(mut font_options) = font_options {
+            font_options.set_hint_style(hint_style);
+            font_options.set_hint_metrics(hint_metrics);
+            pangocairo::functions::context_set_font_options(context, Some(&font_options));
+        }
+        context.set_round_glyph_positions(rounds_glyph_positions);
+    }
+}
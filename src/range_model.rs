@@ -0,0 +1,210 @@
+use std::ops::Range;
+
+use gtk::{
+    glib::{self, closure_local},
+    prelude::*,
+    subclass::prelude::*,
+};
+
+mod imp {
+    use std::{cell::Cell, sync::OnceLock};
+
+    use glib::subclass::Signal;
+
+    use super::*;
+
+    #[derive(Debug)]
+    pub struct RangeModel {
+        pub(super) x_min: Cell<f64>,
+        pub(super) x_max: Cell<f64>,
+        pub(super) y_min: Cell<f64>,
+        pub(super) y_max: Cell<f64>,
+    }
+
+    impl Default for RangeModel {
+        fn default() -> Self {
+            Self {
+                x_min: Cell::new(0.0),
+                x_max: Cell::new(1.0),
+                y_min: Cell::new(0.0),
+                y_max: Cell::new(1.0),
+            }
+        }
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for RangeModel {
+        const NAME: &'static str = "PlottersGtk4RangeModel";
+        type Type = super::RangeModel;
+    }
+
+    impl ObjectImpl for RangeModel {
+        fn properties() -> &'static [glib::ParamSpec] {
+            static PROPERTIES: OnceLock<Vec<glib::ParamSpec>> = OnceLock::new();
+
+            PROPERTIES.get_or_init(|| {
+                vec![
+                    glib::ParamSpecDouble::builder("x-min")
+                        .explicit_notify()
+                        .build(),
+                    glib::ParamSpecDouble::builder("x-max")
+                        .default_value(1.0)
+                        .explicit_notify()
+                        .build(),
+                    glib::ParamSpecDouble::builder("y-min")
+                        .explicit_notify()
+                        .build(),
+                    glib::ParamSpecDouble::builder("y-max")
+                        .default_value(1.0)
+                        .explicit_notify()
+                        .build(),
+                ]
+            })
+        }
+
+        fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+            let obj = self.obj();
+
+            match pspec.name() {
+                "x-min" => obj.set_x_range(value.get().unwrap()..obj.x_max()),
+                "x-max" => obj.set_x_range(obj.x_min()..value.get().unwrap()),
+                "y-min" => obj.set_y_range(value.get().unwrap()..obj.y_max()),
+                "y-max" => obj.set_y_range(obj.y_min()..value.get().unwrap()),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            let obj = self.obj();
+
+            match pspec.name() {
+                "x-min" => obj.x_min().into(),
+                "x-max" => obj.x_max().into(),
+                "y-min" => obj.y_min().into(),
+                "y-max" => obj.y_max().into(),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+
+            SIGNALS.get_or_init(|| vec![Signal::builder("changed").build()])
+        }
+    }
+}
+
+glib::wrapper! {
+    /// The visible data ranges of a plot.
+    ///
+    /// A range model can be shared by multiple [`PlotView`](crate::PlotView)s,
+    /// so that panning or zooming one of them updates the others. To only link
+    /// one axis, bind the corresponding properties of separate models instead.
+    ///
+    /// This can be used on GTK UI files using its type name `PlottersGtk4RangeModel`.
+    pub struct RangeModel(ObjectSubclass<imp::RangeModel>);
+}
+
+impl RangeModel {
+    /// Creates a new range model with the given x and y ranges.
+    pub fn new(x_range: Range<f64>, y_range: Range<f64>) -> Self {
+        let obj = glib::Object::new::<Self>();
+        obj.set_ranges(x_range, y_range);
+        obj
+    }
+
+    /// Returns the lower bound of the x range.
+    pub fn x_min(&self) -> f64 {
+        self.imp().x_min.get()
+    }
+
+    /// Returns the upper bound of the x range.
+    pub fn x_max(&self) -> f64 {
+        self.imp().x_max.get()
+    }
+
+    /// Returns the lower bound of the y range.
+    pub fn y_min(&self) -> f64 {
+        self.imp().y_min.get()
+    }
+
+    /// Returns the upper bound of the y range.
+    pub fn y_max(&self) -> f64 {
+        self.imp().y_max.get()
+    }
+
+    /// Returns the x range.
+    pub fn x_range(&self) -> Range<f64> {
+        self.x_min()..self.x_max()
+    }
+
+    /// Returns the y range.
+    pub fn y_range(&self) -> Range<f64> {
+        self.y_min()..self.y_max()
+    }
+
+    /// Sets the x range.
+    pub fn set_x_range(&self, x_range: Range<f64>) {
+        self.set_ranges(x_range, self.y_range());
+    }
+
+    /// Sets the y range.
+    pub fn set_y_range(&self, y_range: Range<f64>) {
+        self.set_ranges(self.x_range(), y_range);
+    }
+
+    /// Sets both the x and y ranges, emitting `changed` only once.
+    pub fn set_ranges(&self, x_range: Range<f64>, y_range: Range<f64>) {
+        let imp = self.imp();
+
+        let _guard = self.freeze_notify();
+        let mut changed = false;
+
+        for (cell, value, name) in [
+            (&imp.x_min, x_range.start, "x-min"),
+            (&imp.x_max, x_range.end, "x-max"),
+            (&imp.y_min, y_range.start, "y-min"),
+            (&imp.y_max, y_range.end, "y-max"),
+        ] {
+            if cell.get() != value {
+                cell.set(value);
+                self.notify(name);
+                changed = true;
+            }
+        }
+
+        if changed {
+            self.emit_by_name::<()>("changed", &[]);
+        }
+    }
+
+    /// Moves both ranges by the given amounts, in data units.
+    pub fn pan(&self, dx: f64, dy: f64) {
+        let Range { start: x0, end: x1 } = self.x_range();
+        let Range { start: y0, end: y1 } = self.y_range();
+        self.set_ranges(x0 + dx..x1 + dx, y0 + dy..y1 + dy);
+    }
+
+    /// Scales both ranges by `factor` around `center`, in data units.
+    ///
+    /// A factor greater than `1.0` zooms in, and a factor less than `1.0`
+    /// zooms out.
+    pub fn zoom(&self, factor: f64, (cx, cy): (f64, f64)) {
+        let scale = |Range { start, end }: Range<f64>, center: f64| {
+            center - (center - start) / factor..center + (end - center) / factor
+        };
+        self.set_ranges(scale(self.x_range(), cx), scale(self.y_range(), cy));
+    }
+
+    /// Connects to the `changed` signal, emitted once after any of the
+    /// ranges change.
+    pub fn connect_changed<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure("changed", false, closure_local!(|obj: &Self| f(obj)))
+    }
+}
+
+impl Default for RangeModel {
+    fn default() -> Self {
+        glib::Object::new()
+    }
+}
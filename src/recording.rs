@@ -0,0 +1,1533 @@
+//! Recording of drawn primitives into a display list, to replay them later.
+//!
+//! A [`Recording`] holds only plain data, so it can be kept around, compared
+//! with [`Recording::diff`] in tests, serialized, sent between threads, and
+//! replayed into any [`DrawingBackend`], such as a
+//! [`SnapshotBackend`](crate::SnapshotBackend). The [`RecordingBackend`]
+//! itself measures text with Pango, so it must be used on the thread GTK
+//! runs on, like the other backends.
+//!
+//! Two recordings of the same chart with different data can also be
+//! interpolated, which [`Paintable::morph_to`](crate::Paintable::morph_to)
+//! uses to animate bar heights and line shapes when values change.
+
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    convert::Infallible,
+    hash::{Hash, Hasher},
+    mem::Discriminant,
+    ops::Range,
+};
+
+use gtk::{gdk, glib, graphene::Rect, gsk, pango, prelude::*};
+use plotters_backend::{
+    text_anchor::{HPos, Pos, VPos},
+    BackendColor, BackendCoord, BackendStyle, BackendTextStyle, DrawingBackend, DrawingErrorKind,
+    FontFamily, FontStyle, FontTransform,
+};
+
+use crate::{common, context, export, InvalidRecordingError, SnapshotBackend};
+
+/// The style of a recorded primitive.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RecordedStyle {
+    /// The red, green, and blue components of the color.
+    pub rgb: (u8, u8, u8),
+    /// The alpha component of the color, from `0.0` to `1.0`.
+    pub alpha: f64,
+    /// The stroke width.
+    pub stroke_width: u32,
+}
+
+impl RecordedStyle {
+    fn new<S: BackendStyle>(style: &S) -> Self {
+        let color = style.color();
+        Self {
+            rgb: color.rgb,
+            alpha: color.alpha,
+            stroke_width: style.stroke_width(),
+        }
+    }
+
+    fn faded(&self, opacity: f64) -> Self {
+        Self {
+            alpha: self.alpha * opacity,
+            ..*self
+        }
+    }
+
+    fn lerp(&self, to: &Self, t: f64) -> Self {
+        Self {
+            rgb: lerp_rgb(self.rgb, to.rgb, t),
+            alpha: lerp(self.alpha, to.alpha, t),
+            stroke_width: lerp(self.stroke_width as f64, to.stroke_width as f64, t).round() as u32,
+        }
+    }
+}
+
+impl BackendStyle for RecordedStyle {
+    fn color(&self) -> BackendColor {
+        BackendColor {
+            alpha: self.alpha,
+            rgb: self.rgb,
+        }
+    }
+
+    fn stroke_width(&self) -> u32 {
+        self.stroke_width
+    }
+}
+
+/// The style of recorded text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedTextStyle {
+    /// The font family, as returned by [`FontFamily::as_str`].
+    pub family: String,
+    /// The font style, as returned by [`FontStyle::as_str`].
+    pub style: String,
+    /// The font size, in pixels.
+    pub size: f64,
+    /// The red, green, and blue components of the color.
+    pub rgb: (u8, u8, u8),
+    /// The alpha component of the color, from `0.0` to `1.0`.
+    pub alpha: f64,
+    /// The clockwise rotation, in degrees, one of `0`, `90`, `180`, or
+    /// `270`.
+    pub rotation: u16,
+    /// The position of the anchor within the text, from `(0.0, 0.0)` for
+    /// the top-left corner to `(1.0, 1.0)` for the bottom-right corner.
+    pub anchor: (f32, f32),
+}
+
+impl RecordedTextStyle {
+    fn new<TStyle: BackendTextStyle>(style: &TStyle) -> Self {
+        let color = style.color();
+        let anchor = style.anchor();
+        Self {
+            family: style.family().as_str().to_string(),
+            style: style.style().as_str().to_string(),
+            size: style.size(),
+            rgb: color.rgb,
+            alpha: color.alpha,
+            rotation: match style.transform() {
+                FontTransform::None => 0,
+                FontTransform::Rotate90 => 90,
+                FontTransform::Rotate180 => 180,
+                FontTransform::Rotate270 => 270,
+            },
+            anchor: (
+                match anchor.h_pos {
+                    HPos::Left => 0.0,
+                    HPos::Center => 0.5,
+                    HPos::Right => 1.0,
+                },
+                match anchor.v_pos {
+                    VPos::Top => 0.0,
+                    VPos::Center => 0.5,
+                    VPos::Bottom => 1.0,
+                },
+            ),
+        }
+    }
+
+    fn faded(&self, opacity: f64) -> Self {
+        Self {
+            alpha: self.alpha * opacity,
+            ..self.clone()
+        }
+    }
+
+    fn lerp(&self, to: &Self, t: f64) -> Self {
+        Self {
+            size: lerp(self.size, to.size, t),
+            rgb: lerp_rgb(self.rgb, to.rgb, t),
+            alpha: lerp(self.alpha, to.alpha, t),
+            ..to.clone()
+        }
+    }
+}
+
+impl BackendTextStyle for RecordedTextStyle {
+    type FontError = Infallible;
+
+    fn color(&self) -> BackendColor {
+        BackendColor {
+            alpha: self.alpha,
+            rgb: self.rgb,
+        }
+    }
+
+    fn size(&self) -> f64 {
+        self.size
+    }
+
+    fn transform(&self) -> FontTransform {
+        match self.rotation {
+            90 => FontTransform::Rotate90,
+            180 => FontTransform::Rotate180,
+            270 => FontTransform::Rotate270,
+            _ => FontTransform::None,
+        }
+    }
+
+    fn style(&self) -> FontStyle {
+        FontStyle::from(self.style.as_str())
+    }
+
+    fn anchor(&self) -> Pos {
+        let (h, v) = self.anchor;
+        let h_pos = if h < 0.25 {
+            HPos::Left
+        } else if h > 0.75 {
+            HPos::Right
+        } else {
+            HPos::Center
+        };
+        let v_pos = if v < 0.25 {
+            VPos::Top
+        } else if v > 0.75 {
+            VPos::Bottom
+        } else {
+            VPos::Center
+        };
+        Pos::new(h_pos, v_pos)
+    }
+
+    fn family(&self) -> FontFamily<'_> {
+        FontFamily::from(self.family.as_str())
+    }
+
+    /// Returns a rough estimate of the box of the text.
+    ///
+    /// This is only used by backends that cannot draw text on their own,
+    /// unlike the ones of this crate, which measure text with Pango.
+    fn layout_box(&self, text: &str) -> Result<((i32, i32), (i32, i32)), Self::FontError> {
+        let width = self.size * 0.6 * text.chars().count() as f64;
+        Ok(((0, 0), (width.round() as i32, self.size.round() as i32)))
+    }
+
+    /// Draws nothing, as recorded text can only be drawn by backends that
+    /// draw text on their own, such as the ones of this crate.
+    fn draw<E, DrawFunc: FnMut(i32, i32, BackendColor) -> Result<(), E>>(
+        &self,
+        _text: &str,
+        _pos: BackendCoord,
+        _draw: DrawFunc,
+    ) -> Result<Result<(), E>, Self::FontError> {
+        Ok(Ok(()))
+    }
+}
+
+/// A recorded primitive.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DrawCommand {
+    /// A pixel, recorded from [`DrawingBackend::draw_pixel`].
+    Pixel {
+        point: BackendCoord,
+        style: RecordedStyle,
+    },
+    /// A line, recorded from [`DrawingBackend::draw_line`].
+    Line {
+        from: BackendCoord,
+        to: BackendCoord,
+        style: RecordedStyle,
+    },
+    /// A rect, recorded from [`DrawingBackend::draw_rect`].
+    Rect {
+        upper_left: BackendCoord,
+        bottom_right: BackendCoord,
+        style: RecordedStyle,
+        fill: bool,
+    },
+    /// A path, recorded from [`DrawingBackend::draw_path`].
+    Path {
+        points: Vec<BackendCoord>,
+        style: RecordedStyle,
+    },
+    /// A filled polygon, recorded from [`DrawingBackend::fill_polygon`].
+    Polygon {
+        points: Vec<BackendCoord>,
+        style: RecordedStyle,
+    },
+    /// A circle, recorded from [`DrawingBackend::draw_circle`].
+    Circle {
+        center: BackendCoord,
+        radius: u32,
+        style: RecordedStyle,
+        fill: bool,
+    },
+    /// Text, recorded from [`DrawingBackend::draw_text`].
+    Text {
+        text: String,
+        pos: BackendCoord,
+        style: RecordedTextStyle,
+    },
+    /// The start of a group of primitives, recorded from
+    /// [`RecordingBackend::with_group`].
+    BeginGroup { name: String },
+    /// The end of the group started by the last unmatched
+    /// [`DrawCommand::BeginGroup`].
+    EndGroup,
+}
+
+impl DrawCommand {
+    /// Returns the color of the primitive, or `None` for group markers.
+    fn rgb(&self) -> Option<(u8, u8, u8)> {
+        match self {
+            Self::Pixel { style, .. }
+            | Self::Line { style, .. }
+            | Self::Rect { style, .. }
+            | Self::Path { style, .. }
+            | Self::Polygon { style, .. }
+            | Self::Circle { style, .. } => Some(style.rgb),
+            Self::Text { style, .. } => Some(style.rgb),
+            Self::BeginGroup { .. } | Self::EndGroup => None,
+        }
+    }
+
+    /// Returns the primitive with its opacity multiplied by `opacity`.
+    fn faded(&self, opacity: f64) -> Self {
+        let mut ret = self.clone();
+        match &mut ret {
+            Self::Pixel { style, .. }
+            | Self::Line { style, .. }
+            | Self::Rect { style, .. }
+            | Self::Path { style, .. }
+            | Self::Polygon { style, .. }
+            | Self::Circle { style, .. } => *style = style.faded(opacity),
+            Self::Text { style, .. } => *style = style.faded(opacity),
+            Self::BeginGroup { .. } | Self::EndGroup => {}
+        }
+        ret
+    }
+
+    /// Returns the primitive between `self` at `0.0` and `other` at `1.0`,
+    /// or `None` if they are different kinds of primitives.
+    fn lerp(&self, other: &Self, t: f64) -> Option<Self> {
+        let command = match (self, other) {
+            (
+                Self::Pixel { point, style },
+                Self::Pixel {
+                    point: to_point,
+                    style: to_style,
+                },
+            ) => Self::Pixel {
+                point: lerp_point(*point, *to_point, t),
+                style: style.lerp(to_style, t),
+            },
+            (
+                Self::Line { from, to, style },
+                Self::Line {
+                    from: to_from,
+                    to: to_to,
+                    style: to_style,
+                },
+            ) => Self::Line {
+                from: lerp_point(*from, *to_from, t),
+                to: lerp_point(*to, *to_to, t),
+                style: style.lerp(to_style, t),
+            },
+            (
+                Self::Rect {
+                    upper_left,
+                    bottom_right,
+                    style,
+                    ..
+                },
+                Self::Rect {
+                    upper_left: to_upper_left,
+                    bottom_right: to_bottom_right,
+                    style: to_style,
+                    fill,
+                },
+            ) => Self::Rect {
+                upper_left: lerp_point(*upper_left, *to_upper_left, t),
+                bottom_right: lerp_point(*bottom_right, *to_bottom_right, t),
+                style: style.lerp(to_style, t),
+                fill: *fill,
+            },
+            (
+                Self::Path { points, style },
+                Self::Path {
+                    points: to_points,
+                    style: to_style,
+                },
+            ) => Self::Path {
+                points: lerp_points(points, to_points, t),
+                style: style.lerp(to_style, t),
+            },
+            (
+                Self::Polygon { points, style },
+                Self::Polygon {
+                    points: to_points,
+                    style: to_style,
+                },
+            ) => Self::Polygon {
+                points: lerp_points(points, to_points, t),
+                style: style.lerp(to_style, t),
+            },
+            (
+                Self::Circle {
+                    center,
+                    radius,
+                    style,
+                    ..
+                },
+                Self::Circle {
+                    center: to_center,
+                    radius: to_radius,
+                    style: to_style,
+                    fill,
+                },
+            ) => Self::Circle {
+                center: lerp_point(*center, *to_center, t),
+                radius: lerp(*radius as f64, *to_radius as f64, t).round() as u32,
+                style: style.lerp(to_style, t),
+                fill: *fill,
+            },
+            (
+                Self::Text { pos, style, .. },
+                Self::Text {
+                    text,
+                    pos: to_pos,
+                    style: to_style,
+                },
+            ) => Self::Text {
+                text: text.clone(),
+                pos: lerp_point(*pos, *to_pos, t),
+                style: style.lerp(to_style, t),
+            },
+            _ => return None,
+        };
+        Some(command)
+    }
+}
+
+/// A display list of primitives, recorded with [`RecordingBackend`].
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Recording {
+    size: (u32, u32),
+    commands: Vec<DrawCommand>,
+}
+
+impl Recording {
+    /// Creates an empty recording of the given size.
+    pub fn new(size: (u32, u32)) -> Self {
+        Self {
+            size,
+            commands: Vec::new(),
+        }
+    }
+
+    /// Returns the size of the backend the primitives were recorded from.
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    /// Returns the recorded primitives, in the order they were drawn.
+    pub fn commands(&self) -> &[DrawCommand] {
+        &self.commands
+    }
+
+    /// Returns whether no primitives were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// Draws the recorded primitives on `backend`, in the order they were
+    /// drawn.
+    pub fn replay<DB: DrawingBackend>(
+        &self,
+        backend: &mut DB,
+    ) -> Result<(), DrawingErrorKind<DB::ErrorType>> {
+        backend.ensure_prepared()?;
+        replay_commands(&self.commands, backend)?;
+        backend.present()
+    }
+
+    /// Renders the recorded primitives into a texture of the recorded size.
+    ///
+    /// The texture is rendered with the native renderer of `surface`, or
+    /// with a Cairo renderer if `surface` is `None` or has no renderer, as in
+    /// [`Paintable::render_to_texture`](crate::Paintable::render_to_texture).
+    pub fn render_to_texture(
+        &self,
+        surface: Option<&gdk::Surface>,
+    ) -> Result<gdk::Texture, glib::Error> {
+        let renderer = export::renderer_for_surface(surface)?;
+
+        let (width, height) = self.size;
+        let bounds = Rect::new(0.0, 0.0, width.max(1) as f32, height.max(1) as f32);
+
+        // Ensure that empty recordings still render to a texture of the
+        // right size.
+        let snapshot = gtk::Snapshot::new();
+        snapshot.append_color(&gdk::RGBA::TRANSPARENT, &bounds);
+        if let Some(node) = self.to_node() {
+            snapshot.append_node(&node);
+        }
+        let texture = renderer.render_texture(snapshot.to_node().unwrap(), Some(&bounds));
+        renderer.unrealize();
+
+        Ok(texture)
+    }
+
+    /// Returns the differences of the primitives of `self` to the ones of
+    /// `other`, comparing them by their position in the display list.
+    ///
+    /// This is useful to assert in tests that a chart draws what is
+    /// expected, with a more helpful failure than comparing pixels.
+    pub fn diff(&self, other: &Recording) -> Vec<CommandDiff> {
+        let mut diffs = self
+            .commands
+            .iter()
+            .zip(&other.commands)
+            .enumerate()
+            .filter(|(_, (command, other_command))| command != other_command)
+            .map(|(index, _)| CommandDiff::Changed(index))
+            .collect::<Vec<_>>();
+
+        let n_common = self.commands.len().min(other.commands.len());
+        diffs.extend((n_common..self.commands.len()).map(CommandDiff::Removed));
+        diffs.extend((n_common..other.commands.len()).map(CommandDiff::Added));
+
+        diffs
+    }
+
+    /// Serializes the recording into a compact binary form.
+    ///
+    /// The recording can be restored with [`Recording::deserialize`], such
+    /// as to cache charts on disk or to send them to another process.
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut writer = Writer::default();
+        writer.bytes(MAGIC);
+        writer.u32(self.size.0);
+        writer.u32(self.size.1);
+        writer.u32(self.commands.len() as u32);
+        for command in &self.commands {
+            writer.command(command);
+        }
+        writer.buf
+    }
+
+    /// Restores a recording serialized with [`Recording::serialize`].
+    ///
+    /// This returns an error if `bytes` is not a valid serialized
+    /// recording.
+    pub fn deserialize(bytes: &[u8]) -> Result<Self, InvalidRecordingError> {
+        let mut reader = Reader { bytes, offset: 0 };
+
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(InvalidRecordingError { offset: 0 });
+        }
+
+        let size = (reader.u32()?, reader.u32()?);
+        let n_commands = reader.u32()?;
+        let commands = (0..n_commands)
+            .map(|_| reader.command())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if reader.offset != bytes.len() {
+            return Err(InvalidRecordingError {
+                offset: reader.offset,
+            });
+        }
+
+        Ok(Self { size, commands })
+    }
+
+    /// Returns the node of the recorded primitives, drawn with a
+    /// [`SnapshotBackend`].
+    pub(crate) fn to_node(&self) -> Option<gsk::RenderNode> {
+        render_commands(&self.commands, self.size)
+    }
+
+    /// Returns the ranges of the chunks of the display list, which are its
+    /// top-level groups and the runs of commands between them.
+    ///
+    /// Runs are split after the commands that are chunk boundaries by their
+    /// contents, and after at most [`MAX_CHUNK_LEN`] commands, so that
+    /// inserting or removing a command only changes the chunk it is in, and
+    /// the later chunks are still reused.
+    fn chunks(&self) -> Vec<Range<usize>> {
+        let mut chunks = Vec::new();
+        let mut start = 0;
+        let mut depth = 0usize;
+
+        for (index, command) in self.commands.iter().enumerate() {
+            match command {
+                DrawCommand::BeginGroup { .. } => {
+                    if depth == 0 && start < index {
+                        chunks.push(start..index);
+                        start = index;
+                    }
+                    depth += 1;
+                }
+                DrawCommand::EndGroup => {
+                    depth = depth.saturating_sub(1);
+                    if depth == 0 {
+                        chunks.push(start..index + 1);
+                        start = index + 1;
+                    }
+                }
+                _ => {
+                    if depth == 0
+                        && (index + 1 - start == MAX_CHUNK_LEN || is_chunk_boundary(command))
+                    {
+                        chunks.push(start..index + 1);
+                        start = index + 1;
+                    }
+                }
+            }
+        }
+
+        if start < self.commands.len() {
+            chunks.push(start..self.commands.len());
+        }
+
+        chunks
+    }
+
+    /// Returns the nodes of the chunks of the display list, reusing the nodes
+    /// in `cache` of the chunks that did not change, and rendering only the
+    /// others.
+    ///
+    /// `cache` maps the serialized commands of chunks to their nodes, and is
+    /// updated to hold only the chunks of this recording.
+    pub(crate) fn chunk_nodes(
+        &self,
+        cache: &mut HashMap<Vec<u8>, gsk::RenderNode>,
+    ) -> Vec<gsk::RenderNode> {
+        let mut previous = std::mem::take(cache);
+        let mut nodes = Vec::new();
+
+        for range in self.chunks() {
+            let commands = &self.commands[range];
+
+            let mut writer = Writer::default();
+            for command in commands {
+                writer.command(command);
+            }
+            let key = writer.buf;
+
+            let node = previous
+                .remove(&key)
+                .or_else(|| cache.get(&key).cloned())
+                .or_else(|| render_commands(commands, self.size));
+            if let Some(node) = node {
+                cache.insert(key, node.clone());
+                nodes.push(node);
+            }
+        }
+
+        nodes
+    }
+
+    /// Returns the recording between `self` at `0.0` and `to` at `1.0`.
+    ///
+    /// Primitives are matched by their group, kind, color, and, for text,
+    /// their text, in the order they were drawn, so that the bars and lines
+    /// of a series are matched with the ones of the same series. Matched
+    /// primitives are interpolated, while the others fade out or in.
+    pub(crate) fn interpolate(&self, to: &Recording, t: f64) -> Recording {
+        let mut from_indices = match_keys(&self.commands)
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, key)| Some((key?, index)))
+            .collect::<HashMap<_, _>>();
+
+        let mut is_matched = vec![false; self.commands.len()];
+        let mut to_commands = Vec::with_capacity(to.commands.len());
+
+        for (command, key) in to.commands.iter().zip(match_keys(&to.commands)) {
+            let from_command = key
+                .and_then(|key| from_indices.remove(&key))
+                .and_then(|index| {
+                    let command = self.commands[index].lerp(command, t)?;
+                    is_matched[index] = true;
+                    Some(command)
+                });
+
+            match from_command {
+                Some(command) => to_commands.push(command),
+                None if command.rgb().is_some() => to_commands.push(command.faded(t)),
+                None => to_commands.push(command.clone()),
+            }
+        }
+
+        // Draw the primitives that fade out beneath the others.
+        let mut commands = Vec::with_capacity(to_commands.len());
+        if t < 1.0 {
+            commands.extend(
+                self.commands
+                    .iter()
+                    .zip(&is_matched)
+                    .filter(|(command, is_matched)| !**is_matched && command.rgb().is_some())
+                    .map(|(command, _)| command.faded(1.0 - t)),
+            );
+        }
+        commands.extend(to_commands);
+
+        Recording {
+            size: to.size,
+            commands,
+        }
+    }
+}
+
+/// Draws `commands` on `backend`, in order.
+fn replay_commands<DB: DrawingBackend>(
+    commands: &[DrawCommand],
+    backend: &mut DB,
+) -> Result<(), DrawingErrorKind<DB::ErrorType>> {
+    for command in commands {
+        match command {
+            DrawCommand::Pixel { point, style } => {
+                backend.draw_pixel(*point, style.color())?;
+            }
+            DrawCommand::Line { from, to, style } => {
+                backend.draw_line(*from, *to, style)?;
+            }
+            DrawCommand::Rect {
+                upper_left,
+                bottom_right,
+                style,
+                fill,
+            } => {
+                backend.draw_rect(*upper_left, *bottom_right, style, *fill)?;
+            }
+            DrawCommand::Path { points, style } => {
+                backend.draw_path(points.iter().copied(), style)?;
+            }
+            DrawCommand::Polygon { points, style } => {
+                backend.fill_polygon(points.iter().copied(), style)?;
+            }
+            DrawCommand::Circle {
+                center,
+                radius,
+                style,
+                fill,
+            } => {
+                backend.draw_circle(*center, *radius, style, *fill)?;
+            }
+            DrawCommand::Text { text, pos, style } => {
+                backend.draw_text(text, style, *pos)?;
+            }
+            DrawCommand::BeginGroup { .. } | DrawCommand::EndGroup => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the node of `commands`, drawn with a [`SnapshotBackend`] of
+/// `size`.
+fn render_commands(commands: &[DrawCommand], size: (u32, u32)) -> Option<gsk::RenderNode> {
+    let snapshot = gtk::Snapshot::new();
+    {
+        let mut backend = SnapshotBackend::new(&snapshot, size);
+        let _ = replay_commands(commands, &mut backend);
+    }
+    snapshot.to_node()
+}
+
+/// A difference between two recordings, as returned by [`Recording::diff`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandDiff {
+    /// The primitive at the index differs.
+    Changed(usize),
+    /// The primitive at the index is only in the other recording.
+    Added(usize),
+    /// The primitive at the index is only in the first recording.
+    Removed(usize),
+}
+
+/// The maximum number of ungrouped commands in a chunk of a display list,
+/// which is the unit of nodes reused by [`Recording::chunk_nodes`].
+const MAX_CHUNK_LEN: usize = 256;
+
+/// One in this many commands is a chunk boundary, which is the average
+/// number of ungrouped commands in a chunk.
+const CHUNK_BOUNDARY_PERIOD: u64 = 64;
+
+/// Returns whether a run of ungrouped commands is split after `command`,
+/// by a hash of its contents.
+fn is_chunk_boundary(command: &DrawCommand) -> bool {
+    let mut writer = Writer::default();
+    writer.command(command);
+
+    let mut hasher = DefaultHasher::new();
+    writer.buf.hash(&mut hasher);
+    hasher.finish() % CHUNK_BOUNDARY_PERIOD == 0
+}
+
+/// The start of serialized recordings, followed by the version of the
+/// format.
+const MAGIC: &[u8] = b"PGTKREC1";
+
+#[derive(Default)]
+struct Writer {
+    buf: Vec<u8>,
+}
+
+impl Writer {
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+    }
+
+    fn u8(&mut self, value: u8) {
+        self.buf.push(value);
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn f32(&mut self, value: f32) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn f64(&mut self, value: f64) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn str(&mut self, value: &str) {
+        self.u32(value.len() as u32);
+        self.bytes(value.as_bytes());
+    }
+
+    fn point(&mut self, (x, y): BackendCoord) {
+        self.bytes(&x.to_le_bytes());
+        self.bytes(&y.to_le_bytes());
+    }
+
+    fn points(&mut self, points: &[BackendCoord]) {
+        self.u32(points.len() as u32);
+        for &point in points {
+            self.point(point);
+        }
+    }
+
+    fn rgb(&mut self, (r, g, b): (u8, u8, u8)) {
+        self.bytes(&[r, g, b]);
+    }
+
+    fn style(&mut self, style: &RecordedStyle) {
+        self.rgb(style.rgb);
+        self.f64(style.alpha);
+        self.u32(style.stroke_width);
+    }
+
+    fn text_style(&mut self, style: &RecordedTextStyle) {
+        self.str(&style.family);
+        self.str(&style.style);
+        self.f64(style.size);
+        self.rgb(style.rgb);
+        self.f64(style.alpha);
+        self.u16(style.rotation);
+        self.f32(style.anchor.0);
+        self.f32(style.anchor.1);
+    }
+
+    fn command(&mut self, command: &DrawCommand) {
+        match command {
+            DrawCommand::Pixel { point, style } => {
+                self.u8(0);
+                self.point(*point);
+                self.style(style);
+            }
+            DrawCommand::Line { from, to, style } => {
+                self.u8(1);
+                self.point(*from);
+                self.point(*to);
+                self.style(style);
+            }
+            DrawCommand::Rect {
+                upper_left,
+                bottom_right,
+                style,
+                fill,
+            } => {
+                self.u8(2);
+                self.point(*upper_left);
+                self.point(*bottom_right);
+                self.style(style);
+                self.u8(*fill as u8);
+            }
+            DrawCommand::Path { points, style } => {
+                self.u8(3);
+                self.points(points);
+                self.style(style);
+            }
+            DrawCommand::Polygon { points, style } => {
+                self.u8(4);
+                self.points(points);
+                self.style(style);
+            }
+            DrawCommand::Circle {
+                center,
+                radius,
+                style,
+                fill,
+            } => {
+                self.u8(5);
+                self.point(*center);
+                self.u32(*radius);
+                self.style(style);
+                self.u8(*fill as u8);
+            }
+            DrawCommand::Text { text, pos, style } => {
+                self.u8(6);
+                self.str(text);
+                self.point(*pos);
+                self.text_style(style);
+            }
+            DrawCommand::BeginGroup { name } => {
+                self.u8(7);
+                self.str(name);
+            }
+            DrawCommand::EndGroup => {
+                self.u8(8);
+            }
+        }
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], InvalidRecordingError> {
+        let bytes = self
+            .offset
+            .checked_add(len)
+            .and_then(|end| self.bytes.get(self.offset..end))
+            .ok_or(InvalidRecordingError {
+                offset: self.offset,
+            })?;
+        self.offset += len;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], InvalidRecordingError> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn u8(&mut self) -> Result<u8, InvalidRecordingError> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, InvalidRecordingError> {
+        Ok(u16::from_le_bytes(self.array()?))
+    }
+
+    fn u32(&mut self) -> Result<u32, InvalidRecordingError> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn i32(&mut self) -> Result<i32, InvalidRecordingError> {
+        Ok(i32::from_le_bytes(self.array()?))
+    }
+
+    fn f32(&mut self) -> Result<f32, InvalidRecordingError> {
+        Ok(f32::from_le_bytes(self.array()?))
+    }
+
+    fn f64(&mut self) -> Result<f64, InvalidRecordingError> {
+        Ok(f64::from_le_bytes(self.array()?))
+    }
+
+    fn bool(&mut self) -> Result<bool, InvalidRecordingError> {
+        let offset = self.offset;
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(InvalidRecordingError { offset }),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, InvalidRecordingError> {
+        let offset = self.offset;
+        let len = self.u32()? as usize;
+        let bytes = self.take(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| InvalidRecordingError { offset })
+    }
+
+    fn point(&mut self) -> Result<BackendCoord, InvalidRecordingError> {
+        Ok((self.i32()?, self.i32()?))
+    }
+
+    fn points(&mut self) -> Result<Vec<BackendCoord>, InvalidRecordingError> {
+        let len = self.u32()?;
+        (0..len).map(|_| self.point()).collect()
+    }
+
+    fn rgb(&mut self) -> Result<(u8, u8, u8), InvalidRecordingError> {
+        let [r, g, b] = self.array()?;
+        Ok((r, g, b))
+    }
+
+    fn style(&mut self) -> Result<RecordedStyle, InvalidRecordingError> {
+        Ok(RecordedStyle {
+            rgb: self.rgb()?,
+            alpha: self.f64()?,
+            stroke_width: self.u32()?,
+        })
+    }
+
+    fn text_style(&mut self) -> Result<RecordedTextStyle, InvalidRecordingError> {
+        Ok(RecordedTextStyle {
+            family: self.string()?,
+            style: self.string()?,
+            size: self.f64()?,
+            rgb: self.rgb()?,
+            alpha: self.f64()?,
+            rotation: self.u16()?,
+            anchor: (self.f32()?, self.f32()?),
+        })
+    }
+
+    fn command(&mut self) -> Result<DrawCommand, InvalidRecordingError> {
+        let offset = self.offset;
+        let command = match self.u8()? {
+            0 => DrawCommand::Pixel {
+                point: self.point()?,
+                style: self.style()?,
+            },
+            1 => DrawCommand::Line {
+                from: self.point()?,
+                to: self.point()?,
+                style: self.style()?,
+            },
+            2 => DrawCommand::Rect {
+                upper_left: self.point()?,
+                bottom_right: self.point()?,
+                style: self.style()?,
+                fill: self.bool()?,
+            },
+            3 => DrawCommand::Path {
+                points: self.points()?,
+                style: self.style()?,
+            },
+            4 => DrawCommand::Polygon {
+                points: self.points()?,
+                style: self.style()?,
+            },
+            5 => DrawCommand::Circle {
+                center: self.point()?,
+                radius: self.u32()?,
+                style: self.style()?,
+                fill: self.bool()?,
+            },
+            6 => DrawCommand::Text {
+                text: self.string()?,
+                pos: self.point()?,
+                style: self.text_style()?,
+            },
+            7 => DrawCommand::BeginGroup {
+                name: self.string()?,
+            },
+            8 => DrawCommand::EndGroup,
+            _ => return Err(InvalidRecordingError { offset }),
+        };
+        Ok(command)
+    }
+}
+
+/// Identifies a primitive across recordings.
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct MatchKey<'a> {
+    group: Option<&'a str>,
+    kind: Discriminant<DrawCommand>,
+    rgb: (u8, u8, u8),
+    text: Option<&'a str>,
+    occurrence: usize,
+}
+
+/// Returns the keys of `commands`, or `None` for group markers.
+fn match_keys(commands: &[DrawCommand]) -> Vec<Option<MatchKey<'_>>> {
+    let mut groups = Vec::new();
+    let mut occurrences = HashMap::new();
+
+    commands
+        .iter()
+        .map(|command| {
+            match command {
+                DrawCommand::BeginGroup { name } => groups.push(name.as_str()),
+                DrawCommand::EndGroup => {
+                    groups.pop();
+                }
+                _ => {}
+            }
+
+            let rgb = command.rgb()?;
+            let group = groups.last().copied();
+            let kind = std::mem::discriminant(command);
+            let text = match command {
+                DrawCommand::Text { text, .. } => Some(text.as_str()),
+                _ => None,
+            };
+
+            let occurrence = occurrences.entry((group, kind, rgb, text)).or_insert(0);
+            let key = MatchKey {
+                group,
+                kind,
+                rgb,
+                text,
+                occurrence: *occurrence,
+            };
+            *occurrence += 1;
+
+            Some(key)
+        })
+        .collect()
+}
+
+fn lerp(from: f64, to: f64, t: f64) -> f64 {
+    from + (to - from) * t
+}
+
+fn lerp_rgb(from: (u8, u8, u8), to: (u8, u8, u8), t: f64) -> (u8, u8, u8) {
+    let channel = |from: u8, to: u8| lerp(from as f64, to as f64, t).round() as u8;
+    (
+        channel(from.0, to.0),
+        channel(from.1, to.1),
+        channel(from.2, to.2),
+    )
+}
+
+fn lerp_point(from: BackendCoord, to: BackendCoord, t: f64) -> BackendCoord {
+    (
+        lerp(from.0 as f64, to.0 as f64, t).round() as i32,
+        lerp(from.1 as f64, to.1 as f64, t).round() as i32,
+    )
+}
+
+/// Interpolates the points of `to` from the points of `from` at the same
+/// relative position along the path, so that paths with different numbers of
+/// points still morph smoothly.
+fn lerp_points(from: &[BackendCoord], to: &[BackendCoord], t: f64) -> Vec<BackendCoord> {
+    if from.is_empty() {
+        return to.to_vec();
+    }
+
+    to.iter()
+        .enumerate()
+        .map(|(index, &point)| {
+            let from_index = if to.len() > 1 {
+                index * (from.len() - 1) / (to.len() - 1)
+            } else {
+                0
+            };
+            lerp_point(from[from_index], point, t)
+        })
+        .collect()
+}
+
+/// Backend that records the drawn primitives into a [`Recording`].
+///
+/// Text is measured with Pango, as in the other backends, so charts lay out
+/// the same as when drawn directly.
+#[derive(Debug)]
+pub struct RecordingBackend<'a> {
+    recording: &'a mut Recording,
+    layout: pango::Layout,
+    options: common::Options,
+}
+
+impl<'a> RecordingBackend<'a> {
+    /// Creates a new backend of the given size that records into
+    /// `recording`, replacing its previous contents.
+    pub fn new(recording: &'a mut Recording, size: (u32, u32)) -> Self {
+        *recording = Recording::new(size);
+
+        let layout = pango::Layout::new(&context::default_pango_context());
+
+        Self {
+            recording,
+            layout,
+            options: common::Options::default(),
+        }
+    }
+
+    /// Calls `f` with the primitives drawn within it recorded in a group
+    /// named `name`.
+    ///
+    /// Groups only matter when interpolating recordings, such as with
+    /// [`Paintable::morph_to`](crate::Paintable::morph_to), where primitives
+    /// are only matched with the ones of the group of the same name. This is
+    /// useful to keep series of the same color apart.
+    pub fn with_group<R>(&mut self, name: &str, f: impl FnOnce(&mut Self) -> R) -> R {
+        self.push(DrawCommand::BeginGroup {
+            name: name.to_string(),
+        });
+        let ret = f(self);
+        self.push(DrawCommand::EndGroup);
+
+        ret
+    }
+
+    fn push(&mut self, command: DrawCommand) {
+        self.recording.commands.push(command);
+    }
+}
+
+impl<'a> DrawingBackend for RecordingBackend<'a> {
+    type ErrorType = Infallible;
+
+    #[inline]
+    fn get_size(&self) -> (u32, u32) {
+        self.recording.size
+    }
+
+    fn ensure_prepared(&mut self) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        Ok(())
+    }
+
+    fn present(&mut self) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        Ok(())
+    }
+
+    fn draw_pixel(
+        &mut self,
+        point: BackendCoord,
+        color: BackendColor,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        self.push(DrawCommand::Pixel {
+            point,
+            style: RecordedStyle::new(&color),
+        });
+        Ok(())
+    }
+
+    fn draw_line<S: BackendStyle>(
+        &mut self,
+        from: BackendCoord,
+        to: BackendCoord,
+        style: &S,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        self.push(DrawCommand::Line {
+            from,
+            to,
+            style: RecordedStyle::new(style),
+        });
+        Ok(())
+    }
+
+    fn draw_rect<S: BackendStyle>(
+        &mut self,
+        upper_left: BackendCoord,
+        bottom_right: BackendCoord,
+        style: &S,
+        fill: bool,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        self.push(DrawCommand::Rect {
+            upper_left,
+            bottom_right,
+            style: RecordedStyle::new(style),
+            fill,
+        });
+        Ok(())
+    }
+
+    fn draw_path<S: BackendStyle, I: IntoIterator<Item = BackendCoord>>(
+        &mut self,
+        raw_path: I,
+        style: &S,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        self.push(DrawCommand::Path {
+            points: raw_path.into_iter().collect(),
+            style: RecordedStyle::new(style),
+        });
+        Ok(())
+    }
+
+    fn fill_polygon<S: BackendStyle, I: IntoIterator<Item = BackendCoord>>(
+        &mut self,
+        vert: I,
+        style: &S,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        self.push(DrawCommand::Polygon {
+            points: vert.into_iter().collect(),
+            style: RecordedStyle::new(style),
+        });
+        Ok(())
+    }
+
+    fn draw_circle<S: BackendStyle>(
+        &mut self,
+        center: BackendCoord,
+        radius: u32,
+        style: &S,
+        fill: bool,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        self.push(DrawCommand::Circle {
+            center,
+            radius,
+            style: RecordedStyle::new(style),
+            fill,
+        });
+        Ok(())
+    }
+
+    fn estimate_text_size<TStyle: BackendTextStyle>(
+        &self,
+        text: &str,
+        style: &TStyle,
+    ) -> Result<(u32, u32), DrawingErrorKind<Self::ErrorType>> {
+        common::estimate_text_size(&self.options, &self.layout, text, style)
+    }
+
+    fn draw_text<TStyle: BackendTextStyle>(
+        &mut self,
+        text: &str,
+        style: &TStyle,
+        pos: BackendCoord,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        self.push(DrawCommand::Text {
+            text: text.to_string(),
+            pos,
+            style: RecordedTextStyle::new(style),
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STYLE: RecordedStyle = RecordedStyle {
+        rgb: (10, 20, 30),
+        alpha: 0.5,
+        stroke_width: 2,
+    };
+
+    /// Returns a recording with every kind of command.
+    fn recording() -> Recording {
+        let text_style = RecordedTextStyle {
+            family: "sans-serif".to_string(),
+            style: "bold".to_string(),
+            size: 12.5,
+            rgb: (40, 50, 60),
+            alpha: 1.0,
+            rotation: 90,
+            anchor: (0.5, 1.0),
+        };
+
+        Recording {
+            size: (640, 480),
+            commands: vec![
+                DrawCommand::BeginGroup {
+                    name: "séries".to_string(),
+                },
+                DrawCommand::Pixel {
+                    point: (1, 2),
+                    style: STYLE,
+                },
+                DrawCommand::Line {
+                    from: (-3, 4),
+                    to: (5, -6),
+                    style: STYLE,
+                },
+                DrawCommand::Rect {
+                    upper_left: (7, 8),
+                    bottom_right: (9, 10),
+                    style: STYLE,
+                    fill: true,
+                },
+                DrawCommand::Path {
+                    points: vec![(0, 0), (10, 20), (i32::MAX, i32::MIN)],
+                    style: STYLE,
+                },
+                DrawCommand::Polygon {
+                    points: Vec::new(),
+                    style: STYLE,
+                },
+                DrawCommand::Circle {
+                    center: (11, 12),
+                    radius: 13,
+                    style: STYLE,
+                    fill: false,
+                },
+                DrawCommand::Text {
+                    text: "Temperature (°C)".to_string(),
+                    pos: (14, 15),
+                    style: text_style,
+                },
+                DrawCommand::EndGroup,
+            ],
+        }
+    }
+
+    /// Returns the offset of the first command of a serialized recording.
+    fn first_command_offset() -> usize {
+        MAGIC.len() + 3 * 4
+    }
+
+    #[test]
+    fn serialize_round_trips() {
+        let recording = recording();
+        let bytes = recording.serialize();
+        assert_eq!(Recording::deserialize(&bytes).unwrap(), recording);
+
+        let empty = Recording::new((1, 2));
+        let bytes = empty.serialize();
+        assert_eq!(bytes.len(), first_command_offset());
+        assert_eq!(Recording::deserialize(&bytes).unwrap(), empty);
+    }
+
+    #[test]
+    fn deserialize_rejects_truncated_input() {
+        let bytes = recording().serialize();
+
+        for len in 0..bytes.len() {
+            let err = Recording::deserialize(&bytes[..len]).unwrap_err();
+            assert!(err.offset() <= len, "{} > {}", err.offset(), len);
+        }
+    }
+
+    #[test]
+    fn deserialize_rejects_trailing_bytes() {
+        let mut bytes = recording().serialize();
+        let len = bytes.len();
+        bytes.push(0);
+
+        assert_eq!(Recording::deserialize(&bytes).unwrap_err().offset(), len);
+    }
+
+    #[test]
+    fn deserialize_rejects_corrupt_input() {
+        let bytes = recording().serialize();
+
+        let mut magic = bytes.clone();
+        magic[MAGIC.len() - 1] = b'2';
+        assert_eq!(Recording::deserialize(&magic).unwrap_err().offset(), 0);
+
+        let offset = first_command_offset();
+        let mut tag = bytes.clone();
+        tag[offset] = 9;
+        assert_eq!(Recording::deserialize(&tag).unwrap_err().offset(), offset);
+
+        // More commands than there are.
+        let mut n_commands = bytes.clone();
+        n_commands[offset - 4..offset].copy_from_slice(&10_u32.to_le_bytes());
+        assert_eq!(
+            Recording::deserialize(&n_commands).unwrap_err().offset(),
+            bytes.len()
+        );
+
+        // A string longer than the input, and one that is not UTF-8.
+        let name_offset = offset + 1;
+        let mut len = bytes.clone();
+        len[name_offset..name_offset + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(
+            Recording::deserialize(&len).unwrap_err().offset(),
+            name_offset + 4
+        );
+        let mut utf8 = bytes.clone();
+        utf8[name_offset + 4] = 0xff;
+        assert_eq!(
+            Recording::deserialize(&utf8).unwrap_err().offset(),
+            name_offset
+        );
+
+        // The fill of the rect, which is neither 0 nor 1.
+        let mut writer = Writer::default();
+        for command in &recording().commands[..4] {
+            writer.command(command);
+        }
+        let fill_offset = offset + writer.buf.len() - 1;
+        let mut fill = bytes;
+        assert_eq!(fill[fill_offset], 1);
+        fill[fill_offset] = 2;
+        assert_eq!(
+            Recording::deserialize(&fill).unwrap_err().offset(),
+            fill_offset
+        );
+    }
+
+    #[test]
+    fn chunks_are_content_defined() {
+        let pixel = |x| DrawCommand::Pixel {
+            point: (x, 0),
+            style: STYLE,
+        };
+        let chunk_contents = |recording: &Recording| {
+            recording
+                .chunks()
+                .into_iter()
+                .map(|range| recording.commands[range].to_vec())
+                .collect::<Vec<_>>()
+        };
+
+        let recording = Recording {
+            size: (1000, 1),
+            commands: (0..1000).map(pixel).collect(),
+        };
+        let chunks = chunk_contents(&recording);
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|chunk| chunk.len() <= MAX_CHUNK_LEN));
+        assert_eq!(chunks.concat(), recording.commands);
+
+        // Only the chunk the command is inserted into changes.
+        let mut inserted = recording.clone();
+        inserted.commands.insert(10, pixel(-1));
+        let inserted_chunks = chunk_contents(&inserted);
+        let n_new = inserted_chunks
+            .iter()
+            .filter(|chunk| !chunks.contains(chunk))
+            .count();
+        assert!(n_new <= 2, "{n_new} new chunks");
+    }
+
+    #[test]
+    fn diff() {
+        let recording = recording();
+        assert!(recording.diff(&recording).is_empty());
+        assert!(recording.diff(&recording.clone()).is_empty());
+
+        let mut changed = recording.clone();
+        changed.commands[1] = DrawCommand::Pixel {
+            point: (1, 3),
+            style: STYLE,
+        };
+        changed.commands[7] = DrawCommand::EndGroup;
+        assert_eq!(
+            recording.diff(&changed),
+            [CommandDiff::Changed(1), CommandDiff::Changed(7)]
+        );
+
+        let mut longer = recording.clone();
+        longer.commands.push(DrawCommand::EndGroup);
+        longer.commands.push(DrawCommand::EndGroup);
+        assert_eq!(
+            recording.diff(&longer),
+            [CommandDiff::Added(9), CommandDiff::Added(10)]
+        );
+        assert_eq!(
+            longer.diff(&recording),
+            [CommandDiff::Removed(9), CommandDiff::Removed(10)]
+        );
+
+        let mut shorter = changed;
+        shorter.commands.truncate(4);
+        assert_eq!(
+            recording.diff(&shorter),
+            [
+                CommandDiff::Changed(1),
+                CommandDiff::Removed(4),
+                CommandDiff::Removed(5),
+                CommandDiff::Removed(6),
+                CommandDiff::Removed(7),
+                CommandDiff::Removed(8),
+            ]
+        );
+    }
+}
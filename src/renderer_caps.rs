@@ -0,0 +1,86 @@
+use gtk::{gsk, prelude::*};
+
+/// The render nodes a renderer draws correctly, so that drawing can fall
+/// back to simpler nodes for renderers that do not.
+///
+/// GPU renderers draw everything the backends produce, while the Cairo
+/// renderer, used without a GPU and for exports, draws some nodes on the CPU
+/// too slowly to redraw plots on every frame. This is
+/// set with [`PaintableBackend::set_renderer_caps`] or
+/// [`SnapshotBackend::set_renderer_caps`], or on the [`Paintable`] for
+/// every backend drawing on it, which [`PlotView`] does for the renderer of
+/// its window.
+///
+/// [`PaintableBackend::set_renderer_caps`]: crate::PaintableBackend::set_renderer_caps
+/// [`SnapshotBackend::set_renderer_caps`]: crate::SnapshotBackend::set_renderer_caps
+/// [`Paintable`]: crate::Paintable
+/// [`PlotView`]: crate::PlotView
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RendererCaps {
+    /// Whether mask nodes are drawn, used to keep translucent strokes from
+    /// blending over the fill of shapes and to highlight hit regions.
+    ///
+    /// Mask nodes were added in GTK 4.10, and every renderer draws them
+    /// since. Without them, the fill and stroke are drawn separately,
+    /// darkening the inner half of translucent strokes, and highlights are
+    /// drawn with color matrices and outlined bounds.
+    pub masks: bool,
+    /// Whether blurs are drawn, used for the shadows of
+    /// [`TextHalo::Shadow`].
+    ///
+    /// Without them, shadows are drawn unblurred.
+    ///
+    /// [`TextHalo::Shadow`]: crate::TextHalo::Shadow
+    pub blurs: bool,
+    /// Whether strokes are drawn with gradients, used for the segments of
+    /// paths drawn with `draw_mapped_path`.
+    ///
+    /// Without them, each segment is drawn in the color of its middle.
+    pub gradient_strokes: bool,
+}
+
+impl Default for RendererCaps {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+impl RendererCaps {
+    /// The capabilities of renderers that draw every node.
+    pub const ALL: Self = Self {
+        masks: true,
+        blurs: true,
+        gradient_strokes: true,
+    };
+
+    /// The capabilities of the Cairo renderer of GTK 4.10 or later, which
+    /// blurs on the CPU and draws every gradient stroke into a surface of
+    /// its own.
+    pub const CAIRO: Self = Self {
+        masks: true,
+        blurs: false,
+        gradient_strokes: false,
+    };
+
+    /// Returns the capabilities of `renderer`, with the version of GTK that
+    /// is running.
+    pub fn for_renderer(renderer: &impl IsA<gsk::Renderer>) -> Self {
+        let caps = if renderer.is::<gsk::CairoRenderer>() {
+            Self::CAIRO
+        } else {
+            Self::ALL
+        };
+        Self {
+            masks: gtk::check_version(4, 10, 0).is_none(),
+            ..caps
+        }
+    }
+
+    /// Returns the capabilities of the renderer of the window of `widget`,
+    /// or `None` if it is not realized.
+    pub fn for_widget(widget: &impl IsA<gtk::Widget>) -> Option<Self> {
+        let renderer = widget.native()?.renderer()?;
+        Some(Self::for_renderer(&renderer))
+    }
+}
@@ -0,0 +1,142 @@
+use gtk::prelude::*;
+use plotters::{chart::ChartBuilder, style::TextStyle};
+use plotters_backend::{BackendCoord, DrawingBackend};
+
+use crate::{BackendContext, Paintable, PaintableBackend, SnapshotBackend};
+
+/// The space between labels and the plotting area, in pixels.
+const LABEL_PADDING: u32 = 8;
+
+/// The layout of two charts sharing a plotting area, one with the y axis
+/// on the left, the primary axis, and one with the y axis on the right, the
+/// secondary axis, such as temperature and rainfall over the same days.
+///
+/// Each chart is drawn as its own layer with its own y scale, with
+/// [`SecondaryAxisLayout::draw`]. Plotters draws the labels of a chart on
+/// every side that has a label area, so each chart only gets the label area
+/// of its own axis, and a margin instead of the label area of the other,
+/// which keeps the plotting areas of both aligned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SecondaryAxisLayout {
+    /// The margin around both charts, in pixels.
+    pub margin: u32,
+    /// The height of the area of the x axis labels, in pixels.
+    pub x_label_area_size: u32,
+    /// The width of the area of the primary y axis labels, in pixels.
+    pub primary_label_area_size: u32,
+    /// The width of the area of the secondary y axis labels, in pixels.
+    pub secondary_label_area_size: u32,
+}
+
+impl Default for SecondaryAxisLayout {
+    fn default() -> Self {
+        Self {
+            margin: 10,
+            x_label_area_size: 30,
+            primary_label_area_size: 40,
+            secondary_label_area_size: 40,
+        }
+    }
+}
+
+impl SecondaryAxisLayout {
+    /// Sets the widths of the label areas of the y axes to fit the widest of
+    /// `primary_labels` and `secondary_labels`, such as the formatted tick
+    /// labels, when drawn with `style` on `backend`.
+    pub fn fit_labels<DB: DrawingBackend>(
+        &mut self,
+        backend: &DB,
+        primary_labels: &[String],
+        secondary_labels: &[String],
+        style: &TextStyle<'_>,
+    ) {
+        let max_width = |labels: &[String]| {
+            labels
+                .iter()
+                .filter_map(|label| backend.estimate_text_size(label, style).ok())
+                .map(|(width, _)| width)
+                .max()
+                .unwrap_or(0)
+        };
+        self.primary_label_area_size = max_width(primary_labels) + LABEL_PADDING;
+        self.secondary_label_area_size = max_width(secondary_labels) + LABEL_PADDING;
+    }
+
+    /// Sets the margins and label areas of the chart of the primary axis.
+    pub fn apply_primary<DB: DrawingBackend>(&self, builder: &mut ChartBuilder<'_, '_, DB>) {
+        builder
+            .margin(self.margin)
+            .margin_right(self.margin + self.secondary_label_area_size)
+            .x_label_area_size(self.x_label_area_size)
+            .y_label_area_size(self.primary_label_area_size);
+    }
+
+    /// Sets the margins and label areas of the chart of the secondary axis.
+    ///
+    /// The x axis is not given a label area, so that its labels are only
+    /// drawn by the primary chart. The secondary chart should also disable
+    /// its mesh lines, such as with `configure_mesh().disable_mesh()`, to
+    /// not draw a second grid over the first.
+    pub fn apply_secondary<DB: DrawingBackend>(&self, builder: &mut ChartBuilder<'_, '_, DB>) {
+        builder
+            .margin(self.margin)
+            .margin_left(self.margin + self.primary_label_area_size)
+            .margin_bottom(self.margin + self.x_label_area_size)
+            .right_y_label_area_size(self.secondary_label_area_size);
+    }
+
+    /// Returns the upper left and bottom right corners of the plotting area
+    /// shared by both charts, within a drawing of `size`.
+    pub fn plotting_rect(&self, (width, height): (u32, u32)) -> (BackendCoord, BackendCoord) {
+        let left = self.margin + self.primary_label_area_size;
+        let right = self.margin + self.secondary_label_area_size;
+        let bottom = self.margin + self.x_label_area_size;
+        (
+            (left as i32, self.margin as i32),
+            (
+                width.saturating_sub(right) as i32,
+                height.saturating_sub(bottom) as i32,
+            ),
+        )
+    }
+
+    /// Draws the chart of the primary axis with `primary` and the chart of
+    /// the secondary axis with `secondary` above it, as one frame of
+    /// `paintable`.
+    ///
+    /// Each closure is given a [`SnapshotBackend`] of its own layer of the
+    /// size of the paintable, on which the chart should be built with
+    /// [`SecondaryAxisLayout::apply_primary`] or
+    /// [`SecondaryAxisLayout::apply_secondary`] respectively.
+    pub fn draw(
+        &self,
+        paintable: &Paintable,
+        primary: impl FnOnce(SnapshotBackend),
+        secondary: impl FnOnce(SnapshotBackend),
+    ) {
+        let size = paintable.size();
+        let context = BackendContext::new();
+
+        let primary_snapshot = gtk::Snapshot::new();
+        primary(SnapshotBackend::with_context(
+            &primary_snapshot,
+            size,
+            &context,
+        ));
+        let secondary_snapshot = gtk::Snapshot::new();
+        secondary(SnapshotBackend::with_context(
+            &secondary_snapshot,
+            size,
+            &context,
+        ));
+
+        let mut backend = PaintableBackend::with_context(paintable, &context);
+        let snapshot = backend.snapshot_mut();
+        for node in [primary_snapshot.to_node(), secondary_snapshot.to_node()]
+            .into_iter()
+            .flatten()
+        {
+            snapshot.append_node(node);
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use std::collections::HashSet;
+
+use gtk::{gio, glib, prelude::*, subclass::prelude::*};
+
+mod imp {
+    use std::cell::RefCell;
+
+    use super::*;
+
+    #[derive(Debug, Default)]
+    pub struct PlotSelectionModel {
+        pub(super) items: RefCell<Vec<gtk::StringObject>>,
+        /// The IDs of the items, to look them up without going through
+        /// the items.
+        pub(super) ids: RefCell<HashSet<String>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PlotSelectionModel {
+        const NAME: &'static str = "PlottersGtk4PlotSelectionModel";
+        type Type = super::PlotSelectionModel;
+        type Interfaces = (gio::ListModel,);
+    }
+
+    impl ObjectImpl for PlotSelectionModel {}
+
+    impl ListModelImpl for PlotSelectionModel {
+        fn item_type(&self) -> glib::Type {
+            gtk::StringObject::static_type()
+        }
+
+        fn n_items(&self) -> u32 {
+            self.items.borrow().len() as u32
+        }
+
+        fn item(&self, position: u32) -> Option<glib::Object> {
+            self.items
+                .borrow()
+                .get(position as usize)
+                .map(|item| item.clone().upcast())
+        }
+    }
+}
+
+glib::wrapper! {
+    /// The IDs of the selected elements of plots, in the order they were
+    /// selected.
+    ///
+    /// Elements are the content drawn within [`PaintableBackend::with_id`].
+    /// A selection model can be shared by multiple
+    /// [`PlotView`](crate::PlotView)s, so that selecting elements in one of
+    /// them highlights the elements of the same IDs in the others.
+    ///
+    /// This is a list of [`gtk::StringObject`]s of the IDs, so that it can
+    /// also be shown in list widgets, such as a list of the selected data
+    /// points next to the plots.
+    ///
+    /// This can be used on GTK UI files using its type name `PlottersGtk4PlotSelectionModel`.
+    ///
+    /// [`PaintableBackend::with_id`]: crate::PaintableBackend::with_id
+    pub struct PlotSelectionModel(ObjectSubclass<imp::PlotSelectionModel>)
+        @implements gio::ListModel;
+}
+
+impl PlotSelectionModel {
+    /// Creates a new selection model with nothing selected.
+    pub fn new() -> Self {
+        glib::Object::new()
+    }
+
+    /// Returns whether the element of `id` is selected.
+    pub fn is_selected(&self, id: &str) -> bool {
+        self.imp().ids.borrow().contains(id)
+    }
+
+    /// Returns the IDs of the selected elements.
+    pub fn selected_ids(&self) -> Vec<String> {
+        self.imp()
+            .items
+            .borrow()
+            .iter()
+            .map(|item| item.string().to_string())
+            .collect()
+    }
+
+    /// Selects the element of `id`, if it is not selected already.
+    pub fn select(&self, id: &str) {
+        if !self.imp().ids.borrow_mut().insert(id.to_string()) {
+            return;
+        }
+
+        let position = {
+            let mut items = self.imp().items.borrow_mut();
+            items.push(gtk::StringObject::new(id));
+            items.len() - 1
+        };
+        self.items_changed(position as u32, 0, 1);
+    }
+
+    /// Unselects the element of `id`, if it is selected.
+    pub fn unselect(&self, id: &str) {
+        if !self.imp().ids.borrow_mut().remove(id) {
+            return;
+        }
+
+        let position = {
+            let mut items = self.imp().items.borrow_mut();
+            let position = items.iter().position(|item| item.string() == id).unwrap();
+            items.remove(position);
+            position
+        };
+        self.items_changed(position as u32, 1, 0);
+    }
+
+    /// Selects only the elements of `ids`.
+    pub fn set_selected_ids(&self, ids: &[impl AsRef<str>]) {
+        let mut new_id_set = HashSet::new();
+        let new_ids = ids
+            .iter()
+            .map(|id| id.as_ref())
+            .filter(|id| new_id_set.insert(id.to_string()))
+            .collect::<Vec<_>>();
+        if self.selected_ids() == new_ids {
+            return;
+        }
+
+        self.imp().ids.replace(new_id_set);
+        let n_removed = {
+            let mut items = self.imp().items.borrow_mut();
+            let n_removed = items.len();
+            *items = new_ids.into_iter().map(gtk::StringObject::new).collect();
+            n_removed
+        };
+        self.items_changed(0, n_removed as u32, self.n_items());
+    }
+
+    /// Unselects all elements.
+    pub fn clear(&self) {
+        self.set_selected_ids(&[] as &[&str]);
+    }
+}
+
+impl Default for PlotSelectionModel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,150 @@
+use gtk::{gdk, glib, prelude::*, subclass::prelude::*};
+use plotters::style::Color;
+
+use crate::common::BackendColorExt;
+
+/// The marker drawn next to the name of a [`Series`] in a
+/// [`Legend`](crate::Legend).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, glib::Enum)]
+#[enum_type(name = "PlottersGtk4SeriesMarker")]
+pub enum SeriesMarker {
+    #[default]
+    Line,
+    Circle,
+    Square,
+    Triangle,
+    Cross,
+}
+
+mod imp {
+    use std::{
+        cell::{Cell, OnceCell},
+        sync::OnceLock,
+    };
+
+    use super::*;
+
+    #[derive(Debug, Default)]
+    pub struct Series {
+        pub(super) name: OnceCell<String>,
+        pub(super) color: OnceCell<gdk::RGBA>,
+        pub(super) marker: Cell<SeriesMarker>,
+        pub(super) is_hidden: Cell<bool>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for Series {
+        const NAME: &'static str = "PlottersGtk4Series";
+        type Type = super::Series;
+    }
+
+    impl ObjectImpl for Series {
+        fn properties() -> &'static [glib::ParamSpec] {
+            static PROPERTIES: OnceLock<Vec<glib::ParamSpec>> = OnceLock::new();
+
+            PROPERTIES.get_or_init(|| {
+                vec![
+                    glib::ParamSpecString::builder("name")
+                        .construct_only()
+                        .build(),
+                    glib::ParamSpecBoxed::builder::<gdk::RGBA>("color")
+                        .construct_only()
+                        .build(),
+                    glib::ParamSpecEnum::builder::<SeriesMarker>("marker")
+                        .construct_only()
+                        .build(),
+                    glib::ParamSpecBoolean::builder("visible")
+                        .default_value(true)
+                        .explicit_notify()
+                        .build(),
+                ]
+            })
+        }
+
+        fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+            match pspec.name() {
+                "name" => {
+                    let name = value.get::<Option<String>>().unwrap();
+                    self.name.set(name.unwrap_or_default()).unwrap();
+                }
+                "color" => {
+                    // Boxed properties have no default value, so fall back to
+                    // black when the series is built without a color, such as
+                    // from a UI file.
+                    let color = value.get::<Option<gdk::RGBA>>().unwrap();
+                    self.color.set(color.unwrap_or(gdk::RGBA::BLACK)).unwrap();
+                }
+                "marker" => {
+                    let marker = value.get().unwrap();
+                    self.marker.set(marker);
+                }
+                "visible" => {
+                    let is_visible = value.get().unwrap();
+                    self.obj().set_visible(is_visible);
+                }
+                _ => unimplemented!(),
+            }
+        }
+
+        fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            match pspec.name() {
+                "name" => self.obj().name().into(),
+                "color" => self.obj().color().into(),
+                "marker" => self.obj().marker().into(),
+                "visible" => self.obj().is_visible().into(),
+                _ => unimplemented!(),
+            }
+        }
+    }
+}
+
+glib::wrapper! {
+    /// A named group of plotted elements that can be shown in a
+    /// [`Legend`](crate::Legend).
+    ///
+    /// Series are registered with [`Paintable::register_series`](crate::Paintable::register_series).
+    pub struct Series(ObjectSubclass<imp::Series>);
+}
+
+impl Series {
+    /// Creates a new series with the given name, color, and marker.
+    pub fn new(name: &str, color: &impl Color, marker: SeriesMarker) -> Self {
+        glib::Object::builder()
+            .property("name", name)
+            .property("color", color.to_backend_color().to_rgba())
+            .property("marker", marker)
+            .build()
+    }
+
+    /// Returns the name of the series.
+    pub fn name(&self) -> String {
+        self.imp().name.get().unwrap().clone()
+    }
+
+    /// Returns the color of the series.
+    pub fn color(&self) -> gdk::RGBA {
+        *self.imp().color.get().unwrap()
+    }
+
+    /// Returns the marker of the series.
+    pub fn marker(&self) -> SeriesMarker {
+        self.imp().marker.get()
+    }
+
+    /// Sets whether the series should be drawn.
+    ///
+    /// It is up to the drawing code to skip hidden series.
+    pub fn set_visible(&self, is_visible: bool) {
+        if is_visible == self.is_visible() {
+            return;
+        }
+
+        self.imp().is_hidden.set(!is_visible);
+        self.notify("visible");
+    }
+
+    /// Returns whether the series should be drawn.
+    pub fn is_visible(&self) -> bool {
+        !self.imp().is_hidden.get()
+    }
+}
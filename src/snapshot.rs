@@ -1,36 +1,566 @@
-use std::convert::Infallible;
+use std::{convert::Infallible, ops::Range};
 
-use gtk::{pango, prelude::*};
+use gtk::{
+    gdk,
+    graphene::{Point, Rect},
+    gsk, pango,
+    prelude::*,
+};
 use plotters_backend::{
     BackendColor, BackendCoord, BackendStyle, BackendTextStyle, DrawingBackend, DrawingErrorKind,
 };
 
-use crate::common;
+use crate::{
+    backend::ScopeState, colormaps::Colormap, common, optimize, BackendContext, Background,
+    GtkBackend, InvalidInputPolicy, Quality, RendererCaps, SnapshotScope, StyleClasses,
+    TextAttributes, TextCoverage, TextExtents, TextHalo,
+};
 
 /// Backend that draws to a [`gtk::Snapshot`].
+///
+/// Some primitives are batched, so the backend must be presented or dropped
+/// before the node of the snapshot is taken.
 #[derive(Debug)]
-pub struct SnapshotBackend<'a> {
-    snapshot: &'a gtk::Snapshot,
+pub struct SnapshotBackend {
+    snapshot: gtk::Snapshot,
+    context: BackendContext,
     layout: pango::Layout,
     size: (u32, u32),
+    cull_margin: Option<u32>,
+    options: common::Options,
+    style_classes: StyleClasses,
+    scratch: common::Scratch,
+    background: Option<Background>,
+    is_background_filled: bool,
 }
 
-impl<'a> SnapshotBackend<'a> {
+impl SnapshotBackend {
     /// Creates a new drawing backend backed with [`gtk::Snapshot`] with
     /// the given width and height.
-    pub fn new(snapshot: &'a gtk::Snapshot, (w, h): (u32, u32)) -> Self {
-        let font_map = pangocairo::FontMap::default();
-        let context = font_map.create_context();
-        let layout = pango::Layout::new(&context);
+    ///
+    /// This creates new resources for drawing text. For plots redrawn often,
+    /// use [`SnapshotBackend::with_context`] with a [`BackendContext`] that
+    /// is kept across redraws instead.
+    pub fn new(snapshot: &gtk::Snapshot, size: (u32, u32)) -> Self {
+        Self::with_context(snapshot, size, &BackendContext::new())
+    }
+
+    /// Creates a new drawing backend backed with [`gtk::Snapshot`] with
+    /// the given width and height, reusing the resources of `context`.
+    pub fn with_context(
+        snapshot: &gtk::Snapshot,
+        (w, h): (u32, u32),
+        context: &BackendContext,
+    ) -> Self {
         Self {
-            snapshot,
-            layout,
+            snapshot: snapshot.clone(),
+            context: context.clone(),
+            layout: context.layout().clone(),
             size: (w, h),
+            cull_margin: None,
+            options: common::Options::default(),
+            style_classes: StyleClasses::default(),
+            scratch: common::Scratch::default(),
+            background: None,
+            is_background_filled: false,
+        }
+    }
+
+    /// Sets the snapshot drawn to, such as the one of the next frame of the
+    /// widget the backend is kept in.
+    ///
+    /// Along with [`SnapshotBackend::set_size`], this lets stateful widgets
+    /// keep one backend across [`WidgetImpl::snapshot`] calls, rather than
+    /// creating a new one for every frame. What was drawn is flushed to the
+    /// previous snapshot, and the background, if any, is filled again on the
+    /// new snapshot before the next primitive is drawn.
+    ///
+    /// [`WidgetImpl::snapshot`]: gtk::subclass::widget::WidgetImpl::snapshot
+    pub fn set_snapshot(&mut self, snapshot: &gtk::Snapshot) {
+        self.scratch.flush();
+        self.snapshot = snapshot.clone();
+        self.is_background_filled = false;
+    }
+
+    /// Sets the width and height of the drawing, such as when the widget it
+    /// is drawn for is resized.
+    ///
+    /// This keeps the text layout and the other settings of the backend, so
+    /// that one backend can draw several frames of different sizes. What is
+    /// already drawn is kept as is, while the background, if any, is filled
+    /// again for the new size before the next primitive is drawn. Charts
+    /// must be rebuilt from a new drawing area afterwards, as plotters reads
+    /// the size of the backend only when creating one.
+    pub fn set_size(&mut self, (w, h): (u32, u32)) {
+        if (w, h) == self.size {
+            return;
+        }
+
+        self.scratch.flush();
+        self.size = (w, h);
+        self.is_background_filled = false;
+        self.update_cull_bounds();
+    }
+
+    /// Sets what fills the target rect before anything is drawn, or `None`
+    /// to leave it as is, which is the default.
+    ///
+    /// The background is filled when the backend is first prepared, which
+    /// plotters does before drawing the first element, so this must be set
+    /// before drawing.
+    pub fn set_background(&mut self, background: Option<Background>) {
+        self.background = background;
+    }
+
+    /// Returns what fills the target rect before anything is drawn.
+    pub fn background(&self) -> Option<Background> {
+        self.background
+    }
+
+    /// Sets the multiplier applied to the stroke width of every drawn style.
+    ///
+    /// This is useful for drawing thinner or thicker strokes than what
+    /// [`BackendStyle::stroke_width`] allows, as it is only an integer.
+    pub fn set_stroke_width_scale(&mut self, scale: f32) {
+        self.options.stroke_width_scale = scale;
+    }
+
+    /// Returns the multiplier applied to the stroke width of every drawn style.
+    pub fn stroke_width_scale(&self) -> f32 {
+        self.options.stroke_width_scale
+    }
+
+    /// Sets the width used to draw strokes with a stroke width of zero, or
+    /// `None` to not draw them, as other plotters backends do.
+    ///
+    /// This defaults to `None`. See [`HairlineStyle`](crate::HairlineStyle)
+    /// for more details.
+    pub fn set_hairline_width(&mut self, width: Option<f32>) {
+        self.options.hairline_width = width;
+    }
+
+    /// Returns the width used to draw strokes with a stroke width of zero,
+    /// or `None` if they are not drawn.
+    pub fn hairline_width(&self) -> Option<f32> {
+        self.options.hairline_width
+    }
+
+    /// Sets the rule used to determine the interior of filled polygons.
+    ///
+    /// This defaults to [`gsk::FillRule::Winding`]. Use
+    /// [`gsk::FillRule::EvenOdd`] for self-intersecting polygons that should
+    /// have holes, such as star shapes.
+    pub fn set_fill_rule(&mut self, fill_rule: gsk::FillRule) {
+        self.options.fill_rule = fill_rule;
+    }
+
+    /// Returns the rule used to determine the interior of filled polygons.
+    pub fn fill_rule(&self) -> gsk::FillRule {
+        self.options.fill_rule
+    }
+
+    /// Sets the decoration drawn behind text, or `None` to draw text as is.
+    pub fn set_text_halo(&mut self, text_halo: Option<TextHalo>) {
+        self.options.text_halo = text_halo;
+    }
+
+    /// Returns the decoration drawn behind text.
+    pub fn text_halo(&self) -> Option<TextHalo> {
+        self.options.text_halo
+    }
+
+    /// Sets the extents of text that are measured and aligned to the text
+    /// anchor.
+    ///
+    /// Both measuring and drawing text use the same extents, so labels are
+    /// positioned consistently with their measured size.
+    pub fn set_text_extents(&mut self, text_extents: TextExtents) {
+        self.options.text_extents = text_extents;
+    }
+
+    /// Returns the extents of text that are measured and aligned to the text
+    /// anchor.
+    pub fn text_extents(&self) -> TextExtents {
+        self.options.text_extents
+    }
+
+    /// Sets whether text rotated by 90° or 270° is laid out vertically.
+    ///
+    /// Vertical layouts keep the glyphs of vertical scripts, such as CJK,
+    /// upright, as is appropriate for y-axis labels in these scripts, while
+    /// rotating other scripts along the line. The text always runs from top
+    /// to bottom.
+    pub fn set_vertical_text(&mut self, is_vertical_text: bool) {
+        self.options.vertical_text = is_vertical_text;
+    }
+
+    /// Returns whether text rotated by 90° or 270° is laid out vertically.
+    pub fn is_vertical_text(&self) -> bool {
+        self.options.vertical_text
+    }
+
+    /// Sets whether strokes are snapped to the pixel grid.
+    ///
+    /// With pixel snapping, stroke widths of at least one pixel are rounded
+    /// to whole pixels, and odd-width strokes are offset by half a pixel, so
+    /// that axes, grids, and rect borders are drawn crisp, matching the look
+    /// of the bitmap backend. This is meant for displays with a scale factor
+    /// of 1, where the logical pixels are the device pixels.
+    pub fn set_pixel_snap(&mut self, is_pixel_snap: bool) {
+        self.options.pixel_snap = is_pixel_snap;
+    }
+
+    /// Returns whether strokes are snapped to the pixel grid.
+    pub fn is_pixel_snap(&self) -> bool {
+        self.options.pixel_snap
+    }
+
+    /// Sets how far outside of the backend bounds primitives are still
+    /// drawn, in pixels, or `None` to draw all primitives, which is the
+    /// default.
+    ///
+    /// With a margin, pixels, lines, circles, and polygons entirely beyond
+    /// it are skipped, and segments of paths beyond it are left out, instead
+    /// of being turned into nodes that are only clipped when composited.
+    /// This reduces the node count of zoomed-in views of large datasets. The
+    /// margin should cover anything drawn past the points, such as markers
+    /// drawn with [`plotters`] elements around them.
+    ///
+    /// Nothing is skipped within [`Self::with_transform`], as the transform
+    /// may move primitives into view.
+    pub fn set_cull_margin(&mut self, margin: Option<u32>) {
+        self.cull_margin = margin;
+        self.update_cull_bounds();
+    }
+
+    /// Returns how far outside of the backend bounds primitives are still
+    /// drawn.
+    pub fn cull_margin(&self) -> Option<u32> {
+        self.cull_margin
+    }
+
+    fn update_cull_bounds(&mut self) {
+        self.options.cull_bounds = self
+            .cull_margin
+            .map(|margin| common::cull_bounds(self.size, margin));
+    }
+
+    /// Starts a scope of state pushed on the snapshot, such as a clip,
+    /// returning the snapshot to push it on and the cull bounds to restore
+    /// when leaving it.
+    ///
+    /// Culling is disabled within the scope, as it may move primitives into
+    /// view.
+    pub(crate) fn enter_scope(&mut self) -> (gtk::Snapshot, ScopeState) {
+        self.scratch.flush();
+        let scope_state = ScopeState {
+            cull_bounds: self.options.cull_bounds.take(),
+            tile_size: None,
+        };
+        (self.snapshot.clone(), scope_state)
+    }
+
+    /// Ends a scope started with [`Self::enter_scope`], before the state
+    /// pushed on the snapshot is popped.
+    pub(crate) fn leave_scope(&mut self, scope_state: ScopeState) {
+        self.options.cull_bounds = scope_state.cull_bounds;
+        self.scratch.flush();
+    }
+
+    /// Sets the trade-off between rendering speed and quality.
+    ///
+    /// See [`Quality`] for what each preset changes. This defaults to
+    /// [`Quality::Normal`].
+    pub fn set_quality(&mut self, quality: Quality) {
+        if quality == self.options.quality {
+            return;
         }
+
+        self.scratch.flush();
+        self.options.quality = quality;
+        self.layout = self.context.layout_for_quality(quality);
+    }
+
+    /// Returns the trade-off between rendering speed and quality.
+    pub fn quality(&self) -> Quality {
+        self.options.quality
+    }
+
+    /// Sets the capabilities of the renderer the drawing is rendered with,
+    /// falling back to simpler nodes for what it does not support.
+    ///
+    /// This defaults to [`RendererCaps::ALL`].
+    pub fn set_renderer_caps(&mut self, renderer_caps: RendererCaps) {
+        self.options.renderer_caps = renderer_caps;
+    }
+
+    /// Returns the capabilities of the renderer the drawing is rendered
+    /// with.
+    pub fn renderer_caps(&self) -> RendererCaps {
+        self.options.renderer_caps
+    }
+
+    /// Sets what happens when a primitive has invalid input, such as
+    /// coordinates, radii, stroke widths, or font sizes that are out of range
+    /// because of `NaN` or infinite values in the plotted data.
+    ///
+    /// By default, such primitives are skipped.
+    pub fn set_invalid_input_policy(&mut self, policy: InvalidInputPolicy) {
+        self.options.invalid_input_policy = policy;
+    }
+
+    /// Returns what happens when a primitive has invalid input.
+    pub fn invalid_input_policy(&self) -> InvalidInputPolicy {
+        self.options.invalid_input_policy
+    }
+
+    /// Sets the typographic attributes applied to all drawn text.
+    ///
+    /// For example, enabling [`TextAttributes::tabular_figures`] aligns
+    /// numeric axis tick labels in columns.
+    pub fn set_text_attributes(&mut self, text_attributes: TextAttributes) {
+        self.options.text_attributes = text_attributes;
+    }
+
+    /// Returns the typographic attributes applied to all drawn text.
+    pub fn text_attributes(&self) -> TextAttributes {
+        self.options.text_attributes
+    }
+
+    /// Sets the font families that are tried, in order, for characters that
+    /// the family of the text style does not have.
+    ///
+    /// Which font is picked for such characters otherwise depends on the
+    /// system's font configuration, which may end up with monochrome emoji or
+    /// missing glyphs. For example, `&["Noto Color Emoji"]` makes sure emoji in
+    /// category labels are drawn in color. Color glyphs are drawn with their
+    /// own colors rather than the text style's color. Whether they are can be
+    /// checked with [`SnapshotBackend::text_coverage`].
+    pub fn set_fallback_families(&mut self, families: &[&str]) {
+        self.options.fallback_families = families.iter().map(|family| family.to_string()).collect();
+    }
+
+    /// Returns the font families that are tried for characters that the
+    /// family of the text style does not have.
+    pub fn fallback_families(&self) -> &[String] {
+        &self.options.fallback_families
+    }
+
+    /// Returns how `text` is covered by the fonts it is drawn with in
+    /// `style`, with the fallback families of this backend.
+    ///
+    /// This verifies that the fonts of the system draw labels as intended,
+    /// such as emoji in color rather than in monochrome or as boxes, so
+    /// that fallback families can be set or text replaced otherwise.
+    pub fn text_coverage<TStyle: BackendTextStyle>(
+        &self,
+        text: &str,
+        style: &TStyle,
+    ) -> TextCoverage {
+        common::text_coverage(&self.options, &self.layout, text, style)
+    }
+
+    /// Draws the current contents of `paintable`, scaled to fit the rect
+    /// from `upper_left` to `bottom_right`.
+    ///
+    /// This can be used to compose multiple independently-updated plots,
+    /// such as other [`Paintable`](crate::Paintable)s, into one scene.
+    pub fn draw_paintable(
+        &mut self,
+        paintable: &impl IsA<gdk::Paintable>,
+        upper_left: BackendCoord,
+        bottom_right: BackendCoord,
+    ) -> Result<(), DrawingErrorKind<Infallible>> {
+        common::draw_paintable(
+            &self.snapshot,
+            &mut self.scratch,
+            paintable,
+            upper_left,
+            bottom_right,
+        )
+    }
+
+    /// Strokes a path colored by the values of its points, mapping `value_range`
+    /// onto `colormap`.
+    ///
+    /// The color is interpolated along each segment, such as for sensor data
+    /// colored by magnitude. Each segment is a separate node, so prefer
+    /// [`DrawingBackend::draw_path`] for paths of a single color.
+    pub fn draw_mapped_path<I: IntoIterator<Item = (BackendCoord, f64)>>(
+        &mut self,
+        raw_path: I,
+        value_range: Range<f64>,
+        colormap: Colormap,
+        stroke_width: u32,
+    ) -> Result<(), DrawingErrorKind<Infallible>> {
+        common::draw_mapped_path(
+            &self.snapshot,
+            &self.options,
+            &mut self.scratch,
+            raw_path,
+            &value_range,
+            colormap,
+            stroke_width,
+        )
+    }
+
+    /// Draws `paintable` as a marker centered on `center`, scaled to fit a
+    /// square of `size` while keeping its aspect ratio.
+    ///
+    /// This can be used to draw data points as icons, such as a
+    /// [`gtk::IconPaintable`] of weather symbols, or avatars loaded as
+    /// [`gdk::Texture`]s.
+    pub fn draw_paintable_marker(
+        &mut self,
+        paintable: &impl IsA<gdk::Paintable>,
+        center: BackendCoord,
+        size: u32,
+    ) -> Result<(), DrawingErrorKind<Infallible>> {
+        common::draw_paintable_marker(&self.snapshot, &mut self.scratch, paintable, center, size)
+    }
+
+    /// Returns the snapshot being drawn to, to draw with GTK APIs in between
+    /// plotters primitives, such as [`gtk::IconPaintable`]s or badges at
+    /// exact plot coordinates.
+    ///
+    /// What is drawn on the returned scope is appended when it is dropped,
+    /// without leaking the state of the snapshot, see [`SnapshotScope`].
+    pub fn snapshot_mut(&mut self) -> SnapshotScope<'_> {
+        self.scratch.flush();
+        SnapshotScope::new(&self.snapshot)
+    }
+
+    /// Calls `f` with the drawing transformed by `transform`.
+    ///
+    /// Everything drawn within `f` is wrapped in a [`gsk::TransformNode`],
+    /// which is useful for custom elements that rotate, skew, or scale a
+    /// group of primitives.
+    pub fn with_transform<R>(
+        &mut self,
+        transform: &gsk::Transform,
+        f: impl FnOnce(&mut Self) -> R,
+    ) -> R {
+        let snapshot = self.snapshot.clone();
+
+        self.scratch.flush();
+        snapshot.save();
+        snapshot.transform(Some(transform));
+        let cull_bounds = self.options.cull_bounds.take();
+        let ret = f(self);
+        self.options.cull_bounds = cull_bounds;
+        self.scratch.flush();
+        snapshot.restore();
+
+        ret
+    }
+
+    /// Calls `f` with everything drawn within it only shown when drawn on a
+    /// [`Paintable`] that is drawn at `min_scale` of its size or more.
+    ///
+    /// See [`PaintableBackend::with_min_scale`].
+    ///
+    /// [`Paintable`]: crate::Paintable
+    /// [`PaintableBackend::with_min_scale`]: crate::PaintableBackend::with_min_scale
+    pub fn with_min_scale<R>(&mut self, min_scale: f32, f: impl FnOnce(&mut Self) -> R) -> R {
+        let snapshot = self.snapshot.clone();
+
+        self.scratch.flush();
+        optimize::push_lod_group(&snapshot, min_scale);
+        let ret = f(self);
+        self.scratch.flush();
+        snapshot.pop();
+
+        ret
+    }
+
+    /// Calls `f` with the drawing inset by `margins`, such as the padding of
+    /// the widget the plot is shown in.
+    ///
+    /// Within `f`, the origin is at the top left corner of the inner rect,
+    /// the size of the backend is the size of the inner rect, and everything
+    /// drawn is clipped to it. This keeps room around the chart for GTK
+    /// widgets overlaid on the plot, such as buttons or a legend, while the
+    /// coordinates of the chart stay relative to its own area.
+    pub fn with_inset<R>(&mut self, margins: &gtk::Border, f: impl FnOnce(&mut Self) -> R) -> R {
+        let snapshot = self.snapshot.clone();
+        let (width, height) = self.size;
+        let left = margins.left().max(0) as u32;
+        let top = margins.top().max(0) as u32;
+        let inner_size = (
+            width.saturating_sub(left + margins.right().max(0) as u32),
+            height.saturating_sub(top + margins.bottom().max(0) as u32),
+        );
+
+        self.scratch.flush();
+        snapshot.save();
+        snapshot.translate(&Point::new(left as f32, top as f32));
+        snapshot.push_clip(&Rect::new(
+            0.0,
+            0.0,
+            inner_size.0 as f32,
+            inner_size.1 as f32,
+        ));
+        let outer_size = std::mem::replace(&mut self.size, inner_size);
+        let cull_bounds = self.options.cull_bounds;
+        // Keep culling disabled within transforms.
+        if cull_bounds.is_some() {
+            self.update_cull_bounds();
+        }
+        let ret = f(self);
+        self.size = outer_size;
+        self.options.cull_bounds = cull_bounds;
+        self.scratch.flush();
+        snapshot.pop();
+        snapshot.restore();
+
+        ret
+    }
+
+    /// Calls `f` with the text drawn and measured within it using
+    /// `text_attributes`, instead of the attributes set with
+    /// [`Self::set_text_attributes`].
+    pub fn with_text_attributes<R>(
+        &mut self,
+        text_attributes: TextAttributes,
+        f: impl FnOnce(&mut Self) -> R,
+    ) -> R {
+        let prev = std::mem::replace(&mut self.options.text_attributes, text_attributes);
+        let ret = f(self);
+        self.options.text_attributes = prev;
+
+        ret
+    }
+
+    /// Sets the styles of the classes of [`Self::with_class`].
+    pub fn set_style_classes(&mut self, style_classes: StyleClasses) {
+        self.style_classes = style_classes;
+    }
+
+    /// Returns the styles of the classes of [`Self::with_class`].
+    pub fn style_classes(&self) -> &StyleClasses {
+        &self.style_classes
+    }
+
+    /// Calls `f` with the primitives drawn within it using the style of
+    /// `class`, set with [`Self::set_style_classes`].
+    ///
+    /// The style of the class overrides the color and stroke width of the
+    /// drawn primitives. Classes without a style keep the style of the
+    /// enclosing class, if any.
+    pub fn with_class<R>(&mut self, class: &str, f: impl FnOnce(&mut Self) -> R) -> R {
+        let Some(class_style) = self.style_classes.get(class).copied() else {
+            return f(self);
+        };
+
+        let prev = std::mem::replace(&mut self.options.class_style, class_style);
+        let ret = f(self);
+        self.options.class_style = prev;
+
+        ret
     }
 }
 
-impl<'a> DrawingBackend for SnapshotBackend<'a> {
+impl DrawingBackend for SnapshotBackend {
     type ErrorType = Infallible;
 
     #[inline]
@@ -39,10 +569,17 @@ impl<'a> DrawingBackend for SnapshotBackend<'a> {
     }
 
     fn ensure_prepared(&mut self) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        if let Some(background) = self.background.filter(|_| !self.is_background_filled) {
+            let (w, h) = self.size;
+            background.append_to(&self.snapshot, &Rect::new(0.0, 0.0, w as f32, h as f32));
+            self.is_background_filled = true;
+        }
+
         Ok(())
     }
 
     fn present(&mut self) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        self.scratch.flush();
         Ok(())
     }
 
@@ -52,7 +589,13 @@ impl<'a> DrawingBackend for SnapshotBackend<'a> {
         point: BackendCoord,
         color: BackendColor,
     ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
-        common::draw_pixel(self.snapshot, point, color)
+        common::draw_pixel(
+            &self.snapshot,
+            &self.options,
+            &mut self.scratch,
+            point,
+            color,
+        )
     }
 
     #[inline]
@@ -62,7 +605,14 @@ impl<'a> DrawingBackend for SnapshotBackend<'a> {
         to: BackendCoord,
         style: &S,
     ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
-        common::draw_line(self.snapshot, from, to, style)
+        common::draw_line(
+            &self.snapshot,
+            &self.options,
+            &mut self.scratch,
+            from,
+            to,
+            style,
+        )
     }
 
     #[inline]
@@ -73,7 +623,16 @@ impl<'a> DrawingBackend for SnapshotBackend<'a> {
         style: &S,
         fill: bool,
     ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
-        common::draw_rect(self.snapshot, upper_left, bottom_right, style, fill)
+        common::draw_rect(
+            &self.snapshot,
+            &self.options,
+            &mut self.scratch,
+            self.size,
+            upper_left,
+            bottom_right,
+            style,
+            fill,
+        )
     }
 
     #[inline]
@@ -82,7 +641,13 @@ impl<'a> DrawingBackend for SnapshotBackend<'a> {
         raw_path: I,
         style: &S,
     ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
-        common::draw_path(self.snapshot, raw_path, style)
+        common::draw_path(
+            &self.snapshot,
+            &self.options,
+            &mut self.scratch,
+            raw_path,
+            style,
+        )
     }
 
     #[inline]
@@ -91,7 +656,13 @@ impl<'a> DrawingBackend for SnapshotBackend<'a> {
         vert: I,
         style: &S,
     ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
-        common::fill_polygon(self.snapshot, vert, style)
+        common::fill_polygon(
+            &self.snapshot,
+            &self.options,
+            &mut self.scratch,
+            vert,
+            style,
+        )
     }
 
     #[inline]
@@ -102,7 +673,15 @@ impl<'a> DrawingBackend for SnapshotBackend<'a> {
         style: &S,
         fill: bool,
     ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
-        common::draw_circle(self.snapshot, center, radius, style, fill)
+        common::draw_circle(
+            &self.snapshot,
+            &self.options,
+            &mut self.scratch,
+            center,
+            radius,
+            style,
+            fill,
+        )
     }
 
     #[inline]
@@ -111,7 +690,7 @@ impl<'a> DrawingBackend for SnapshotBackend<'a> {
         text: &str,
         style: &TStyle,
     ) -> Result<(u32, u32), DrawingErrorKind<Self::ErrorType>> {
-        common::estimate_text_size(&self.layout, text, style)
+        common::estimate_text_size(&self.options, &self.layout, text, style)
     }
 
     #[inline]
@@ -121,6 +700,216 @@ impl<'a> DrawingBackend for SnapshotBackend<'a> {
         style: &TStyle,
         pos: BackendCoord,
     ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
-        common::draw_text(self.snapshot, &self.layout, text, style, pos)
+        common::draw_text(
+            &self.snapshot,
+            &self.options,
+            &mut self.scratch,
+            &self.layout,
+            text,
+            style,
+            pos,
+        )
+    }
+}
+
+impl Drop for SnapshotBackend {
+    fn drop(&mut self) {
+        self.scratch.flush();
+    }
+}
+
+impl GtkBackend for SnapshotBackend {
+    fn gtk_snapshot(&mut self) -> &gtk::Snapshot {
+        self.scratch.flush();
+        &self.snapshot
+    }
+
+    fn stroke_width<S: BackendStyle>(&self, style: &S) -> f32 {
+        self.options.stroke_width(style)
+    }
+
+    fn draw_shape<F: BackendStyle, S: BackendStyle>(
+        &mut self,
+        path: &gsk::Path,
+        fill_style: &F,
+        stroke_style: &S,
+    ) -> Result<(), DrawingErrorKind<Infallible>> {
+        common::draw_shape(
+            &self.snapshot,
+            &self.options,
+            &mut self.scratch,
+            path,
+            fill_style,
+            stroke_style,
+        )
+    }
+
+    fn stroke_path<S: BackendStyle>(
+        &mut self,
+        path: &gsk::Path,
+        style: &S,
+    ) -> Result<(), DrawingErrorKind<Infallible>> {
+        common::stroke_path(
+            &self.snapshot,
+            &self.options,
+            &mut self.scratch,
+            path,
+            style,
+        )
+    }
+
+    fn fill_path<S: BackendStyle>(
+        &mut self,
+        path: &gsk::Path,
+        style: &S,
+    ) -> Result<(), DrawingErrorKind<Infallible>> {
+        common::fill_path(
+            &self.snapshot,
+            &self.options,
+            &mut self.scratch,
+            path,
+            style,
+        )
+    }
+
+    fn set_text_attributes(&mut self, text_attributes: TextAttributes) {
+        SnapshotBackend::set_text_attributes(self, text_attributes);
+    }
+
+    fn text_attributes(&self) -> TextAttributes {
+        SnapshotBackend::text_attributes(self)
+    }
+
+    fn set_text_extents(&mut self, text_extents: TextExtents) {
+        SnapshotBackend::set_text_extents(self, text_extents);
+    }
+
+    fn text_extents(&self) -> TextExtents {
+        SnapshotBackend::text_extents(self)
+    }
+
+    fn with_clip<R>(
+        &mut self,
+        clip: &Rect,
+        transform: Option<&gsk::Transform>,
+        f: impl FnOnce(&mut Self) -> R,
+    ) -> R {
+        let (snapshot, scope_state) = self.enter_scope();
+        snapshot.push_clip(clip);
+        snapshot.save();
+        snapshot.transform(transform);
+        let ret = f(self);
+        self.leave_scope(scope_state);
+        snapshot.restore();
+        snapshot.pop();
+
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use plotters::style::{IntoFont, TextStyle, BLACK};
+
+    use super::*;
+    use crate::headless;
+
+    /// Returns the corners of the box of the pixels of `texture` that are
+    /// not transparent.
+    fn ink_box(texture: &gdk::Texture) -> (BackendCoord, BackendCoord) {
+        let (width, height) = (texture.width() as usize, texture.height() as usize);
+        let mut pixels = vec![0; width * height * 4];
+        texture.download(&mut pixels, width * 4);
+
+        let mut min = (i32::MAX, i32::MAX);
+        let mut max = (i32::MIN, i32::MIN);
+        for (index, pixel) in pixels.chunks_exact(4).enumerate() {
+            if pixel[3] != 0 {
+                let (x, y) = ((index % width) as i32, (index / width) as i32);
+                min = (min.0.min(x), min.1.min(y));
+                max = (max.0.max(x + 1), max.1.max(y + 1));
+            }
+        }
+        (min, max)
+    }
+
+    /// Draws `text` at `pos` with `text_extents`, returning the measured
+    /// size and the box of the drawn pixels.
+    fn measure_and_draw(
+        text: &str,
+        pos: BackendCoord,
+        text_extents: TextExtents,
+    ) -> ((u32, u32), (BackendCoord, BackendCoord)) {
+        let style = TextStyle::from(("sans-serif", 20).into_font()).color(&BLACK);
+        let mut size = (0, 0);
+        let texture = headless::render_to_texture((300, 100), |mut backend| {
+            backend.set_text_extents(text_extents);
+            size = backend.estimate_text_size(text, &style).unwrap();
+            backend.draw_text(text, &style, pos).unwrap();
+        })
+        .unwrap();
+        (size, ink_box(&texture))
+    }
+
+    #[test]
+    fn ink_extents_match_drawn_text() {
+        headless::test_synced(|| {
+            for text in ["Plot", "jpg", "x²", "1,234.5"] {
+                let ((width, height), (min, max)) =
+                    measure_and_draw(text, (20, 20), TextExtents::Ink);
+
+                // Allow for antialiasing at the edges of the glyphs.
+                assert!((min.0 - 20).abs() <= 1, "{}: {:?}", text, min);
+                assert!((min.1 - 20).abs() <= 1, "{}: {:?}", text, min);
+                assert!(
+                    (max.0 - 20 - width as i32).abs() <= 1,
+                    "{}: {:?}",
+                    text,
+                    max
+                );
+                assert!(
+                    (max.1 - 20 - height as i32).abs() <= 1,
+                    "{}: {:?}",
+                    text,
+                    max
+                );
+            }
+        });
+    }
+
+    #[test]
+    fn logical_extents_contain_drawn_text() {
+        headless::test_synced(|| {
+            for text in ["Plot", "jpg", "1,234.5"] {
+                let ((width, height), (min, max)) =
+                    measure_and_draw(text, (20, 20), TextExtents::Logical);
+
+                assert!(min.0 >= 19 && min.1 >= 20, "{}: {:?}", text, min);
+                assert!(
+                    max.0 <= 21 + width as i32 && max.1 <= 20 + height as i32,
+                    "{}: {:?}",
+                    text,
+                    max
+                );
+            }
+        });
+    }
+
+    #[test]
+    fn text_coverage_reports_unknown_glyphs() {
+        headless::test_synced(|| {
+            let style = TextStyle::from(("sans-serif", 20).into_font()).color(&BLACK);
+            let snapshot = gtk::Snapshot::new();
+            let backend = SnapshotBackend::new(&snapshot, (100, 100));
+
+            let coverage = backend.text_coverage("Plot", &style);
+            assert_eq!(coverage.n_unknown_glyphs, 0);
+            assert!(!coverage.has_color_glyphs);
+
+            // A private use character of the last plane, which fonts do not
+            // have.
+            let coverage = backend.text_coverage("Plot \u{10FFFD}", &style);
+            assert_eq!(coverage.n_unknown_glyphs, 1);
+        });
     }
 }
@@ -0,0 +1,203 @@
+use std::ops::Range;
+
+use gtk::{gdk, graphene::Point, gsk, prelude::*};
+
+use crate::{InvalidSizeError, Paintable};
+
+/// A small line plot without axes, drawn directly as render nodes.
+///
+/// This is for dashboards and lists with dozens of tiny plots, where setting
+/// up a plotters chart and backend for each of them is wasteful. The values
+/// are spaced evenly across the width, and non-finite values leave a gap in
+/// the line.
+#[derive(Debug)]
+pub struct Sparkline {
+    paintable: Paintable,
+    color: gdk::RGBA,
+    line_width: f32,
+    value_range: Option<Range<f64>>,
+    baseline: Option<f64>,
+    baseline_color: gdk::RGBA,
+    shows_last_point: bool,
+    last_point_radius: f32,
+}
+
+impl Sparkline {
+    /// Creates a sparkline of the given size.
+    pub fn new(size: (u32, u32)) -> Result<Self, InvalidSizeError> {
+        Ok(Self {
+            paintable: Paintable::new(size)?,
+            color: gdk::RGBA::new(0.21, 0.52, 0.89, 1.0),
+            line_width: 1.5,
+            value_range: None,
+            baseline: None,
+            baseline_color: gdk::RGBA::new(0.5, 0.5, 0.5, 0.5),
+            shows_last_point: true,
+            last_point_radius: 2.0,
+        })
+    }
+
+    /// Returns the paintable the sparkline is drawn on.
+    pub fn paintable(&self) -> &Paintable {
+        &self.paintable
+    }
+
+    /// Sets the color of the line and the last point.
+    pub fn set_color(&mut self, color: gdk::RGBA) {
+        self.color = color;
+    }
+
+    /// Returns the color of the line and the last point.
+    pub fn color(&self) -> gdk::RGBA {
+        self.color
+    }
+
+    /// Sets the width of the line, in pixels.
+    pub fn set_line_width(&mut self, line_width: f32) {
+        self.line_width = line_width;
+    }
+
+    /// Returns the width of the line, in pixels.
+    pub fn line_width(&self) -> f32 {
+        self.line_width
+    }
+
+    /// Sets the value range shown across the height, or `None` to fit the
+    /// drawn values, which is the default.
+    ///
+    /// A fixed range keeps sparklines of related values comparable.
+    pub fn set_value_range(&mut self, value_range: Option<Range<f64>>) {
+        self.value_range = value_range;
+    }
+
+    /// Returns the value range shown across the height, if fixed.
+    pub fn value_range(&self) -> Option<Range<f64>> {
+        self.value_range.clone()
+    }
+
+    /// Sets the value a horizontal reference line is drawn at, such as zero
+    /// or a target, or `None` to not draw it, which is the default.
+    pub fn set_baseline(&mut self, baseline: Option<f64>) {
+        self.baseline = baseline;
+    }
+
+    /// Returns the value the reference line is drawn at.
+    pub fn baseline(&self) -> Option<f64> {
+        self.baseline
+    }
+
+    /// Sets the color of the reference line.
+    pub fn set_baseline_color(&mut self, color: gdk::RGBA) {
+        self.baseline_color = color;
+    }
+
+    /// Returns the color of the reference line.
+    pub fn baseline_color(&self) -> gdk::RGBA {
+        self.baseline_color
+    }
+
+    /// Sets whether a dot marks the last value, which is the default.
+    pub fn set_shows_last_point(&mut self, shows_last_point: bool) {
+        self.shows_last_point = shows_last_point;
+    }
+
+    /// Returns whether a dot marks the last value.
+    pub fn shows_last_point(&self) -> bool {
+        self.shows_last_point
+    }
+
+    /// Draws `values` on the paintable, replacing what was drawn before.
+    pub fn draw(&self, values: &[f64]) {
+        let Some(value_range) = self
+            .value_range
+            .clone()
+            .or_else(|| self.fitted_range(values))
+        else {
+            self.paintable.clear();
+            return;
+        };
+
+        let (width, height) = self.paintable.size();
+        let pad = self.last_point_radius.max(self.line_width / 2.0);
+        let plot_width = (width as f32 - 2.0 * pad).max(0.0);
+        let plot_height = (height as f32 - 2.0 * pad).max(0.0);
+
+        let span = value_range.end - value_range.start;
+        let y_of = |value: f64| {
+            let fraction = if span == 0.0 {
+                0.5
+            } else {
+                ((value_range.end - value) / span) as f32
+            };
+            pad + fraction * plot_height
+        };
+        let x_of = |index: usize| {
+            if values.len() < 2 {
+                width as f32 / 2.0
+            } else {
+                pad + index as f32 * plot_width / (values.len() - 1) as f32
+            }
+        };
+
+        let snapshot = gtk::Snapshot::new();
+
+        if let Some(baseline) = self.baseline.filter(|baseline| baseline.is_finite()) {
+            let y = y_of(baseline);
+            let path_builder = gsk::PathBuilder::new();
+            path_builder.move_to(0.0, y);
+            path_builder.line_to(width as f32, y);
+            snapshot.append_stroke(
+                &path_builder.to_path(),
+                &gsk::Stroke::new(1.0),
+                &self.baseline_color,
+            );
+        }
+
+        let path_builder = gsk::PathBuilder::new();
+        let mut is_in_run = false;
+        let mut last_point = None;
+        for (index, &value) in values.iter().enumerate() {
+            if !value.is_finite() {
+                is_in_run = false;
+                continue;
+            }
+
+            let (x, y) = (x_of(index), y_of(value));
+            if is_in_run {
+                path_builder.line_to(x, y);
+            } else {
+                path_builder.move_to(x, y);
+                is_in_run = true;
+            }
+            last_point = Some(Point::new(x, y));
+        }
+
+        let stroke = gsk::Stroke::new(self.line_width);
+        stroke.set_line_join(gsk::LineJoin::Round);
+        stroke.set_line_cap(gsk::LineCap::Round);
+        snapshot.append_stroke(&path_builder.to_path(), &stroke, &self.color);
+
+        if let Some(last_point) = last_point.filter(|_| self.shows_last_point) {
+            let path_builder = gsk::PathBuilder::new();
+            path_builder.add_circle(&last_point, self.last_point_radius);
+            snapshot.append_fill(&path_builder.to_path(), gsk::FillRule::Winding, &self.color);
+        }
+
+        self.paintable.set_node(snapshot.to_node());
+    }
+
+    /// Returns the range of the finite values and the baseline.
+    fn fitted_range(&self, values: &[f64]) -> Option<Range<f64>> {
+        values
+            .iter()
+            .copied()
+            .chain(self.baseline)
+            .filter(|value| value.is_finite())
+            .fold(None, |range: Option<Range<f64>>, value| {
+                Some(match range {
+                    Some(range) => range.start.min(value)..range.end.max(value),
+                    None => value..value,
+                })
+            })
+    }
+}
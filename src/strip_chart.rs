@@ -0,0 +1,153 @@
+use std::ops::Range;
+
+use plotters::style::{Color, ShapeStyle, BLUE};
+use plotters_backend::DrawingBackend;
+
+use crate::{BackendContext, Paintable, PaintableBackend};
+
+/// A scrolling plot of live samples over a fixed time window, like an
+/// oscilloscope or a system monitor.
+///
+/// New samples are drawn on the right edge of the paintable while the
+/// previous contents move left, so each push only draws the strip of pixels
+/// the time advanced by, with
+/// [`PaintableBackend::present_scrolled`], instead of redrawing the whole
+/// window. Contents that move past the left edge are dropped.
+///
+/// Samples must be pushed in increasing time order. Axes, grid lines and
+/// labels do not scroll, so they should be drawn around the paintable,
+/// such as in an overlay.
+#[derive(Debug)]
+pub struct StripChart {
+    paintable: Paintable,
+    /// Reused across pushes, as every push draws.
+    context: BackendContext,
+    time_window: f64,
+    value_range: Range<f64>,
+    style: ShapeStyle,
+    /// The time at the right edge of the paintable.
+    end_time: Option<f64>,
+    /// The fraction of a pixel the time advanced by but was not scrolled.
+    remainder: f64,
+    /// The samples since the last scroll, starting with the sample drawn
+    /// last.
+    pending: Vec<(f64, f64)>,
+}
+
+impl StripChart {
+    /// Creates a strip chart on `paintable`, showing the last `time_window`
+    /// units of time across its width and `value_range` across its height.
+    pub fn new(paintable: &Paintable, time_window: f64, value_range: Range<f64>) -> Self {
+        Self {
+            paintable: paintable.clone(),
+            context: BackendContext::new(),
+            time_window,
+            value_range,
+            style: BLUE.stroke_width(1),
+            end_time: None,
+            remainder: 0.0,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Returns the paintable the chart is drawn on.
+    pub fn paintable(&self) -> &Paintable {
+        &self.paintable
+    }
+
+    /// Returns the time span shown across the width of the paintable.
+    pub fn time_window(&self) -> f64 {
+        self.time_window
+    }
+
+    /// Returns the value range shown across the height of the paintable.
+    pub fn value_range(&self) -> Range<f64> {
+        self.value_range.clone()
+    }
+
+    /// Sets the style of the line of the samples drawn from now on.
+    pub fn set_style(&mut self, style: impl Into<ShapeStyle>) {
+        self.style = style.into();
+    }
+
+    /// Returns the style of the line of the samples.
+    pub fn style(&self) -> ShapeStyle {
+        self.style
+    }
+
+    /// Returns the time at the right edge of the paintable, or `None` if no
+    /// samples were pushed.
+    pub fn end_time(&self) -> Option<f64> {
+        self.end_time
+    }
+
+    /// Appends a sample on the right edge, scrolling the chart by the time
+    /// elapsed since the last sample.
+    ///
+    /// Samples less than a pixel apart are kept until the chart scrolls by
+    /// at least a pixel. Samples older than the last one are ignored.
+    pub fn push(&mut self, time: f64, value: f64) {
+        let (width, height) = self.paintable.size();
+        if !time.is_finite() || !value.is_finite() || self.time_window <= 0.0 {
+            return;
+        }
+
+        let Some(end_time) = self.end_time else {
+            self.end_time = Some(time);
+            self.pending.push((time, value));
+            return;
+        };
+
+        if self
+            .pending
+            .last()
+            .is_some_and(|&(last_time, _)| time < last_time)
+        {
+            return;
+        }
+
+        let pixels_per_time = width as f64 / self.time_window;
+        let advance = (time - end_time) * pixels_per_time + self.remainder;
+        let dx = (advance.floor() as i64).min(width as i64) as i32;
+        self.pending.push((time, value));
+
+        if dx == 0 {
+            return;
+        }
+
+        self.remainder = advance - advance.floor();
+        self.end_time = Some(time);
+
+        // After scrolling, `time` is the unscrolled fraction of a pixel past
+        // the right edge.
+        let value_span = self.value_range.end - self.value_range.start;
+        let points = self
+            .pending
+            .iter()
+            .map(|&(sample_time, sample_value)| {
+                let x = width as f64 - (time - sample_time) * pixels_per_time + self.remainder;
+                let y = if value_span == 0.0 {
+                    height as f64 / 2.0
+                } else {
+                    (self.value_range.end - sample_value) / value_span * height as f64
+                };
+                (x.round() as i32, y.round() as i32)
+            })
+            .collect::<Vec<_>>();
+        let last = self.pending.pop().unwrap();
+        self.pending.clear();
+        self.pending.push(last);
+
+        let mut backend = PaintableBackend::with_context(&self.paintable, &self.context);
+        let _ = backend.draw_path(points, &self.style);
+        backend.present_scrolled(dx, (width as i32 - dx, 0), (width as i32, height as i32));
+    }
+
+    /// Removes all samples and clears the paintable.
+    pub fn clear(&mut self) {
+        self.end_time = None;
+        self.remainder = 0.0;
+        self.pending.clear();
+        self.paintable.clear();
+    }
+}
@@ -0,0 +1,351 @@
+use std::collections::HashMap;
+
+use gtk::{gdk, graphene::Rect, gsk, pango, prelude::*};
+use plotters_backend::{BackendColor, BackendStyle};
+
+/// Extension trait for [`BackendStyle`] to draw strokes thinner than one
+/// logical pixel.
+///
+/// Strokes with a width of zero are drawn with the backend's hairline width,
+/// which must be set with [`PaintableBackend::set_hairline_width`] or
+/// [`SnapshotBackend::set_hairline_width`], such as to `Some(0.5)`. Without
+/// it, they are not drawn, as with other plotters backends.
+///
+/// [`PaintableBackend::set_hairline_width`]: crate::PaintableBackend::set_hairline_width
+/// [`SnapshotBackend::set_hairline_width`]: crate::SnapshotBackend::set_hairline_width
+pub trait HairlineStyle: BackendStyle + Sized {
+    /// Returns a style with the same color, but drawn as a hairline.
+    fn hairline(self) -> Hairline<Self> {
+        Hairline(self)
+    }
+}
+
+impl<S: BackendStyle> HairlineStyle for S {}
+
+/// A style that is drawn as a hairline.
+///
+/// This is created by [`HairlineStyle::hairline`].
+#[derive(Debug, Clone, Copy)]
+pub struct Hairline<S>(S);
+
+impl<S: BackendStyle> BackendStyle for Hairline<S> {
+    fn color(&self) -> BackendColor {
+        self.0.color()
+    }
+
+    fn stroke_width(&self) -> u32 {
+        0
+    }
+}
+
+/// Decoration drawn behind text to keep it readable over busy plots.
+///
+/// This is set with [`PaintableBackend::set_text_halo`] or
+/// [`SnapshotBackend::set_text_halo`].
+///
+/// [`PaintableBackend::set_text_halo`]: crate::PaintableBackend::set_text_halo
+/// [`SnapshotBackend::set_text_halo`]: crate::SnapshotBackend::set_text_halo
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextHalo {
+    /// An outline of the given color and width around the glyphs.
+    Outline { color: gdk::RGBA, width: f32 },
+    /// A rounded rect of the given color behind the text, extending past the
+    /// text by the given padding.
+    Pill { color: gdk::RGBA, padding: f32 },
+    /// A copy of the glyphs of the given color behind the text, moved by
+    /// the given offset and blurred by the given radius.
+    ///
+    /// The shadow is not blurred for renderers without
+    /// [`RendererCaps::blurs`](crate::RendererCaps::blurs).
+    Shadow {
+        color: gdk::RGBA,
+        offset: (f32, f32),
+        blur_radius: f32,
+    },
+}
+
+/// The extents of text that are measured and aligned to the text anchor.
+///
+/// This is set with [`PaintableBackend::set_text_extents`] or
+/// [`SnapshotBackend::set_text_extents`].
+///
+/// [`PaintableBackend::set_text_extents`]: crate::PaintableBackend::set_text_extents
+/// [`SnapshotBackend::set_text_extents`]: crate::SnapshotBackend::set_text_extents
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TextExtents {
+    /// The extents of the line, including the space reserved for ascenders,
+    /// descenders, and line spacing. This keeps labels of different text on a
+    /// common line.
+    #[default]
+    Logical,
+    /// The extents of the drawn glyphs only. This matches the bounding box
+    /// plotters measures for other backends, and accounts for glyphs that
+    /// extend past the line, such as superscripts.
+    Ink,
+    /// The extents of the font rather than of the text, from its ascent
+    /// above the baseline of the first line to its descent below the
+    /// baseline of the last line. These are the same for all single lines of
+    /// a font, so text is anchored on its baseline, and labels of different
+    /// glyph heights line up like GTK labels do, even when some glyphs fall
+    /// back to other fonts.
+    Baseline,
+}
+
+/// How text is covered by the fonts it is drawn with.
+///
+/// This is returned by [`PaintableBackend::text_coverage`] and
+/// [`SnapshotBackend::text_coverage`], to verify that the fonts of the
+/// system, with the fallback families of the backend, draw labels such as
+/// emoji as intended.
+///
+/// [`PaintableBackend::text_coverage`]: crate::PaintableBackend::text_coverage
+/// [`SnapshotBackend::text_coverage`]: crate::SnapshotBackend::text_coverage
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TextCoverage {
+    /// The number of characters that no font has a glyph for, which are
+    /// drawn as boxes.
+    pub n_unknown_glyphs: u32,
+    /// Whether some glyphs are drawn in their own colors, such as the ones
+    /// of color emoji fonts.
+    pub has_color_glyphs: bool,
+}
+
+/// Typographic attributes applied to drawn text on top of its
+/// [`BackendTextStyle`](plotters_backend::BackendTextStyle).
+///
+/// These are set for all text with [`PaintableBackend::set_text_attributes`]
+/// or [`SnapshotBackend::set_text_attributes`], or for the text drawn within a
+/// closure with [`PaintableBackend::with_text_attributes`] or
+/// [`SnapshotBackend::with_text_attributes`].
+///
+/// [`PaintableBackend::set_text_attributes`]: crate::PaintableBackend::set_text_attributes
+/// [`SnapshotBackend::set_text_attributes`]: crate::SnapshotBackend::set_text_attributes
+/// [`PaintableBackend::with_text_attributes`]: crate::PaintableBackend::with_text_attributes
+/// [`SnapshotBackend::with_text_attributes`]: crate::SnapshotBackend::with_text_attributes
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct TextAttributes {
+    /// Extra space between letters, in pixels.
+    pub letter_spacing: f32,
+    /// Whether digits have the same width, so that numeric labels, such as
+    /// axis tick labels, align in columns. This enables the `tnum` font
+    /// feature.
+    pub tabular_figures: bool,
+    /// Whether lowercase letters are drawn as small capitals. This enables
+    /// the `smcp` font feature.
+    pub small_caps: bool,
+    /// The weight of the text, overriding the weight of the
+    /// [`FontStyle`](plotters_backend::FontStyle), or `None` to use it.
+    ///
+    /// This allows weights that [`FontStyle`](plotters_backend::FontStyle)
+    /// cannot express, such as [`pango::Weight::Semibold`] for headings and
+    /// [`pango::Weight::Light`] for secondary labels.
+    pub weight: Option<pango::Weight>,
+    /// The slant of the text, overriding the slant of the
+    /// [`FontStyle`](plotters_backend::FontStyle), or `None` to use it.
+    ///
+    /// Combined with [`FontStyle::Bold`](plotters_backend::FontStyle::Bold),
+    /// [`pango::Style::Italic`] draws bold italic text.
+    pub style: Option<pango::Style>,
+    /// The maximum width of the text, in pixels, beyond which it is
+    /// shortened with an ellipsis, or `None` to not limit it.
+    ///
+    /// This keeps long labels, such as category names, from overflowing the
+    /// chart or colliding with their neighbors. The measured size of the
+    /// text is that of the shortened text.
+    pub max_width: Option<f32>,
+    /// Where text wider than [`TextAttributes::max_width`] is shortened, or
+    /// `None` to shorten it at the end.
+    pub ellipsize: Option<pango::EllipsizeMode>,
+    /// How text wider than [`TextAttributes::max_width`] is wrapped onto
+    /// more lines instead of being shortened, such as
+    /// [`pango::WrapMode::WordChar`] for long captions, or `None` to shorten
+    /// it.
+    ///
+    /// The lines are anchored together, and a [`TextHalo::Pill`] is sized
+    /// to all of them.
+    pub wrap: Option<pango::WrapMode>,
+}
+
+impl TextAttributes {
+    /// Returns the font features to enable, in the CSS `font-feature-settings`
+    /// syntax.
+    pub(crate) fn font_features(&self) -> String {
+        let mut features = Vec::new();
+        if self.tabular_figures {
+            features.push("tnum");
+        }
+        if self.small_caps {
+            features.push("smcp");
+        }
+        features.join(", ")
+    }
+}
+
+/// What is drawn beneath a plot.
+///
+/// This is set with [`Paintable::set_background`] or
+/// [`SnapshotBackend::set_background`], and fills the whole plot even when
+/// it is resized, unlike a rect drawn with plotters.
+///
+/// [`Paintable::set_background`]: crate::Paintable::set_background
+/// [`SnapshotBackend::set_background`]: crate::SnapshotBackend::set_background
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Background {
+    /// A solid color.
+    Color(gdk::RGBA),
+    /// A linear gradient from `start` to `end`, from top to bottom if
+    /// `orientation` is [`gtk::Orientation::Vertical`], or from left to right
+    /// otherwise.
+    LinearGradient {
+        start: gdk::RGBA,
+        end: gdk::RGBA,
+        orientation: gtk::Orientation,
+    },
+}
+
+impl Background {
+    /// Fills `bounds` with the background.
+    pub(crate) fn append_to(&self, snapshot: &impl IsA<gtk::Snapshot>, bounds: &Rect) {
+        match *self {
+            Self::Color(color) => snapshot.append_color(&color, bounds),
+            Self::LinearGradient {
+                start,
+                end,
+                orientation,
+            } => {
+                let end_point = if orientation == gtk::Orientation::Vertical {
+                    bounds.bottom_left()
+                } else {
+                    bounds.top_right()
+                };
+                snapshot.append_linear_gradient(
+                    bounds,
+                    &bounds.top_left(),
+                    &end_point,
+                    &[
+                        gsk::ColorStop::new(0.0, start),
+                        gsk::ColorStop::new(1.0, end),
+                    ],
+                );
+            }
+        }
+    }
+}
+
+/// The style of the primitives drawn within a style class, overriding the
+/// color and stroke width of their
+/// [`BackendStyle`](plotters_backend::BackendStyle).
+///
+/// See [`StyleClasses`].
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ClassStyle {
+    /// The color, or `None` to use the color of the primitive.
+    pub color: Option<gdk::RGBA>,
+    /// The stroke width, in pixels, or `None` to use the stroke width of the
+    /// primitive.
+    pub stroke_width: Option<f32>,
+}
+
+impl ClassStyle {
+    pub(crate) fn color(&self, color: BackendColor) -> BackendColor {
+        self.color.map_or(color, |rgba| BackendColor {
+            alpha: rgba.alpha() as f64,
+            rgb: (
+                (rgba.red() * 255.0).round() as u8,
+                (rgba.green() * 255.0).round() as u8,
+                (rgba.blue() * 255.0).round() as u8,
+            ),
+        })
+    }
+}
+
+/// Styles of named classes of chart elements, such as `"grid"` or `"axis"`.
+///
+/// These are set with [`PaintableBackend::set_style_classes`] or
+/// [`SnapshotBackend::set_style_classes`], and the primitives drawn within
+/// [`PaintableBackend::with_class`] or [`SnapshotBackend::with_class`] are
+/// drawn with the style of the class. Use [`StyleClasses::for_widget`] to
+/// resolve the styles from the CSS of the app, so that charts can be
+/// restyled without recompiling.
+///
+/// [`PaintableBackend::set_style_classes`]: crate::PaintableBackend::set_style_classes
+/// [`SnapshotBackend::set_style_classes`]: crate::SnapshotBackend::set_style_classes
+/// [`PaintableBackend::with_class`]: crate::PaintableBackend::with_class
+/// [`SnapshotBackend::with_class`]: crate::SnapshotBackend::with_class
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct StyleClasses {
+    styles: HashMap<String, ClassStyle>,
+}
+
+impl StyleClasses {
+    /// Creates an empty set of styles.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Resolves the colors of `classes` from the CSS applied to `widget`.
+    ///
+    /// Each class is added to `widget` itself while its color is read, so
+    /// it is matched with a selector such as `plotview.grid`, and the color
+    /// is taken from the `color` property, if it differs from the color of
+    /// `widget` without the class. CSS custom properties can be used to keep
+    /// the colors in one place:
+    ///
+    /// ```css
+    /// plotview {
+    ///   --grid-color: alpha(currentColor, 0.15);
+    /// }
+    ///
+    /// plotview.grid {
+    ///   color: var(--grid-color);
+    /// }
+    /// ```
+    ///
+    /// GTK has no API to read other properties of a widget, so stroke widths
+    /// are left unset, and can be set afterwards with [`Self::insert`].
+    ///
+    /// This must be called again when the style of the widget changes, for
+    /// example, when switching between light and dark color schemes. As this
+    /// changes the style of `widget` while reading it, it must not be called
+    /// from [`WidgetImpl::css_changed`](gtk::subclass::widget::WidgetImpl::css_changed)
+    /// of `widget`.
+    pub fn for_widget(widget: &impl IsA<gtk::Widget>, classes: &[&str]) -> Self {
+        let widget = widget.as_ref();
+        let widget_color = widget.color();
+
+        let mut style_classes = Self::new();
+        for class in classes {
+            // Leave the classes the widget already has alone.
+            let has_class = widget.has_css_class(class);
+            widget.add_css_class(class);
+            let color = widget.color();
+            if !has_class {
+                widget.remove_css_class(class);
+            }
+
+            style_classes.insert(
+                class,
+                ClassStyle {
+                    color: (color != widget_color).then_some(color),
+                    stroke_width: None,
+                },
+            );
+        }
+        style_classes
+    }
+
+    /// Sets the style of `class`, replacing the previous style.
+    pub fn insert(&mut self, class: &str, style: ClassStyle) {
+        self.styles.insert(class.to_string(), style);
+    }
+
+    /// Removes the style of `class`.
+    pub fn remove(&mut self, class: &str) -> Option<ClassStyle> {
+        self.styles.remove(class)
+    }
+
+    /// Returns the style of `class`.
+    pub fn get(&self, class: &str) -> Option<&ClassStyle> {
+        self.styles.get(class)
+    }
+}
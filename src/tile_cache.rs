@@ -0,0 +1,367 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    fmt,
+    ops::Range,
+    rc::Rc,
+};
+
+use gtk::{gdk, glib, graphene::Rect, gsk, prelude::*};
+
+use crate::{export, BackendContext, InvalidSizeError, RendererCaps, SnapshotBackend};
+
+/// The default maximum number of cached tiles.
+const DEFAULT_MAX_TILES: usize = 128;
+
+/// The number of missing tiles rendered per frame, so that zooming into an
+/// uncached level does not stall the frame.
+const TILES_PER_FRAME: usize = 4;
+
+/// How many levels up a cached tile is looked for, to stand in for a tile
+/// that is not rendered yet.
+const MAX_FALLBACK_LEVELS: i32 = 4;
+
+type DrawFunc = Box<dyn Fn(SnapshotBackend, Range<f64>, Range<f64>)>;
+
+/// The position of a tile, at a zoom level along each axis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct TileKey {
+    level: (i32, i32),
+    index: (i64, i64),
+}
+
+impl TileKey {
+    /// Returns the key of the tile `n_levels` levels up that contains this
+    /// tile.
+    fn ancestor(self, n_levels: i32) -> Self {
+        let factor = 1_i64 << n_levels;
+        Self {
+            level: (self.level.0 - n_levels, self.level.1 - n_levels),
+            index: (
+                self.index.0.div_euclid(factor),
+                self.index.1.div_euclid(factor),
+            ),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Tile {
+    texture: gdk::Texture,
+    last_used: u64,
+}
+
+struct Inner {
+    tile_size: u32,
+    base_span: (f64, f64),
+    draw_func: DrawFunc,
+    context: BackendContext,
+    max_tiles: Cell<usize>,
+    tiles: RefCell<HashMap<TileKey, Tile>>,
+    /// Incremented on every frame, to find the least recently used tiles.
+    clock: Cell<u64>,
+    /// The renderer used when none is given, created when first needed.
+    fallback_renderer: RefCell<Option<gsk::Renderer>>,
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        if let Some(renderer) = self.fallback_renderer.take() {
+            renderer.unrealize();
+        }
+    }
+}
+
+/// A cache of textures of a plot rendered in tiles at several zoom levels,
+/// so that revisiting a region and zoom level is instant.
+///
+/// This is meant for deep zooming into datasets too large to redraw every
+/// frame. The data space is divided into square tiles of `tile_size`
+/// pixels, where a tile at level zero spans the base span of the cache, and
+/// each level up halves the span of its tiles. The x and y axes have their
+/// own levels, so that zooming only one of them keeps the tiles sharp.
+///
+/// Tiles are drawn with the draw function, given a [`SnapshotBackend`] of
+/// the size of the tile and the data ranges it spans, which should be
+/// mapped to the whole backend, with no margins or labels. Tiles missing
+/// from the cache are rendered a few per frame, with cached tiles of a
+/// coarser level standing in until then, and the least recently used tiles
+/// are evicted beyond [`TileCache::max_tiles`].
+///
+/// This is usually shown with [`PlotView::set_tile_cache`].
+///
+/// [`PlotView::set_tile_cache`]: crate::PlotView::set_tile_cache
+#[derive(Clone)]
+pub struct TileCache {
+    inner: Rc<Inner>,
+}
+
+impl fmt::Debug for TileCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TileCache")
+            .field("tile_size", &self.inner.tile_size)
+            .field("base_span", &self.inner.base_span)
+            .field("max_tiles", &self.inner.max_tiles)
+            .field("n_tiles", &self.n_tiles())
+            .finish_non_exhaustive()
+    }
+}
+
+impl TileCache {
+    /// Creates a cache of tiles of `tile_size` pixels, where a tile at
+    /// level zero spans `base_span` in data units along x and y, such as the
+    /// spans of the home ranges of the plot.
+    pub fn new(
+        tile_size: u32,
+        base_span: (f64, f64),
+        draw_func: impl Fn(SnapshotBackend, Range<f64>, Range<f64>) + 'static,
+    ) -> Result<Self, InvalidSizeError> {
+        if tile_size == 0 || tile_size > i32::MAX as u32 {
+            return Err(InvalidSizeError {
+                size: (tile_size, tile_size),
+            });
+        }
+
+        Ok(Self {
+            inner: Rc::new(Inner {
+                tile_size,
+                base_span,
+                draw_func: Box::new(draw_func),
+                context: BackendContext::new(),
+                max_tiles: Cell::new(DEFAULT_MAX_TILES),
+                tiles: RefCell::default(),
+                clock: Cell::new(0),
+                fallback_renderer: RefCell::default(),
+            }),
+        })
+    }
+
+    /// Returns the width and height of the tiles, in pixels.
+    pub fn tile_size(&self) -> u32 {
+        self.inner.tile_size
+    }
+
+    /// Sets the maximum number of cached tiles, evicting the least recently
+    /// used tiles beyond it.
+    ///
+    /// This defaults to 128. Each tile takes four bytes per pixel.
+    pub fn set_max_tiles(&self, max_tiles: usize) {
+        self.inner.max_tiles.set(max_tiles);
+        self.evict();
+    }
+
+    /// Returns the maximum number of cached tiles.
+    pub fn max_tiles(&self) -> usize {
+        self.inner.max_tiles.get()
+    }
+
+    /// Returns the number of cached tiles.
+    pub fn n_tiles(&self) -> usize {
+        self.inner.tiles.borrow().len()
+    }
+
+    /// Drops all cached tiles, such as when the data changed.
+    pub fn invalidate(&self) {
+        self.inner.tiles.borrow_mut().clear();
+    }
+
+    /// Draws the tiles covering `x_range` and `y_range` onto `snapshot`,
+    /// mapped to a rect of `size` at the origin, with `scale` device pixels
+    /// per unit.
+    ///
+    /// Tiles are rendered with `renderer`, such as the renderer of the
+    /// window the plot is shown in, or with a Cairo renderer if `None`.
+    ///
+    /// This returns `false` if some tiles were not rendered yet, in which
+    /// case this should be called again on the next frame.
+    pub fn snapshot(
+        &self,
+        snapshot: &gtk::Snapshot,
+        x_range: Range<f64>,
+        y_range: Range<f64>,
+        size: (f64, f64),
+        scale: f64,
+        renderer: Option<&gsk::Renderer>,
+    ) -> bool {
+        let inner = &self.inner;
+
+        let (width, height) = size;
+        let x_span = x_range.end - x_range.start;
+        let y_span = y_range.end - y_range.start;
+        if !(width > 0.0 && height > 0.0 && x_span > 0.0 && y_span > 0.0) {
+            return true;
+        }
+
+        inner.clock.set(inner.clock.get() + 1);
+
+        let level = (
+            self.level_for(x_span, inner.base_span.0, width * scale),
+            self.level_for(y_span, inner.base_span.1, height * scale),
+        );
+        let tile_span = (
+            inner.base_span.0 / 2_f64.powi(level.0),
+            inner.base_span.1 / 2_f64.powi(level.1),
+        );
+        let index_range = |range: &Range<f64>, span: f64| {
+            (range.start / span).floor() as i64..=(range.end / span).floor() as i64
+        };
+
+        // Round to device pixels, so that adjacent tiles leave no seams.
+        let to_x = |x: f64| ((x - x_range.start) / x_span * width * scale).round() / scale;
+        let to_y = |y: f64| ((y_range.end - y) / y_span * height * scale).round() / scale;
+        let screen_rect = |key: TileKey| {
+            let span = (
+                inner.base_span.0 / 2_f64.powi(key.level.0),
+                inner.base_span.1 / 2_f64.powi(key.level.1),
+            );
+            let (x0, x1) = (
+                to_x(key.index.0 as f64 * span.0),
+                to_x((key.index.0 + 1) as f64 * span.0),
+            );
+            let (y0, y1) = (
+                to_y((key.index.1 + 1) as f64 * span.1),
+                to_y(key.index.1 as f64 * span.1),
+            );
+            Rect::new(x0 as f32, y0 as f32, (x1 - x0) as f32, (y1 - y0) as f32)
+        };
+
+        let mut n_rendered = 0;
+        let mut is_complete = true;
+
+        for iy in index_range(&y_range, tile_span.1) {
+            for ix in index_range(&x_range, tile_span.0) {
+                let key = TileKey {
+                    level,
+                    index: (ix, iy),
+                };
+                let rect = screen_rect(key);
+
+                if let Some(texture) = self.cached_texture(key) {
+                    snapshot.append_texture(&texture, &rect);
+                    continue;
+                }
+
+                if n_rendered < TILES_PER_FRAME {
+                    if let Some(texture) = self.render_tile(key, tile_span, renderer) {
+                        snapshot.append_texture(&texture, &rect);
+                    }
+                    n_rendered += 1;
+                    continue;
+                }
+
+                is_complete = false;
+
+                let ancestor = (1..=MAX_FALLBACK_LEVELS).find_map(|n_levels| {
+                    let ancestor = key.ancestor(n_levels);
+                    self.cached_texture(ancestor)
+                        .map(|texture| (ancestor, texture))
+                });
+                if let Some((ancestor, texture)) = ancestor {
+                    snapshot.push_clip(&rect);
+                    snapshot.append_texture(&texture, &screen_rect(ancestor));
+                    snapshot.pop();
+                }
+            }
+        }
+
+        self.evict();
+
+        is_complete
+    }
+
+    /// Returns the level whose tiles have at least as many pixels per data
+    /// unit as the screen, where `span` spans `device_size` pixels.
+    fn level_for(&self, span: f64, base_span: f64, device_size: f64) -> i32 {
+        let ratio = device_size * base_span / (span * self.inner.tile_size as f64);
+        if ratio.is_finite() && ratio > 0.0 {
+            ratio.log2().ceil().clamp(-62.0, 62.0) as i32
+        } else {
+            0
+        }
+    }
+
+    /// Returns the texture of the tile if it is cached, marking it as used.
+    fn cached_texture(&self, key: TileKey) -> Option<gdk::Texture> {
+        let mut tiles = self.inner.tiles.borrow_mut();
+        let tile = tiles.get_mut(&key)?;
+        tile.last_used = self.inner.clock.get();
+        Some(tile.texture.clone())
+    }
+
+    fn render_tile(
+        &self,
+        key: TileKey,
+        tile_span: (f64, f64),
+        renderer: Option<&gsk::Renderer>,
+    ) -> Option<gdk::Texture> {
+        let inner = &self.inner;
+
+        let renderer = match renderer {
+            Some(renderer) => renderer.clone(),
+            None => {
+                let mut fallback_renderer = inner.fallback_renderer.borrow_mut();
+                if fallback_renderer.is_none() {
+                    match export::renderer_for_surface(None) {
+                        Ok(renderer) => *fallback_renderer = Some(renderer),
+                        Err(err) => {
+                            glib::g_warning!(
+                                "plotters-gtk4",
+                                "Failed to create tile renderer: {:?}",
+                                err
+                            );
+                            return None;
+                        }
+                    }
+                }
+                fallback_renderer.clone().unwrap()
+            }
+        };
+
+        let x_start = key.index.0 as f64 * tile_span.0;
+        let y_start = key.index.1 as f64 * tile_span.1;
+        let size = inner.tile_size;
+        let bounds = Rect::new(0.0, 0.0, size as f32, size as f32);
+
+        // Ensure that empty tiles still render to a texture of the right size.
+        let snapshot = gtk::Snapshot::new();
+        snapshot.append_color(&gdk::RGBA::TRANSPARENT, &bounds);
+        let mut backend = SnapshotBackend::with_context(&snapshot, (size, size), &inner.context);
+        backend.set_renderer_caps(RendererCaps::for_renderer(&renderer));
+        (inner.draw_func)(
+            backend,
+            x_start..x_start + tile_span.0,
+            y_start..y_start + tile_span.1,
+        );
+        let texture = renderer.render_texture(snapshot.to_node().unwrap(), Some(&bounds));
+
+        inner.tiles.borrow_mut().insert(
+            key,
+            Tile {
+                texture: texture.clone(),
+                last_used: inner.clock.get(),
+            },
+        );
+
+        Some(texture)
+    }
+
+    /// Evicts the least recently used tiles beyond the maximum.
+    fn evict(&self) {
+        let inner = &self.inner;
+        let mut tiles = inner.tiles.borrow_mut();
+
+        let n_excess = tiles.len().saturating_sub(inner.max_tiles.get());
+        if n_excess == 0 {
+            return;
+        }
+
+        let mut by_last_used = tiles
+            .iter()
+            .map(|(key, tile)| (tile.last_used, *key))
+            .collect::<Vec<_>>();
+        by_last_used.sort_unstable_by_key(|(last_used, _)| *last_used);
+        for (_, key) in by_last_used.into_iter().take(n_excess) {
+            tiles.remove(&key);
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use std::{collections::VecDeque, ops::Range};
+
+/// A fixed-capacity ring buffer of `(time, value)` samples for live plots.
+///
+/// When full, pushing a new sample drops the oldest one. Samples must be
+/// pushed in increasing time order.
+///
+/// Use [`TimeSeriesBuffer::decimate`] to reduce the samples to at most two
+/// points (the minimum and maximum) per pixel column before drawing, so only
+/// what is visually distinguishable is drawn, even at high sample rates.
+#[derive(Debug, Clone)]
+pub struct TimeSeriesBuffer {
+    samples: VecDeque<(f64, f64)>,
+    capacity: usize,
+}
+
+impl TimeSeriesBuffer {
+    /// Creates a new buffer that holds at most `capacity` samples.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Returns the maximum number of samples the buffer holds.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of samples in the buffer.
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Returns whether the buffer has no samples.
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Appends a sample, dropping the oldest sample if the buffer is full.
+    pub fn push(&mut self, time: f64, value: f64) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((time, value));
+    }
+
+    /// Removes all samples.
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    /// Returns an iterator over all samples, from oldest to newest.
+    pub fn iter(&self) -> impl Iterator<Item = (f64, f64)> + '_ {
+        self.samples.iter().copied()
+    }
+
+    /// Returns the time range spanned by the samples.
+    pub fn time_range(&self) -> Option<Range<f64>> {
+        let (start, _) = self.samples.front()?;
+        let (end, _) = self.samples.back()?;
+        Some(*start..*end)
+    }
+
+    /// Returns the range of values of the samples within `time_range`.
+    pub fn value_range(&self, time_range: Range<f64>) -> Option<Range<f64>> {
+        self.samples_in(time_range).map(|(_, value)| value).fold(
+            None,
+            |range: Option<Range<f64>>, value| {
+                Some(match range {
+                    Some(range) => range.start.min(value)..range.end.max(value),
+                    None => value..value,
+                })
+            },
+        )
+    }
+
+    /// Returns the samples within `time_range`, reduced to at most the first
+    /// minimum and maximum samples of each of the `n_columns` columns the time
+    /// range is split into.
+    ///
+    /// `n_columns` is usually the width, in pixels, of the plotting area.
+    pub fn decimate(&self, time_range: Range<f64>, n_columns: usize) -> Vec<(f64, f64)> {
+        let span = time_range.end - time_range.start;
+        if n_columns == 0 || span <= 0.0 {
+            return Vec::new();
+        }
+
+        let column_width = span / n_columns as f64;
+        let column_of =
+            |time: f64| (((time - time_range.start) / column_width) as usize).min(n_columns - 1);
+
+        let mut points = Vec::with_capacity(2 * n_columns);
+        let mut samples = self.samples_in(time_range.clone()).peekable();
+
+        while let Some(first) = samples.next() {
+            let column = column_of(first.0);
+
+            let mut min = first;
+            let mut max = first;
+            while let Some(&sample) = samples.peek() {
+                if column_of(sample.0) != column {
+                    break;
+                }
+                if sample.1 < min.1 {
+                    min = sample;
+                }
+                if sample.1 > max.1 {
+                    max = sample;
+                }
+                samples.next();
+            }
+
+            if min.0 <= max.0 {
+                points.push(min);
+                if max.0 != min.0 {
+                    points.push(max);
+                }
+            } else {
+                points.push(max);
+                points.push(min);
+            }
+        }
+
+        points
+    }
+
+    fn samples_in(&self, time_range: Range<f64>) -> impl Iterator<Item = (f64, f64)> + '_ {
+        let start = self
+            .samples
+            .partition_point(|(time, _)| *time < time_range.start);
+        self.samples
+            .range(start..)
+            .copied()
+            .take_while(move |(time, _)| *time <= time_range.end)
+    }
+}
+
+impl Extend<(f64, f64)> for TimeSeriesBuffer {
+    fn extend<T: IntoIterator<Item = (f64, f64)>>(&mut self, iter: T) {
+        for (time, value) in iter {
+            self.push(time, value);
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use std::time::Duration;
+
+use gtk::{glib, gsk};
+
+use crate::recording::Recording;
+
+/// The interval between the frames of a transition, about 60 per second.
+///
+/// Paintables have no frame clock of their own, so the frames are driven by
+/// a timeout when no widget is attached to drive them.
+pub(crate) const TRANSITION_FRAME_INTERVAL: Duration = Duration::from_millis(16);
+
+/// A curve mapping the linear progress of a transition to the progress that
+/// is shown.
+///
+/// This is passed to [`Paintable::transition_to`](crate::Paintable::transition_to).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Easing {
+    /// Constant speed.
+    Linear,
+    /// Starts slow and accelerates, quadratically.
+    EaseInQuad,
+    /// Starts fast and decelerates, quadratically.
+    EaseOutQuad,
+    /// Accelerates, then decelerates, quadratically.
+    EaseInOutQuad,
+    /// Starts slow and accelerates, cubically.
+    EaseInCubic,
+    /// Starts fast and decelerates, cubically.
+    EaseOutCubic,
+    /// Accelerates, then decelerates, cubically.
+    #[default]
+    EaseInOutCubic,
+}
+
+impl Easing {
+    /// Returns the shown progress at the linear progress `t`, both from
+    /// `0.0` to `1.0`.
+    pub fn apply(&self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Self::Linear => t,
+            Self::EaseInQuad => t * t,
+            Self::EaseOutQuad => 1.0 - (1.0 - t).powi(2),
+            Self::EaseInOutQuad => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+                }
+            }
+            Self::EaseInCubic => t * t * t,
+            Self::EaseOutCubic => 1.0 - (1.0 - t).powi(3),
+            Self::EaseInOutCubic => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+        }
+    }
+}
+
+/// How the contents of a paintable change during a [`Transition`].
+#[derive(Debug)]
+enum TransitionKind {
+    /// The previous node fades out while the new one fades in.
+    CrossFade {
+        previous_node: Option<gsk::RenderNode>,
+    },
+    /// The primitives of one recording are interpolated into the ones of
+    /// another.
+    Morph { from: Recording, to: Recording },
+}
+
+/// A transition of the contents of a paintable.
+#[derive(Debug)]
+pub(crate) struct Transition {
+    kind: TransitionKind,
+    start_time: i64,
+    duration: Duration,
+    easing: Easing,
+}
+
+impl Transition {
+    /// Creates a transition that cross-fades from `previous_node` to the
+    /// current node, starting now.
+    pub(crate) fn cross_fade(
+        previous_node: Option<gsk::RenderNode>,
+        duration: Duration,
+        easing: Easing,
+    ) -> Self {
+        Self::new(
+            TransitionKind::CrossFade { previous_node },
+            duration,
+            easing,
+        )
+    }
+
+    /// Creates a transition that morphs `from` into `to`, starting now.
+    pub(crate) fn morph(
+        from: Recording,
+        to: Recording,
+        duration: Duration,
+        easing: Easing,
+    ) -> Self {
+        Self::new(TransitionKind::Morph { from, to }, duration, easing)
+    }
+
+    fn new(kind: TransitionKind, duration: Duration, easing: Easing) -> Self {
+        Self {
+            kind,
+            start_time: glib::monotonic_time(),
+            duration,
+            easing,
+        }
+    }
+
+    /// Returns the node the contents cross-fade from, if this is a
+    /// cross-fade.
+    pub(crate) fn previous_node(&self) -> Option<&gsk::RenderNode> {
+        match &self.kind {
+            TransitionKind::CrossFade { previous_node } => previous_node.as_ref(),
+            TransitionKind::Morph { .. } => None,
+        }
+    }
+
+    /// Returns whether the current node is cross-faded from the previous
+    /// node, rather than replaced on every frame.
+    pub(crate) fn is_cross_fade(&self) -> bool {
+        matches!(self.kind, TransitionKind::CrossFade { .. })
+    }
+
+    /// Returns the recording to show at the current progress, if this is a
+    /// morph.
+    pub(crate) fn morph_frame(&self) -> Option<Recording> {
+        match &self.kind {
+            TransitionKind::CrossFade { .. } => None,
+            TransitionKind::Morph { from, to } => Some(from.interpolate(to, self.progress())),
+        }
+    }
+
+    /// Returns the eased progress of the transition, from `0.0` to `1.0`.
+    pub(crate) fn progress(&self) -> f64 {
+        self.easing.apply(self.linear_progress())
+    }
+
+    /// Returns whether the transition has reached its end.
+    pub(crate) fn is_finished(&self) -> bool {
+        self.linear_progress() >= 1.0
+    }
+
+    fn linear_progress(&self) -> f64 {
+        let duration = self.duration.as_micros() as f64;
+        if duration == 0.0 {
+            return 1.0;
+        }
+
+        let elapsed = (glib::monotonic_time() - self.start_time) as f64;
+        (elapsed / duration).clamp(0.0, 1.0)
+    }
+}
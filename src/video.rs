@@ -0,0 +1,108 @@
+//! Integration with GStreamer for recording or streaming plots.
+
+use gtk::{gdk, glib, gsk, prelude::*};
+
+use crate::export;
+
+/// Pushes every presented frame of a paintable into a GStreamer `appsrc` as
+/// RGBA video frames.
+///
+/// The caps of the `appsrc` are set to match the intrinsic size of the
+/// paintable, and buffers are timestamped as they are pushed, so it can be
+/// used as a live source.
+#[derive(Debug)]
+pub struct AppSrcAdapter {
+    paintable: gdk::Paintable,
+    appsrc: gst_app::AppSrc,
+    renderer: gsk::CairoRenderer,
+    handler_id: Option<glib::SignalHandlerId>,
+}
+
+impl AppSrcAdapter {
+    /// Creates a new adapter that pushes the frames of `paintable` into
+    /// `appsrc`.
+    pub fn new(
+        paintable: &impl IsA<gdk::Paintable>,
+        appsrc: &gst_app::AppSrc,
+    ) -> Result<Self, glib::Error> {
+        let renderer = gsk::CairoRenderer::new();
+        renderer.realize(None)?;
+
+        let info = gst_video::VideoInfo::builder(
+            gst_video::VideoFormat::Rgba,
+            paintable.intrinsic_width().max(1) as u32,
+            paintable.intrinsic_height().max(1) as u32,
+        )
+        .build()
+        .map_err(|err| glib::Error::new(gst::CoreError::Negotiation, &err.to_string()))?;
+        let caps = info
+            .to_caps()
+            .map_err(|err| glib::Error::new(gst::CoreError::Negotiation, &err.to_string()))?;
+
+        appsrc.set_caps(Some(&caps));
+        appsrc.set_format(gst::Format::Time);
+        appsrc.set_is_live(true);
+        appsrc.set_do_timestamp(true);
+
+        let handler_id = paintable.connect_invalidate_contents({
+            let appsrc = appsrc.clone();
+            let renderer = renderer.clone();
+            move |paintable| {
+                let texture = export::render_paintable(&renderer, paintable);
+                if let Err(err) = appsrc.push_buffer(texture_to_buffer(&texture)) {
+                    glib::g_warning!("plotters-gtk4", "Failed to push frame: {:?}", err);
+                }
+            }
+        });
+
+        Ok(Self {
+            paintable: paintable.clone().upcast(),
+            appsrc: appsrc.clone(),
+            renderer,
+            handler_id: Some(handler_id),
+        })
+    }
+
+    /// Returns the `appsrc` the frames are pushed into.
+    pub fn appsrc(&self) -> &gst_app::AppSrc {
+        &self.appsrc
+    }
+
+    /// Stops pushing frames and signals the end of the stream to the
+    /// `appsrc`.
+    pub fn finish(mut self) -> Result<(), gst::FlowError> {
+        self.disconnect();
+        self.appsrc.end_of_stream().map(|_| ())
+    }
+
+    fn disconnect(&mut self) {
+        if let Some(handler_id) = self.handler_id.take() {
+            self.paintable.disconnect(handler_id);
+            self.renderer.unrealize();
+        }
+    }
+}
+
+impl Drop for AppSrcAdapter {
+    fn drop(&mut self) {
+        self.disconnect();
+    }
+}
+
+fn texture_to_buffer(texture: &gdk::Texture) -> gst::Buffer {
+    let mut downloader = gdk::TextureDownloader::new(texture);
+    downloader.set_format(gdk::MemoryFormat::R8g8b8a8);
+    let (bytes, stride) = downloader.download_bytes();
+
+    let row_len = texture.width() as usize * 4;
+    if stride == row_len {
+        return gst::Buffer::from_slice(bytes);
+    }
+
+    let data = bytes
+        .chunks(stride)
+        .flat_map(|row| &row[..row_len])
+        .copied()
+        .collect::<Vec<_>>();
+    gst::Buffer::from_mut_slice(data)
+}
@@ -0,0 +1,109 @@
+use gtk::{gdk, graphene::Point, pango, prelude::*};
+
+use crate::context;
+
+/// What a [`Watermark`] shows.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WatermarkContent {
+    /// Text, such as an attribution or a "DRAFT" stamp, drawn with `font`, a
+    /// font description such as `"Sans Bold 24"`.
+    Text {
+        text: String,
+        font: String,
+        color: gdk::RGBA,
+    },
+    /// A paintable, such as a logo, drawn at its intrinsic size.
+    Paintable(gdk::Paintable),
+}
+
+/// A mark drawn above the plot and annotations of a
+/// [`Paintable`](crate::Paintable), such as for attribution in exported
+/// charts.
+///
+/// This is set with [`Paintable::set_watermark`](crate::Paintable::set_watermark).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Watermark {
+    /// What the watermark shows.
+    pub content: WatermarkContent,
+    /// The horizontal position, [`gtk::Align::Start`] for the left edge,
+    /// [`gtk::Align::End`] for the right edge, or the center otherwise.
+    pub halign: gtk::Align,
+    /// The vertical position, [`gtk::Align::Start`] for the top edge,
+    /// [`gtk::Align::End`] for the bottom edge, or the center otherwise.
+    pub valign: gtk::Align,
+    /// The distance from the edges the watermark is aligned to, in pixels.
+    pub margin: f32,
+    /// The opacity, from `0.0` for invisible to `1.0` for opaque.
+    pub opacity: f64,
+}
+
+impl Watermark {
+    /// Creates a watermark of `content` in the bottom-right corner, at half
+    /// opacity.
+    pub fn new(content: WatermarkContent) -> Self {
+        Self {
+            content,
+            halign: gtk::Align::End,
+            valign: gtk::Align::End,
+            margin: 8.0,
+            opacity: 0.5,
+        }
+    }
+
+    /// Draws the watermark aligned within `size`.
+    pub(crate) fn append_to(&self, snapshot: &gtk::Snapshot, size: (u32, u32)) {
+        if self.opacity <= 0.0 {
+            return;
+        }
+
+        match &self.content {
+            WatermarkContent::Text { text, font, color } => {
+                let layout = pango::Layout::new(&context::default_pango_context());
+                layout.set_font_description(Some(&pango::FontDescription::from_string(font)));
+                layout.set_text(text);
+
+                let (_, extents) = layout.pixel_extents();
+                let content_size = (extents.width() as f32, extents.height() as f32);
+                self.append_aligned(snapshot, size, content_size, |snapshot| {
+                    snapshot.append_layout(&layout, color);
+                });
+            }
+            WatermarkContent::Paintable(paintable) => {
+                let (width, height) = (paintable.intrinsic_width(), paintable.intrinsic_height());
+                let content_size = (width as f32, height as f32);
+                self.append_aligned(snapshot, size, content_size, |snapshot| {
+                    paintable.snapshot(snapshot, width as f64, height as f64);
+                });
+            }
+        }
+    }
+
+    /// Calls `f` to draw the content of `content_size` at its aligned
+    /// position within `size`.
+    fn append_aligned(
+        &self,
+        snapshot: &gtk::Snapshot,
+        (width, height): (u32, u32),
+        (content_width, content_height): (f32, f32),
+        f: impl FnOnce(&gtk::Snapshot),
+    ) {
+        if content_width <= 0.0 || content_height <= 0.0 {
+            return;
+        }
+
+        let align = |align: gtk::Align, available: f32, size: f32| match align {
+            gtk::Align::Start => self.margin,
+            gtk::Align::End => available - size - self.margin,
+            _ => (available - size) / 2.0,
+        };
+        let x = align(self.halign, width as f32, content_width);
+        let y = align(self.valign, height as f32, content_height);
+
+        snapshot.push_opacity(self.opacity.min(1.0));
+        snapshot.save();
+        snapshot.translate(&Point::new(x, y));
+        f(snapshot);
+        snapshot.restore();
+        snapshot.pop();
+    }
+}